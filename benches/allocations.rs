@@ -0,0 +1,117 @@
+//! Count how many times rendering allocates from the global allocator.
+//!
+//! Run with `cargo bench --bench allocations --features
+//! xxx-unstable-internal-use-only`. Each scenario renders its component a
+//! thousand times after an initial render, and prints how many allocations
+//! those renders made in total.
+
+#![cfg(all(
+    feature = "xxx-unstable-internal-use-only",
+    not(target_arch = "wasm32")
+))]
+
+use dodrio::{builder::*, bumpalo, Cached, Node, Render, RenderContext, RootRender, Vdom};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A global allocator that counts allocations, and otherwise defers to the
+/// system allocator.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const RENDERS: usize = 1_000;
+
+/// Render a list that is `self.0` items long, has attributes and listeners.
+struct SimpleList(usize);
+impl<'a> Render<'a> for SimpleList {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut children = bumpalo::collections::Vec::with_capacity_in(self.0, cx.bump);
+        children.extend((0..self.0).map(|_| {
+            li(&cx)
+                .attr("class", "my-list-item")
+                .on("click", |_root, _vdom, _event| {
+                    panic!("no one should call this")
+                })
+                .children([text("a list item")])
+                .finish()
+        }));
+        ol(&cx).attr("id", "my-list").children(children).finish()
+    }
+}
+
+/// A row of a table, which is rendered as a cached subtree.
+#[derive(Default)]
+struct Row(usize);
+impl<'a> Render<'a> for Row {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let label = bumpalo::format!(in cx.bump, "row {}", self.0);
+        tr(&cx)
+            .children([
+                td(&cx).children([text(label.into_bump_str())]).finish(),
+                td(&cx)
+                    .children([button(&cx).children([text("Remove")]).finish()])
+                    .finish(),
+            ])
+            .finish()
+    }
+}
+
+/// Render `self.0` cached rows that are new on every render, as if every row
+/// had been invalidated, so that each render replaces all of the cached
+/// subtrees.
+struct ChangingCachedRows(usize);
+impl<'a> Render<'a> for ChangingCachedRows {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut children = bumpalo::collections::Vec::with_capacity_in(self.0, cx.bump);
+        children.extend((0..self.0).map(|i| Cached::new(Row(i)).render(cx)));
+        table(&cx).children(children).finish()
+    }
+}
+
+fn count_allocations<R, F>(name: &str, mut component: F)
+where
+    R: RootRender,
+    F: FnMut() -> R,
+{
+    let vdom = Vdom::new(&(), component());
+    vdom.immediately_render_and_diff(component());
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..RENDERS {
+        vdom.immediately_render_and_diff(component());
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    println!(
+        "{}: {} allocations in {} renders ({:.2} per render)",
+        name,
+        allocations,
+        RENDERS,
+        allocations as f64 / RENDERS as f64
+    );
+}
+
+fn main() {
+    count_allocations("same-list-of-100", || SimpleList(100));
+    count_allocations("100-changing-cached-rows", || ChangingCachedRows(100));
+}
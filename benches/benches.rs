@@ -1,16 +1,22 @@
+//! Benchmarks for rendering, and for diffing with the recording `ChangeSink`,
+//! so that they run off-browser.
+//!
+//! Run with `cargo bench --bench benches --features
+//! xxx-unstable-internal-use-only`. See `README.md` for baseline numbers.
+
 #![cfg(all(
     feature = "xxx-unstable-internal-use-only",
     not(target_arch = "wasm32")
 ))]
 
 use criterion::{
-    black_box, criterion_group, criterion_main, Benchmark, Criterion, ParameterizedBenchmark,
-    Throughput,
+    black_box, criterion_group, criterion_main, Bencher, Benchmark, Criterion,
+    ParameterizedBenchmark, Throughput,
 };
 use dodrio::{
     builder::*,
     bumpalo::{self, Bump},
-    CachedSet, Node, Render, RenderContext, Vdom,
+    replay, CachedSet, Node, Render, RenderContext,
 };
 use std::cell::RefCell;
 use std::convert::TryInto;
@@ -41,6 +47,129 @@ impl<'a> Render<'a> for SimpleList {
     }
 }
 
+/// Render a `<div/>` with five attributes whose values all depend on `self.0`.
+struct FiveAttributes(u32);
+impl<'a> Render<'a> for FiveAttributes {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let n = self.0;
+        let id = bumpalo::format!(in cx.bump, "id-{}", n);
+        let class = bumpalo::format!(in cx.bump, "class-{}", n);
+        let style = bumpalo::format!(in cx.bump, "width: {}px", n);
+        let title = bumpalo::format!(in cx.bump, "title-{}", n);
+        let label = bumpalo::format!(in cx.bump, "label-{}", n);
+        div(&cx)
+            .attr("id", id.into_bump_str())
+            .attr("class", class.into_bump_str())
+            .attr("style", style.into_bump_str())
+            .attr("title", title.into_bump_str())
+            .attr("aria-label", label.into_bump_str())
+            .finish()
+    }
+}
+
+/// Render a static list of `self.0` items, each with a text child, for a total
+/// of `2 * self.0 + 1` nodes. When `self.1` is true, every item gets a listener,
+/// which forces creating the list node by node.
+struct StaticList(usize, bool);
+impl<'a> Render<'a> for StaticList {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut children = bumpalo::collections::Vec::with_capacity_in(self.0, cx.bump);
+        children.extend((0..self.0).map(|_| {
+            let item = li(&cx).attr("class", "my-list-item");
+            let item = if self.1 {
+                item.on("click", |_root, _vdom, _event| {
+                    panic!("no one should call this")
+                })
+            } else {
+                item
+            };
+            item.children([text("a list item")]).finish()
+        }));
+        ul(&cx).attr("id", "my-list").children(children).finish()
+    }
+}
+
+/// Render a keyed list of `self.0` items. When `self.1` is `Some(i)`, an extra
+/// item is inserted at index `i`.
+struct KeyedList(u32, Option<u32>);
+impl<'a> Render<'a> for KeyedList {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let len = self.0 as usize + if self.1.is_some() { 1 } else { 0 };
+        let mut children = bumpalo::collections::Vec::with_capacity_in(len, cx.bump);
+        for i in 0..self.0 {
+            if self.1 == Some(i) {
+                children.push(li(&cx).key(self.0).children([text("inserted")]).finish());
+            }
+            children.push(li(&cx).key(i).children([text("a list item")]).finish());
+        }
+        ol(&cx).children(children).finish()
+    }
+}
+
+/// Render a tree of `div`s that is `self.0` levels deep, where every
+/// non-leaf node has `self.1` children, and every leaf has a listener.
+struct Tree(u32, usize);
+impl<'a> Render<'a> for Tree {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        fn tree<'a>(cx: &mut RenderContext<'a>, depth: u32, fanout: usize) -> Node<'a> {
+            if depth == 0 {
+                return div(&cx)
+                    .attr("class", "leaf")
+                    .on("click", |_root, _vdom, _event| {
+                        panic!("no one should call this")
+                    })
+                    .finish();
+            }
+            let mut children = bumpalo::collections::Vec::with_capacity_in(fanout, cx.bump);
+            for _ in 0..fanout {
+                children.push(tree(cx, depth - 1, fanout));
+            }
+            div(&cx).attr("class", "branch").children(children).finish()
+        }
+        tree(cx, self.0, self.1)
+    }
+}
+
+/// Render `self.0` sibling `<div>`s, each with a listener, which forces
+/// creating them one by one rather than via HTML.
+struct Divs(usize);
+impl<'a> Render<'a> for Divs {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut children = bumpalo::collections::Vec::with_capacity_in(self.0, cx.bump);
+        children.extend((0..self.0).map(|_| {
+            div(&cx)
+                .on("click", |_root, _vdom, _event| {
+                    panic!("no one should call this")
+                })
+                .finish()
+        }));
+        div(&cx).children(children).finish()
+    }
+}
+
+/// Render `old` and `new` into their own arenas, and benchmark diffing them
+/// with the recording `ChangeSink`.
+fn bench_diff<O, N>(b: &mut Bencher, old: O, new: N)
+where
+    O: for<'a> Render<'a>,
+    N: for<'a> Render<'a>,
+{
+    let cached_set = RefCell::new(CachedSet::default());
+    let (mut old_templates, mut new_templates) = Default::default();
+    let (old_bump, new_bump) = (Bump::new(), Bump::new());
+    let old = old.render(&mut RenderContext::new(
+        &old_bump,
+        &cached_set,
+        &mut old_templates,
+    ));
+    let new = new.render(&mut RenderContext::new(
+        &new_bump,
+        &cached_set,
+        &mut new_templates,
+    ));
+    b.iter(|| black_box(replay::record_ops(&old, &new)));
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench(
         "render",
@@ -80,38 +209,60 @@ fn criterion_benchmark(c: &mut Criterion) {
     );
 
     c.bench(
-        "render-and-diff",
+        "diff",
         ParameterizedBenchmark::new(
             "same-list",
-            |b, &n| {
-                let vdom = Vdom::new(&(), SimpleList(n));
-                b.iter(|| {
-                    vdom.immediately_render_and_diff(SimpleList(n));
-                    black_box(&vdom);
-                })
-            },
+            |b, &n| bench_diff(b, SimpleList(n), SimpleList(n)),
             vec![100, 1_000, 10_000],
         )
-        .with_function("empty-to-full-list-to-empty", |b, &n| {
-            b.iter(|| {
-                let vdom = Vdom::new(&(), Empty);
-                vdom.immediately_render_and_diff(SimpleList(n));
-                black_box(&vdom);
-                vdom.immediately_render_and_diff(Empty);
-                black_box(&vdom);
-            });
+        .with_function("empty-to-full-list", |b, &n| {
+            bench_diff(b, Empty, SimpleList(n))
+        })
+        .with_function("full-list-to-empty", |b, &n| {
+            bench_diff(b, SimpleList(n), Empty)
+        })
+        .with_function("append-one", |b, &n| {
+            bench_diff(b, SimpleList(n), SimpleList(n + 1))
+        })
+        .with_function("same-keyed-list", |b, &n| {
+            let n = n.try_into().unwrap();
+            bench_diff(b, KeyedList(n, None), KeyedList(n, None))
         })
-        .with_function("append-one-and-remove-one", |b, &n| {
-            let vdom = Vdom::new(&(), SimpleList(n));
-            b.iter(|| {
-                vdom.immediately_render_and_diff(SimpleList(n + 1));
-                black_box(&vdom);
-                vdom.immediately_render_and_diff(SimpleList(n));
-                black_box(&vdom);
-            })
+        .with_function("insert-one-keyed", |b, &n| {
+            let n = n.try_into().unwrap();
+            bench_diff(b, KeyedList(n, None), KeyedList(n, Some(n / 2)))
         })
         .throughput(|n| Throughput::Elements((*n).try_into().unwrap())),
     );
+
+    c.bench(
+        "diff",
+        Benchmark::new("five-attributes-changed", |b| {
+            bench_diff(b, FiveAttributes(0), FiveAttributes(1))
+        })
+        .with_function("five-attributes-unchanged", |b| {
+            bench_diff(b, FiveAttributes(0), FiveAttributes(0))
+        }),
+    );
+
+    c.bench(
+        "diff",
+        Benchmark::new("empty-to-500-node-static-tree", |b| {
+            bench_diff(b, Empty, StaticList(250, false))
+        })
+        .with_function("empty-to-500-node-tree-with-listeners", |b| {
+            bench_diff(b, Empty, StaticList(250, true))
+        })
+        .with_function("empty-to-deep-tree", |b| {
+            // 4^6 leaves, 5461 nodes in total.
+            bench_diff(b, Empty, Tree(6, 4))
+        }),
+    );
+
+    c.bench(
+        "create",
+        Benchmark::new("10k-divs", |b| bench_diff(b, Empty, Divs(10_000))),
+    );
 }
 
 criterion_group!(benches, criterion_benchmark);
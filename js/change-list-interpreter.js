@@ -9,26 +9,522 @@ function string(mem, pointer, length) {
   return decoder.decode(buf);
 }
 
+// Elements whose scroll-into-view directive was just set, and that should be
+// scrolled into view once the current change list is applied. See
+// `ElementBuilder::scroll_into_view`.
+const pendingScrolls = [];
+
+// Inputs whose selection range directive was just set, and whose selection
+// should be restored once the current change list, including any new `value`,
+// is applied. See `ElementBuilder::selection_range`.
+const pendingSelections = [];
+
+// Canvases whose 2D context directive was just set, and whose context should be
+// handed to their `dodrio-canvas-2d` listener once the current change list is
+// applied. See `ElementBuilder::canvas_2d`.
+const pendingCanvases = [];
+
+// The number of elements, across all interpreters, whose resize directive is
+// being observed. See `ElementBuilder::on_resize`.
+let resizeObservedCount = 0;
+
+function observeResize(node) {
+  if (node["dodrio-resize-observer"] || typeof ResizeObserver === "undefined") {
+    return;
+  }
+  const observer = new ResizeObserver(entries => {
+    const { width, height } = entries[entries.length - 1].contentRect;
+    node.dispatchEvent(new CustomEvent("dodrio-resize", { detail: { width, height } }));
+  });
+  observer.observe(node);
+  node["dodrio-resize-observer"] = observer;
+  resizeObservedCount++;
+}
+
+function unobserveResize(node) {
+  const observer = node["dodrio-resize-observer"];
+  if (observer) {
+    observer.disconnect();
+    node["dodrio-resize-observer"] = null;
+    resizeObservedCount--;
+  }
+}
+
+// Stop observing the given element and all of its descendants.
+function unobserveResizes(node) {
+  unobserveResize(node);
+  for (const descendant of node.querySelectorAll("[data-dodrio-resize]")) {
+    unobserveResize(descendant);
+  }
+}
+
+// The number of elements, across all interpreters, with the document head
+// directive. Such an element renders its children into `document.head` rather
+// than into itself. Since `document.head` contains other nodes too, the
+// element's physical children are tracked in an array, and each child points
+// back to the element. See `builder::document_head`.
+let headPortalCount = 0;
+
+function isHeadPortal(node) {
+  return !!node["dodrio-head-children"];
+}
+
+// Insert `node` as a child of the given head portal, before `next`, or last if
+// `next` is `null`.
+function insertHeadChild(portal, node, next) {
+  forgetHeadChild(node);
+  const children = portal["dodrio-head-children"];
+  children.splice(next === null ? children.length : children.indexOf(next), 0, node);
+  node["dodrio-head-portal"] = portal;
+  document.head.insertBefore(node, next);
+}
+
+// Forget that the given node is a head portal's child, because it is being
+// moved or removed.
+function forgetHeadChild(node) {
+  const portal = node["dodrio-head-portal"];
+  if (portal) {
+    const children = portal["dodrio-head-children"];
+    children.splice(children.indexOf(node), 1);
+    node["dodrio-head-portal"] = null;
+  }
+}
+
+// Make the given element a head portal, moving any children it already has
+// into `document.head`.
+function openHeadPortal(node) {
+  node["dodrio-head-children"] = [];
+  headPortalCount++;
+  while (node.firstChild !== null) {
+    insertHeadChild(node, node.firstChild, null);
+  }
+}
+
+// Stop the given element from being a head portal, moving its children out of
+// `document.head` and back into it.
+function closeHeadPortal(node) {
+  const children = node["dodrio-head-children"];
+  node["dodrio-head-children"] = null;
+  headPortalCount--;
+  for (const child of children) {
+    child["dodrio-head-portal"] = null;
+    node.appendChild(child);
+  }
+}
+
+// Remove the children of the given element's head portals, and of its
+// descendants', from `document.head`.
+function removeHeadChildren(node) {
+  const portals = Array.from(node.querySelectorAll("[data-dodrio-head]"));
+  portals.push(node);
+  for (const portal of portals) {
+    if (!isHeadPortal(portal)) {
+      continue;
+    }
+    for (const child of portal["dodrio-head-children"]) {
+      beforeRemove(child);
+      child["dodrio-head-portal"] = null;
+      child.remove();
+    }
+    portal["dodrio-head-children"] = null;
+    headPortalCount--;
+  }
+}
+
+// The number of elements, across all interpreters, with the leave transition
+// directive, including ones cloned from templates. Removing nodes only looks
+// for leave transitions while there are any. See
+// `ElementBuilder::leave_transition`.
+let leaveTransitionCount = 0;
+
+function countLeaveTransitions(node) {
+  const count = node.querySelectorAll("[data-dodrio-leave]").length;
+  return node.hasAttribute("data-dodrio-leave") ? count + 1 : count;
+}
+
+// Clean up after the given node and its descendants, because they are being
+// removed.
+function beforeRemove(node) {
+  if (node.nodeType !== Node.ELEMENT_NODE) {
+    return;
+  }
+  if (leaveTransitionCount > 0) {
+    leaveTransitionCount -= countLeaveTransitions(node);
+  }
+  if (resizeObservedCount > 0) {
+    unobserveResizes(node);
+  }
+  if (headPortalCount > 0) {
+    removeHeadChildren(node);
+  }
+}
+
+function nextSibling(node) {
+  const portal = node["dodrio-head-portal"];
+  if (!portal) {
+    return node.nextSibling;
+  }
+  const children = portal["dodrio-head-children"];
+  return children[children.indexOf(node) + 1] || null;
+}
+
+// Insert `node` before `next`, which might be a head portal's child.
+function insertNodeBefore(node, next) {
+  const portal = next["dodrio-head-portal"];
+  if (portal) {
+    insertHeadChild(portal, node, next);
+  } else {
+    next.parentNode.insertBefore(node, next);
+  }
+}
+
+// Form control attributes whose DOM properties are set along with them, so
+// that they take effect even when the property does not simply reflect the
+// attribute. `indeterminate` has no effect as an attribute at all, and `muted`
+// only sets a media element's initial state. The others do reflect on built-in
+// elements, but form-associated custom elements may implement them as plain
+// properties.
+const BOOLEAN_PROPERTIES = new Map([
+  ["indeterminate", "indeterminate"],
+  ["inert", "inert"],
+  ["muted", "muted"],
+  ["readonly", "readOnly"],
+  ["required", "required"],
+]);
+const STRING_PROPERTIES = new Map([["autocomplete", "autocomplete"]]);
+
+// Media element state that has no attribute at all, but that is set through
+// one so that it is only assigned when it changes. Assigning to these never
+// reloads the media, unlike re-setting `src`.
+const NUMBER_PROPERTIES = new Map([
+  ["currenttime", "currentTime"],
+  ["volume", "volume"],
+]);
+
+// Elements whose `value` property is a number rather than a string.
+const NUMERIC_VALUE_ELEMENTS = new Set(["PROGRESS", "METER"]);
+
+// Attributes that a range input's `value` property is clamped by, or that turn
+// an input into a range input in the first place.
+const RANGE_ATTRIBUTES = new Set(["type", "min", "max", "step"]);
+
+// A range input's `value` is clamped to its bounds when it is assigned, so when
+// the bounds change after the value, assign it again to clamp it to the new
+// ones instead.
+function resyncRangeValue(node, name) {
+  if (RANGE_ATTRIBUTES.has(name) && node.type === "range" && node.hasAttribute("value")) {
+    node.value = node.getAttribute("value");
+  }
+}
+
+function setAttribute(node, name, value) {
+  if (name === "data-dodrio-leave" && !node.hasAttribute(name)) {
+    leaveTransitionCount++;
+  }
+  node.setAttribute(name, value);
+
+  if (name === "data-dodrio-scroll-into-view") {
+    pendingScrolls.push(node);
+  }
+  if (name === "data-dodrio-selection-range") {
+    pendingSelections.push(node);
+  }
+  if (name === "data-dodrio-resize") {
+    observeResize(node);
+  }
+  if (name === "data-dodrio-canvas-2d") {
+    pendingCanvases.push(node);
+  }
+  if (name === "data-dodrio-head" && !isHeadPortal(node)) {
+    openHeadPortal(node);
+  }
+
+  // Some attributes are "volatile" and don't work through `setAttribute`.
+  if (name === "value") {
+    node.value = NUMERIC_VALUE_ELEMENTS.has(node.tagName) ? Number(value) : value;
+  }
+  if (name === "checked") {
+    node.checked = true;
+  }
+  if (name === "selected") {
+    node.selected = true;
+  }
+  resyncRangeValue(node, name);
+
+  const booleanProperty = BOOLEAN_PROPERTIES.get(name);
+  if (booleanProperty !== undefined && booleanProperty in node) {
+    node[booleanProperty] = true;
+  }
+  const stringProperty = STRING_PROPERTIES.get(name);
+  if (stringProperty !== undefined && stringProperty in node) {
+    node[stringProperty] = value;
+  }
+  const numberProperty = NUMBER_PROPERTIES.get(name);
+  if (numberProperty !== undefined && numberProperty in node) {
+    node[numberProperty] = Number(value);
+  }
+}
+
+// A detached element's style, used to parse `style` attributes into their
+// declarations. See `setStyleProperties`.
+let scratchStyle = null;
+
+// Apply a `style` attribute's declarations one by one with the CSSOM, which a
+// strict Content Security Policy allows, rather than setting the attribute,
+// which it blocks. See `MountOptions::strict_csp`.
+function setStyleProperties(node, cssText) {
+  if (scratchStyle === null) {
+    scratchStyle = document.createElement("div").style;
+  }
+  scratchStyle.cssText = cssText;
+
+  // Remove the declarations that are no longer in the attribute. Custom
+  // properties are left alone, since they are set by their own attributes.
+  const style = node.style;
+  for (let i = style.length - 1; i >= 0; i--) {
+    const name = style[i];
+    if (!name.startsWith("--") && scratchStyle.getPropertyValue(name) === "") {
+      style.removeProperty(name);
+    }
+  }
+  for (let i = 0; i < scratchStyle.length; i++) {
+    const name = scratchStyle[i];
+    style.setProperty(
+      name,
+      scratchStyle.getPropertyValue(name),
+      scratchStyle.getPropertyPriority(name)
+    );
+  }
+}
+
+function removeAttribute(node, name) {
+  if (name === "data-dodrio-leave" && node.hasAttribute(name)) {
+    leaveTransitionCount--;
+  }
+  if (name === "data-dodrio-head" && isHeadPortal(node)) {
+    closeHeadPortal(node);
+  }
+  node.removeAttribute(name);
+
+  if (name === "data-dodrio-resize") {
+    unobserveResize(node);
+  }
+
+  // Some attributes are "volatile" and don't work through `removeAttribute`.
+  //
+  // Checkboxes' and radio buttons' `value` property reflects the attribute, so
+  // assigning to it would just add the attribute back, set to "null". So does
+  // the numeric `value` of `<progress>` and `<meter>`, set to "0".
+  if (
+    name === "value" &&
+    node.type !== "checkbox" &&
+    node.type !== "radio" &&
+    !NUMERIC_VALUE_ELEMENTS.has(node.tagName)
+  ) {
+    node.value = null;
+  }
+  if (name === "checked") {
+    node.checked = false;
+  }
+  if (name === "selected") {
+    node.selected = false;
+  }
+  resyncRangeValue(node, name);
+
+  // String properties are left alone: where they reflect the attribute,
+  // assigning to them would add it back. Number properties are left alone too,
+  // so that the media keeps playing from wherever it is, as loud as it is.
+  const booleanProperty = BOOLEAN_PROPERTIES.get(name);
+  if (booleanProperty !== undefined && booleanProperty in node) {
+    node[booleanProperty] = false;
+  }
+}
+
+// Scrolling and touch events whose listeners are added as passive, so that the
+// browser can scroll without waiting for them to run. Passive listeners can't
+// cancel the default action of the event.
+const PASSIVE_EVENTS = new Set(["wheel", "mousewheel", "touchstart", "touchmove"]);
+
+// Add the interpreter's event handler to the node on top of its stack, for
+// the Rust callback identified by `a` and `b`.
+function addEventListener(interpreter, eventType, a, b, passive) {
+  const el = top(interpreter.stack);
+  el.addEventListener(eventType, interpreter.eventHandler, { passive });
+  el[`dodrio-a-${interpreter.namespace}-${eventType}`] = a;
+  el[`dodrio-b-${interpreter.namespace}-${eventType}`] = b;
+}
+
+// The number of elements, across all interpreters, that are in the middle of
+// their leave transition. See `ElementBuilder::leave_transition`.
+let leavingCount = 0;
+
+function isLeaving(node) {
+  return node.nodeType === Node.ELEMENT_NODE && node.hasAttribute("data-dodrio-leaving");
+}
+
+// Get the `n`th child of `parent`, not counting leaving children.
+function childAt(parent, n) {
+  if (headPortalCount > 0 && isHeadPortal(parent)) {
+    return parent["dodrio-head-children"][n];
+  }
+  const children = parent.childNodes;
+  if (!parent["dodrio-leaving-count"]) {
+    return children[n];
+  }
+  for (let i = 0; i < children.length; i++) {
+    if (!isLeaving(children[i])) {
+      if (n === 0) {
+        return children[i];
+      }
+      n--;
+    }
+  }
+}
+
+// Get the `n`th from last child of `parent`, not counting leaving children.
+function reverseChildAt(parent, n) {
+  if (headPortalCount > 0 && isHeadPortal(parent)) {
+    const children = parent["dodrio-head-children"];
+    return children[children.length - n - 1];
+  }
+  const children = parent.childNodes;
+  if (!parent["dodrio-leaving-count"]) {
+    return children[children.length - n - 1];
+  }
+  for (let i = children.length - 1; i >= 0; i--) {
+    if (!isLeaving(children[i])) {
+      if (n === 0) {
+        return children[i];
+      }
+      n--;
+    }
+  }
+}
+
+// Get the time, in milliseconds, until all of the given element's transitions
+// have ended.
+function transitionTime(node) {
+  const style = getComputedStyle(node);
+  const durations = style.transitionDuration.split(",");
+  const delays = style.transitionDelay.split(",");
+  let time = 0;
+  for (let i = 0; i < durations.length; i++) {
+    const duration = parseFloat(durations[i]);
+    const delay = parseFloat(delays[i % delays.length]);
+    if (duration > 0) {
+      time = Math.max(time, (duration + delay) * 1000);
+    }
+  }
+  return time;
+}
+
+// Remove the given node from the DOM, or if it has a leave transition, start
+// its leave transition and remove it once the transition ends.
+function removeNode(node) {
+  if (headPortalCount > 0) {
+    forgetHeadChild(node);
+  }
+  if (node.nodeType !== Node.ELEMENT_NODE) {
+    node.remove();
+    return;
+  }
+  if (leavingCount > 0 && isLeaving(node)) {
+    return;
+  }
+  const leaveClass = leaveTransitionCount > 0 ? node.getAttribute("data-dodrio-leave") : null;
+  beforeRemove(node);
+
+  if (leaveClass === null) {
+    node.remove();
+    return;
+  }
+
+  node.classList.add(leaveClass);
+  const time = transitionTime(node);
+  if (time === 0) {
+    node.remove();
+    return;
+  }
+
+  const parent = node.parentNode;
+  node.setAttribute("data-dodrio-leaving", "");
+  parent["dodrio-leaving-count"] = (parent["dodrio-leaving-count"] || 0) + 1;
+  leavingCount++;
+
+  let timeout = null;
+  const finish = event => {
+    if (event && event.target !== node) {
+      return;
+    }
+    clearTimeout(timeout);
+    node.removeEventListener("transitionend", finish);
+    node.removeEventListener("transitioncancel", finish);
+    parent["dodrio-leaving-count"]--;
+    leavingCount--;
+    node.remove();
+  };
+  node.addEventListener("transitionend", finish);
+  node.addEventListener("transitioncancel", finish);
+
+  // If the leave class doesn't actually change any transitioned properties,
+  // then no transition events will fire, so make sure that we eventually
+  // remove the node anyways.
+  timeout = setTimeout(finish, time + 50);
+}
+
+// Remove all of `parent`'s children and replace them with the given text.
+function setChildrenText(parent, text) {
+  let hasLeaveTransitions = false;
+  if (leaveTransitionCount > 0) {
+    for (let child = parent.firstElementChild; child; child = child.nextElementSibling) {
+      if (child.hasAttribute("data-dodrio-leave")) {
+        hasLeaveTransitions = true;
+        break;
+      }
+    }
+  }
+
+  if (!hasLeaveTransitions) {
+    for (let child = parent.firstElementChild; child; child = child.nextElementSibling) {
+      beforeRemove(child);
+    }
+    parent.textContent = text;
+    return;
+  }
+
+  for (const child of Array.from(parent.childNodes)) {
+    removeNode(child);
+  }
+  if (text !== "") {
+    parent.appendChild(document.createTextNode(text));
+  }
+}
+
 const OP_TABLE = [
   // 0
   function setText(interpreter, mem8, mem32, i) {
     const pointer = mem32[i++];
     const length = mem32[i++];
     const str = string(mem8, pointer, length);
-    top(interpreter.stack).textContent = str;
+    const node = top(interpreter.stack);
+    if (node.firstElementChild) {
+      setChildrenText(node, str);
+    } else {
+      node.textContent = str;
+    }
     return i;
   },
 
   // 1
   function removeSelfAndNextSiblings(interpreter, mem8, mem32, i) {
     const node = interpreter.stack.pop();
-    let sibling = node.nextSibling;
+    let sibling = nextSibling(node);
     while (sibling) {
-      const temp = sibling.nextSibling;
-      sibling.remove();
+      const temp = nextSibling(sibling);
+      removeNode(sibling);
       sibling = temp;
     }
-    node.remove();
+    removeNode(node);
     return i;
   },
 
@@ -36,7 +532,16 @@ const OP_TABLE = [
   function replaceWith(interpreter, mem8, mem32, i) {
     const newNode = interpreter.stack.pop();
     const oldNode = interpreter.stack.pop();
-    oldNode.replaceWith(newNode);
+    if (
+      oldNode["dodrio-head-portal"] ||
+      (oldNode.nodeType === Node.ELEMENT_NODE && oldNode.hasAttribute("data-dodrio-leave"))
+    ) {
+      insertNodeBefore(newNode, oldNode);
+      removeNode(oldNode);
+    } else {
+      beforeRemove(oldNode);
+      oldNode.replaceWith(newNode);
+    }
     interpreter.stack.push(newNode);
     return i;
   },
@@ -47,20 +552,7 @@ const OP_TABLE = [
     const valueId = mem32[i++];
     const name = interpreter.getCachedString(nameId);
     const value = interpreter.getCachedString(valueId);
-    const node = top(interpreter.stack);
-    node.setAttribute(name, value);
-
-    // Some attributes are "volatile" and don't work through `setAttribute`.
-    if (name === "value") {
-      node.value = value;
-    }
-    if (name === "checked") {
-      node.checked = true;
-    }
-    if (name === "selected") {
-      node.selected = true;
-    }
-
+    interpreter.setAttribute(top(interpreter.stack), name, value);
     return i;
   },
 
@@ -68,20 +560,7 @@ const OP_TABLE = [
   function removeAttribute(interpreter, mem8, mem32, i) {
     const nameId = mem32[i++];
     const name = interpreter.getCachedString(nameId);
-    const node = top(interpreter.stack);
-    node.removeAttribute(name);
-
-    // Some attributes are "volatile" and don't work through `removeAttribute`.
-    if (name === "value") {
-      node.value = null;
-    }
-    if (name === "checked") {
-      node.checked = false;
-    }
-    if (name === "selected") {
-      node.selected = false;
-    }
-
+    removeAttribute(top(interpreter.stack), name);
     return i;
   },
 
@@ -89,8 +568,7 @@ const OP_TABLE = [
   function pushReverseChild(interpreter, mem8, mem32, i) {
     const n = mem32[i++];
     const parent = top(interpreter.stack);
-    const children = parent.childNodes;
-    const child = children[children.length - n - 1];
+    const child = reverseChildAt(parent, n);
     interpreter.stack.push(child);
     return i;
   },
@@ -100,8 +578,7 @@ const OP_TABLE = [
     const n = mem32[i++];
     interpreter.stack.pop();
     const parent = top(interpreter.stack);
-    const children = parent.childNodes;
-    const child = children[n];
+    const child = childAt(parent, n);
     interpreter.stack.push(child);
     return i;
   },
@@ -115,7 +592,12 @@ const OP_TABLE = [
   // 8
   function appendChild(interpreter, mem8, mem32, i) {
     const child = interpreter.stack.pop();
-    top(interpreter.stack).appendChild(child);
+    const parent = top(interpreter.stack);
+    if (headPortalCount > 0 && isHeadPortal(parent)) {
+      insertHeadChild(parent, child, null);
+    } else {
+      parent.appendChild(child);
+    }
     return i;
   },
 
@@ -132,7 +614,7 @@ const OP_TABLE = [
   function createElement(interpreter, mem8, mem32, i) {
     const tagNameId = mem32[i++];
     const tagName = interpreter.getCachedString(tagNameId);
-    interpreter.stack.push(document.createElement(tagName));
+    interpreter.stack.push(interpreter.createElement(tagName));
     return i;
   },
 
@@ -142,10 +624,7 @@ const OP_TABLE = [
     const eventType = interpreter.getCachedString(eventId);
     const a = mem32[i++];
     const b = mem32[i++];
-    const el = top(interpreter.stack);
-    el.addEventListener(eventType, interpreter.eventHandler);
-    el[`dodrio-a-${eventType}`] = a;
-    el[`dodrio-b-${eventType}`] = b;
+    addEventListener(interpreter, eventType, a, b, PASSIVE_EVENTS.has(eventType));
     return i;
   },
 
@@ -154,8 +633,8 @@ const OP_TABLE = [
     const eventId = mem32[i++];
     const eventType = interpreter.getCachedString(eventId);
     const el = top(interpreter.stack);
-    el[`dodrio-a-${eventType}`] = mem32[i++];
-    el[`dodrio-b-${eventType}`] = mem32[i++];
+    el[`dodrio-a-${interpreter.namespace}-${eventType}`] = mem32[i++];
+    el[`dodrio-b-${interpreter.namespace}-${eventType}`] = mem32[i++];
     return i;
   },
 
@@ -201,9 +680,8 @@ const OP_TABLE = [
     const start = mem32[i++];
     const end = mem32[i++];
     const parent = top(interpreter.stack);
-    const children = parent.childNodes;
     for (let i = start; i < end; i++) {
-      interpreter.temporaries[temp++] = children[i];
+      interpreter.temporaries[temp++] = childAt(parent, i);
     }
     return i;
   },
@@ -212,7 +690,7 @@ const OP_TABLE = [
   function pushChild(interpreter, mem8, mem32, i) {
     const parent = top(interpreter.stack);
     const n = mem32[i++];
-    const child = parent.childNodes[n];
+    const child = childAt(parent, n);
     interpreter.stack.push(child);
     return i;
   },
@@ -228,7 +706,7 @@ const OP_TABLE = [
   function insertBefore(interpreter, mem8, mem32, i) {
     const before = interpreter.stack.pop();
     const after = interpreter.stack.pop();
-    after.parentNode.insertBefore(before, after);
+    insertNodeBefore(before, after);
     interpreter.stack.push(before);
     return i;
   },
@@ -238,8 +716,7 @@ const OP_TABLE = [
     const n = mem32[i++];
     interpreter.stack.pop();
     const parent = top(interpreter.stack);
-    const children = parent.childNodes;
-    const child = children[children.length - n - 1];
+    const child = reverseChildAt(parent, n);
     interpreter.stack.push(child);
     return i;
   },
@@ -248,8 +725,8 @@ const OP_TABLE = [
   function removeChild(interpreter, mem8, mem32, i) {
     const n = mem32[i++];
     const parent = top(interpreter.stack);
-    const child = parent.childNodes[n];
-    child.remove();
+    const child = childAt(parent, n);
+    removeNode(child);
     return i;
   },
 
@@ -264,8 +741,9 @@ const OP_TABLE = [
   // 24
   function saveTemplate(interpreter, mem8, mem32, i) {
     const id = mem32[i++];
-    const template = top(interpreter.stack);
-    interpreter.saveTemplate(id, template.cloneNode(true));
+    const template = top(interpreter.stack).cloneNode(true);
+    template["dodrio-leave-count"] = leaveTransitionCount > 0 ? countLeaveTransitions(template) : 0;
+    interpreter.saveTemplate(id, template);
     return i;
   },
 
@@ -273,8 +751,134 @@ const OP_TABLE = [
   function pushTemplate(interpreter, mem8, mem32, i) {
     const id = mem32[i++];
     const template = interpreter.getTemplate(id);
+    leaveTransitionCount += template["dodrio-leave-count"];
     interpreter.stack.push(template.cloneNode(true));
     return i;
+  },
+
+  // 26
+  function setStyleProperty(interpreter, mem8, mem32, i) {
+    const nameId = mem32[i++];
+    const valueId = mem32[i++];
+    const name = interpreter.getCachedString(nameId);
+    const value = interpreter.getCachedString(valueId);
+    top(interpreter.stack).style.setProperty(name, value);
+    return i;
+  },
+
+  // 27
+  function removeStyleProperty(interpreter, mem8, mem32, i) {
+    const nameId = mem32[i++];
+    const name = interpreter.getCachedString(nameId);
+    top(interpreter.stack).style.removeProperty(name);
+    return i;
+  },
+
+  // 28
+  function setAttributesBatch(interpreter, mem8, mem32, i) {
+    const count = mem32[i++];
+    const end = i + count;
+    const node = top(interpreter.stack);
+    while (i < end) {
+      const name = interpreter.getCachedString(mem32[i++]);
+      const value = interpreter.getCachedString(mem32[i++]);
+      interpreter.setAttribute(node, name, value);
+    }
+    return i;
+  },
+
+  // 29
+  function removeAttributesBatch(interpreter, mem8, mem32, i) {
+    const count = mem32[i++];
+    const end = i + count;
+    const node = top(interpreter.stack);
+    while (i < end) {
+      const name = interpreter.getCachedString(mem32[i++]);
+      removeAttribute(node, name);
+    }
+    return i;
+  },
+
+  // 30
+  function createElementFromHtml(interpreter, mem8, mem32, i) {
+    const pointer = mem32[i++];
+    const length = mem32[i++];
+    const html = string(mem8, pointer, length);
+    const template = interpreter.htmlTemplate;
+    template.innerHTML = html;
+    const node = template.content.firstChild;
+    node.remove();
+    interpreter.stack.push(node);
+    return i;
+  },
+
+  // 31
+  function setProperty(interpreter, mem8, mem32, i) {
+    const name = interpreter.getCachedString(mem32[i++]);
+    const value = interpreter.values[mem32[i++]];
+    top(interpreter.stack)[name] = value;
+    return i;
+  },
+
+  // 32
+  function removeProperty(interpreter, mem8, mem32, i) {
+    const name = interpreter.getCachedString(mem32[i++]);
+    top(interpreter.stack)[name] = null;
+    return i;
+  },
+
+  // 33
+  function createElementWithIs(interpreter, mem8, mem32, i) {
+    const tagName = interpreter.getCachedString(mem32[i++]);
+    const is = interpreter.getCachedString(mem32[i++]);
+    interpreter.stack.push(document.createElement(tagName, { is }));
+    return i;
+  },
+
+  // 34
+  function replaceSiblingsWith(interpreter, mem8, mem32, i) {
+    const count = mem32[i++];
+    const stack = interpreter.stack;
+    const newNodes = stack.splice(stack.length - count, count);
+    const first = stack.pop();
+    const oldNodes = [];
+    for (let node = first; oldNodes.length < count; node = nextSibling(node)) {
+      if (!isLeaving(node)) {
+        oldNodes.push(node);
+      }
+    }
+    if (first["dodrio-head-portal"]) {
+      for (const node of newNodes) {
+        insertNodeBefore(node, first);
+      }
+    } else {
+      const fragment = document.createDocumentFragment();
+      for (const node of newNodes) {
+        fragment.appendChild(node);
+      }
+      first.parentNode.insertBefore(fragment, first);
+    }
+    for (const node of oldNodes) {
+      removeNode(node);
+    }
+    stack.push(newNodes[count - 1]);
+    return i;
+  },
+
+  // 35
+  function pushRawNode(interpreter, mem8, mem32, i) {
+    interpreter.stack.push(interpreter.values[mem32[i++]]);
+    return i;
+  },
+
+  // 36
+  function newNonPassiveEventListener(interpreter, mem8, mem32, i) {
+    const eventId = mem32[i++];
+    const eventType = interpreter.getCachedString(eventId);
+    const a = mem32[i++];
+    const b = mem32[i++];
+    addEventListener(interpreter, eventType, a, b, false);
+    return i;
   }
 ];
 
@@ -282,11 +886,22 @@ export class ChangeListInterpreter {
   constructor(container) {
     this.trampoline = null;
     this.container = container;
+    // The physical node for the root of the virtual DOM. When it is `null`,
+    // the root is the container's first child.
+    this.root = null;
     this.ranges = [];
     this.stack = [];
     this.strings = new Map();
     this.temporaries = [];
     this.templates = new Map();
+    this.values = [];
+    this.htmlTemplate = document.createElement("template");
+    // When cloning elements is enabled, a map from tag name to an empty
+    // prototype element to clone. See `MountOptions::clone_elements`.
+    this.prototypes = null;
+    // See `MountOptions::strict_csp` and `MountOptions::style_nonce`.
+    this.strictCsp = false;
+    this.styleNonce = null;
   }
 
   unmount() {
@@ -296,11 +911,86 @@ export class ChangeListInterpreter {
     // call a method on this instance again, it will throw.
     this.trampoline = null;
     this.container = null;
+    this.root = null;
     this.ranges = null;
     this.stack = null;
     this.strings = null;
     this.temporaries = null;
     this.templates = null;
+    this.values = null;
+    this.htmlTemplate = null;
+    this.prototypes = null;
+  }
+
+  setCloneElements(clone) {
+    this.prototypes = clone ? new Map() : null;
+  }
+
+  setStrictCsp(strict) {
+    this.strictCsp = strict;
+  }
+
+  setStyleNonce(nonce) {
+    this.styleNonce = nonce;
+  }
+
+  createElement(tagName) {
+    const element = this.createBareElement(tagName);
+    if (this.styleNonce !== null && tagName === "style") {
+      element.nonce = this.styleNonce;
+    }
+    return element;
+  }
+
+  createBareElement(tagName) {
+    if (this.prototypes === null) {
+      return document.createElement(tagName);
+    }
+    let prototype = this.prototypes.get(tagName);
+    if (prototype === undefined) {
+      prototype = document.createElement(tagName);
+      this.prototypes.set(tagName, prototype);
+    }
+    return prototype.cloneNode(false);
+  }
+
+  setAttribute(node, name, value) {
+    if (this.strictCsp && name === "style") {
+      setStyleProperties(node, value);
+    } else {
+      setAttribute(node, name, value);
+    }
+  }
+
+  setRoot(root) {
+    this.root = root;
+  }
+
+  rootNode() {
+    return this.root !== null ? this.root : this.container.firstChild;
+  }
+
+  // Replace the root's physical node with a new, empty `<div>`, as when
+  // mounting. See `DiffMode::Lenient`.
+  resetRoot() {
+    const old = this.rootNode();
+    const root = document.createElement("div");
+    if (old !== null && old.parentNode === this.container) {
+      beforeRemove(old);
+      this.container.replaceChild(root, old);
+    } else {
+      this.container.appendChild(root);
+    }
+    if (this.root !== null) {
+      this.root = root;
+    }
+  }
+
+  removeRoot() {
+    if (this.root !== null) {
+      beforeRemove(this.root);
+      this.root.remove();
+    }
   }
 
   addChangeListRange(start, len) {
@@ -313,7 +1003,7 @@ export class ChangeListInterpreter {
       return;
     }
 
-    this.stack.push(this.container.firstChild);
+    this.stack.push(this.root === null ? childAt(this.container, 0) : this.root);
     const mem8 = new Uint8Array(memory.buffer);
     const mem32 = new Uint32Array(memory.buffer);
 
@@ -324,8 +1014,48 @@ export class ChangeListInterpreter {
     }
 
     this.ranges.length = 0;
+    if (this.root !== null) {
+      // The root might have been replaced.
+      this.root = this.stack[0];
+    }
     this.stack.length = 0;
     this.temporaries.length = 0;
+    this.values.length = 0;
+
+    // Scroll only after all changes are applied, so that layout is final.
+    for (const node of pendingScrolls) {
+      const options = node.getAttribute("data-dodrio-scroll-into-view");
+      if (options !== null && node.isConnected) {
+        const [block, behavior] = options.split(" ");
+        node.scrollIntoView({ block, behavior });
+      }
+    }
+    pendingScrolls.length = 0;
+
+    for (const node of pendingSelections) {
+      const range = node.getAttribute("data-dodrio-selection-range");
+      if (range !== null) {
+        const [start, end] = range.split(" ").map(Number);
+        try {
+          node.setSelectionRange(start, end);
+        } catch (e) {
+          // Only text-like inputs have a selection.
+        }
+      }
+    }
+    pendingSelections.length = 0;
+
+    // Dispatch on a later micro-task, once the virtual DOM is done rendering,
+    // so that the listeners can re-enter it.
+    for (const node of pendingCanvases) {
+      const context = typeof node.getContext === "function" ? node.getContext("2d") : null;
+      if (context !== null) {
+        Promise.resolve().then(() => {
+          node.dispatchEvent(new CustomEvent("dodrio-canvas-2d", { detail: context }));
+        });
+      }
+    }
+    pendingCanvases.length = 0;
   }
 
   applyChangeRange(mem8, mem32, start, len) {
@@ -336,6 +1066,11 @@ export class ChangeListInterpreter {
     }
   }
 
+  addValue(value) {
+    this.values.push(value);
+    return this.values.length - 1;
+  }
+
   addCachedString(str, id) {
     this.strings.set(id, str);
   }
@@ -356,21 +1091,30 @@ export class ChangeListInterpreter {
     return this.templates.get(id);
   }
 
-  initEventsTrampoline(trampoline) {
+  initEventsTrampoline(trampoline, namespace) {
     this.trampoline = trampoline;
+    this.namespace = namespace;
     trampoline.mounted = true;
     this.eventHandler = function(event) {
       if (!trampoline.mounted) {
         throw new Error("invocation of listener after VDOM has been unmounted");
       }
 
+      // Elements that are in the middle of their leave transition are no
+      // longer part of the virtual DOM, so ignore their events.
+      if (leavingCount > 0 && this.closest("[data-dodrio-leaving]")) {
+        return;
+      }
+
       // `this` always refers to the element the handler was added to.
       // Since we're adding the handler to all elements our content wants
       // to listen for events on, this ensures that we always get the right
-      // values for `a` and `b`.
+      // values for `a` and `b`. The property names are namespaced, so that
+      // multiple Vdoms listening to the same element never read each other's
+      // values.
       const type = event.type;
-      const a = this[`dodrio-a-${type}`];
-      const b = this[`dodrio-b-${type}`];
+      const a = this[`dodrio-a-${namespace}-${type}`];
+      const b = this[`dodrio-b-${namespace}-${type}`];
       trampoline(event, a, b);
     }
   }
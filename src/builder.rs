@@ -1,7 +1,19 @@
 //! Helpers for building virtual DOM nodes.
 
-use crate::{node::NodeKey, Attribute, Listener, Node, RootRender, VdomWeak};
+use crate::{
+    node::{ElementNode, NodeKey, NodeKind},
+    Attribute, AttributeValue, Listener, Node,
+};
+#[cfg(feature = "dom")]
+use crate::{RootRender, VdomWeak};
 use bumpalo::Bump;
+use std::hash::Hash;
+#[cfg(feature = "dom")]
+use std::rc::Rc;
+#[cfg(feature = "dom")]
+use wasm_bindgen::{JsCast, JsValue};
+#[cfg(feature = "dom")]
+use wasm_bindgen_futures::JsFuture;
 
 /// A virtual DOM element builder.
 ///
@@ -22,6 +34,21 @@ where
     attributes: Attributes,
     children: Children,
     namespace: Option<&'a str>,
+    managed_children: bool,
+}
+
+/// Where to align an element that is scrolled into view. See
+/// `ElementBuilder::scroll_into_view`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollAlignment {
+    /// Align the element with the top of the scrolled area.
+    Start,
+    /// Align the element with the center of the scrolled area.
+    Center,
+    /// Align the element with the bottom of the scrolled area.
+    End,
+    /// Only scroll as little as necessary to make the element visible.
+    Nearest,
 }
 
 impl<'a>
@@ -68,6 +95,7 @@ impl<'a>
             attributes: bumpalo::collections::Vec::new_in(bump),
             children: bumpalo::collections::Vec::new_in(bump),
             namespace: None,
+            managed_children: false,
         }
     }
 }
@@ -118,6 +146,7 @@ where
             attributes: self.attributes,
             children: self.children,
             namespace: self.namespace,
+            managed_children: self.managed_children,
         }
     }
 
@@ -157,6 +186,7 @@ where
             attributes,
             children: self.children,
             namespace: self.namespace,
+            managed_children: self.managed_children,
         }
     }
 
@@ -196,6 +226,7 @@ where
             attributes: self.attributes,
             children,
             namespace: self.namespace,
+            managed_children: self.managed_children,
         }
     }
 
@@ -223,6 +254,7 @@ where
             attributes: self.attributes,
             children: self.children,
             namespace,
+            managed_children: self.managed_children,
         }
     }
 
@@ -243,8 +275,10 @@ where
     ///
     /// Keys must be unique among siblings.
     ///
-    /// All sibling nodes must be keyed, or they must all not be keyed. You may
-    /// not mix keyed and unkeyed siblings.
+    /// Keyed and unkeyed siblings may be mixed, for example a keyed list of
+    /// items between an unkeyed header and footer. Unkeyed siblings are matched
+    /// up with the old unkeyed siblings by their position amongst only the
+    /// unkeyed siblings.
     ///
     /// # Example
     ///
@@ -265,6 +299,33 @@ where
         self
     }
 
+    /// Set this element's key to a hash of `key`.
+    ///
+    /// This is like `key`, but accepts any `Hash` type, such as `u64` ids or
+    /// enums, instead of only `u32`s. See `NodeKey::from_hash`.
+    ///
+    /// # Invariants You Must Uphold
+    ///
+    /// Keys must be unique among siblings, the same as with `key`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let user_id: u64 = 9_007_199_254_740_993;
+    /// let my_li = li(&b)
+    ///     .key_hashed(&user_id)
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn key_hashed<K: Hash + ?Sized>(mut self, key: &K) -> Self {
+        self.key = NodeKey::from_hash(key);
+        self
+    }
+
     /// Create the virtual DOM node described by this builder.
     ///
     /// # Example
@@ -291,18 +352,22 @@ where
         let attributes: &'a Attributes = self.bump.alloc(self.attributes);
         let attributes: &'a [Attribute<'a>] = attributes.as_ref();
 
-        Node::element(
+        Node::from_element_node(
             self.bump,
-            self.key,
-            self.tag_name,
-            listeners,
-            attributes,
-            children,
-            self.namespace,
+            ElementNode {
+                key: self.key,
+                tag_name: self.tag_name,
+                listeners,
+                attributes,
+                children,
+                namespace: self.namespace,
+                managed_children: self.managed_children,
+            },
         )
     }
 }
 
+#[cfg(feature = "dom")]
 impl<'a, Attributes, Children>
     ElementBuilder<'a, bumpalo::collections::Vec<'a, Listener<'a>>, Attributes, Children>
 where
@@ -315,6 +380,17 @@ where
     /// `callback` function is the function that will be invoked if the
     /// specified event occurs.
     ///
+    /// Listeners for `wheel`, `mousewheel`, `touchstart`, and `touchmove`
+    /// events are passive, so that they never hold up scrolling. Calling
+    /// `preventDefault` on those events has no effect; use `on_non_passive`
+    /// or `on_prevent_default` for listeners that need to.
+    ///
+    /// Listeners are registered with the physical DOM in the order they are
+    /// added. On re-renders, listeners for events that were already listened
+    /// for keep their original registration, even if they are added in a
+    /// different order, and listeners for new events are registered after
+    /// them, in the order they are added.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -330,25 +406,1387 @@ where
     ///     .finish();
     /// ```
     #[inline]
-    pub fn on<F>(mut self, event: &'a str, callback: F) -> Self
-    where
-        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
-    {
-        self.listeners.push(Listener {
-            event,
-            callback: self.bump.alloc(callback),
-        });
+    pub fn on<F>(mut self, event: &'a str, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        self.listeners
+            .push(Listener::new(event, self.bump.alloc(callback)));
+        self
+    }
+
+    /// Add a new event listener to this element, with an identity token.
+    ///
+    /// Closures can't be compared, so when re-rendering, diffing has to
+    /// assume that a listener's callback changed and point the physical
+    /// listener at the new one. When the listener for an event has the same
+    /// `id` as it did in the previous render, diffing instead assumes that its
+    /// callback does the same thing, and skips updating the physical DOM. The
+    /// new callback is still the one that gets invoked.
+    ///
+    /// Only reuse an `id` for callbacks that behave the same, even if they
+    /// capture different values.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// const SAVE: u32 = 1;
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let my_button = button(&b)
+    ///     .on_with_id("click", SAVE, |root, vdom, event| {
+    ///         // Save...
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_with_id<F>(mut self, event: &'a str, id: u32, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        let mut listener = Listener::new(event, self.bump.alloc(callback));
+        listener.id = Some(id);
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Add a new event listener to this element that only holds a weak
+    /// reference to the given `state`.
+    ///
+    /// Listeners are allocated in the render bump arena, which never runs
+    /// destructors, so anything a listener closure captures is never dropped.
+    /// Capturing an `Rc` in a listener therefore leaks whatever it points to.
+    /// This method captures a `Weak` instead, and only invokes `callback`, with
+    /// the upgraded state, if the state is still alive when the event happens.
+    /// Once the state is dropped, the listener is a no-op.
+    ///
+    /// The `Weak` itself is never dropped either, though. So only `T`'s
+    /// destructor is guaranteed to run, once the last `Rc` goes away: the
+    /// `Rc`'s allocation, which is the size of `T` plus two counts, is still
+    /// leaked. Use this to make sure that `T`'s resources are released, not to
+    /// reclaim its memory.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let b = Bump::new();
+    /// let clicks = Rc::new(Cell::new(0));
+    ///
+    /// let my_button = button(&b)
+    ///     .on_weak("click", &clicks, |clicks, root, vdom, event| {
+    ///         clicks.set(clicks.get() + 1);
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_weak<T, F>(self, event: &'a str, state: &Rc<T>, callback: F) -> Self
+    where
+        T: 'static,
+        F: 'static + Fn(Rc<T>, &mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        let state = Rc::downgrade(state);
+        self.on(event, move |root, vdom, event| {
+            if let Some(state) = state.upgrade() {
+                callback(state, root, vdom, event);
+            }
+        })
+    }
+
+    /// Add a new event listener to this element that is never passive, so
+    /// that `callback` can call `preventDefault` on any event.
+    ///
+    /// Only listeners for `wheel`, `mousewheel`, `touchstart`, and `touchmove`
+    /// events are passive to begin with. The browser has to wait for
+    /// non-passive ones to run before scrolling, so only use this for
+    /// listeners that do cancel scrolling, such as in a custom pan-and-zoom
+    /// widget.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let map = div(&b)
+    ///     .on_non_passive("wheel", |root, vdom, event| {
+    ///         event.prevent_default();
+    ///         // Zoom the map instead of scrolling the page...
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_non_passive<F>(mut self, event: &'a str, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        let mut listener = Listener::new(event, self.bump.alloc(callback));
+        listener.non_passive = true;
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Add a new event listener to this element that calls `preventDefault`
+    /// on the event before invoking `callback`.
+    ///
+    /// This is useful for things like form `submit` listeners, which should
+    /// never let the browser navigate away. The listener is never passive;
+    /// see `on_non_passive`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let my_form = form(&b)
+    ///     .on_prevent_default("submit", |root, vdom, event| {
+    ///         // Handle the submission without navigating...
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_prevent_default<F>(self, event: &'a str, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        self.on_non_passive(event, move |root, vdom, event| {
+            event.prevent_default();
+            callback(root, vdom, event);
+        })
+    }
+
+    /// Add a new event listener to this element that calls `stopPropagation`
+    /// on the event before invoking `callback`, so that the event is not
+    /// dispatched to any ancestors' listeners.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let my_button = button(&b)
+    ///     .on_stop_propagation("click", |root, vdom, event| {
+    ///         // Only this button handles the click...
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_stop_propagation<F>(self, event: &'a str, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        self.on(event, move |root, vdom, event| {
+            event.stop_propagation();
+            callback(root, vdom, event);
+        })
+    }
+
+    /// Add a new event listener for a high-frequency pointer event, such as
+    /// `pointermove`, that invokes `callback` with every event that the
+    /// browser coalesced into the dispatched one.
+    ///
+    /// Browsers dispatch at most about one `pointermove` per frame, and fold
+    /// any intermediate pointer positions into it. Drawing apps and the like
+    /// need those positions for smooth strokes. In browsers without
+    /// `getCoalescedEvents`, or when nothing was coalesced, `callback` is
+    /// given just the dispatched event.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let drawing = canvas(&b)
+    ///     .on_coalesced("pointermove", |root, vdom, events| {
+    ///         for event in events {
+    ///             // Draw a line to each of the pointer's positions...
+    ///         }
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_coalesced<F>(self, event: &'a str, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, Vec<web_sys::Event>),
+    {
+        self.on(event, move |root, vdom, event| {
+            callback(root, vdom, coalesced_events(&event));
+        })
+    }
+
+    /// Make this element a drag-and-drop drop target.
+    ///
+    /// An element only accepts drops if its `dragenter` and `dragover` events
+    /// have their default action prevented, and most browsers will navigate to
+    /// dropped links and files unless the `drop` event's default action is
+    /// prevented too. This method adds a coordinated set of `dragenter`,
+    /// `dragover`, and `drop` listeners that always call `preventDefault`, and
+    /// invokes the `on_drop` callback when something is dropped onto this
+    /// element.
+    ///
+    /// Because it adds them itself, do not add your own `dragenter`,
+    /// `dragover`, or `drop` listeners to an element that is a drop target.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let dropzone = div(&b)
+    ///     .drop_target(|root, vdom, event| {
+    ///         // Use `event.dyn_into::<web_sys::DragEvent>()` to get at the
+    ///         // dropped data...
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn drop_target<F>(self, on_drop: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        self.on("dragenter", |_root, _vdom, event| event.prevent_default())
+            .on("dragover", |_root, _vdom, event| event.prevent_default())
+            .on("drop", move |root, vdom, event| {
+                event.prevent_default();
+                on_drop(root, vdom, event);
+            })
+    }
+
+    /// Add a `load` event listener to this element, such as an `<img>`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let my_img = img(&b)
+    ///     .attr("src", "cat.jpg")
+    ///     .on_load(|root, vdom, event| {
+    ///         // The image has loaded...
+    ///     })
+    ///     .on_error(|root, vdom, event| {
+    ///         // The image failed to load...
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_load<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        self.on("load", callback)
+    }
+
+    /// Add an `error` event listener to this element, such as an `<img>` that
+    /// fails to load. See `on_load` for an example.
+    #[inline]
+    pub fn on_error<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Event),
+    {
+        self.on("error", callback)
+    }
+
+    /// Invoke `callback` with the new open state of this `<details>` or
+    /// `<dialog>` element whenever it is opened or closed, whether by the user
+    /// or by rendering.
+    ///
+    /// This keeps a component's state in sync with the user expanding and
+    /// collapsing a `<details>` element. Combine it with `open` to make the
+    /// element controlled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let faq = details(&b)
+    ///     .on_toggle(|root, vdom, open| {
+    ///         // Remember whether the answer is showing...
+    ///     })
+    ///     .children([summary(&b).children([text("Why?")]).finish()])
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_toggle<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, bool),
+    {
+        self.on("toggle", move |root, vdom, event| {
+            let open = event
+                .current_target()
+                .and_then(|target| js_sys::Reflect::get(&target, &"open".into()).ok())
+                .and_then(|open| open.as_bool())
+                .unwrap_or(false);
+            callback(root, vdom, open);
+        })
+    }
+
+    /// Invoke `callback` with this input's value as a number whenever the user
+    /// changes it, such as on every step while dragging an
+    /// `<input type="range">`.
+    ///
+    /// This listens for `input` events, rather than `change` events, which are
+    /// only dispatched once the user lets go of the slider. The number is the
+    /// input's `valueAsNumber`, which is `NaN` for inputs that don't hold a
+    /// number. Combine it with `value_as_number` to make the input controlled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    /// # let volume = 0.5;
+    ///
+    /// let slider = input(&b)
+    ///     .attr("type", "range")
+    ///     .attr("min", "0")
+    ///     .attr("max", "1")
+    ///     .attr("step", "0.01")
+    ///     .value_as_number(volume)
+    ///     .on_input_value(|root, vdom, volume| {
+    ///         // Update the volume, and re-render...
+    ///         vdom.schedule_render();
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_input_value<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, f64),
+    {
+        self.on("input", move |root, vdom, event| {
+            let value = event
+                .current_target()
+                .and_then(|target| js_sys::Reflect::get(&target, &"valueAsNumber".into()).ok())
+                .and_then(|value| value.as_f64())
+                .unwrap_or(std::f64::NAN);
+            callback(root, vdom, value);
+        })
+    }
+
+    /// Invoke `callback` with this `<img>` element once its image has loaded
+    /// and been decoded.
+    ///
+    /// Once decoded, an image can be shown without delaying the next frame,
+    /// which makes this useful for fading in images in galleries. This is
+    /// implemented by calling `img.decode()` from a `load` listener, so do not
+    /// add another `load` listener to the same element; use this callback
+    /// instead. Images that fail to decode do not invoke `callback`.
+    ///
+    /// Like `VdomWeak::with_component`, the callback is invoked on a later
+    /// tick of the micro-task queue.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let my_img = img(&b)
+    ///     .attr("src", "cat.jpg")
+    ///     .on_decoded(|root, vdom, img| {
+    ///         // Mark the image as ready to be shown, and re-render...
+    ///         vdom.schedule_render();
+    ///     })
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn on_decoded<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::Element),
+    {
+        let callback = Rc::new(callback);
+        self.on("load", move |_root, vdom, event| {
+            let img = match event.current_target() {
+                Some(target) => target.unchecked_into::<web_sys::Element>(),
+                None => return,
+            };
+            let decoded = JsFuture::from(decode(&img));
+            let callback = callback.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if decoded.await.is_ok() {
+                    let weak = vdom.clone();
+                    let _ = vdom
+                        .with_component(move |root| callback(root, weak, img))
+                        .await;
+                }
+            });
+        })
+    }
+}
+
+// Get the events that were coalesced into `event` with
+// `event.getCoalescedEvents()`, or just `event` itself if there are none.
+#[cfg(feature = "dom")]
+fn coalesced_events(event: &web_sys::Event) -> Vec<web_sys::Event> {
+    let events: Vec<web_sys::Event> = js_sys::Reflect::get(event, &"getCoalescedEvents".into())
+        .ok()
+        .and_then(|get| get.dyn_into::<js_sys::Function>().ok())
+        .and_then(|get| get.call0(event).ok())
+        .and_then(|events| events.dyn_into::<js_sys::Array>().ok())
+        .map(|events| {
+            (0..events.length())
+                .map(|i| events.get(i).unchecked_into())
+                .collect()
+        })
+        .unwrap_or_default();
+    if events.is_empty() {
+        vec![event.clone()]
+    } else {
+        events
+    }
+}
+
+// Call `img.decode()`, which returns a promise that resolves once the image is
+// decoded. In browsers without `decode`, the promise resolves immediately.
+#[cfg(feature = "dom")]
+fn decode(img: &web_sys::Element) -> js_sys::Promise {
+    js_sys::Reflect::get(img, &"decode".into())
+        .ok()
+        .and_then(|decode| decode.dyn_into::<js_sys::Function>().ok())
+        .and_then(|decode| decode.call0(img).ok())
+        .and_then(|promise| promise.dyn_into::<js_sys::Promise>().ok())
+        .unwrap_or_else(|| js_sys::Promise::resolve(&JsValue::undefined()))
+}
+
+impl<'a, Listeners, Children>
+    ElementBuilder<'a, Listeners, bumpalo::collections::Vec<'a, Attribute<'a>>, Children>
+where
+    Listeners: 'a + AsRef<[Listener<'a>]>,
+    Children: 'a + AsRef<[Node<'a>]>,
+{
+    /// Add a new attribute to this element.
+    ///
+    /// String event handler attributes, such as `onclick`, are set like any
+    /// other attribute, so the browser compiles them into inline event
+    /// handlers, and diffing replaces or removes them as their value changes.
+    /// Prefer listeners added with `on`, which can call back into Rust and are
+    /// not blocked by content security policies; debug builds warn about
+    /// inline event handlers.
+    ///
+    /// SVG presentation attributes, such as `fill` and `stroke`, are set as
+    /// attributes too. They have the lowest precedence in the cascade, so any
+    /// stylesheet rule or `style` attribute that sets the same property
+    /// overrides them. Diffing only ever compares against the previous
+    /// render's attribute values and never reads the computed style, so it
+    /// does not fight such overrides: it just leaves the attribute in place
+    /// underneath them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Create the `<div id="my-div"/>` element.
+    /// let my_div = div(&b).attr("id", "my-div").finish();
+    /// ```
+    #[inline]
+    pub fn attr(mut self, name: &'a str, value: &'a str) -> Self {
+        self.attributes.push(Attribute {
+            name,
+            value: AttributeValue::Str(value),
+            volatile: false,
+            create_only: false,
+        });
+        self
+    }
+
+    /// Add a new attribute to this element, but only if `condition` is true.
+    ///
+    /// When `condition` is false, the element has no such attribute at all,
+    /// rather than an empty one.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    /// # let is_current = true;
+    ///
+    /// let link = a(&b)
+    ///     .attr("href", "/inbox")
+    ///     .attr_if(is_current, "aria-current", "page")
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn attr_if(self, condition: bool, name: &'a str, value: &'a str) -> Self {
+        if condition {
+            self.attr(name, value)
+        } else {
+            self
+        }
+    }
+
+    /// Add a new attribute to this element that is re-set on the physical DOM
+    /// node on every render, even when its value did not change.
+    ///
+    /// This is useful for attributes that something other than this virtual
+    /// DOM, such as a third-party script, might modify. Only this particular
+    /// attribute is affected: other attributes with the same name are still
+    /// only set when their value changes. The `value`, `checked`, `selected`,
+    /// `indeterminate`, and `muted` attributes are always volatile.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Keep re-asserting that this widget is expanded.
+    /// let widget = div(&b).attr_volatile("aria-expanded", "true").finish();
+    /// ```
+    #[inline]
+    pub fn attr_volatile(mut self, name: &'a str, value: &'a str) -> Self {
+        self.attributes.push(Attribute {
+            name,
+            value: AttributeValue::Str(value),
+            volatile: true,
+            create_only: false,
+        });
+        self
+    }
+
+    /// Add a new attribute with a non-string value to this element.
+    ///
+    /// See `AttributeValue` for how each type of value is applied to the
+    /// physical DOM node.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Create the `<input type="range" max="5"/>` element.
+    /// let slider = input(&b)
+    ///     .attr("type", "range")
+    ///     .typed_attr("max", 5)
+    ///     .typed_attr("disabled", false)
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn typed_attr<V>(mut self, name: &'a str, value: V) -> Self
+    where
+        V: Into<AttributeValue<'a>>,
+    {
+        self.attributes.push(Attribute {
+            name,
+            value: value.into(),
+            volatile: false,
+            create_only: false,
+        });
+        self
+    }
+
+    /// Conditionally add a "boolean-style" attribute to this element.
+    ///
+    /// If the `should_add` parameter is true, then adds an attribute with the
+    /// given `name` and an empty string value. If the `should_add` parameter is
+    /// false, then the attribute is not added.
+    ///
+    /// This method is useful for attributes whose semantics are defined by
+    /// whether or not the attribute is present or not, and whose value is
+    /// ignored. Example attributes like this include:
+    ///
+    /// * `checked`
+    /// * `hidden`
+    /// * `selected`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    /// use js_sys::Math;
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Create the `<div>` that is randomly hidden 50% of the time.
+    /// let my_div = div(&b)
+    ///     .bool_attr("hidden", Math::random() >= 0.5)
+    ///     .finish();
+    /// ```
+    pub fn bool_attr(mut self, name: &'a str, should_add: bool) -> Self {
+        if should_add {
+            self.attributes.push(Attribute {
+                name,
+                value: AttributeValue::Str(""),
+                volatile: false,
+                create_only: false,
+            });
+        }
+        self
+    }
+
+    /// Make this element a customized built-in element, such as
+    /// `<button is="fancy-button">`.
+    ///
+    /// The name is given to `document.createElement` when the element is
+    /// created, since a customized built-in element cannot be upgraded by
+    /// setting an `is` attribute later. Changing it in a later render creates
+    /// the element anew.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let my_button = button(&b)
+    ///     .is("fancy-button")
+    ///     .children([text("Click me")])
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn is(self, name: &'a str) -> Self {
+        self.attr("is", name)
+    }
+
+    /// Defer loading this `<img>` or `<iframe>` until it is close to the
+    /// viewport, by setting `loading="lazy"`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let thumbnail = img(&b)
+    ///     .attr("src", "thumbnail.jpg")
+    ///     .lazy_loading()
+    ///     .async_decoding()
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn lazy_loading(self) -> Self {
+        self.attr("loading", "lazy")
+    }
+
+    /// Let the browser decode this `<img>` off the main thread, by setting
+    /// `decoding="async"`. See `lazy_loading` for an example.
+    #[inline]
+    pub fn async_decoding(self) -> Self {
+        self.attr("decoding", "async")
+    }
+
+    /// Associate this form control with the `<form>` element whose `id` is
+    /// `form_id`, even if this control is not a descendant of that form.
+    ///
+    /// This sets the `form` attribute. Changing it in a later render moves
+    /// the control to the other form, so pressing enter in a text input, for
+    /// example, implicitly submits the form it is currently associated with.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // A search box in the page header that submits the search form below.
+    /// let search_box = input(&b)
+    ///     .attr("type", "search")
+    ///     .form_owner("search-form")
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn form_owner(self, form_id: &'a str) -> Self {
+        self.attr("form", form_id)
+    }
+
+    /// Make this button an invoker that toggles the
+    /// [popover](https://developer.mozilla.org/en-US/docs/Web/API/Popover_API)
+    /// element whose `id` is `popover_id`.
+    ///
+    /// This sets the `popovertarget` attribute. The browser looks the target
+    /// up by `id` whenever the button is pressed, so changing it in a later
+    /// render reassociates the button with the other popover, and removing it
+    /// makes the button an ordinary button again.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let menu = div(&b)
+    ///     .children([
+    ///         button(&b)
+    ///             .popover_target("menu")
+    ///             .children([text("Menu")])
+    ///             .finish(),
+    ///         div(&b).attr("id", "menu").attr("popover", "").finish(),
+    ///     ])
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn popover_target(self, popover_id: &'a str) -> Self {
+        self.attr("popovertarget", popover_id)
+    }
+
+    /// Make this button an invoker that sends `command` to the element whose
+    /// `id` is `target_id`, by setting the `commandfor` and `command`
+    /// attributes.
+    ///
+    /// Built-in commands include `"show-popover"`, `"hide-popover"`,
+    /// `"toggle-popover"`, `"show-modal"`, and `"close"`. Custom commands
+    /// start with `--`, and are only delivered to the target as `command`
+    /// events. As with `popover_target`, the target is looked up by `id` when
+    /// the button is pressed, so either attribute can change between renders.
+    #[inline]
+    pub fn command_for(self, target_id: &'a str, command: &'a str) -> Self {
+        self.attr("commandfor", target_id).attr("command", command)
+    }
+
+    /// Set the numeric value of this `<progress>`, `<meter>`, or range input.
+    ///
+    /// This is the same as `.typed_attr("value", value)`. Like any `value`,
+    /// it is re-applied on every render, and on `<progress>` and `<meter>`
+    /// elements it is assigned to the `value` property as a number. Omit it
+    /// on a `<progress>` element to make the progress bar indeterminate.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    /// # let fraction_done = 0.5;
+    ///
+    /// let upload = progress(&b)
+    ///     .typed_attr("max", 1)
+    ///     .value_as_number(fraction_done)
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn value_as_number(self, value: f64) -> Self {
+        self.typed_attr("value", value)
+    }
+
+    /// Seek this `<audio>` or `<video>` element to `seconds` from the start of
+    /// its media.
+    ///
+    /// Media elements have no attribute for their playback position, so this
+    /// sets a `currenttime` attribute that is assigned to the `currentTime`
+    /// property. Unlike `value`, it is only assigned when it changes from one
+    /// render to the next, so re-rendering with the same position does not
+    /// interrupt playback by seeking back to it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    /// # let chapter_start = 90.0;
+    ///
+    /// let player = video(&b)
+    ///     .attr("src", "talk.webm")
+    ///     .current_time(chapter_start)
+    ///     .volume(0.5)
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn current_time(self, seconds: f64) -> Self {
+        self.typed_attr("currenttime", seconds)
+    }
+
+    /// Set the volume of this `<audio>` or `<video>` element, from `0.0` for
+    /// silent to `1.0` for loudest.
+    ///
+    /// Like `current_time`, this sets a `volume` attribute that is assigned to
+    /// the property of the same name, only when it changes, and does not
+    /// interrupt playback. See `current_time` for an example.
+    #[inline]
+    pub fn volume(self, volume: f64) -> Self {
+        self.typed_attr("volume", volume)
+    }
+
+    /// Set the
+    /// [`spellcheck`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/spellcheck)
+    /// attribute to `"true"` or `"false"`.
+    ///
+    /// Unlike boolean attributes such as `hidden`, `spellcheck` is enumerated:
+    /// omitting it inherits spell checking from the parent element, so turning
+    /// it off takes an explicit `"false"`. The element's `spellcheck` property
+    /// reflects the attribute.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let username = input(&b).name("username").spellcheck(false).finish();
+    /// ```
+    #[inline]
+    pub fn spellcheck(self, spellcheck: bool) -> Self {
+        self.attr("spellcheck", if spellcheck { "true" } else { "false" })
+    }
+
+    /// Set the
+    /// [`translate`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/translate)
+    /// attribute to `"yes"` or `"no"`.
+    ///
+    /// Like `spellcheck`, `translate` is enumerated and inherited, so turning
+    /// it off takes an explicit `"no"`. The element's `translate` property
+    /// reflects the attribute.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// let brand = span(&b).translate(false).children([text("Dodrio")]).finish();
+    /// ```
+    #[inline]
+    pub fn translate(self, translate: bool) -> Self {
+        self.attr("translate", if translate { "yes" } else { "no" })
+    }
+
+    /// Set the initial value of this form element, without controlling it.
+    ///
+    /// Unlike `.attr("value", ...)`, which re-applies the value on every
+    /// render, the default value is only applied when the element is created
+    /// and is ignored by subsequent diffs. This way, an uncontrolled input
+    /// keeps whatever the user typed into it across re-renders. This is the
+    /// same as React's `defaultValue`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Create an `<input>` that starts out with "Hello" in it.
+    /// let my_input = input(&b).default_value("Hello").finish();
+    /// ```
+    #[inline]
+    pub fn default_value(mut self, value: &'a str) -> Self {
+        self.attributes.push(Attribute {
+            name: "value",
+            value: AttributeValue::Str(value),
+            volatile: false,
+            create_only: true,
+        });
+        self
+    }
+
+    /// Set whether this checkbox or radio button is initially checked, without
+    /// controlling it.
+    ///
+    /// Like `default_value`, this is only applied when the element is created
+    /// and is ignored by subsequent diffs. This is the same as React's
+    /// `defaultChecked`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Create a checkbox that starts out checked.
+    /// let my_checkbox = input(&b)
+    ///     .attr("type", "checkbox")
+    ///     .default_checked(true)
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn default_checked(mut self, checked: bool) -> Self {
+        self.attributes.push(Attribute {
+            name: "checked",
+            value: AttributeValue::Bool(checked),
+            volatile: false,
+            create_only: true,
+        });
+        self
+    }
+
+    /// Control whether this `<details>` or `<dialog>` element is open.
+    ///
+    /// The user can open and close a `<details>` element without going
+    /// through the virtual DOM, so unlike other attributes, the `open`
+    /// attribute set by this method is re-applied to the physical DOM on every
+    /// render, even when its value did not change. Use `on_toggle` to update
+    /// the component's state when the user opens or closes the element, or
+    /// else the next render will undo the user's change. To leave the element
+    /// uncontrolled instead, do not call this method.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    /// # let is_open = true;
+    ///
+    /// let faq = details(&b)
+    ///     .open(is_open)
+    ///     .on_toggle(|root, vdom, open| {
+    ///         // Set `is_open` to `open` and re-render...
+    ///     })
+    ///     .children([summary(&b).children([text("Why?")]).finish()])
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn open(mut self, open: bool) -> Self {
+        self.attributes.push(Attribute {
+            name: "open",
+            value: AttributeValue::Bool(open),
+            volatile: true,
+            create_only: false,
+        });
+        self
+    }
+
+    /// Give this element a CSS leave transition.
+    ///
+    /// When this element is removed, rather than deleting it from the physical
+    /// DOM immediately, the given `leave_class` is added to it, and it is only
+    /// deleted once its CSS transition ends. If adding the class doesn't start
+    /// a transition, then the element is deleted immediately.
+    ///
+    /// A leaving element is no longer part of the virtual DOM: it does not
+    /// receive events and is ignored by subsequent diffs.
+    ///
+    /// This is implemented with a `data-dodrio-leave` attribute.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Fades out when removed, given CSS like:
+    /// //
+    /// //     .toast { transition: opacity 0.5s; }
+    /// //     .toast.x-leave { opacity: 0; }
+    /// let toast = div(&b)
+    ///     .attr("class", "toast")
+    ///     .leave_transition("x-leave")
+    ///     .children([text("Saved!")])
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn leave_transition(self, leave_class: &'a str) -> Self {
+        self.attr("data-dodrio-leave", leave_class)
+    }
+
+    /// Scroll this element into view once the render that creates it, or
+    /// first gives it this directive, is applied to the physical DOM.
+    ///
+    /// The element is only scrolled into view when the directive is newly
+    /// applied, or when its options change, not on every render. This way,
+    /// the user is free to scroll away afterwards.
+    ///
+    /// Elements within cached subtrees are not scrolled into view.
+    ///
+    /// This is implemented with a `data-dodrio-scroll-into-view` attribute.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Smoothly scroll to the newest chat message when it arrives.
+    /// let message = li(&b)
+    ///     .scroll_into_view(ScrollAlignment::End, true)
+    ///     .children([text("Hi!")])
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn scroll_into_view(self, block: ScrollAlignment, smooth: bool) -> Self {
+        let block = match block {
+            ScrollAlignment::Start => "start",
+            ScrollAlignment::Center => "center",
+            ScrollAlignment::End => "end",
+            ScrollAlignment::Nearest => "nearest",
+        };
+        let behavior = if smooth { "smooth" } else { "auto" };
+        let options = bumpalo::format!(in self.bump, "{} {}", block, behavior);
+        self.attr("data-dodrio-scroll-into-view", options.into_bump_str())
+    }
+
+    /// Select the text from `start` to `end` in this `<input>` or
+    /// `<textarea>`, once each render is applied to the physical DOM.
+    ///
+    /// Since the `value` attribute is re-set on every render, a controlled
+    /// input would otherwise lose its caret position whenever its value is
+    /// updated. Render the selection along with the value to restore it. When
+    /// `start` and `end` are equal, this places the caret at that position.
+    ///
+    /// Positions are counted in UTF-16 code units, like the DOM's
+    /// `selectionStart` and `selectionEnd`. Elements without a selection, such
+    /// as checkboxes, ignore this directive.
+    ///
+    /// This is implemented with a volatile `data-dodrio-selection-range`
+    /// attribute.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // Keep the caret after the third character of a masked phone number.
+    /// let phone = input(&b)
+    ///     .attr("value", "555-01")
+    ///     .selection_range(3, 3)
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn selection_range(self, start: u32, end: u32) -> Self {
+        let range = bumpalo::format!(in self.bump, "{} {}", start, end);
+        self.attr_volatile("data-dodrio-selection-range", range.into_bump_str())
+    }
+
+    /// Hand this element's children over to code outside of dodrio, such as a
+    /// charting or mapping library.
+    ///
+    /// The children are created along with the element, and after that they
+    /// are never diffed again: the children in later renders are ignored, and
+    /// whatever the outside code does to the physical children is left alone.
+    /// The element's own attributes and listeners are still diffed as usual.
+    ///
+    /// Listeners within the children stop being called after the next render,
+    /// so have the outside code add its own. Adding or removing this directive
+    /// in a later render recreates the element and its children.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    ///
+    /// // A container for a map widget, which fills it with its own elements.
+    /// let map = div(&b)
+    ///     .attr("class", "map")
+    ///     .managed_children()
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn managed_children(mut self) -> Self {
+        self.managed_children = true;
         self
     }
 }
 
+macro_rules! attribute_methods {
+    ( $(
+        $(#[$attr:meta])*
+        $name:ident = $attribute:expr;
+    )* ) => {
+        $(
+            $(#[$attr])*
+            #[inline]
+            pub fn $name(self, value: &'a str) -> Self {
+                self.attr($attribute, value)
+            }
+        )*
+    };
+    ( $(
+        $(#[$attr:meta])*
+        $name:ident ? $attribute:expr;
+    )* ) => {
+        $(
+            $(#[$attr])*
+            #[inline]
+            pub fn $name(self, should_add: bool) -> Self {
+                self.bool_attr($attribute, should_add)
+            }
+        )*
+    };
+}
+
+/// Typed shorthands for common attributes.
+///
+/// Each of these is exactly equivalent to calling `attr` or `bool_attr` with
+/// the attribute's name, but catches typos in that name at compile time.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo::Bump};
+///
+/// let b = Bump::new();
+///
+/// // The same as `.attr("type", "email").attr("name", "email")` and so on.
+/// let email = input(&b)
+///     .type_("email")
+///     .name("email")
+///     .placeholder("you@example.com")
+///     .required(true)
+///     .finish();
+/// ```
 impl<'a, Listeners, Children>
     ElementBuilder<'a, Listeners, bumpalo::collections::Vec<'a, Attribute<'a>>, Children>
 where
     Listeners: 'a + AsRef<[Listener<'a>]>,
     Children: 'a + AsRef<[Node<'a>]>,
 {
-    /// Add a new attribute to this element.
+    // Organized in the same order as
+    // https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes
+    attribute_methods! {
+        /// Set the
+        /// [`accept`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/accept)
+        /// attribute.
+        accept = "accept";
+        /// Set the
+        /// [`action`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/form#attr-action)
+        /// attribute.
+        action = "action";
+        /// Set the
+        /// [`alt`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-alt)
+        /// attribute.
+        alt = "alt";
+        /// Set the
+        /// [`autocomplete`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/autocomplete)
+        /// attribute.
+        autocomplete = "autocomplete";
+        /// Set the
+        /// [`autocapitalize`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/autocapitalize)
+        /// attribute: `"off"`, `"none"`, `"on"`, `"sentences"`, `"words"`, or
+        /// `"characters"`.
+        autocapitalize = "autocapitalize";
+        /// Set the
+        /// [`class`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/class)
+        /// attribute.
+        class = "class";
+        /// Set the
+        /// [`content`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/meta#attr-content)
+        /// attribute.
+        content = "content";
+        /// Set the
+        /// [`fetchpriority`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-fetchpriority)
+        /// attribute: `"high"`, `"low"`, or `"auto"`.
+        fetchpriority = "fetchpriority";
+        /// Set the
+        /// [`for`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/for)
+        /// attribute.
+        for_ = "for";
+        /// Set the
+        /// [`height`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-height)
+        /// attribute.
+        height = "height";
+        /// Set the
+        /// [`href`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/a#attr-href)
+        /// attribute.
+        href = "href";
+        /// Set the
+        /// [`id`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/id)
+        /// attribute.
+        id = "id";
+        /// Set the
+        /// [`lang`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/lang)
+        /// attribute.
+        lang = "lang";
+        /// Set the
+        /// [`loading`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-loading)
+        /// attribute: `"eager"` or `"lazy"`.
+        loading = "loading";
+        /// Set the
+        /// [`max`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/max)
+        /// attribute.
+        max = "max";
+        /// Set the
+        /// [`maxlength`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/maxlength)
+        /// attribute.
+        maxlength = "maxlength";
+        /// Set the
+        /// [`media`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/source#attr-media)
+        /// attribute.
+        media = "media";
+        /// Set the
+        /// [`method`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/form#attr-method)
+        /// attribute.
+        method = "method";
+        /// Set the
+        /// [`min`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/min)
+        /// attribute.
+        min = "min";
+        /// Set the
+        /// [`name`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input#attr-name)
+        /// attribute.
+        name = "name";
+        /// Set the
+        /// [`pattern`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/pattern)
+        /// attribute.
+        pattern = "pattern";
+        /// Set the
+        /// [`placeholder`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input#attr-placeholder)
+        /// attribute.
+        placeholder = "placeholder";
+        /// Set the
+        /// [`rel`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/rel)
+        /// attribute.
+        rel = "rel";
+        /// Set the
+        /// [`role`](https://developer.mozilla.org/en-US/docs/Web/Accessibility/ARIA/Roles)
+        /// attribute.
+        role = "role";
+        /// Set the
+        /// [`sizes`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-sizes)
+        /// attribute.
+        sizes = "sizes";
+        /// Set the
+        /// [`src`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-src)
+        /// attribute.
+        src = "src";
+        /// Set the
+        /// [`srcset`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-srcset)
+        /// attribute.
+        srcset = "srcset";
+        /// Set the
+        /// [`step`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/step)
+        /// attribute.
+        step = "step";
+        /// Set the
+        /// [`tabindex`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/tabindex)
+        /// attribute.
+        tabindex = "tabindex";
+        /// Set the
+        /// [`target`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/a#attr-target)
+        /// attribute.
+        target = "target";
+        /// Set the
+        /// [`title`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/title)
+        /// attribute.
+        title = "title";
+        /// Set the
+        /// [`type`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input#attr-type)
+        /// attribute.
+        type_ = "type";
+        /// Set the
+        /// [`value`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input#attr-value)
+        /// attribute.
+        value = "value";
+        /// Set the
+        /// [`width`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#attr-width)
+        /// attribute.
+        width = "width";
+    }
+
+    attribute_methods! {
+        /// Set or omit the
+        /// [`autofocus`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/autofocus)
+        /// attribute.
+        autofocus ? "autofocus";
+        /// Set or omit the
+        /// [`checked`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input#attr-checked)
+        /// attribute.
+        checked ? "checked";
+        /// Set or omit the
+        /// [`disabled`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/disabled)
+        /// attribute.
+        ///
+        /// A disabled `<fieldset>` disables all of the form controls within
+        /// it, other than those in its first `<legend>`. That is up to the
+        /// browser, so toggling a fieldset only ever changes the fieldset's
+        /// own attribute, not its descendants'.
+        disabled ? "disabled";
+        /// Set or omit the
+        /// [`hidden`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/hidden)
+        /// attribute.
+        hidden ? "hidden";
+        /// Set or omit the
+        /// [`indeterminate`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input/checkbox#indeterminate_state_checkboxes)
+        /// state of a checkbox.
+        indeterminate ? "indeterminate";
+        /// Set or omit the
+        /// [`inert`](https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes/inert)
+        /// attribute.
+        inert ? "inert";
+        /// Set or omit the
+        /// [`multiple`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/multiple)
+        /// attribute.
+        multiple ? "multiple";
+        /// Set or omit the
+        /// [`muted`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/video#attr-muted)
+        /// attribute.
+        ///
+        /// The attribute only sets a media element's initial state, so the
+        /// `muted` property is set along with it, on every render. That mutes
+        /// and unmutes the element without interrupting its playback.
+        muted ? "muted";
+        /// Set or omit the
+        /// [`readonly`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/readonly)
+        /// attribute.
+        readonly ? "readonly";
+        /// Set or omit the
+        /// [`required`](https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes/required)
+        /// attribute.
+        required ? "required";
+        /// Set or omit the
+        /// [`selected`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/option#attr-selected)
+        /// attribute.
+        selected ? "selected";
+    }
+}
+
+#[cfg(feature = "dom")]
+impl<'a, Children>
+    ElementBuilder<
+        'a,
+        bumpalo::collections::Vec<'a, Listener<'a>>,
+        bumpalo::collections::Vec<'a, Attribute<'a>>,
+        Children,
+    >
+where
+    Children: 'a + AsRef<[Node<'a>]>,
+{
+    /// Invoke `callback` with this element's new content box width and
+    /// height, in pixels, whenever its size changes.
+    ///
+    /// The element is watched with a `ResizeObserver` from the moment it is
+    /// created until it is removed, at which point the observer is
+    /// disconnected. The callback is also invoked once with the element's
+    /// initial size, after it is first laid out. In browsers without
+    /// `ResizeObserver`, the callback is never invoked.
+    ///
+    /// Elements within cached subtrees are not observed.
+    ///
+    /// This is implemented with a `data-dodrio-resize` attribute and a
+    /// `dodrio-resize` listener, so do not add your own `dodrio-resize`
+    /// listener to an element that has this directive.
     ///
     /// # Example
     ///
@@ -357,47 +1795,78 @@ where
     ///
     /// let b = Bump::new();
     ///
-    /// // Create the `<div id="my-div"/>` element.
-    /// let my_div = div(&b).attr("id", "my-div").finish();
+    /// let chart = div(&b)
+    ///     .on_resize(|root, vdom, width, height| {
+    ///         // Re-layout the chart to fit its new size...
+    ///     })
+    ///     .finish();
     /// ```
     #[inline]
-    pub fn attr(mut self, name: &'a str, value: &'a str) -> Self {
-        self.attributes.push(Attribute { name, value });
-        self
+    pub fn on_resize<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, f64, f64),
+    {
+        self.on("dodrio-resize", move |root, vdom, event| {
+            let detail = js_sys::Reflect::get(&event, &"detail".into())
+                .unwrap_or_else(|_| JsValue::undefined());
+            let dimension = |name: &str| {
+                js_sys::Reflect::get(&detail, &name.into())
+                    .ok()
+                    .and_then(|value| value.as_f64())
+                    .unwrap_or(0.0)
+            };
+            callback(root, vdom, dimension("width"), dimension("height"));
+        })
+        .attr("data-dodrio-resize", "")
     }
 
-    /// Conditionally add a "boolean-style" attribute to this element.
+    /// Acquire this `<canvas>` element's 2D rendering context once the element
+    /// is created, and hand it to `callback`.
     ///
-    /// If the `should_add` parameter is true, then adds an attribute with the
-    /// given `name` and an empty string value. If the `should_add` parameter is
-    /// false, then the attribute is not added.
+    /// The callback is invoked once per physical `<canvas>`, on a later tick of
+    /// the micro-task queue after the change list that created it is applied,
+    /// so it may draw directly or schedule a render. When the element is
+    /// removed, the callback is removed along with its other listeners. If the
+    /// element is not a `<canvas>`, or its context has already been acquired
+    /// with a different type, the callback is never invoked.
     ///
-    /// This method is useful for attributes whose semantics are defined by
-    /// whether or not the attribute is present or not, and whose value is
-    /// ignored. Example attributes like this include:
+    /// Elements within cached subtrees are not handed to the callback.
     ///
-    /// * `checked`
-    /// * `hidden`
-    /// * `selected`
+    /// This is implemented with a `data-dodrio-canvas-2d` attribute and a
+    /// `dodrio-canvas-2d` listener, so do not add your own `dodrio-canvas-2d`
+    /// listener to an element that has this directive.
     ///
     /// # Example
     ///
     /// ```no_run
     /// use dodrio::{builder::*, bumpalo::Bump};
-    /// use js_sys::Math;
     ///
     /// let b = Bump::new();
     ///
-    /// // Create the `<div>` that is randomly hidden 50% of the time.
-    /// let my_div = div(&b)
-    ///     .bool_attr("hidden", Math::random() >= 0.5)
+    /// let sketch = canvas(&b)
+    ///     .attr("width", "300")
+    ///     .attr("height", "150")
+    ///     .canvas_2d(|root, vdom, ctx| {
+    ///         ctx.fill_rect(10.0, 10.0, 100.0, 50.0);
+    ///     })
     ///     .finish();
     /// ```
-    pub fn bool_attr(mut self, name: &'a str, should_add: bool) -> Self {
-        if should_add {
-            self.attributes.push(Attribute { name, value: "" });
-        }
-        self
+    #[inline]
+    pub fn canvas_2d<F>(self, callback: F) -> Self
+    where
+        F: 'static + Fn(&mut dyn RootRender, VdomWeak, web_sys::CanvasRenderingContext2d),
+    {
+        self.on("dodrio-canvas-2d", move |root, vdom, event| {
+            let context = match js_sys::Reflect::get(&event, &"detail".into())
+                .ok()
+                .and_then(|detail| detail.dyn_into().ok())
+            {
+                Some(context) => context,
+                None => return,
+            };
+            callback(root, vdom, context);
+        })
+        .attr("data-dodrio-canvas-2d", "")
     }
 }
 
@@ -427,6 +1896,36 @@ where
         self.children.push(child);
         self
     }
+
+    /// Add a new child to this element, but only if `condition` is true.
+    ///
+    /// The child is built by calling `child`, which is only called when
+    /// `condition` is true.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo::Bump};
+    ///
+    /// let b = Bump::new();
+    /// # let has_error = false;
+    ///
+    /// let field = div(&b)
+    ///     .child(input(&b).finish())
+    ///     .child_if(has_error, || p(&b).children([text("Required")]).finish())
+    ///     .finish();
+    /// ```
+    #[inline]
+    pub fn child_if<F>(self, condition: bool, child: F) -> Self
+    where
+        F: FnOnce() -> Node<'a>,
+    {
+        if condition {
+            self.child(child())
+        } else {
+            self
+        }
+    }
 }
 
 macro_rules! builder_constructors {
@@ -1016,6 +2515,57 @@ builder_constructors! {
     image <> "http://www.w3.org/2000/svg";
 }
 
+/// Build a placeholder element whose children are rendered into
+/// `document.head`, rather than into the placeholder itself.
+///
+/// This lets components declaratively manage the page's `<title>`, `<meta>`
+/// tags, and so on, from anywhere in the tree. The children are diffed just
+/// like any other children, and are removed from `document.head` when the
+/// placeholder is removed. Other nodes in `document.head` are left alone.
+///
+/// The children should be elements that belong in `document.head`, and the
+/// placeholder should not be inside a cached subtree.
+///
+/// This is implemented with a `<dodrio-head>` element with a
+/// `data-dodrio-head` attribute.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo::Bump};
+///
+/// let b = Bump::new();
+///
+/// let page = div(&b)
+///     .children([
+///         document_head(&b)
+///             .children([
+///                 title(&b).children([text("Settings")]).finish(),
+///                 meta(&b)
+///                     .name("description")
+///                     .content("Change your settings.")
+///                     .finish(),
+///             ])
+///             .finish(),
+///         h1(&b).children([text("Settings")]).finish(),
+///     ])
+///     .finish();
+/// ```
+#[inline]
+pub fn document_head<'a, B>(
+    bump: B,
+) -> ElementBuilder<
+    'a,
+    bumpalo::collections::Vec<'a, Listener<'a>>,
+    bumpalo::collections::Vec<'a, Attribute<'a>>,
+    bumpalo::collections::Vec<'a, Node<'a>>,
+>
+where
+    B: Into<&'a Bump>,
+{
+    ElementBuilder::new(bump, "dodrio-head").attr("data-dodrio-head", "")
+}
+
 /// Construct a text node.
 ///
 /// This is `dodrio`'s virtual DOM equivalent of `document.createTextNode`.
@@ -1032,6 +2582,24 @@ pub fn text<'a>(contents: &'a str) -> Node<'a> {
     Node::text(contents)
 }
 
+/// Construct a text node with `contents`' leading and trailing whitespace
+/// trimmed off.
+///
+/// The text node borrows a slice of `contents`, so nothing is allocated.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::builder::*;
+///
+/// // The same as `text("hello, dodrio!")`.
+/// let my_text = text_trimmed("\n    hello, dodrio!\n");
+/// ```
+#[inline]
+pub fn text_trimmed<'a>(contents: &'a str) -> Node<'a> {
+    Node::text(contents.trim())
+}
+
 /// Construct an attribute for an element.
 ///
 /// # Example
@@ -1045,7 +2613,12 @@ pub fn text<'a>(contents: &'a str) -> Node<'a> {
 /// let my_id_attr = attr("id", "my-id");
 /// ```
 pub fn attr<'a>(name: &'a str, value: &'a str) -> Attribute<'a> {
-    Attribute { name, value }
+    Attribute {
+        name,
+        value: AttributeValue::Str(value),
+        volatile: false,
+        create_only: false,
+    }
 }
 
 /// Create an event listener.
@@ -1064,12 +2637,560 @@ pub fn attr<'a>(name: &'a str, value: &'a str) -> Attribute<'a> {
 ///     // do something when a click happens...
 /// });
 /// ```
+#[cfg(feature = "dom")]
 pub fn on<'a, F>(bump: &'a Bump, event: &'a str, callback: F) -> Listener<'a>
 where
     F: Fn(&mut dyn RootRender, VdomWeak, web_sys::Event) + 'static,
 {
-    Listener {
-        event,
-        callback: bump.alloc(callback),
+    Listener::new(event, bump.alloc(callback))
+}
+
+/// Project content into the `<slot>`s of a template node.
+///
+/// This lets a reusable component render its layout once, with `<slot>`
+/// placeholders, and have its caller decide what goes into them. Every
+/// `<slot name="...">` element within `template` is replaced by the content
+/// given for that name in `slots`. A `<slot>` without a `name` attribute is
+/// named `""`. If no content is given for a slot, then it is replaced by its
+/// own children, which serve as fallback content.
+///
+/// This is a purely virtual DOM projection that happens while rendering, and
+/// no `<slot>` elements end up in the physical DOM. It is unrelated to shadow
+/// DOM slots. Projected content is not itself searched for slots, and the root
+/// of `template` is never treated as a slot.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo::Bump};
+///
+/// let b = Bump::new();
+///
+/// // A card layout with a named header slot and a default slot.
+/// let card = div(&b)
+///     .attr("class", "card")
+///     .children([
+///         header(&b)
+///             .children([slot(&b)
+///                 .attr("name", "header")
+///                 .children([text("Untitled")])
+///                 .finish()])
+///             .finish(),
+///         slot(&b).finish(),
+///     ])
+///     .finish();
+///
+/// // Renders as:
+/// //
+/// //     <div class="card">
+/// //       <header><strong>Hello!</strong></header>
+/// //       Lorem ipsum...
+/// //     </div>
+/// let card = project(
+///     &b,
+///     card,
+///     &[
+///         ("header", strong(&b).children([text("Hello!")]).finish()),
+///         ("", text("Lorem ipsum...")),
+///     ],
+/// );
+/// ```
+pub fn project<'a, B>(bump: B, template: Node<'a>, slots: &[(&str, Node<'a>)]) -> Node<'a>
+where
+    B: Into<&'a Bump>,
+{
+    let bump = bump.into();
+    match template.kind {
+        NodeKind::Element(el) => match project_children(bump, el.children, slots) {
+            Some(children) => with_children(bump, el, children),
+            None => template,
+        },
+        NodeKind::Text(_) | NodeKind::Cached(_) => template,
+        #[cfg(feature = "dom")]
+        NodeKind::Raw(_) => template,
+    }
+}
+
+// If `node` is a `<slot>`, get its name and fallback children.
+fn as_slot<'a>(node: &Node<'a>) -> Option<(&'a str, &'a [Node<'a>])> {
+    match node.kind {
+        NodeKind::Element(&ElementNode {
+            tag_name: "slot",
+            namespace: None,
+            attributes,
+            children,
+            ..
+        }) => {
+            let name = attributes
+                .iter()
+                .find(|attr| attr.name == "name")
+                .and_then(|attr| attr.value.as_str())
+                .unwrap_or("");
+            Some((name, children))
+        }
+        _ => None,
+    }
+}
+
+// Copy the given element, but with new children.
+fn with_children<'a>(bump: &'a Bump, el: &ElementNode<'a>, children: &'a [Node<'a>]) -> Node<'a> {
+    Node::from_element_node(bump, ElementNode { children, ..*el })
+}
+
+// Project slot content into the given children, or return `None` if there are
+// no slots amongst them or their descendants.
+fn project_children<'a>(
+    bump: &'a Bump,
+    children: &'a [Node<'a>],
+    slots: &[(&str, Node<'a>)],
+) -> Option<&'a [Node<'a>]> {
+    let mut projected = bumpalo::collections::Vec::with_capacity_in(children.len(), bump);
+    let mut changed = false;
+
+    for child in children {
+        if let Some((name, fallback)) = as_slot(child) {
+            changed = true;
+            match slots.iter().find(|(slot, _)| *slot == name) {
+                Some((_, content)) => projected.push(content.clone()),
+                None => {
+                    let fallback = project_children(bump, fallback, slots).unwrap_or(fallback);
+                    projected.extend(fallback.iter().cloned());
+                }
+            }
+        } else if let NodeKind::Element(el) = child.kind {
+            match project_children(bump, el.children, slots) {
+                Some(grandchildren) => {
+                    changed = true;
+                    projected.push(with_children(bump, el, grandchildren));
+                }
+                None => projected.push(child.clone()),
+            }
+        } else {
+            projected.push(child.clone());
+        }
+    }
+
+    if changed {
+        Some(projected.into_bump_slice())
+    } else {
+        None
+    }
+}
+
+/// Build a composite widget, such as a toolbar, tab list, or listbox, with a
+/// "roving tabindex".
+///
+/// Only the `active` item is reachable with the tab key: it gets
+/// `tabindex="0"`, and every other item gets `tabindex="-1"`. Pressing the
+/// arrow keys, `Home`, or `End` while focus is within `parent` moves focus to
+/// the previous, next, first, or last item, and invokes `on_move` with that
+/// item's index. `on_move` should record the new active index and re-render,
+/// so that the tabindex attributes follow the focus.
+///
+/// Focus moves relative to whichever item the key was pressed in, and the
+/// items' indices do not count any children that `parent` already has. Keys
+/// pressed in text fields, `<select>`s, and other editable elements within
+/// the items are left alone.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo::Bump};
+///
+/// let b = Bump::new();
+/// let active = 0;
+///
+/// let toolbar = roving_tabindex(
+///     div(&b).attr("role", "toolbar"),
+///     vec![
+///         button(&b).children([text("Bold")]),
+///         button(&b).children([text("Italic")]),
+///         button(&b).children([text("Underline")]),
+///     ],
+///     active,
+///     |root, vdom, index| {
+///         // Save `index` as the new active item...
+///         vdom.schedule_render();
+///     },
+/// );
+/// ```
+#[cfg(feature = "dom")]
+pub fn roving_tabindex<'a, I, F>(
+    parent: ElementBuilder<
+        'a,
+        bumpalo::collections::Vec<'a, Listener<'a>>,
+        bumpalo::collections::Vec<'a, Attribute<'a>>,
+        bumpalo::collections::Vec<'a, Node<'a>>,
+    >,
+    items: I,
+    active: usize,
+    on_move: F,
+) -> Node<'a>
+where
+    I: IntoIterator<
+        Item = ElementBuilder<
+            'a,
+            bumpalo::collections::Vec<'a, Listener<'a>>,
+            bumpalo::collections::Vec<'a, Attribute<'a>>,
+            bumpalo::collections::Vec<'a, Node<'a>>,
+        >,
+    >,
+    F: 'static + Fn(&mut dyn RootRender, VdomWeak, usize),
+{
+    // The items come after any children that `parent` already has.
+    let offset = parent.children.len();
+    let mut parent = parent;
+    let mut len = 0;
+    for (i, item) in items.into_iter().enumerate() {
+        let tabindex = if i == active { "0" } else { "-1" };
+        parent = parent.child(item.attr("tabindex", tabindex).finish());
+        len += 1;
+    }
+
+    parent
+        .on("keydown", move |root, vdom, event| {
+            if len == 0 {
+                return;
+            }
+            let parent = match event.current_target() {
+                Some(parent) => parent.unchecked_into::<web_sys::Node>(),
+                None => return,
+            };
+            let target = match event.target() {
+                Some(target) => target.unchecked_into::<web_sys::Node>(),
+                None => return,
+            };
+
+            // Leave the arrow keys to text fields and such within the items.
+            if let Some(target) = target.dyn_ref::<web_sys::Element>() {
+                if target.matches(EDITABLE).unwrap_or(false) {
+                    return;
+                }
+            }
+
+            // Find the item that the event came from, or fall back to the
+            // active item if it came from `parent` itself.
+            let current = if target == parent {
+                active
+            } else {
+                let mut item = target;
+                while item.parent_node().as_ref() != Some(&parent) {
+                    item = match item.parent_node() {
+                        Some(ancestor) => ancestor,
+                        None => return,
+                    };
+                }
+                match child_index(&parent, &item) {
+                    Some(i) if i >= offset && i - offset < len => i - offset,
+                    _ => return,
+                }
+            };
+
+            let key = js_sys::Reflect::get(&event, &"key".into())
+                .ok()
+                .and_then(|key| key.as_string());
+            let next = match key.as_ref().map(|key| key.as_str()) {
+                Some("ArrowRight") | Some("ArrowDown") => (current + 1) % len,
+                Some("ArrowLeft") | Some("ArrowUp") => (current + len - 1) % len,
+                Some("Home") => 0,
+                Some("End") => len - 1,
+                _ => return,
+            };
+            event.prevent_default();
+
+            // Move focus right away, rather than waiting for the re-render.
+            if let Some(item) = nth_child(&parent, offset + next) {
+                if let Some(item) = item.dyn_ref::<web_sys::Element>() {
+                    focus(item);
+                }
+            }
+
+            on_move(root, vdom, next);
+        })
+        .finish()
+}
+
+// Elements within which the arrow keys, `Home`, and `End` edit or pick a value.
+#[cfg(feature = "dom")]
+const EDITABLE: &str = "textarea, select, [contenteditable]:not([contenteditable=\"false\"]), \
+                        input:not([type=\"button\"]):not([type=\"checkbox\"]):not([type=\"image\"])\
+                        :not([type=\"radio\"]):not([type=\"reset\"]):not([type=\"submit\"])";
+
+// Is the given physical child in the middle of its leave transition, and so no
+// longer in the virtual DOM?
+#[cfg(feature = "dom")]
+fn is_leaving(child: &web_sys::Node) -> bool {
+    child
+        .dyn_ref::<web_sys::Element>()
+        .map_or(false, |child| child.has_attribute("data-dodrio-leaving"))
+}
+
+// Get the index of `child` amongst `parent`'s children in the virtual DOM.
+#[cfg(feature = "dom")]
+fn child_index(parent: &web_sys::Node, child: &web_sys::Node) -> Option<usize> {
+    let mut index = 0;
+    let mut sibling = parent.first_child();
+    while let Some(node) = sibling {
+        if &node == child {
+            return Some(index);
+        }
+        if !is_leaving(&node) {
+            index += 1;
+        }
+        sibling = node.next_sibling();
+    }
+    None
+}
+
+// Get `parent`'s `n`th child in the virtual DOM.
+#[cfg(feature = "dom")]
+fn nth_child(parent: &web_sys::Node, mut n: usize) -> Option<web_sys::Node> {
+    let mut sibling = parent.first_child();
+    while let Some(node) = sibling {
+        if !is_leaving(&node) {
+            if n == 0 {
+                return Some(node);
+            }
+            n -= 1;
+        }
+        sibling = node.next_sibling();
+    }
+    None
+}
+
+// The elements that can be reached with the tab key, unless they have a
+// negative `tabindex` or are inert.
+#[cfg(feature = "dom")]
+const TABBABLE: &str = "a[href], area[href], button:not([disabled]), \
+                        input:not([disabled]):not([type=\"hidden\"]), select:not([disabled]), \
+                        textarea:not([disabled]), iframe, audio[controls], video[controls], \
+                        [contenteditable]:not([contenteditable=\"false\"]), [tabindex]";
+
+/// Trap focus within `container`, such as a modal dialog.
+///
+/// Pressing `Tab` on the container's last tabbable descendant moves focus to
+/// its first, and pressing `Shift+Tab` on the first moves focus to the last,
+/// instead of leaving the container. Tabbing between the other descendants is
+/// left to the browser. Descendants with a negative `tabindex`, or that are
+/// within an `inert` element, are skipped.
+///
+/// To keep the rest of the page from being clicked or focused in the meantime,
+/// make it `inert`.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo::Bump};
+///
+/// let b = Bump::new();
+///
+/// let page = div(&b)
+///     .children([
+///         main(&b).inert(true).children([text("...")]).finish(),
+///         focus_trap(div(&b).attr("role", "dialog").children([
+///             input(&b).attr("type", "text").finish(),
+///             button(&b).children([text("OK")]).finish(),
+///         ])),
+///     ])
+///     .finish();
+/// ```
+#[cfg(feature = "dom")]
+pub fn focus_trap<'a, Attributes, Children>(
+    container: ElementBuilder<
+        'a,
+        bumpalo::collections::Vec<'a, Listener<'a>>,
+        Attributes,
+        Children,
+    >,
+) -> Node<'a>
+where
+    Attributes: 'a + AsRef<[Attribute<'a>]>,
+    Children: 'a + AsRef<[Node<'a>]>,
+{
+    container
+        .on("keydown", |_root, _vdom, event| {
+            let key = js_sys::Reflect::get(&event, &"key".into())
+                .ok()
+                .and_then(|key| key.as_string());
+            if key.as_ref().map(|key| key.as_str()) != Some("Tab") {
+                return;
+            }
+            let shift = js_sys::Reflect::get(&event, &"shiftKey".into())
+                .ok()
+                .and_then(|shift| shift.as_bool())
+                .unwrap_or(false);
+
+            let container = match event.current_target() {
+                Some(container) => container.unchecked_into::<web_sys::Element>(),
+                None => return,
+            };
+            let tabbable = tabbable_descendants(&container);
+            let (first, last) = match (tabbable.first(), tabbable.last()) {
+                (Some(first), Some(last)) => (first, last),
+                _ => {
+                    // Nothing to move focus to, but it still must not leave.
+                    event.prevent_default();
+                    return;
+                }
+            };
+
+            let active = container
+                .owner_document()
+                .and_then(|document| document.active_element());
+            let active_is = |element: &web_sys::Element| {
+                active
+                    .as_ref()
+                    .map_or(false, |active| active.is_same_node(Some(&**element)))
+            };
+            let within = active
+                .as_ref()
+                .map_or(false, |active| container.contains(Some(&**active)));
+
+            if shift && (active_is(first) || !within) {
+                event.prevent_default();
+                focus(last);
+            } else if !shift && (active_is(last) || !within) {
+                event.prevent_default();
+                focus(first);
+            }
+        })
+        .finish()
+}
+
+#[cfg(feature = "dom")]
+fn tabbable_descendants(container: &web_sys::Element) -> Vec<web_sys::Element> {
+    let query_selector_all = js_sys::Reflect::get(container, &"querySelectorAll".into())
+        .ok()
+        .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+    let elements = query_selector_all.and_then(|f| f.call1(container, &TABBABLE.into()).ok());
+    let elements = match elements {
+        Some(elements) => js_sys::Array::from(&elements),
+        None => return vec![],
+    };
+    (0..elements.length())
+        .map(|i| elements.get(i).unchecked_into::<web_sys::Element>())
+        .filter(|element| {
+            let negative = element
+                .get_attribute("tabindex")
+                .map_or(false, |tabindex| tabindex.trim().starts_with('-'));
+            let inert = element.closest("[inert]").ok().flatten().is_some();
+            !negative && !inert
+        })
+        .collect()
+}
+
+// Focus the given element, if it is focusable.
+#[cfg(feature = "dom")]
+fn focus(element: &web_sys::Element) {
+    if let Ok(focus) = js_sys::Reflect::get(element, &"focus".into()) {
+        if let Ok(focus) = focus.dyn_into::<js_sys::Function>() {
+            let _ = focus.call0(element);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attr_if_and_child_if_omit_what_is_false() {
+        let b = Bump::new();
+        let node = div(&b)
+            .attr_if(false, "hidden", "")
+            .attr_if(true, "id", "shown")
+            .child_if(true, || span(&b).finish())
+            .child_if(false, || panic!("should not be built"))
+            .finish();
+
+        let element = match node.kind {
+            NodeKind::Element(element) => element,
+            _ => panic!("should be an element"),
+        };
+        assert_eq!(element.attributes.len(), 1);
+        assert_eq!(element.attributes[0].name(), "id");
+        assert_eq!(element.children.len(), 1);
+        match element.children[0].kind {
+            NodeKind::Element(child) => assert_eq!(child.tag_name, "span"),
+            _ => panic!("should be an element"),
+        }
+    }
+
+    #[test]
+    fn typed_attributes_build_the_same_tree_as_attr() {
+        let b = Bump::new();
+
+        let typed = form(&b)
+            .action("/signup")
+            .method("post")
+            .children([
+                label(&b).for_("email").children([text("Email")]).finish(),
+                input(&b)
+                    .id("email")
+                    .type_("email")
+                    .name("email")
+                    .placeholder("you@example.com")
+                    .required(true)
+                    .disabled(false)
+                    .finish(),
+                a(&b)
+                    .href("/terms")
+                    .target("_blank")
+                    .rel("noopener")
+                    .class("link")
+                    .children([text("Terms")])
+                    .finish(),
+            ])
+            .finish();
+
+        let generic = form(&b)
+            .attr("action", "/signup")
+            .attr("method", "post")
+            .children([
+                label(&b)
+                    .attr("for", "email")
+                    .children([text("Email")])
+                    .finish(),
+                input(&b)
+                    .attr("id", "email")
+                    .attr("type", "email")
+                    .attr("name", "email")
+                    .attr("placeholder", "you@example.com")
+                    .bool_attr("required", true)
+                    .bool_attr("disabled", false)
+                    .finish(),
+                a(&b)
+                    .attr("href", "/terms")
+                    .attr("target", "_blank")
+                    .attr("rel", "noopener")
+                    .attr("class", "link")
+                    .children([text("Terms")])
+                    .finish(),
+            ])
+            .finish();
+
+        assert_eq!(format!("{:?}", typed), format!("{:?}", generic));
+    }
+
+    #[test]
+    fn text_trimmed_slices_off_whitespace() {
+        use crate::node::TextNode;
+
+        let contents = "  hi  ";
+        let trimmed = text_trimmed(contents);
+        assert_eq!(format!("{:?}", trimmed), format!("{:?}", text("hi")));
+        match trimmed.kind {
+            NodeKind::Text(TextNode { text }) => {
+                assert_eq!(text, "hi");
+                // Borrowed from `contents`, not copied.
+                assert_eq!(text.as_ptr(), contents[2..].as_ptr());
+            }
+            _ => panic!("should be a text node"),
+        }
+
+        assert_eq!(
+            format!("{:?}", text_trimmed("\n\t  \n")),
+            format!("{:?}", text(""))
+        );
     }
 }
@@ -187,7 +187,7 @@ where
             }
             _ => {
                 let mut key = NodeKey::NONE;
-                let id = CachedSet::insert(cx, false, template, |nested_cx| {
+                let id = CachedSet::insert_with_context(cx, false, template, |nested_cx| {
                     let node = self.inner.render(nested_cx);
                     key = node.key();
                     node
@@ -1,28 +1,60 @@
+#[cfg(feature = "dom")]
+use crate::events::EventsRegistry;
 use crate::{
-    events::EventsRegistry,
     node::{Node, NodeKind},
     render_context::RenderContext,
+    UnwrapThrowExt,
 };
 use bumpalo::Bump;
 use fxhash::{FxHashMap, FxHashSet};
+use std::mem;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::u32;
-use wasm_bindgen::prelude::*;
 
 static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
-pub_unstable_internal! {
-    #[derive(Debug, Default)]
-    pub(crate) struct CachedSet {
-        items: FxHashMap<CacheId, CacheEntry>,
-    }
-}
+/// The set of cached virtual DOM subtrees owned by a `Vdom`.
+///
+/// Usually the cached set is populated lazily while rendering `Cached<R>`
+/// components, but static subtrees that are known ahead of time can be
+/// pre-warmed with `CachedSet::insert` and then handed to
+/// `Vdom::with_cached_set`, so that they aren't built during the first render.
+///
+/// ## Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, CachedSet, Node, Render, RenderContext, Vdom};
+///
+/// struct App {
+///     footer: dodrio::CacheId,
+/// }
+///
+/// impl<'a> Render<'a> for App {
+///     fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+///         div(&cx).children([cx.cached(self.footer)]).finish()
+///     }
+/// }
+///
+/// let mut cached_set = CachedSet::default();
+/// let footer_id = cached_set.insert(|cx| footer(&cx).children([text("Thanks!")]).finish());
+///
+/// # let container: dodrio::Element = unimplemented!();
+/// let vdom = Vdom::with_cached_set(&container, App { footer: footer_id }, cached_set);
+/// ```
+#[derive(Debug, Default)]
+pub struct CachedSet {
+    items: FxHashMap<CacheId, CacheEntry>,
 
-pub_unstable_internal! {
-    #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
-    pub(crate) struct CacheId(u32);
+    // Arenas of garbage collected entries, reset and ready to render new
+    // entries into. Re-rendering a cached subtree would otherwise allocate a
+    // fresh arena every time, and free the old one along with its nodes.
+    pool: Vec<Bump>,
 }
 
+/// An identifier for a subtree inside a `CachedSet`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct CacheId(pub(crate) u32);
+
 #[derive(Debug)]
 pub(crate) struct CacheEntry {
     bump: Bump,
@@ -49,6 +81,7 @@ pub(crate) struct CacheEntry {
 
     // Whether this entry should never be garbage collected. Typically only
     // templates are pinned.
+    #[cfg_attr(not(feature = "dom"), allow(dead_code))]
     pinned: bool,
 }
 
@@ -60,12 +93,14 @@ impl From<CacheId> for u32 {
 }
 
 impl CachedSet {
+    #[cfg(feature = "dom")]
     pub(crate) fn new_roots_set(&self) -> FxHashSet<CacheId> {
         let mut roots = FxHashSet::default();
         roots.reserve(self.items.len());
         roots
     }
 
+    #[cfg(feature = "dom")]
     pub(crate) fn gc(&mut self, registry: &mut EventsRegistry, roots: FxHashSet<CacheId>) {
         let mut marked = FxHashSet::default();
         marked.reserve(self.items.len());
@@ -83,14 +118,38 @@ impl CachedSet {
             }
         }
 
-        self.items.retain(|id, entry| {
-            let keep = entry.pinned || marked.contains(id);
-            if !keep {
+        let garbage: Vec<CacheId> = self
+            .items
+            .iter()
+            .filter(|(id, entry)| !entry.pinned && !marked.contains(id))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in garbage {
+            let entry = self
+                .items
+                .remove(&id)
+                .expect_throw("CachedSet::gc: should have garbage in cached set");
+            {
                 let node: &Node = unsafe { &*entry.node };
                 registry.remove_subtree(node);
             }
-            keep
-        });
+            self.recycle(entry.bump);
+        }
+    }
+
+    // Put a garbage collected entry's arena in the pool, for the next entry to
+    // render into. Nothing references the entry's nodes anymore: the current
+    // virtual DOM only references entries that were marked, and the previous
+    // one only by id.
+    //
+    // The pool holds at most as many arenas as there are live entries, so that
+    // it doesn't hang on to the memory of subtrees that are no longer rendered.
+    #[cfg(feature = "dom")]
+    fn recycle(&mut self, mut bump: Bump) {
+        if self.pool.len() < self.items.len() {
+            bump.reset();
+            self.pool.push(bump);
+        }
     }
 
     // Trace all the transitive edges to other cached entries that the given
@@ -104,6 +163,8 @@ impl CachedSet {
     fn trace_recursive(&self, edges: &mut FxHashSet<CacheId>, node: &Node) {
         match &node.kind {
             NodeKind::Text(_) => return,
+            #[cfg(feature = "dom")]
+            NodeKind::Raw(_) => return,
             NodeKind::Cached(c) => {
                 debug_assert!(self.items.contains_key(&c.id));
                 edges.insert(c.id);
@@ -130,7 +191,31 @@ impl CachedSet {
         CacheId(next.expect_throw("ID_COUNTER overflowed"))
     }
 
-    pub(crate) fn insert<F>(
+    /// Render a subtree with `f` and insert it into this cached set ahead of
+    /// time, returning the id that render components can later reference via
+    /// `RenderContext::cached`.
+    ///
+    /// Pre-warmed subtrees are pinned: they are never garbage collected, even
+    /// if a render does not reference them.
+    ///
+    /// Note that any `Cached<R>` components rendered inside `f` will not share
+    /// templates with the `Vdom` this set is eventually given to.
+    pub fn insert<F>(&mut self, f: F) -> CacheId
+    where
+        F: for<'a> FnOnce(&mut RenderContext<'a>) -> Node<'a>,
+    {
+        let set = crate::RefCell::new(mem::take(self));
+        let id = {
+            let bump = Bump::new();
+            let mut templates = FxHashMap::default();
+            let mut cx = RenderContext::new(&bump, &set, &mut templates);
+            CachedSet::insert_with_context(&mut cx, true, None, f)
+        };
+        *self = set.into_inner();
+        id
+    }
+
+    pub(crate) fn insert_with_context<F>(
         cx: &mut RenderContext,
         pinned: bool,
         template: Option<CacheId>,
@@ -140,9 +225,14 @@ impl CachedSet {
         F: for<'a> FnOnce(&mut RenderContext<'a>) -> Node<'a>,
     {
         let set = cx.cached_set;
-        let bump = Bump::new();
+        let (bump, id) = {
+            let mut set = set.borrow_mut();
+            (set.pool.pop().unwrap_or_else(Bump::new), set.next_id())
+        };
         let (node, edges) = {
             let mut nested_cx = RenderContext::new(&bump, cx.cached_set, cx.templates);
+            nested_cx.id_scope = Some(id);
+            nested_cx.contexts = cx.contexts.clone();
             let node = f(&mut nested_cx);
             let node = bump.alloc(node);
             let edges = {
@@ -163,9 +253,7 @@ impl CachedSet {
             pinned,
         };
 
-        let mut set = set.borrow_mut();
-        let id = set.next_id();
-        set.items.insert(id, entry);
+        set.borrow_mut().items.insert(id, entry);
         id
     }
 
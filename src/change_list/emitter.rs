@@ -2,7 +2,7 @@
 //! instructions and ensuring that each instruction has the correct number of
 //! immediates. It ensures that the resulting change list instruction stream is
 //! *syntactically* correct (opcodes have the correct arity of immediate
-//! arguments, etc), while `ChangeListBuilder` wraps an emitter and additionally
+//! arguments, etc), while `InterpreterSink` wraps an emitter and additionally
 //! ensures that the resulting change list program is *semantically* correct
 //! (doesn't reference cached strings before they've been added to the cache,
 //! etc).
@@ -15,6 +15,8 @@
 //! `Bump::each_allocated_chunk` method for details.
 
 use bumpalo::Bump;
+use std::alloc::Layout;
+use std::ptr;
 
 #[derive(Debug)]
 pub(crate) struct InstructionEmitter {
@@ -48,6 +50,48 @@ impl InstructionEmitter {
     pub fn reset(&mut self) {
         self.bump.reset();
     }
+
+    /// Encode an instruction with a variable number of immediates. The
+    /// instruction is encoded as its discriminant, followed by the count of
+    /// immediates, followed by the immediates themselves.
+    ///
+    /// Everything is allocated as a single contiguous `u32` array, so that the
+    /// instruction is never split across two of the bump arena's chunks.
+    fn variadic(&self, discriminant: u32, immediates: &[u32]) {
+        let len = immediates.len() + 2;
+        let layout = Layout::array::<u32>(len).expect("variadic instruction is too large");
+        let dst = self.bump.alloc_layout(layout).cast::<u32>().as_ptr();
+        unsafe {
+            dst.write(discriminant);
+            dst.add(1).write(immediates.len() as u32);
+            ptr::copy_nonoverlapping(immediates.as_ptr(), dst.add(2), immediates.len());
+        }
+    }
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// ```text
+    /// node = stack.top()
+    /// for (name_key, value_key) in pairs(names_and_values):
+    ///     node.setAttribute(getCachedString(name_key), getCachedString(value_key))
+    /// ```
+    #[inline]
+    pub fn set_attributes_batch(&self, names_and_values: &[u32]) {
+        debug_assert_eq!(names_and_values.len() % 2, 0);
+        self.variadic(28, names_and_values);
+    }
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// ```text
+    /// node = stack.top()
+    /// for name_key in names:
+    ///     node.removeAttribute(getCachedString(name_key))
+    /// ```
+    #[inline]
+    pub fn remove_attributes_batch(&self, names: &[u32]) {
+        self.variadic(29, names);
+    }
 }
 
 macro_rules! define_change_list_instructions {
@@ -289,4 +333,83 @@ define_change_list_instructions! {
     /// stack.push(template.cloneNode(true))
     /// ```
     push_template(id) = 25,
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// ```text
+    /// name = getCachedString(name_key)
+    /// value = getCachedString(value_key)
+    /// stack.top().style.setProperty(name, value)
+    /// ```
+    set_style_property(name_key, value_key) = 26,
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// ```text
+    /// stack.top().style.removeProperty(getCachedString(name_key))
+    /// ```
+    remove_style_property(name_key) = 27,
+
+    // Note: 28 and 29 are the variadic `set_attributes_batch` and
+    // `remove_attributes_batch` instructions defined above.
+
+    /// Stack: `[...] -> [... Node]`
+    ///
+    /// ```text
+    /// template = document.createElement("template")
+    /// template.innerHTML = readString(pointer, length)
+    /// stack.push(template.content.firstChild)
+    /// ```
+    create_element_from_html(pointer, length) = 30,
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// ```text
+    /// stack.top()[getCachedString(name_key)] = getValue(value_index)
+    /// ```
+    set_property(name_key, value_index) = 31,
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// ```text
+    /// stack.top()[getCachedString(name_key)] = null
+    /// ```
+    remove_property(name_key) = 32,
+
+    /// Stack: `[...] -> [... Node]`
+    ///
+    /// ```text
+    /// tag_name = getCachedString(tag_name_key)
+    /// is = getCachedString(is_key)
+    /// stack.push(document.createElement(tag_name, { is }))
+    /// ```
+    create_element_with_is(tag_name_key, is_key) = 33,
+
+    /// Stack: `[... Node Node*count] -> [... Node]`
+    ///
+    /// ```text
+    /// new = stack.splice(stack.length - count, count)
+    /// first = stack.pop()
+    /// old = [first, ...first's next count - 1 siblings]
+    /// first.parentNode.insertBefore(fragment(new), first)
+    /// old.forEach(node => node.remove())
+    /// stack.push(new[count - 1])
+    /// ```
+    replace_siblings_with(count) = 34,
+
+    /// Stack: `[...] -> [... Node]`
+    ///
+    /// ```text
+    /// stack.push(getValue(value_index))
+    /// ```
+    push_raw_node(value_index) = 35,
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// ```text
+    /// event = getCachedString(event_key)
+    /// callback = createProxyToRustCallback(a, b)
+    /// stack.top().addEventListener(event, callback, { passive: false })
+    /// ```
+    new_non_passive_event_listener(event_key, a, b) = 36,
 }
@@ -0,0 +1,347 @@
+//! The default `ChangeSink`, which encodes operations into a change list that
+//! the JavaScript interpreter applies to the physical DOM.
+
+use super::emitter::InstructionEmitter;
+use super::js;
+use super::sink::ChangeSink;
+use super::strings::{StringKey, StringsCache};
+use crate::{cached_set::CacheId, Listener};
+use wasm_bindgen::JsValue;
+
+#[derive(Debug)]
+pub(crate) struct InterpreterSink {
+    strings: StringsCache,
+    emitter: InstructionEmitter,
+    interpreter: js::ChangeListInterpreter,
+    // String keys of attributes queued up to be set or removed in a single
+    // batched instruction. See `flush_attributes`.
+    set_attributes_batch: Vec<u32>,
+    remove_attributes_batch: Vec<u32>,
+    // Serialized HTML strings that instructions point into. They must be kept
+    // alive until the change list is applied.
+    html: Vec<String>,
+}
+
+impl Drop for InterpreterSink {
+    fn drop(&mut self) {
+        self.interpreter.unmount();
+    }
+}
+
+impl InterpreterSink {
+    pub(crate) fn new(container: &crate::Element) -> InterpreterSink {
+        InterpreterSink {
+            strings: StringsCache::new(),
+            emitter: InstructionEmitter::new(),
+            interpreter: js::ChangeListInterpreter::new(container),
+            set_attributes_batch: Vec::new(),
+            remove_attributes_batch: Vec::new(),
+            html: Vec::new(),
+        }
+    }
+
+    pub(crate) fn init_events_trampoline(
+        &mut self,
+        trampoline: &crate::EventsTrampoline,
+        namespace: u32,
+    ) {
+        self.interpreter
+            .init_events_trampoline(trampoline, namespace);
+    }
+
+    /// Add a listener for `event` to the node on top of the stack, whose
+    /// callback is registered under `parts`. See `Listener::registry_parts`.
+    pub(crate) fn new_event_listener_with_parts(
+        &mut self,
+        event: &str,
+        (a, b): (u32, u32),
+        non_passive: bool,
+    ) {
+        debug_assert!(a != 0);
+        let event_id = self.ensure_string(event);
+        if non_passive {
+            self.emitter
+                .new_non_passive_event_listener(event_id.into(), a, b);
+        } else {
+            self.emitter.new_event_listener(event_id.into(), a, b);
+        }
+    }
+
+    /// Point the node on top of the stack's listener for `event` at the
+    /// callback registered under `parts`.
+    pub(crate) fn update_event_listener_with_parts(&mut self, event: &str, (a, b): (u32, u32)) {
+        debug_assert!(a != 0);
+        let event_id = self.ensure_string(event);
+        self.emitter.update_event_listener(event_id.into(), a, b);
+    }
+
+    /// Use the given element, rather than the container's first child, as the
+    /// physical DOM node for the root of the virtual DOM.
+    pub(crate) fn set_root(&self, root: &crate::Element) {
+        self.interpreter.set_root(root);
+    }
+
+    /// Remove the root set with `set_root` from the physical DOM.
+    pub(crate) fn remove_root(&self) {
+        self.interpreter.remove_root();
+    }
+
+    /// Get the physical DOM node for the root of the virtual DOM, if there is
+    /// one.
+    pub(crate) fn root_node(&self) -> Option<web_sys::Node> {
+        self.interpreter.root_node()
+    }
+
+    /// Replace the root's physical DOM node with a new, empty `<div>`.
+    pub(crate) fn reset_root(&self) {
+        self.interpreter.reset_root();
+    }
+
+    /// Create elements by cloning a cached prototype element with the same tag
+    /// name, rather than with `document.createElement`.
+    pub(crate) fn set_clone_elements(&self, clone: bool) {
+        self.interpreter.set_clone_elements(clone);
+    }
+
+    /// Apply `style` attributes through the CSSOM, rather than with
+    /// `setAttribute`.
+    pub(crate) fn set_strict_csp(&self, strict: bool) {
+        self.interpreter.set_strict_csp(strict);
+    }
+
+    /// Give every created `<style>` element the given `nonce`.
+    pub(crate) fn set_style_nonce(&self, nonce: &str) {
+        self.interpreter.set_style_nonce(nonce);
+    }
+
+    fn ensure_string(&mut self, string: &str) -> StringKey {
+        self.strings.ensure_string(string, &self.emitter)
+    }
+
+    #[inline]
+    pub(crate) fn attributes_are_flushed(&self) -> bool {
+        self.set_attributes_batch.is_empty() && self.remove_attributes_batch.is_empty()
+    }
+
+    #[cfg(all(
+        feature = "xxx-unstable-internal-use-only",
+        not(target_arch = "wasm32")
+    ))]
+    fn apply(&mut self) {
+        // Nothing to actually apply the changes to.
+    }
+
+    #[cfg(not(all(
+        feature = "xxx-unstable-internal-use-only",
+        not(target_arch = "wasm32")
+    )))]
+    fn apply(&mut self) {
+        let interpreter = &self.interpreter;
+        self.emitter.each_instruction_sequence(|seq| {
+            interpreter.add_change_list_range(seq.as_ptr() as usize, seq.len());
+        });
+        interpreter.apply_changes(wasm_bindgen::memory());
+    }
+}
+
+impl ChangeSink for InterpreterSink {
+    fn push_child(&mut self, n: u32) {
+        self.emitter.push_child(n);
+    }
+
+    fn push_reverse_child(&mut self, n: u32) {
+        self.emitter.push_reverse_child(n);
+    }
+
+    fn pop_push_child(&mut self, n: u32) {
+        self.emitter.pop_push_child(n);
+    }
+
+    fn pop_push_reverse_child(&mut self, n: u32) {
+        self.emitter.pop_push_reverse_child(n);
+    }
+
+    fn pop(&mut self) {
+        self.emitter.pop();
+    }
+
+    fn push_temporary(&mut self, temp: u32) {
+        self.emitter.push_temporary(temp);
+    }
+
+    fn save_children_to_temporaries(&mut self, temp_base: u32, start: u32, end: u32) {
+        self.emitter
+            .save_children_to_temporaries(temp_base, start, end);
+    }
+
+    fn remove_child(&mut self, n: u32) {
+        self.emitter.remove_child(n);
+    }
+
+    fn insert_before(&mut self) {
+        self.emitter.insert_before();
+    }
+
+    fn append_child(&mut self) {
+        self.emitter.append_child();
+    }
+
+    fn replace_with(&mut self) {
+        self.emitter.replace_with();
+    }
+
+    fn replace_siblings_with(&mut self, count: u32) {
+        self.emitter.replace_siblings_with(count);
+    }
+
+    fn remove_self_and_next_siblings(&mut self) {
+        self.emitter.remove_self_and_next_siblings();
+    }
+
+    fn create_text_node(&mut self, text: &str) {
+        self.emitter
+            .create_text_node(text.as_ptr() as u32, text.len() as u32);
+    }
+
+    fn create_element(&mut self, tag_name: &str) {
+        let tag_name_id = self.ensure_string(tag_name);
+        self.emitter.create_element(tag_name_id.into());
+    }
+
+    fn create_element_ns(&mut self, tag_name: &str, ns: &str) {
+        let tag_name_id = self.ensure_string(tag_name);
+        let ns_id = self.ensure_string(ns);
+        self.emitter
+            .create_element_ns(tag_name_id.into(), ns_id.into());
+    }
+
+    fn create_element_with_is(&mut self, tag_name: &str, is: &str) {
+        let tag_name_id = self.ensure_string(tag_name);
+        let is_id = self.ensure_string(is);
+        self.emitter
+            .create_element_with_is(tag_name_id.into(), is_id.into());
+    }
+
+    fn create_element_from_html(&mut self, html: String) {
+        self.emitter
+            .create_element_from_html(html.as_ptr() as u32, html.len() as u32);
+        // Moving the `String` does not move its heap buffer, so the pointer we
+        // just emitted stays valid.
+        self.html.push(html);
+    }
+
+    fn push_raw_node(&mut self, node: &web_sys::Node) {
+        let value_index = self.interpreter.add_value(node);
+        self.emitter.push_raw_node(value_index);
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.emitter
+            .set_text(text.as_ptr() as u32, text.len() as u32);
+    }
+
+    fn set_attribute(&mut self, name: &str, value: &str) {
+        let name_id = self.ensure_string(name);
+        let value_id = self.ensure_string(value);
+        self.set_attributes_batch.push(name_id.into());
+        self.set_attributes_batch.push(value_id.into());
+    }
+
+    fn remove_attribute(&mut self, name: &str) {
+        let name_id = self.ensure_string(name);
+        self.remove_attributes_batch.push(name_id.into());
+    }
+
+    fn flush_attributes(&mut self) {
+        match self.set_attributes_batch.len() {
+            0 => {}
+            2 => {
+                debug!("emit: set_attribute(..)");
+                self.emitter
+                    .set_attribute(self.set_attributes_batch[0], self.set_attributes_batch[1]);
+            }
+            n => {
+                debug!("emit: set_attributes_batch({} attributes)", n / 2);
+                self.emitter
+                    .set_attributes_batch(&self.set_attributes_batch);
+            }
+        }
+        self.set_attributes_batch.clear();
+
+        match self.remove_attributes_batch.len() {
+            0 => {}
+            1 => {
+                debug!("emit: remove_attribute(..)");
+                self.emitter
+                    .remove_attribute(self.remove_attributes_batch[0]);
+            }
+            n => {
+                debug!("emit: remove_attributes_batch({} attributes)", n);
+                self.emitter
+                    .remove_attributes_batch(&self.remove_attributes_batch);
+            }
+        }
+        self.remove_attributes_batch.clear();
+    }
+
+    fn set_class(&mut self, class: &str) {
+        let class_id = self.ensure_string(class);
+        self.emitter.set_class(class_id.into());
+    }
+
+    fn set_style_property(&mut self, name: &str, value: &str) {
+        let name_id = self.ensure_string(name);
+        let value_id = self.ensure_string(value);
+        self.emitter
+            .set_style_property(name_id.into(), value_id.into());
+    }
+
+    fn remove_style_property(&mut self, name: &str) {
+        let name_id = self.ensure_string(name);
+        self.emitter.remove_style_property(name_id.into());
+    }
+
+    fn set_property(&mut self, name: &str, value: &JsValue) {
+        let name_id = self.ensure_string(name);
+        let value_index = self.interpreter.add_value(value);
+        self.emitter.set_property(name_id.into(), value_index);
+    }
+
+    fn remove_property(&mut self, name: &str) {
+        let name_id = self.ensure_string(name);
+        self.emitter.remove_property(name_id.into());
+    }
+
+    fn new_event_listener(&mut self, listener: &Listener) {
+        self.new_event_listener_with_parts(
+            listener.event,
+            listener.registry_parts(),
+            listener.non_passive,
+        );
+    }
+
+    fn update_event_listener(&mut self, listener: &Listener) {
+        self.update_event_listener_with_parts(listener.event, listener.registry_parts());
+    }
+
+    fn remove_event_listener(&mut self, event: &str) {
+        let event_id = self.ensure_string(event);
+        self.emitter.remove_event_listener(event_id.into());
+    }
+
+    fn save_template(&mut self, id: CacheId) {
+        self.emitter.save_template(id.into());
+    }
+
+    fn push_template(&mut self, id: CacheId) {
+        self.emitter.push_template(id.into());
+    }
+
+    fn finish(&mut self) {
+        debug_assert!(self.attributes_are_flushed());
+        self.strings.drop_unused_strings(&self.emitter);
+        self.apply();
+        self.emitter.reset();
+        self.html.clear();
+    }
+}
@@ -7,8 +7,25 @@ cfg_if::cfg_if! {
                 ChangeListInterpreter {}
             }
             pub fn unmount(&self) {}
+            pub fn set_root(&self, _root: &crate::Element) {}
+            pub fn remove_root(&self) {}
+            pub fn set_clone_elements(&self, _clone: bool) {}
+            pub fn set_strict_csp(&self, _strict: bool) {}
+            pub fn set_style_nonce(&self, _nonce: &str) {}
+            pub fn root_node(&self) -> Option<web_sys::Node> {
+                None
+            }
+            pub fn reset_root(&self) {}
             pub fn add_change_list_range(&self, _start: usize, _len: usize) {}
-            pub fn init_events_trampoline(&self, _trampoline: &crate::EventsTrampoline) {}
+            pub fn add_value(&self, _value: &wasm_bindgen::JsValue) -> u32 {
+                0
+            }
+            pub fn init_events_trampoline(
+                &self,
+                _trampoline: &crate::EventsTrampoline,
+                _namespace: u32,
+            ) {
+            }
         }
     } else {
         use wasm_bindgen::prelude::*;
@@ -24,9 +41,33 @@ cfg_if::cfg_if! {
             #[wasm_bindgen(structural, method)]
             pub fn unmount(this: &ChangeListInterpreter);
 
+            #[wasm_bindgen(structural, method, js_name = setRoot)]
+            pub fn set_root(this: &ChangeListInterpreter, root: &web_sys::Element);
+
+            #[wasm_bindgen(structural, method, js_name = removeRoot)]
+            pub fn remove_root(this: &ChangeListInterpreter);
+
+            #[wasm_bindgen(structural, method, js_name = rootNode)]
+            pub fn root_node(this: &ChangeListInterpreter) -> Option<web_sys::Node>;
+
+            #[wasm_bindgen(structural, method, js_name = resetRoot)]
+            pub fn reset_root(this: &ChangeListInterpreter);
+
+            #[wasm_bindgen(structural, method, js_name = setCloneElements)]
+            pub fn set_clone_elements(this: &ChangeListInterpreter, clone: bool);
+
+            #[wasm_bindgen(structural, method, js_name = setStrictCsp)]
+            pub fn set_strict_csp(this: &ChangeListInterpreter, strict: bool);
+
+            #[wasm_bindgen(structural, method, js_name = setStyleNonce)]
+            pub fn set_style_nonce(this: &ChangeListInterpreter, nonce: &str);
+
             #[wasm_bindgen(structural, method, js_name = addChangeListRange)]
             pub fn add_change_list_range(this: &ChangeListInterpreter, start: usize, len: usize);
 
+            #[wasm_bindgen(structural, method, js_name = addValue)]
+            pub fn add_value(this: &ChangeListInterpreter, value: &JsValue) -> u32;
+
             #[wasm_bindgen(structural, method, js_name = applyChanges)]
             pub fn apply_changes(this: &ChangeListInterpreter, memory: JsValue);
 
@@ -34,6 +75,7 @@ cfg_if::cfg_if! {
             pub fn init_events_trampoline(
                 this: &ChangeListInterpreter,
                 trampoline: &crate::EventsTrampoline,
+                namespace: u32,
             );
         }
     }
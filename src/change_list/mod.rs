@@ -1,99 +1,214 @@
 pub(crate) mod emitter;
+pub(crate) mod interpreter;
 pub(crate) mod strings;
 pub(crate) mod traversal;
 
 // Note: has to be `pub` because of `wasm-bindgen` visibility restrictions.
 pub mod js;
 
-use self::emitter::InstructionEmitter;
-use self::strings::{StringKey, StringsCache};
+mod sink;
+pub use self::sink::ChangeSink;
+
+use self::interpreter::InterpreterSink;
 use self::traversal::{MoveTo, Traversal};
-use crate::{cached_set::CacheId, Listener};
-use fxhash::FxHashSet;
+use crate::{
+    cached_set::CacheId,
+    observer::{AttributeChange, AttributeObserver, ChangeKind, DiffObserver},
+    AttributeValue, Listener,
+};
+use fxhash::{FxHashMap, FxHashSet};
+use std::fmt;
+
+/// Statistics about a diff, for finding hotspots in rendering. See
+/// `Vdom::last_diff_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    /// The number of operations that the diff applied to the physical DOM,
+    /// the same as `Vdom::last_diff_op_count`.
+    pub op_count: usize,
+
+    /// The deepest level below the root that the diff descended to. Diffing
+    /// only the root is depth 0, diffing its children is depth 1, and so on.
+    /// Newly created subtrees are not descended into.
+    pub max_depth: usize,
+
+    /// The length of the longest list of old or new children that the diff
+    /// compared.
+    pub widest_children: usize,
+}
 
 #[derive(Debug)]
-pub(crate) struct ChangeListPersistentState {
-    strings: StringsCache,
-    emitter: InstructionEmitter,
+pub(crate) struct ChangeListPersistentState<S = InterpreterSink> {
+    sink: S,
     traversal: Traversal,
-    interpreter: js::ChangeListInterpreter,
     templates: FxHashSet<CacheId>,
+    diff_observer: Option<DiffObserver>,
+    attribute_observer: Option<AttributeObserver>,
+    attribute_comparators: FxHashMap<String, AttributeComparator>,
+    // The number of operations sent to the sink by the last diff.
+    op_count: usize,
+    // How many levels below the root the last diff is currently in, the most
+    // it has been in, and the longest list of children it has diffed.
+    depth: usize,
+    max_depth: usize,
+    widest_children: usize,
+    // The path of child indices from the root to the node currently being
+    // diffed. Only used for reporting changes to the `diff_observer` and
+    // `attribute_observer`, and for logging with the `op-paths` feature.
+    path: Vec<usize>,
+    // Whether to avoid creating subtrees from HTML, whose `style` attributes a
+    // strict Content Security Policy would block. See
+    // `MountOptions::strict_csp`.
+    strict_csp: bool,
 }
 
-pub(crate) struct ChangeListBuilder<'a> {
-    state: &'a mut ChangeListPersistentState,
+pub(crate) struct ChangeListBuilder<'a, S: ChangeSink = InterpreterSink> {
+    state: &'a mut ChangeListPersistentState<S>,
     next_temporary: u32,
     forcing_new_listeners: bool,
+    // Have attribute changes been sent to the sink since the last
+    // `flush_attributes`?
+    attributes_are_queued: bool,
 }
 
-impl Drop for ChangeListPersistentState {
-    fn drop(&mut self) {
-        self.interpreter.unmount();
+impl ChangeListPersistentState {
+    pub(crate) fn new(container: &crate::Element) -> ChangeListPersistentState {
+        ChangeListPersistentState::with_sink(InterpreterSink::new(container))
+    }
+
+    pub(crate) fn init_events_trampoline(
+        &mut self,
+        trampoline: &crate::EventsTrampoline,
+        namespace: u32,
+    ) {
+        self.sink.init_events_trampoline(trampoline, namespace);
+    }
+
+    pub(crate) fn set_root(&self, root: &crate::Element) {
+        self.sink.set_root(root);
+    }
+
+    pub(crate) fn remove_root(&self) {
+        self.sink.remove_root();
+    }
+
+    pub(crate) fn root_node(&self) -> Option<web_sys::Node> {
+        self.sink.root_node()
+    }
+
+    pub(crate) fn reset_root(&self) {
+        self.sink.reset_root();
+    }
+
+    pub(crate) fn set_clone_elements(&self, clone: bool) {
+        self.sink.set_clone_elements(clone);
+    }
+
+    pub(crate) fn set_strict_csp(&mut self, strict: bool) {
+        self.strict_csp = strict;
+        self.sink.set_strict_csp(strict);
+    }
+
+    pub(crate) fn set_style_nonce(&self, nonce: &str) {
+        self.sink.set_style_nonce(nonce);
     }
 }
 
-impl ChangeListPersistentState {
-    pub(crate) fn new(container: &crate::Element) -> ChangeListPersistentState {
-        let strings = StringsCache::new();
-        let emitter = InstructionEmitter::new();
-        let traversal = Traversal::new();
-        let interpreter = js::ChangeListInterpreter::new(container);
-        let templates = Default::default();
+impl<S: ChangeSink> ChangeListPersistentState<S> {
+    pub(crate) fn with_sink(sink: S) -> ChangeListPersistentState<S> {
         ChangeListPersistentState {
-            strings,
-            emitter,
-            traversal,
-            interpreter,
-            templates,
+            sink,
+            traversal: Traversal::new(),
+            templates: Default::default(),
+            diff_observer: None,
+            attribute_observer: None,
+            attribute_comparators: Default::default(),
+            op_count: 0,
+            depth: 0,
+            max_depth: 0,
+            widest_children: 0,
+            path: Vec::new(),
+            strict_csp: false,
         }
     }
 
-    pub(crate) fn init_events_trampoline(&mut self, trampoline: &crate::EventsTrampoline) {
-        self.interpreter.init_events_trampoline(trampoline);
+    pub(crate) fn set_diff_observer(&mut self, observer: Option<DiffObserver>) {
+        self.diff_observer = observer;
     }
 
-    pub(crate) fn builder<'a>(&'a mut self) -> ChangeListBuilder<'a> {
-        ChangeListBuilder {
-            state: self,
-            next_temporary: 0,
-            forcing_new_listeners: false,
+    pub(crate) fn set_attribute_observer(&mut self, observer: Option<AttributeObserver>) {
+        self.attribute_observer = observer;
+    }
+
+    pub(crate) fn set_attribute_comparator(
+        &mut self,
+        name: &str,
+        comparator: Option<AttributeComparator>,
+    ) {
+        match comparator {
+            Some(comparator) => {
+                self.attribute_comparators
+                    .insert(name.to_string(), comparator);
+            }
+            None => {
+                self.attribute_comparators.remove(name);
+            }
         }
     }
-}
 
-cfg_if::cfg_if! {
-    if #[cfg(all(feature = "xxx-unstable-internal-use-only", not(target_arch = "wasm32")))] {
-        impl ChangeListBuilder<'_> {
-            pub(crate) fn finish(self) {
-                self.state.strings.drop_unused_strings(&self.state.emitter);
+    // Do we need to track the path to the node currently being diffed?
+    #[inline]
+    fn is_observed(&self) -> bool {
+        cfg!(feature = "op-paths")
+            || self.diff_observer.is_some()
+            || self.attribute_observer.is_some()
+    }
 
-                // Nothing to actually apply the changes to.
+    /// The number of operations that the last diff sent to the sink. Moving
+    /// around the tree counts, while operations internal to the sink, such as
+    /// caching strings, do not.
+    pub(crate) fn op_count(&self) -> usize {
+        self.op_count
+    }
 
-                self.state.emitter.reset();
-                self.state.traversal.reset();
-            }
+    /// Statistics about the last diff.
+    pub(crate) fn stats(&self) -> DiffStats {
+        DiffStats {
+            op_count: self.op_count,
+            max_depth: self.max_depth,
+            widest_children: self.widest_children,
         }
-    } else {
-        impl ChangeListBuilder<'_> {
-            pub(crate) fn finish(self) {
-                self.state.strings.drop_unused_strings(&self.state.emitter);
+    }
 
-                // Apply the changes.
-                let interpreter = &self.state.interpreter;
-                self.state.emitter.each_instruction_sequence(|seq| {
-                    interpreter.add_change_list_range(seq.as_ptr() as usize, seq.len());
-                });
-                interpreter.apply_changes(wasm_bindgen::memory());
+    pub(crate) fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
 
-                self.state.emitter.reset();
-                self.state.traversal.reset();
-            }
+    pub(crate) fn builder<'a>(&'a mut self) -> ChangeListBuilder<'a, S> {
+        self.op_count = 0;
+        self.depth = 0;
+        self.max_depth = 0;
+        self.widest_children = 0;
+        ChangeListBuilder {
+            state: self,
+            next_temporary: 0,
+            forcing_new_listeners: false,
+            attributes_are_queued: false,
         }
     }
 }
 
+impl<S: ChangeSink> ChangeListBuilder<'_, S> {
+    pub(crate) fn finish(self) {
+        debug_assert!(!self.attributes_are_queued);
+        debug_assert!(self.state.path.is_empty());
+        self.state.sink.finish();
+        self.state.traversal.reset();
+    }
+}
+
 /// Traversal methods.
-impl ChangeListBuilder<'_> {
+impl<S: ChangeSink> ChangeListBuilder<'_, S> {
     pub fn go_down(&mut self) {
         self.state.traversal.down();
     }
@@ -130,31 +245,38 @@ impl ChangeListBuilder<'_> {
             return;
         }
 
+        debug_assert!(
+            !self.attributes_are_queued,
+            "should flush queued attributes before moving to another node"
+        );
+
+        let sink = &mut self.state.sink;
         for mv in self.state.traversal.commit() {
+            self.state.op_count += 1;
             match mv {
                 MoveTo::Parent => {
                     debug!("emit: pop");
-                    self.state.emitter.pop();
+                    sink.pop();
                 }
                 MoveTo::Child(n) => {
                     debug!("emit: push_child({})", n);
-                    self.state.emitter.push_child(n);
+                    sink.push_child(n);
                 }
                 MoveTo::ReverseChild(n) => {
                     debug!("emit: push_reverse_child({})", n);
-                    self.state.emitter.push_reverse_child(n);
+                    sink.push_reverse_child(n);
                 }
                 MoveTo::Sibling(n) => {
                     debug!("emit: pop_push_child({})", n);
-                    self.state.emitter.pop_push_child(n);
+                    sink.pop_push_child(n);
                 }
                 MoveTo::ReverseSibling(n) => {
                     debug!("emit: pop_push_reverse_child({})", n);
-                    self.state.emitter.pop_push_reverse_child(n);
+                    sink.pop_push_reverse_child(n);
                 }
                 MoveTo::TempChild(temp) => {
                     debug!("emit: push_temporary({})", temp);
-                    self.state.emitter.push_temporary(temp);
+                    sink.push_temporary(temp);
                 }
             }
         }
@@ -165,7 +287,136 @@ impl ChangeListBuilder<'_> {
     }
 }
 
-impl ChangeListBuilder<'_> {
+/// Diff observer methods.
+impl<S: ChangeSink> ChangeListBuilder<'_, S> {
+    /// Note that we are about to diff the current node's `index`th child.
+    #[inline]
+    pub fn enter_child(&mut self, index: usize) {
+        self.state.depth += 1;
+        self.state.max_depth = self.state.max_depth.max(self.state.depth);
+        if self.state.is_observed() {
+            self.state.path.push(index);
+        }
+    }
+
+    /// Note that we are done diffing the child we last entered.
+    #[inline]
+    pub fn exit_child(&mut self) {
+        self.state.depth -= 1;
+        if self.state.is_observed() {
+            self.state.path.pop();
+        }
+    }
+
+    /// Note that we are about to diff a list of `len` children.
+    #[inline]
+    pub fn diffing_children(&mut self, len: usize) {
+        self.state.widest_children = self.state.widest_children.max(len);
+    }
+
+    /// Report a change to the current node to the diff observer, if any.
+    pub fn observe(&mut self, kind: ChangeKind) {
+        if let Some(observer) = self.state.diff_observer.as_mut() {
+            observer.observe(&self.state.path, kind);
+        }
+    }
+
+    /// Report a change to the current node's `index`th child to the diff
+    /// observer, if any.
+    pub fn observe_child(&mut self, index: usize, kind: ChangeKind) {
+        if let Some(observer) = self.state.diff_observer.as_mut() {
+            self.state.path.push(index);
+            observer.observe(&self.state.path, kind);
+            self.state.path.pop();
+        }
+    }
+
+    /// Report a change to each of the current node's children in the range
+    /// `start..end` to the diff observer, if any.
+    pub fn observe_children(&mut self, start: usize, end: usize, kind: ChangeKind) {
+        if self.state.diff_observer.is_some() {
+            for index in start..end {
+                self.observe_child(index, kind);
+            }
+        }
+    }
+
+    /// May large static subtrees be created from HTML? Not under a strict
+    /// Content Security Policy, which would block their `style` attributes.
+    #[inline]
+    pub fn allows_static_html(&self) -> bool {
+        !self.state.strict_csp
+    }
+
+    /// Is there an attribute observer to report attribute changes to?
+    #[inline]
+    pub fn observes_attributes(&self) -> bool {
+        self.state.attribute_observer.is_some()
+    }
+
+    /// Report a change to one of the current node's attributes to the
+    /// attribute observer, if any.
+    pub fn observe_attribute(
+        &mut self,
+        name: &str,
+        old: Option<AttributeValue>,
+        new: Option<AttributeValue>,
+    ) {
+        if let Some(observer) = self.state.attribute_observer.as_mut() {
+            observer.observe(&self.state.path, AttributeChange { name, old, new });
+        }
+    }
+
+    /// Does a custom comparator registered for the attribute `name` consider
+    /// its `old` and `new` string values equivalent?
+    #[inline]
+    pub fn attribute_values_are_equivalent(
+        &self,
+        name: &str,
+        old: AttributeValue,
+        new: AttributeValue,
+    ) -> bool {
+        if self.state.attribute_comparators.is_empty() {
+            return false;
+        }
+        match (old, new, self.state.attribute_comparators.get(name)) {
+            (AttributeValue::Str(old), AttributeValue::Str(new), Some(comparator)) => {
+                (comparator.0)(old, new)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A custom equality function for the string values of one attribute. See
+/// `Vdom::set_attribute_comparator`.
+pub(crate) struct AttributeComparator(Box<dyn Fn(&str, &str) -> bool>);
+
+impl fmt::Debug for AttributeComparator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AttributeComparator").finish()
+    }
+}
+
+impl AttributeComparator {
+    pub(crate) fn new<F>(f: F) -> AttributeComparator
+    where
+        F: 'static + Fn(&str, &str) -> bool,
+    {
+        AttributeComparator(Box::new(f))
+    }
+}
+
+impl<S: ChangeSink> ChangeListBuilder<'_, S> {
+    // Get the sink, to send it one operation.
+    #[inline]
+    fn emit(&mut self) -> &mut S {
+        self.state.op_count += 1;
+        #[cfg(feature = "op-paths")]
+        debug!("  caused by the node at {:?}", self.state.path);
+        &mut self.state.sink
+    }
+
     pub fn next_temporary(&self) -> u32 {
         self.next_temporary
     }
@@ -183,108 +434,160 @@ impl ChangeListBuilder<'_> {
             temp_base, start, end
         );
         self.next_temporary = temp_base + (end - start) as u32;
-        self.state
-            .emitter
+        self.emit()
             .save_children_to_temporaries(temp_base, start as u32, end as u32);
         temp_base
     }
 
-    pub fn push_temporary(&self, temp: u32) {
+    pub fn push_temporary(&mut self, temp: u32) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: push_temporary({})", temp);
-        self.state.emitter.push_temporary(temp);
+        self.emit().push_temporary(temp);
     }
 
-    pub fn remove_child(&self, child: usize) {
+    pub fn remove_child(&mut self, child: usize) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: remove_child({})", child);
-        self.state.emitter.remove_child(child as u32);
+        self.emit().remove_child(child as u32);
     }
 
-    pub fn insert_before(&self) {
+    pub fn insert_before(&mut self) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: insert_before()");
-        self.state.emitter.insert_before();
+        self.emit().insert_before();
     }
 
-    pub fn ensure_string(&mut self, string: &str) -> StringKey {
-        self.state
-            .strings
-            .ensure_string(string, &self.state.emitter)
-    }
-
-    pub fn set_text(&self, text: &str) {
+    pub fn set_text(&mut self, text: &str) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: set_text({:?})", text);
-        self.state
-            .emitter
-            .set_text(text.as_ptr() as u32, text.len() as u32);
+        self.emit().set_text(text);
     }
 
-    pub fn remove_self_and_next_siblings(&self) {
+    pub fn remove_self_and_next_siblings(&mut self) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: remove_self_and_next_siblings()");
-        self.state.emitter.remove_self_and_next_siblings();
+        self.emit().remove_self_and_next_siblings();
     }
 
-    pub fn replace_with(&self) {
+    pub fn replace_with(&mut self) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: replace_with()");
-        self.state.emitter.replace_with();
+        self.emit().replace_with();
     }
 
-    pub fn set_attribute(&mut self, name: &str, value: &str, is_namespaced: bool) {
+    pub fn replace_siblings_with(&mut self, count: usize) {
+        debug_assert!(self.traversal_is_committed());
+        debug!("emit: replace_siblings_with({})", count);
+        self.emit().replace_siblings_with(count as u32);
+    }
+
+    /// Queue setting an attribute on the node on top of the change list stack.
+    ///
+    /// The sink may batch attributes that don't have any special handling
+    /// until `flush_attributes`, to cut down on the number of instructions
+    /// the interpreter has to dispatch.
+    pub fn queue_set_attribute(&mut self, name: &str, value: &str, is_namespaced: bool) {
         debug_assert!(self.traversal_is_committed());
         if name == "class" && !is_namespaced {
-            let class_id = self.ensure_string(value);
             debug!("emit: set_class({:?})", value);
-            self.state.emitter.set_class(class_id.into());
+            self.emit().set_class(value);
+        } else if is_custom_property(name) {
+            debug!("emit: set_style_property({:?}, {:?})", name, value);
+            self.emit().set_style_property(name, value);
+        } else {
+            debug!("queue: set_attribute({:?}, {:?})", name, value);
+            self.emit().set_attribute(name, value);
+            self.attributes_are_queued = true;
+        }
+    }
+
+    /// Queue removing an attribute from the node on top of the change list
+    /// stack. See `queue_set_attribute`.
+    pub fn queue_remove_attribute(&mut self, name: &str) {
+        debug_assert!(self.traversal_is_committed());
+        if is_custom_property(name) {
+            debug!("emit: remove_style_property({:?})", name);
+            self.emit().remove_style_property(name);
         } else {
-            let name_id = self.ensure_string(name);
-            let value_id = self.ensure_string(value);
-            debug!("emit: set_attribute({:?}, {:?})", name, value);
-            self.state
-                .emitter
-                .set_attribute(name_id.into(), value_id.into());
+            debug!("queue: remove_attribute({:?})", name);
+            self.emit().remove_attribute(name);
+            self.attributes_are_queued = true;
         }
     }
 
-    pub fn remove_attribute(&mut self, name: &str) {
+    /// Set a property to a JavaScript value on the node on top of the change
+    /// list stack.
+    ///
+    /// Any queued attributes are flushed first, so that properties such as
+    /// `value` see the node's new `type`, `min` and `max`.
+    pub fn set_property(&mut self, name: &str, value: &wasm_bindgen::JsValue) {
         debug_assert!(self.traversal_is_committed());
-        debug!("emit: remove_attribute({:?})", name);
-        let name_id = self.ensure_string(name);
-        self.state.emitter.remove_attribute(name_id.into());
+        self.flush_attributes();
+        debug!("emit: set_property({:?}, ..)", name);
+        self.emit().set_property(name, value);
     }
 
-    pub fn append_child(&self) {
+    /// Reset a property that was set with `set_property` on the node on top of
+    /// the change list stack.
+    pub fn remove_property(&mut self, name: &str) {
+        debug_assert!(self.traversal_is_committed());
+        self.flush_attributes();
+        debug!("emit: remove_property({:?})", name);
+        self.emit().remove_property(name);
+    }
+
+    /// Apply the attribute sets and removals queued up by
+    /// `queue_set_attribute` and `queue_remove_attribute`.
+    ///
+    /// Must be called before moving off of the node the attributes were
+    /// queued for.
+    pub fn flush_attributes(&mut self) {
+        if self.attributes_are_queued {
+            self.state.sink.flush_attributes();
+            self.attributes_are_queued = false;
+        }
+    }
+
+    pub fn append_child(&mut self) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: append_child()");
-        self.state.emitter.append_child();
+        self.emit().append_child();
     }
 
-    pub fn create_text_node(&self, text: &str) {
+    pub fn create_text_node(&mut self, text: &str) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: create_text_node({:?})", text);
-        self.state
-            .emitter
-            .create_text_node(text.as_ptr() as u32, text.len() as u32);
+        self.emit().create_text_node(text);
+    }
+
+    pub fn create_element_from_html(&mut self, html: String) {
+        debug_assert!(self.traversal_is_committed());
+        debug!("emit: create_element_from_html({:?})", html);
+        self.emit().create_element_from_html(html);
+    }
+
+    pub fn push_raw_node(&mut self, node: &web_sys::Node) {
+        debug_assert!(self.traversal_is_committed());
+        debug!("emit: push_raw_node(..)");
+        self.emit().push_raw_node(node);
     }
 
     pub fn create_element(&mut self, tag_name: &str) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: create_element({:?})", tag_name);
-        let tag_name_id = self.ensure_string(tag_name);
-        self.state.emitter.create_element(tag_name_id.into());
+        self.emit().create_element(tag_name);
+    }
+
+    pub fn create_element_with_is(&mut self, tag_name: &str, is: &str) {
+        debug_assert!(self.traversal_is_committed());
+        debug!("emit: create_element_with_is({:?}, {:?})", tag_name, is);
+        self.emit().create_element_with_is(tag_name, is);
     }
 
     pub fn create_element_ns(&mut self, tag_name: &str, ns: &str) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: create_element_ns({:?}, {:?})", tag_name, ns);
-        let tag_name_id = self.ensure_string(tag_name);
-        let ns_id = self.ensure_string(ns);
-        self.state
-            .emitter
-            .create_element_ns(tag_name_id.into(), ns_id.into());
+        self.emit().create_element_ns(tag_name, ns);
     }
 
     pub fn push_force_new_listeners(&mut self) -> bool {
@@ -293,6 +596,11 @@ impl ChangeListBuilder<'_> {
         old
     }
 
+    #[inline]
+    pub fn is_forcing_new_listeners(&self) -> bool {
+        self.forcing_new_listeners
+    }
+
     pub fn pop_force_new_listeners(&mut self, previous: bool) {
         debug_assert!(self.forcing_new_listeners);
         self.forcing_new_listeners = previous;
@@ -301,10 +609,7 @@ impl ChangeListBuilder<'_> {
     pub fn new_event_listener(&mut self, listener: &Listener) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: new_event_listener({:?})", listener);
-        let (a, b) = listener.get_callback_parts();
-        debug_assert!(a != 0);
-        let event_id = self.ensure_string(listener.event);
-        self.state.emitter.new_event_listener(event_id.into(), a, b);
+        self.emit().new_event_listener(listener);
     }
 
     pub fn update_event_listener(&mut self, listener: &Listener) {
@@ -316,19 +621,13 @@ impl ChangeListBuilder<'_> {
         }
 
         debug!("emit: update_event_listener({:?})", listener);
-        let (a, b) = listener.get_callback_parts();
-        debug_assert!(a != 0);
-        let event_id = self.ensure_string(listener.event);
-        self.state
-            .emitter
-            .update_event_listener(event_id.into(), a, b);
+        self.emit().update_event_listener(listener);
     }
 
     pub fn remove_event_listener(&mut self, event: &str) {
         debug_assert!(self.traversal_is_committed());
         debug!("emit: remove_event_listener({:?})", event);
-        let event_id = self.ensure_string(event);
-        self.state.emitter.remove_event_listener(event_id.into());
+        self.emit().remove_event_listener(event);
     }
 
     #[inline]
@@ -341,13 +640,249 @@ impl ChangeListBuilder<'_> {
         debug_assert!(!self.has_template(id));
         debug!("emit: save_template({:?})", id);
         self.state.templates.insert(id);
-        self.state.emitter.save_template(id.into());
+        self.emit().save_template(id);
     }
 
     pub fn push_template(&mut self, id: CacheId) {
         debug_assert!(self.traversal_is_committed());
         debug_assert!(self.has_template(id));
         debug!("emit: push_template({:?})", id);
-        self.state.emitter.push_template(id.into());
+        self.emit().push_template(id);
+    }
+}
+
+/// CSS custom properties (`--accent`, etc) cannot be HTML attributes, so
+/// "attributes" with these names are set on the element's inline style
+/// declaration instead.
+#[inline]
+fn is_custom_property(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builder, CachedSet};
+    use bumpalo::Bump;
+    use wasm_bindgen::JsValue;
+
+    /// A sink that records a description of each operation it receives.
+    #[derive(Debug, Default)]
+    struct RecordingSink {
+        ops: Vec<String>,
+    }
+
+    impl ChangeSink for RecordingSink {
+        fn push_child(&mut self, n: u32) {
+            self.ops.push(format!("push_child({})", n));
+        }
+        fn push_reverse_child(&mut self, n: u32) {
+            self.ops.push(format!("push_reverse_child({})", n));
+        }
+        fn pop_push_child(&mut self, n: u32) {
+            self.ops.push(format!("pop_push_child({})", n));
+        }
+        fn pop_push_reverse_child(&mut self, n: u32) {
+            self.ops.push(format!("pop_push_reverse_child({})", n));
+        }
+        fn pop(&mut self) {
+            self.ops.push("pop".into());
+        }
+        fn push_temporary(&mut self, temp: u32) {
+            self.ops.push(format!("push_temporary({})", temp));
+        }
+        fn save_children_to_temporaries(&mut self, temp_base: u32, start: u32, end: u32) {
+            self.ops.push(format!(
+                "save_children_to_temporaries({}, {}, {})",
+                temp_base, start, end
+            ));
+        }
+        fn remove_child(&mut self, n: u32) {
+            self.ops.push(format!("remove_child({})", n));
+        }
+        fn insert_before(&mut self) {
+            self.ops.push("insert_before".into());
+        }
+        fn append_child(&mut self) {
+            self.ops.push("append_child".into());
+        }
+        fn replace_with(&mut self) {
+            self.ops.push("replace_with".into());
+        }
+        fn replace_siblings_with(&mut self, count: u32) {
+            self.ops.push(format!("replace_siblings_with({})", count));
+        }
+        fn remove_self_and_next_siblings(&mut self) {
+            self.ops.push("remove_self_and_next_siblings".into());
+        }
+        fn create_text_node(&mut self, text: &str) {
+            self.ops.push(format!("create_text_node({})", text));
+        }
+        fn create_element(&mut self, tag_name: &str) {
+            self.ops.push(format!("create_element({})", tag_name));
+        }
+        fn create_element_ns(&mut self, tag_name: &str, ns: &str) {
+            self.ops
+                .push(format!("create_element_ns({}, {})", tag_name, ns));
+        }
+        fn create_element_with_is(&mut self, tag_name: &str, is: &str) {
+            self.ops
+                .push(format!("create_element_with_is({}, {})", tag_name, is));
+        }
+        fn create_element_from_html(&mut self, html: String) {
+            self.ops.push(format!("create_element_from_html({})", html));
+        }
+        fn push_raw_node(&mut self, _node: &web_sys::Node) {
+            self.ops.push("push_raw_node".into());
+        }
+        fn set_text(&mut self, text: &str) {
+            self.ops.push(format!("set_text({})", text));
+        }
+        fn set_attribute(&mut self, name: &str, value: &str) {
+            self.ops.push(format!("set_attribute({}, {})", name, value));
+        }
+        fn remove_attribute(&mut self, name: &str) {
+            self.ops.push(format!("remove_attribute({})", name));
+        }
+        fn flush_attributes(&mut self) {
+            self.ops.push("flush_attributes".into());
+        }
+        fn set_class(&mut self, class: &str) {
+            self.ops.push(format!("set_class({})", class));
+        }
+        fn set_style_property(&mut self, name: &str, value: &str) {
+            self.ops
+                .push(format!("set_style_property({}, {})", name, value));
+        }
+        fn remove_style_property(&mut self, name: &str) {
+            self.ops.push(format!("remove_style_property({})", name));
+        }
+        fn set_property(&mut self, name: &str, _value: &JsValue) {
+            self.ops.push(format!("set_property({})", name));
+        }
+        fn remove_property(&mut self, name: &str) {
+            self.ops.push(format!("remove_property({})", name));
+        }
+        fn new_event_listener(&mut self, listener: &Listener) {
+            self.ops
+                .push(format!("new_event_listener({})", listener.event()));
+        }
+        fn update_event_listener(&mut self, listener: &Listener) {
+            self.ops
+                .push(format!("update_event_listener({})", listener.event()));
+        }
+        fn remove_event_listener(&mut self, event: &str) {
+            self.ops.push(format!("remove_event_listener({})", event));
+        }
+        fn save_template(&mut self, _id: CacheId) {
+            self.ops.push("save_template".into());
+        }
+        fn push_template(&mut self, _id: CacheId) {
+            self.ops.push("push_template".into());
+        }
+        fn finish(&mut self) {
+            self.ops.push("finish".into());
+        }
+    }
+
+    #[test]
+    fn builder_forwards_every_operation_to_sink() {
+        let bump = Bump::new();
+        let click = builder::on(&bump, "click", |_, _, _| {});
+        let template = CachedSet::default().insert(|cx| builder::div(&cx).finish());
+
+        let mut state = ChangeListPersistentState::with_sink(RecordingSink::default());
+        let mut change_list = state.builder();
+
+        change_list.go_down_to_child(1);
+        change_list.go_to_sibling(2);
+        change_list.commit_traversal();
+        change_list.set_text("hello");
+        change_list.go_up();
+        change_list.go_down_to_reverse_child(0);
+        change_list.commit_traversal();
+        change_list.queue_set_attribute("id", "a", false);
+        change_list.queue_set_attribute("class", "b", false);
+        change_list.queue_set_attribute("class", "c", true);
+        change_list.queue_set_attribute("--accent", "red", false);
+        change_list.queue_remove_attribute("title");
+        change_list.queue_remove_attribute("--accent");
+        change_list.set_property("x", &JsValue::NULL);
+        change_list.remove_property("x");
+        // Setting the property flushed the queue, so there is nothing to flush.
+        change_list.flush_attributes();
+        change_list.new_event_listener(&click);
+        change_list.update_event_listener(&click);
+        let forcing = change_list.push_force_new_listeners();
+        change_list.update_event_listener(&click);
+        change_list.pop_force_new_listeners(forcing);
+        change_list.remove_event_listener("click");
+        let temp = change_list.save_children_to_temporaries(0, 2);
+        change_list.go_down_to_temp_child(temp + 1);
+        change_list.commit_traversal();
+        change_list.go_to_temp_sibling(temp);
+        change_list.commit_traversal();
+        change_list.push_temporary(temp);
+        change_list.insert_before();
+        change_list.remove_child(0);
+        change_list.create_text_node("text");
+        change_list.create_element("div");
+        change_list.create_element_ns("svg", "http://www.w3.org/2000/svg");
+        change_list.create_element_with_is("button", "fancy-button");
+        change_list.create_element_from_html("<p>hi</p>".into());
+        change_list.append_child();
+        change_list.replace_with();
+        change_list.replace_siblings_with(2);
+        change_list.remove_self_and_next_siblings();
+        change_list.save_template(template);
+        assert!(change_list.has_template(template));
+        change_list.push_template(template);
+        change_list.go_up();
+        change_list.commit_traversal();
+        change_list.finish();
+
+        assert_eq!(
+            state.sink.ops,
+            vec![
+                "push_child(2)",
+                "set_text(hello)",
+                "pop_push_reverse_child(0)",
+                "set_attribute(id, a)",
+                "set_class(b)",
+                "set_attribute(class, c)",
+                "set_style_property(--accent, red)",
+                "remove_attribute(title)",
+                "remove_style_property(--accent)",
+                "flush_attributes",
+                "set_property(x)",
+                "remove_property(x)",
+                "new_event_listener(click)",
+                "update_event_listener(click)",
+                "new_event_listener(click)",
+                "remove_event_listener(click)",
+                "save_children_to_temporaries(0, 0, 2)",
+                "push_temporary(1)",
+                "pop",
+                "push_temporary(0)",
+                "push_temporary(0)",
+                "insert_before",
+                "remove_child(0)",
+                "create_text_node(text)",
+                "create_element(div)",
+                "create_element_ns(svg, http://www.w3.org/2000/svg)",
+                "create_element_with_is(button, fancy-button)",
+                "create_element_from_html(<p>hi</p>)",
+                "append_child",
+                "replace_with",
+                "replace_siblings_with(2)",
+                "remove_self_and_next_siblings",
+                "save_template",
+                "push_template",
+                "pop",
+                "finish",
+            ]
+        );
+        // Everything but flushing and finishing counts as an operation.
+        assert_eq!(state.op_count(), 34);
     }
 }
@@ -0,0 +1,177 @@
+//! The `ChangeSink` trait: the interface between diffing and a rendering
+//! backend.
+//!
+//! Diffing produces a sequence of operations for a simple stack machine. Each
+//! operation manipulates the node on top of the stack, or pushes and pops
+//! nodes. A `ChangeSink` receives these operations and applies them to some
+//! backend. The default sink encodes them into a change list that is
+//! interpreted in JavaScript against the physical DOM, but other sinks might
+//! record them for testing, or drive a native UI toolkit.
+
+use crate::{cached_set::CacheId, Listener};
+use wasm_bindgen::JsValue;
+
+/// A rendering backend that applies the operations produced by diffing.
+///
+/// In the stack effect comments below, the top of the stack is on the right.
+/// Temporaries are a separate array of saved nodes, indexed by number.
+pub trait ChangeSink {
+    /// Stack: `[... Node] -> [... Node Child]`
+    ///
+    /// Push the top node's `n`th child.
+    fn push_child(&mut self, n: u32);
+
+    /// Stack: `[... Node] -> [... Node Child]`
+    ///
+    /// Push the top node's `n`th from last child.
+    fn push_reverse_child(&mut self, n: u32);
+
+    /// Stack: `[... Parent Child] -> [... Parent Sibling]`
+    ///
+    /// Replace the top node with its parent's `n`th child.
+    fn pop_push_child(&mut self, n: u32);
+
+    /// Stack: `[... Parent Child] -> [... Parent Sibling]`
+    ///
+    /// Replace the top node with its parent's `n`th from last child.
+    fn pop_push_reverse_child(&mut self, n: u32);
+
+    /// Stack: `[... Node] -> [...]`
+    fn pop(&mut self);
+
+    /// Stack: `[...] -> [... Node]`
+    ///
+    /// Push the node saved as the given temporary.
+    fn push_temporary(&mut self, temp: u32);
+
+    /// Stack: `[... Parent] -> [... Parent]`
+    ///
+    /// Save the top node's children in the range `start..end` as the
+    /// temporaries `temp_base..temp_base + (end - start)`.
+    fn save_children_to_temporaries(&mut self, temp_base: u32, start: u32, end: u32);
+
+    /// Stack: `[... Parent] -> [... Parent]`
+    ///
+    /// Remove the top node's `n`th child.
+    fn remove_child(&mut self, n: u32);
+
+    /// Stack: `[... Next New] -> [... New]`
+    ///
+    /// Insert the top node before the node beneath it, which is then popped.
+    fn insert_before(&mut self);
+
+    /// Stack: `[... Parent Child] -> [... Parent]`
+    fn append_child(&mut self);
+
+    /// Stack: `[... Old New] -> [... New]`
+    ///
+    /// Replace the node beneath the top node with the top node.
+    fn replace_with(&mut self);
+
+    /// Stack: `[... Old New_1 ... New_count] -> [... New_count]`
+    ///
+    /// Replace `Old` and its next `count - 1` siblings with the `count` new
+    /// nodes on top of the stack, all at once.
+    fn replace_siblings_with(&mut self, count: u32);
+
+    /// Stack: `[... Node] -> [...]`
+    ///
+    /// Remove the top node and all of its following siblings.
+    fn remove_self_and_next_siblings(&mut self);
+
+    /// Stack: `[...] -> [... TextNode]`
+    fn create_text_node(&mut self, text: &str);
+
+    /// Stack: `[...] -> [... Element]`
+    fn create_element(&mut self, tag_name: &str);
+
+    /// Stack: `[...] -> [... Element]`
+    fn create_element_ns(&mut self, tag_name: &str, ns: &str);
+
+    /// Stack: `[...] -> [... Element]`
+    ///
+    /// Create a customized built-in element. See `ElementBuilder::is`.
+    fn create_element_with_is(&mut self, tag_name: &str, is: &str);
+
+    /// Stack: `[...] -> [... Element]`
+    ///
+    /// Create an element, and its whole subtree, from the given HTML. The
+    /// subtree is fully static: see the `html` module for details.
+    fn create_element_from_html(&mut self, html: String);
+
+    /// Stack: `[...] -> [... Node]`
+    ///
+    /// Push an existing physical DOM node, rather than creating one. See
+    /// `Node::raw`.
+    fn push_raw_node(&mut self, node: &web_sys::Node);
+
+    /// Stack: `[... TextNode] -> [... TextNode]`
+    fn set_text(&mut self, text: &str);
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// Sinks may defer applying attribute changes until `flush_attributes` is
+    /// called.
+    fn set_attribute(&mut self, name: &str, value: &str);
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// See `set_attribute`.
+    fn remove_attribute(&mut self, name: &str);
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// Apply any deferred `set_attribute` and `remove_attribute` operations.
+    /// This is always called before moving off of the element they were for.
+    fn flush_attributes(&mut self) {}
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// Set the class of a non-namespaced element.
+    fn set_class(&mut self, class: &str);
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// Set a CSS custom property, such as `--accent`, on the element's inline
+    /// style.
+    fn set_style_property(&mut self, name: &str, value: &str);
+
+    /// Stack: `[... Element] -> [... Element]`
+    fn remove_style_property(&mut self, name: &str);
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// Set a property of the element to an arbitrary JavaScript value.
+    fn set_property(&mut self, name: &str, value: &JsValue);
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// Reset a property set with `set_property`.
+    fn remove_property(&mut self, name: &str);
+
+    /// Stack: `[... Element] -> [... Element]`
+    fn new_event_listener(&mut self, listener: &Listener);
+
+    /// Stack: `[... Element] -> [... Element]`
+    ///
+    /// Replace the callback of the element's existing listener for
+    /// `listener`'s event.
+    fn update_event_listener(&mut self, listener: &Listener);
+
+    /// Stack: `[... Element] -> [... Element]`
+    fn remove_event_listener(&mut self, event: &str);
+
+    /// Stack: `[... Node] -> [... Node]`
+    ///
+    /// Save the top node as the template for the given cached node.
+    fn save_template(&mut self, id: CacheId);
+
+    /// Stack: `[...] -> [... Node]`
+    ///
+    /// Push a deep clone of the template saved for the given cached node.
+    fn push_template(&mut self, id: CacheId);
+
+    /// Apply all of the operations received since the last call to `finish`.
+    /// The stack and temporaries are empty afterwards.
+    fn finish(&mut self);
+}
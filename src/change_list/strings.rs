@@ -38,8 +38,14 @@ impl StringsCache {
             let key = StringKey(self.next_string_key);
             self.next_string_key += 1;
             let entry = StringsCacheEntry { key, used: true };
-            self.entries.insert(string.to_string(), entry);
+            // Point the instruction at our own copy of the string, rather than
+            // the given one, so that the given string doesn't have to outlive
+            // the change list. Moving the copy into the map does not move its
+            // heap buffer, and the entry is not dropped before the change list
+            // is applied, since it is marked as used.
+            let string = string.to_string();
             emitter.add_cached_string(string.as_ptr() as u32, string.len() as u32, key.into());
+            self.entries.insert(string, entry);
             key
         }
     }
@@ -1,9 +1,15 @@
 use crate::{
     cached_set::{CacheId, CachedSet},
-    change_list::ChangeListBuilder,
+    change_list::{ChangeListBuilder, ChangeSink},
     events::EventsRegistry,
-    node::{Attribute, ElementNode, Listener, Node, NodeKind, TextNode},
+    html::{is_valid_attribute_name, StaticCounts, StaticHtml},
+    node::{
+        Attribute, AttributeValue, ElementNode, Listener, Node, NodeKey, NodeKind, RawNode,
+        TextNode,
+    },
+    observer::ChangeKind,
 };
+use bumpalo::Bump;
 use fxhash::{FxHashMap, FxHashSet};
 use std::cmp::Ordering;
 use std::u32;
@@ -18,9 +24,9 @@ use wasm_bindgen::UnwrapThrowExt;
 //     [... node]
 //
 // The change list stack is in the same state when this function exits.
-pub(crate) fn diff(
+pub(crate) fn diff<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &Node,
     new: &Node,
@@ -37,11 +43,15 @@ pub(crate) fn diff(
             }
         }
 
+        // Even when the element's only child is a text node with the same
+        // text, e.g. `<span>text</span>` becoming `text`, the structure
+        // changes, so the node is replaced rather than reusing the text node.
         (&NodeKind::Text(_), &NodeKind::Element(_)) => {
             change_list.commit_traversal();
             create(cached_set, change_list, registry, new, cached_roots);
             registry.remove_subtree(&old);
             change_list.replace_with();
+            observe_replaced(change_list);
         }
 
         (&NodeKind::Element(_), &NodeKind::Text(_)) => {
@@ -50,43 +60,66 @@ pub(crate) fn diff(
             // Note: text nodes cannot have event listeners, so we don't need to
             // remove the old node's listeners from our registry her.
             change_list.replace_with();
+            observe_replaced(change_list);
         }
 
         (
             &NodeKind::Element(ElementNode {
                 key: _,
-                tag_name: new_tag_name,
+                tag_name: _,
                 listeners: new_listeners,
                 attributes: new_attributes,
                 children: new_children,
                 namespace: new_namespace,
+                managed_children,
             }),
             &NodeKind::Element(ElementNode {
-                key: _,
-                tag_name: old_tag_name,
                 listeners: old_listeners,
                 attributes: old_attributes,
                 children: old_children,
-                namespace: old_namespace,
+                ..
             }),
         ) => {
-            if new_tag_name != old_tag_name || new_namespace != old_namespace {
+            if cfg!(debug_assertions) {
+                check_aliasing(old, new);
+            }
+            if must_replace(old, new) {
+                // The physical element can't be changed into the new one, so
+                // it is recreated. Note that `create` registers and attaches
+                // all of `new`'s listeners to the recreated element, so event
+                // handling carries over. We can't move `old`'s listeners over
+                // instead, because they are freed along with the old render.
                 change_list.commit_traversal();
                 create(cached_set, change_list, registry, new, cached_roots);
                 registry.remove_subtree(&old);
                 change_list.replace_with();
+                observe_replaced(change_list);
                 return;
             }
             diff_listeners(change_list, registry, old_listeners, new_listeners);
-            diff_attributes(change_list, old_attributes, new_attributes, new_namespace.is_some());
-            diff_children(
-                cached_set,
+            diff_attributes(
                 change_list,
-                registry,
-                old_children,
-                new_children,
-                cached_roots,
+                old_attributes,
+                new_attributes,
+                new_namespace.is_some(),
             );
+            if *managed_children {
+                // The physical children belong to someone else now, so leave
+                // them be. Their old listeners' callbacks are about to be
+                // freed, though, so stop dispatching events to them.
+                for child in old_children.iter() {
+                    registry.remove_subtree(child);
+                }
+            } else {
+                diff_children(
+                    cached_set,
+                    change_list,
+                    registry,
+                    old_children,
+                    new_children,
+                    cached_roots,
+                );
+            }
         }
 
         // Both the new and old nodes are cached.
@@ -146,57 +179,186 @@ pub(crate) fn diff(
             create(cached_set, change_list, registry, new, cached_roots);
             registry.remove_subtree(&old);
             change_list.replace_with();
+            observe_replaced(change_list);
+        }
+
+        // Raw nodes are only ever compared by identity. If it is the same
+        // physical node as last time, there is nothing to do.
+        (
+            &NodeKind::Raw(RawNode { node: new_node }),
+            &NodeKind::Raw(RawNode { node: old_node }),
+        ) if new_node == old_node => {}
+
+        // Otherwise, replace the old node with the new one, whichever of them
+        // is raw.
+        (&NodeKind::Raw(_), _) | (_, &NodeKind::Raw(_)) => {
+            change_list.commit_traversal();
+            create(cached_set, change_list, registry, new, cached_roots);
+            registry.remove_subtree(&old);
+            change_list.replace_with();
+            observe_replaced(change_list);
         }
     }
 }
 
+// Does `path` lead to a node within `old` through element nodes only? See
+// `patch`.
+pub(crate) fn patch_path_is_valid(old: &Node, path: &[usize]) -> bool {
+    let mut node = old;
+    for &index in path {
+        node = match node.kind {
+            NodeKind::Element(element) => match element.children.get(index) {
+                Some(child) => child,
+                None => return false,
+            },
+            _ => return false,
+        };
+    }
+    true
+}
+
+// Diff the subtree at `path` within `old` with `new_subtree`, and return a copy
+// of `old` in which that subtree is replaced by `new_subtree`. Only the
+// ancestors of the patched subtree are copied, into `bump`; everything else is
+// shared with `old`. The `path` must be valid, as checked by
+// `patch_path_is_valid`.
+//
+// Upon entry to this function, the physical DOM node for `old` must be on the
+// top of the change list stack:
+//
+//     [... node]
+//
+// The change list stack is in the same state when this function exits.
+pub(crate) fn patch<'a, S: ChangeSink>(
+    bump: &'a Bump,
+    cached_set: &CachedSet,
+    change_list: &mut ChangeListBuilder<S>,
+    registry: &mut EventsRegistry,
+    old: &Node<'a>,
+    path: &[usize],
+    new_subtree: Node<'a>,
+    cached_roots: &mut FxHashSet<CacheId>,
+) -> Node<'a> {
+    let (&index, rest) = match path.split_first() {
+        None => {
+            diff(
+                cached_set,
+                change_list,
+                registry,
+                old,
+                &new_subtree,
+                cached_roots,
+            );
+            return new_subtree;
+        }
+        Some(split) => split,
+    };
+
+    let old = match old.kind {
+        NodeKind::Element(old) => old,
+        _ => panic!("patch path should only lead through element nodes"),
+    };
+    let old_child = old
+        .children
+        .get(index)
+        .expect_throw("patch path should lead to an existing child");
+
+    change_list.go_down_to_child(index);
+    change_list.enter_child(index);
+    let new_child = patch(
+        bump,
+        cached_set,
+        change_list,
+        registry,
+        old_child,
+        rest,
+        new_subtree,
+        cached_roots,
+    );
+    change_list.exit_child();
+    change_list.go_up();
+
+    let children = bump.alloc_slice_clone(old.children);
+    children[index] = new_child;
+    Node::from_element_node(bump, ElementNode { children, ..*old })
+}
+
 // Diff event listeners between `old` and `new`.
 //
+// Physical listeners are registered in the order that they were added with the
+// builder, and are never re-registered just because they moved within `new`:
+// new listeners are registered in `new`'s order, after all existing ones, and
+// removed listeners are unregistered in `old`'s order, after all changes to
+// `new`'s listeners. So the emitted operations only depend on the two lists.
+//
 // The listeners' node must be on top of the change list stack:
 //
 //     [... node]
 //
 // The change list stack is left unchanged.
-fn diff_listeners(
-    change_list: &mut ChangeListBuilder,
+fn diff_listeners<S: ChangeSink>(
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Listener],
     new: &[Listener],
 ) {
-    if !old.is_empty() || !new.is_empty() {
-        change_list.commit_traversal();
-    }
-
     'outer1: for new_l in new {
-        unsafe {
-            // Safety relies on removing `new_l` from the registry manually at
-            // the end of its lifetime. This happens below in the `'outer2`
-            // loop, and elsewhere in diffing when removing old dom trees.
-            registry.add(new_l);
-        }
-
         for old_l in old {
             if new_l.event == old_l.event {
-                change_list.update_event_listener(new_l);
+                if !new_l.has_same_passivity(old_l) {
+                    // The physical listener's options can't be changed, so
+                    // it is removed and added again.
+                    add_listener(registry, new_l);
+                    change_list.commit_traversal();
+                    change_list.remove_event_listener(old_l.event);
+                    change_list.new_event_listener(new_l);
+                } else if new_l.has_same_id(old_l) && !change_list.is_forcing_new_listeners() {
+                    // The physical listener stays as it is, and the registry
+                    // entry it refers to is pointed at the new callback.
+                    new_l.take_over(old_l);
+                    registry.remove(old_l);
+                    add_listener(registry, new_l);
+                } else {
+                    add_listener(registry, new_l);
+                    change_list.commit_traversal();
+                    change_list.update_event_listener(new_l);
+                }
                 continue 'outer1;
             }
         }
 
+        add_listener(registry, new_l);
+        change_list.commit_traversal();
         change_list.new_event_listener(new_l);
     }
 
     'outer2: for old_l in old {
-        registry.remove(old_l);
-
         for new_l in new {
             if new_l.event == old_l.event {
+                // Leave `old_l`'s registry entry alone if `new_l` took it
+                // over above.
+                if new_l.registry_parts() != old_l.registry_parts() {
+                    registry.remove(old_l);
+                }
                 continue 'outer2;
             }
         }
+        registry.remove(old_l);
+        change_list.commit_traversal();
         change_list.remove_event_listener(old_l.event);
     }
 }
 
+fn add_listener(registry: &mut EventsRegistry, listener: &Listener) {
+    unsafe {
+        // Safety relies on removing `listener` from the registry manually at
+        // the end of its lifetime. This happens in the `'outer2` loop of
+        // `diff_listeners`, and elsewhere in diffing when removing old dom
+        // trees.
+        registry.add(listener);
+    }
+}
+
 // Diff a node's attributes.
 //
 // The attributes' node must be on top of the change list stack:
@@ -204,38 +366,294 @@ fn diff_listeners(
 //     [... node]
 //
 // The change list stack is left unchanged.
-fn diff_attributes(change_list: &mut ChangeListBuilder, old: &[Attribute], new: &[Attribute], is_namespaced: bool) {
+fn diff_attributes<S: ChangeSink>(
+    change_list: &mut ChangeListBuilder<S>,
+    old: &[Attribute],
+    new: &[Attribute],
+    is_namespaced: bool,
+) {
     // Do O(n^2) passes to add/update and remove attributes, since
     // there are almost always very few attributes.
+    //
+    // All the changes are queued up and then flushed at the end, so that they
+    // are emitted as (at most) one batched set and one batched removal.
+    //
+    // Namespaced elements' attributes, including SVG presentation attributes
+    // like `fill`, always go through `setAttribute`, never a property.
     'outer: for new_attr in new {
-        if new_attr.is_volatile() {
+        if new_attr.is_create_only() || new_attr.is_customized_builtin_name(is_namespaced) {
+            continue;
+        } else if new_attr.is_volatile() {
             change_list.commit_traversal();
-            change_list.set_attribute(new_attr.name, new_attr.value, is_namespaced);
+            set_attribute_value(change_list, new_attr.name, new_attr.value, is_namespaced);
+            if change_list.observes_attributes() {
+                let old_value = old
+                    .iter()
+                    .find(|old_attr| old_attr.name == new_attr.name)
+                    .map(|old_attr| old_attr.value);
+                if old_value != Some(new_attr.value) {
+                    change_list.observe_attribute(new_attr.name, old_value, Some(new_attr.value));
+                }
+            }
         } else {
             for old_attr in old {
                 if old_attr.name == new_attr.name {
-                    if old_attr.value != new_attr.value {
+                    if !attribute_values_are_equal(new_attr.name, old_attr.value, new_attr.value)
+                        && !change_list.attribute_values_are_equivalent(
+                            new_attr.name,
+                            old_attr.value,
+                            new_attr.value,
+                        )
+                    {
                         change_list.commit_traversal();
-                        change_list.set_attribute(new_attr.name, new_attr.value, is_namespaced);
+                        if let AttributeValue::JsValue(_) = old_attr.value {
+                            change_list.remove_property(old_attr.name);
+                        }
+                        set_attribute_value(
+                            change_list,
+                            new_attr.name,
+                            new_attr.value,
+                            is_namespaced,
+                        );
+                        change_list.observe_attribute(
+                            new_attr.name,
+                            Some(old_attr.value),
+                            Some(new_attr.value),
+                        );
                     }
                     continue 'outer;
                 }
             }
 
-            change_list.commit_traversal();
-            change_list.set_attribute(new_attr.name, new_attr.value, is_namespaced);
+            if !new_attr.value.is_absent() {
+                change_list.commit_traversal();
+                set_attribute_value(change_list, new_attr.name, new_attr.value, is_namespaced);
+                change_list.observe_attribute(new_attr.name, None, Some(new_attr.value));
+            }
         }
     }
 
     'outer2: for old_attr in old {
+        if old_attr.is_create_only() || old_attr.is_customized_builtin_name(is_namespaced) {
+            continue;
+        }
+
         for new_attr in new {
             if old_attr.name == new_attr.name {
                 continue 'outer2;
             }
         }
 
-        change_list.commit_traversal();
-        change_list.remove_attribute(old_attr.name);
+        match old_attr.value {
+            AttributeValue::Bool(false) => {}
+            AttributeValue::JsValue(_) => {
+                change_list.commit_traversal();
+                change_list.remove_property(old_attr.name);
+                change_list.observe_attribute(old_attr.name, Some(old_attr.value), None);
+            }
+            _ => {
+                change_list.commit_traversal();
+                change_list.queue_remove_attribute(old_attr.name);
+                change_list.observe_attribute(old_attr.name, Some(old_attr.value), None);
+            }
+        }
+    }
+
+    change_list.flush_attributes();
+}
+
+// Get the name of the customized built-in element that the given attributes
+// make their element, if any.
+fn customized_builtin_name<'a>(
+    attributes: &[Attribute<'a>],
+    is_namespaced: bool,
+) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attr| attr.is_customized_builtin_name(is_namespaced))
+        .and_then(|attr| attr.value.as_str())
+}
+
+// Are the old and new values of the attribute `name` equivalent, such that we
+// don't need to re-set it?
+//
+// Re-setting an unchanged attribute is not always harmless: re-setting a media
+// element's `src` reloads the media, restarting playback.
+//
+// The comma-separated lists of `srcset` and `sizes` attributes are often built
+// with `format!` and such, so whitespace-only differences are ignored for them.
+fn attribute_values_are_equal(name: &str, old: AttributeValue, new: AttributeValue) -> bool {
+    match (old, new) {
+        (AttributeValue::Str(old), AttributeValue::Str(new)) => {
+            // Fast path for the common case where both renders used the same
+            // string, e.g. a `&'static str`.
+            if old.as_ptr() == new.as_ptr() && old.len() == new.len() {
+                return true;
+            }
+            match name {
+                "srcset" | "sizes" => list_values_are_equal(old, new),
+                _ => old == new,
+            }
+        }
+        (old, new) => old == new,
+    }
+}
+
+// Are the given comma-separated lists the same, ignoring whitespace around and
+// within their items?
+fn list_values_are_equal(old: &str, new: &str) -> bool {
+    let mut old_items = old.split(',');
+    let mut new_items = new.split(',');
+    loop {
+        match (old_items.next(), new_items.next()) {
+            (None, None) => return true,
+            (Some(old_item), Some(new_item)) => {
+                if !old_item.split_whitespace().eq(new_item.split_whitespace()) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+// Setting an attribute with an invalid name throws a DOM exception that is hard
+// to trace back to the offending node, so report such names up front.
+fn check_attribute_names(tag_name: &str, attributes: &[Attribute]) {
+    for attr in attributes {
+        if let AttributeValue::JsValue(_) = attr.value {
+            // Set as a property, not an attribute, so any name goes.
+            continue;
+        }
+        if !attr.name.starts_with("--") && !is_valid_attribute_name(attr.name) {
+            warn!(
+                "invalid attribute name {:?} on a <{}> element; setting it will throw",
+                attr.name, tag_name
+            );
+        }
+    }
+}
+
+// The old and new virtual DOMs are rendered into different bump arenas, and
+// the old one's arena is reset once the new one is rendered and diffed. If the
+// new virtual DOM shares an element, or an element's children, attributes, or
+// listeners, with the old one, then it will be left pointing into freed memory.
+// This only happens through misuse of `unsafe` code, such as extending a
+// node's lifetime to keep it around between renders, so warn about it. Cached
+// nodes are compared by their ids instead, and never reach here.
+fn check_aliasing(old: &Node, new: &Node) {
+    if let (NodeKind::Element(old_el), NodeKind::Element(new_el)) = (&old.kind, &new.kind) {
+        if is_aliased(old_el, new_el) {
+            warn!(
+                "the new virtual DOM shares a <{}> element with the old one, which will be \
+                 freed after this render; render a new node instead of reusing the old one",
+                new_el.tag_name
+            );
+        }
+    }
+}
+
+fn is_aliased(old: &ElementNode, new: &ElementNode) -> bool {
+    fn is_same_slice<T>(old: &[T], new: &[T]) -> bool {
+        !old.is_empty() && old.as_ptr() == new.as_ptr()
+    }
+    std::ptr::eq(old, new)
+        || is_same_slice(old.children, new.children)
+        || is_same_slice(old.attributes, new.attributes)
+        || is_same_slice(old.listeners, new.listeners)
+}
+
+// String `on*` attributes, such as `onclick="..."`, are set like any other
+// attribute, which makes the browser compile them into inline event handlers.
+// That works, but unlike listeners added with `ElementBuilder::on`, the handlers
+// cannot call back into Rust, and they are blocked by most content security
+// policies, so suggest using listeners instead.
+fn check_inline_event_handlers(tag_name: &str, attributes: &[Attribute]) {
+    for attr in attributes {
+        if let AttributeValue::Str(_) = attr.value {
+            if is_inline_event_handler_name(attr.name) {
+                warn!(
+                    "inline event handler attribute {:?} on a <{}> element; prefer a listener \
+                     added with `ElementBuilder::on(\"{}\", ...)`",
+                    attr.name,
+                    tag_name,
+                    &attr.name[2..]
+                );
+            }
+        }
+    }
+}
+
+fn is_inline_event_handler_name(name: &str) -> bool {
+    name.len() > 2 && name.starts_with("on") && name[2..].chars().all(|c| c.is_ascii_lowercase())
+}
+
+// Resource hints and some enumerated global attributes only accept a few
+// keywords, and browsers silently ignore anything else, so a typo such as
+// `fetchpriority="hgh"` or `spellcheck="no"` just quietly has no effect. Warn
+// about values that will be ignored.
+fn check_keyword_attributes(tag_name: &str, attributes: &[Attribute]) {
+    for attr in attributes {
+        if let AttributeValue::Str(value) = attr.value {
+            if let Some(expected) = invalid_keyword(attr.name, value) {
+                warn!(
+                    "invalid {} value {:?} on a <{}> element will be ignored; expected one of {:?}",
+                    attr.name, value, tag_name, expected
+                );
+            }
+        }
+    }
+}
+
+// If `name` is a keyword attribute and `value` is not one of its keywords, get
+// the keywords it does accept.
+fn invalid_keyword(name: &str, value: &str) -> Option<&'static [&'static str]> {
+    let expected: &'static [&'static str] = match name {
+        "fetchpriority" => &["high", "low", "auto"],
+        "loading" => &["eager", "lazy"],
+        "decoding" => &["sync", "async", "auto"],
+        // The empty string means `true` and `yes`, respectively.
+        "spellcheck" => &["true", "false", ""],
+        "translate" => &["yes", "no", ""],
+        "autocapitalize" => &["off", "none", "on", "sentences", "words", "characters"],
+        _ => return None,
+    };
+    if expected
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(value))
+    {
+        None
+    } else {
+        Some(expected)
+    }
+}
+
+// Apply the given attribute value to the node on top of the change list stack,
+// according to the value's type.
+//
+// The change list stack is left unchanged.
+fn set_attribute_value<S: ChangeSink>(
+    change_list: &mut ChangeListBuilder<S>,
+    name: &str,
+    value: AttributeValue,
+    is_namespaced: bool,
+) {
+    match value {
+        AttributeValue::Str(value) => {
+            change_list.queue_set_attribute(name, value, is_namespaced);
+        }
+        AttributeValue::Bool(true) => {
+            change_list.queue_set_attribute(name, "", is_namespaced);
+        }
+        AttributeValue::Bool(false) => {
+            change_list.queue_remove_attribute(name);
+        }
+        AttributeValue::Number(n) => {
+            change_list.queue_set_attribute(name, &n.to_string(), is_namespaced);
+        }
+        AttributeValue::JsValue(value) => {
+            change_list.set_property(name, value);
+        }
     }
 }
 
@@ -247,14 +665,16 @@ fn diff_attributes(change_list: &mut ChangeListBuilder, old: &[Attribute], new:
 //     [... parent]
 //
 // the change list stack is in the same state when this function returns.
-fn diff_children(
+fn diff_children<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Node],
     new: &[Node],
     cached_roots: &mut FxHashSet<CacheId>,
 ) {
+    change_list.diffing_children(old.len().max(new.len()));
+
     if new.is_empty() {
         if !old.is_empty() {
             change_list.commit_traversal();
@@ -267,13 +687,16 @@ fn diff_children(
         match (old.first(), &new[0]) {
             (
                 Some(&Node {
-                    kind: NodeKind::Text(TextNode { text: old_text }),
+                    kind: NodeKind::Text(_),
                 }),
                 &Node {
-                    kind: NodeKind::Text(TextNode { text: new_text }),
+                    kind: NodeKind::Text(_),
                 },
-            ) if old_text == new_text => {
-                // Don't take this fast path...
+            ) if old.len() == 1 => {
+                // Don't take this fast path, since setting the parent's
+                // `textContent` would replace the existing text node. Diff the
+                // text node below instead, which only updates its text, and
+                // only if it changed.
             }
             (
                 _,
@@ -286,6 +709,8 @@ fn diff_children(
                 for o in old {
                     registry.remove_subtree(o);
                 }
+                change_list.observe_children(0, old.len(), ChangeKind::Removed);
+                change_list.observe_child(0, ChangeKind::Created);
                 return;
             }
             (_, _) => {}
@@ -295,32 +720,93 @@ fn diff_children(
     if old.is_empty() {
         if !new.is_empty() {
             change_list.commit_traversal();
-            create_and_append_children(cached_set, change_list, registry, new, cached_roots);
+            create_and_append_children(cached_set, change_list, registry, new, 0, cached_roots);
         }
         return;
     }
 
-    let new_is_keyed = new[0].key().is_some();
-    let old_is_keyed = old[0].key().is_some();
-
-    debug_assert!(
-        new.iter().all(|n| n.key().is_some() == new_is_keyed),
-        "all siblings must be keyed or all siblings must be non-keyed"
-    );
-    debug_assert!(
-        old.iter().all(|o| o.key().is_some() == old_is_keyed),
-        "all siblings must be keyed or all siblings must be non-keyed"
-    );
+    // Siblings may mix keyed and unkeyed children (for example, a keyed list
+    // between an unkeyed header and footer). As long as there are keyed
+    // children on both sides, use keyed diffing, and match the unkeyed
+    // children up positionally amongst themselves.
+    let new_is_keyed = new.iter().any(|n| n.key().is_some());
+    let old_is_keyed = old.iter().any(|o| o.key().is_some());
 
     if new_is_keyed && old_is_keyed {
+        let old_keys = child_keys(old);
+        let new_keys = child_keys(new);
         let t = change_list.next_temporary();
-        diff_keyed_children(cached_set, change_list, registry, old, new, cached_roots);
+        diff_keyed_children(
+            cached_set,
+            change_list,
+            registry,
+            old,
+            new,
+            &old_keys,
+            &new_keys,
+            cached_roots,
+        );
         change_list.set_next_temporary(t);
     } else {
         diff_non_keyed_children(cached_set, change_list, registry, old, new, cached_roots);
     }
 }
 
+// The key that old and new children are matched up by when diffing keyed
+// children.
+//
+// Keyed children use their `NodeKey`. Unkeyed children that are siblings of
+// keyed children are keyed by their position amongst only the unkeyed siblings,
+// so that, e.g., an unkeyed header is always diffed against the old unkeyed
+// header, regardless of how the keyed children around it were reordered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum ChildKey {
+    Keyed(NodeKey),
+    Unkeyed(u32),
+}
+
+fn child_keys(children: &[Node]) -> Vec<ChildKey> {
+    let mut unkeyed_count = 0;
+    children
+        .iter()
+        .map(|child| {
+            let key = child.key();
+            if key.is_some() {
+                ChildKey::Keyed(key)
+            } else {
+                unkeyed_count += 1;
+                ChildKey::Unkeyed(unkeyed_count - 1)
+            }
+        })
+        .collect()
+}
+
+// Find the first key that is shared by more than one of the given siblings.
+fn find_duplicate_key(keys: &[ChildKey]) -> Option<NodeKey> {
+    let mut seen = FxHashSet::default();
+    seen.reserve(keys.len());
+    keys.iter().find_map(|key| match *key {
+        ChildKey::Keyed(key) if !seen.insert(key) => Some(key),
+        _ => None,
+    })
+}
+
+// Report that siblings share the same key. This is a warning, unless the
+// `strict-keys` feature is enabled, in which case it is a panic.
+fn report_duplicate_key(key: NodeKey) {
+    if cfg!(feature = "strict-keys") {
+        panic!(
+            "keyed siblings must each have a unique key, found duplicate {:?}",
+            key
+        );
+    } else {
+        warn!(
+            "keyed siblings must each have a unique key, found duplicate {:?}",
+            key
+        );
+    }
+}
+
 // Diffing "keyed" children.
 //
 // With keyed children, we care about whether we delete, move, or create nodes
@@ -336,40 +822,37 @@ fn diff_children(
 //
 // https://github.com/infernojs/inferno/blob/36fd96/packages/inferno/src/DOM/patching.ts#L530-L739
 //
+// Children are matched up by key alone, so a keyed child whose element type
+// changed (say, a `<span>` swapped for an `<input>` when editing) is still
+// matched with its old self. Diffing the pair then replaces the old physical
+// node in place, which keeps its siblings where they are, rather than removing
+// it and inserting the new one elsewhere.
+//
 // When entering this function, the parent must be on top of the change list
 // stack:
 //
 //     [... parent]
 //
 // Upon exiting, the change list stack is in the same state.
-fn diff_keyed_children(
+fn diff_keyed_children<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Node],
     new: &[Node],
+    old_keys: &[ChildKey],
+    new_keys: &[ChildKey],
     cached_roots: &mut FxHashSet<CacheId>,
 ) {
+    debug_assert_eq!(old.len(), old_keys.len());
+    debug_assert_eq!(new.len(), new_keys.len());
+
+    // Duplicate keys lead to incorrectly reusing and moving physical DOM
+    // nodes, so in debug builds, report them as early as possible.
     if cfg!(debug_assertions) {
-        let mut keys = FxHashSet::default();
-        let mut assert_unique_keys = |children: &[Node]| {
-            keys.clear();
-            for child in children {
-                let key = child.key();
-                debug_assert!(
-                    key.is_some(),
-                    "if any sibling is keyed, all siblings must be keyed"
-                );
-                keys.insert(key);
-            }
-            debug_assert_eq!(
-                children.len(),
-                keys.len(),
-                "keyed siblings must each have a unique key"
-            );
-        };
-        assert_unique_keys(old);
-        assert_unique_keys(new);
+        if let Some(key) = find_duplicate_key(new_keys) {
+            report_duplicate_key(key);
+        }
     }
 
     // First up, we diff all the nodes with the same key at the beginning of the
@@ -377,11 +860,19 @@ fn diff_keyed_children(
     //
     // `shared_prefix_count` is the count of how many nodes at the start of
     // `new` and `old` share the same keys.
-    let shared_prefix_count =
-        match diff_keyed_prefix(cached_set, change_list, registry, old, new, cached_roots) {
-            KeyedPrefixResult::Finished => return,
-            KeyedPrefixResult::MoreWorkToDo(count) => count,
-        };
+    let shared_prefix_count = match diff_keyed_prefix(
+        cached_set,
+        change_list,
+        registry,
+        old,
+        new,
+        old_keys,
+        new_keys,
+        cached_roots,
+    ) {
+        KeyedPrefixResult::Finished => return,
+        KeyedPrefixResult::MoreWorkToDo(count) => count,
+    };
 
     // Next, we find out how many of the nodes at the end of the children have
     // the same key. We do _not_ diff them yet, since we want to emit the change
@@ -390,11 +881,11 @@ fn diff_keyed_children(
     //
     // `shared_suffix_count` is the count of how many nodes at the end of `new`
     // and `old` share the same keys.
-    let shared_suffix_count = old[shared_prefix_count..]
+    let shared_suffix_count = old_keys[shared_prefix_count..]
         .iter()
         .rev()
-        .zip(new[shared_prefix_count..].iter().rev())
-        .take_while(|&(old, new)| old.key() == new.key())
+        .zip(new_keys[shared_prefix_count..].iter().rev())
+        .take_while(|&(old, new)| old == new)
         .count();
 
     let old_shared_suffix_start = old.len() - shared_suffix_count;
@@ -407,12 +898,16 @@ fn diff_keyed_children(
         cached_set,
         change_list,
         registry,
-        &old[shared_prefix_count..old_shared_suffix_start],
-        &new[shared_prefix_count..new_shared_suffix_start],
+        KeyedMiddle {
+            old: &old[shared_prefix_count..old_shared_suffix_start],
+            new: &new[shared_prefix_count..new_shared_suffix_start],
+            old_keys: &old_keys[shared_prefix_count..old_shared_suffix_start],
+            new_keys: &new_keys[shared_prefix_count..new_shared_suffix_start],
+            shared_prefix_count,
+            shared_suffix_count,
+            old_shared_suffix_start,
+        },
         cached_roots,
-        shared_prefix_count,
-        shared_suffix_count,
-        old_shared_suffix_start,
     );
 
     // Finally, diff the nodes at the end of `old` and `new` that share keys.
@@ -449,24 +944,28 @@ enum KeyedPrefixResult {
 //     [... parent]
 //
 // Upon exit, the change list stack is the same.
-fn diff_keyed_prefix(
+fn diff_keyed_prefix<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Node],
     new: &[Node],
+    old_keys: &[ChildKey],
+    new_keys: &[ChildKey],
     cached_roots: &mut FxHashSet<CacheId>,
 ) -> KeyedPrefixResult {
     change_list.go_down();
     let mut shared_prefix_count = 0;
 
     for (i, (old, new)) in old.iter().zip(new.iter()).enumerate() {
-        if old.key() != new.key() {
+        if old_keys[i] != new_keys[i] {
             break;
         }
 
         change_list.go_to_sibling(i);
+        change_list.enter_child(i);
         diff(cached_set, change_list, registry, old, new, cached_roots);
+        change_list.exit_child();
         shared_prefix_count += 1;
     }
 
@@ -480,6 +979,7 @@ fn diff_keyed_prefix(
             change_list,
             registry,
             &new[shared_prefix_count..],
+            shared_prefix_count,
             cached_roots,
         );
         return KeyedPrefixResult::Finished;
@@ -490,7 +990,12 @@ fn diff_keyed_prefix(
     if shared_prefix_count == new.len() {
         change_list.go_to_sibling(shared_prefix_count);
         change_list.commit_traversal();
-        remove_self_and_next_siblings(change_list, registry, &old[shared_prefix_count..]);
+        remove_self_and_next_siblings(
+            change_list,
+            registry,
+            &old[shared_prefix_count..],
+            shared_prefix_count,
+        );
         return KeyedPrefixResult::Finished;
     }
 
@@ -498,6 +1003,22 @@ fn diff_keyed_prefix(
     KeyedPrefixResult::MoreWorkToDo(shared_prefix_count)
 }
 
+// The children left between the shared-key prefix and suffix, along with where
+// they sit amongst their siblings.
+struct KeyedMiddle<'a, 'b> {
+    old: &'a [Node<'b>],
+    new: &'a [Node<'b>],
+    old_keys: &'a [ChildKey],
+    new_keys: &'a [ChildKey],
+    // The number of children before the middle, in both `old` and `new`.
+    shared_prefix_count: usize,
+    // The number of children after the middle, in both `old` and `new`.
+    shared_suffix_count: usize,
+    // The index, amongst all of the old children, of the first one after the
+    // middle.
+    old_shared_suffix_start: usize,
+}
+
 // The most-general, expensive code path for keyed children diffing.
 //
 // We find the longest subsequence within `old` of children that are relatively
@@ -511,20 +1032,26 @@ fn diff_keyed_prefix(
 //     [... parent]
 //
 // Upon exit from this function, it will be restored to that same state.
-fn diff_keyed_middle(
+fn diff_keyed_middle<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
-    old: &[Node],
-    mut new: &[Node],
+    middle: KeyedMiddle,
     cached_roots: &mut FxHashSet<CacheId>,
-    shared_prefix_count: usize,
-    shared_suffix_count: usize,
-    old_shared_suffix_start: usize,
 ) {
+    let KeyedMiddle {
+        old,
+        mut new,
+        old_keys,
+        new_keys,
+        shared_prefix_count,
+        shared_suffix_count,
+        old_shared_suffix_start,
+    } = middle;
+
     // Should have already diffed the shared-key prefixes and suffixes.
-    debug_assert_ne!(new.first().map(|n| n.key()), old.first().map(|o| o.key()));
-    debug_assert_ne!(new.last().map(|n| n.key()), old.last().map(|o| o.key()));
+    debug_assert_ne!(new_keys.first(), old_keys.first());
+    debug_assert_ne!(new_keys.last(), old_keys.last());
 
     // The algorithm below relies upon using `u32::MAX` as a sentinel
     // value, so if we have that many new nodes, it won't work. This
@@ -536,15 +1063,14 @@ fn diff_keyed_middle(
     // Map from each `old` node's key to its index within `old`.
     let mut old_key_to_old_index = FxHashMap::default();
     old_key_to_old_index.reserve(old.len());
-    old_key_to_old_index.extend(old.iter().enumerate().map(|(i, o)| (o.key(), i)));
+    old_key_to_old_index.extend(old_keys.iter().enumerate().map(|(i, &key)| (key, i)));
 
     // The set of shared keys between `new` and `old`.
     let mut shared_keys = FxHashSet::default();
     // Map from each index in `new` to the index of the node in `old` that
     // has the same key.
     let mut new_index_to_old_index = Vec::with_capacity(new.len());
-    new_index_to_old_index.extend(new.iter().map(|n| {
-        let key = n.key();
+    new_index_to_old_index.extend(new_keys.iter().map(|&key| {
         if let Some(&i) = old_key_to_old_index.get(&key) {
             shared_keys.insert(key);
             i
@@ -563,9 +1089,16 @@ fn diff_keyed_middle(
         } else {
             change_list.go_down_to_child(shared_prefix_count);
             change_list.commit_traversal();
-            remove_self_and_next_siblings(change_list, registry, &old[shared_prefix_count..]);
+            remove_self_and_next_siblings(change_list, registry, old, shared_prefix_count);
         }
-        create_and_append_children(cached_set, change_list, registry, new, cached_roots);
+        create_and_append_children(
+            cached_set,
+            change_list,
+            registry,
+            new,
+            shared_prefix_count,
+            cached_roots,
+        );
         return;
     }
 
@@ -575,10 +1108,7 @@ fn diff_keyed_middle(
     let mut start = 0;
     loop {
         let end = (start..old.len())
-            .find(|&i| {
-                let key = old[i].key();
-                !shared_keys.contains(&key)
-            })
+            .find(|&i| !shared_keys.contains(&old_keys[i]))
             .unwrap_or(old.len());
 
         if end - start > 0 {
@@ -605,10 +1135,11 @@ fn diff_keyed_middle(
     // children. Remove from the end first so that we don't mess up indices.
     let mut removed_count = 0;
     for (i, old_child) in old.iter().enumerate().rev() {
-        if !shared_keys.contains(&old_child.key()) {
+        if !shared_keys.contains(&old_keys[i]) {
             registry.remove_subtree(old_child);
             change_list.commit_traversal();
             change_list.remove_child(i + shared_prefix_count);
+            change_list.observe_child(i + shared_prefix_count, ChangeKind::Removed);
             removed_count += 1;
         }
     }
@@ -660,12 +1191,13 @@ fn diff_keyed_middle(
         let last_index = new.len() - 1;
         let last = new.last().unwrap_throw();
         new = &new[..new.len() - 1];
-        if shared_keys.contains(&last.key()) {
+        if shared_keys.contains(&new_keys[last_index]) {
             let old_index = new_index_to_old_index[last_index];
             let temp = old_index_to_temp[old_index];
             // [... parent]
             change_list.go_down_to_temp_child(temp);
             // [... parent last]
+            change_list.enter_child(shared_prefix_count + last_index);
             diff(
                 cached_set,
                 change_list,
@@ -674,12 +1206,14 @@ fn diff_keyed_middle(
                 last,
                 cached_roots,
             );
+            change_list.exit_child();
             if new_index_is_in_lis.contains(&last_index) {
                 // Don't move it, since it is already where it needs to be.
             } else {
                 change_list.commit_traversal();
                 // [... parent last]
                 change_list.append_child();
+                change_list.observe_child(shared_prefix_count + last_index, ChangeKind::Moved);
                 // [... parent]
                 change_list.go_down_to_temp_child(temp);
                 // [... parent last]
@@ -690,6 +1224,7 @@ fn diff_keyed_middle(
             create(cached_set, change_list, registry, last, cached_roots);
             // [... parent last]
             change_list.append_child();
+            change_list.observe_child(shared_prefix_count + last_index, ChangeKind::Created);
             // [... parent]
             change_list.go_down_to_reverse_child(0);
             // [... parent last]
@@ -699,15 +1234,16 @@ fn diff_keyed_middle(
     for (new_index, new_child) in new.iter().enumerate().rev() {
         let old_index = new_index_to_old_index[new_index];
         if old_index == u32::MAX as usize {
-            debug_assert!(!shared_keys.contains(&new_child.key()));
+            debug_assert!(!shared_keys.contains(&new_keys[new_index]));
             change_list.commit_traversal();
             // [... parent successor]
             create(cached_set, change_list, registry, new_child, cached_roots);
             // [... parent successor new_child]
             change_list.insert_before();
+            change_list.observe_child(shared_prefix_count + new_index, ChangeKind::Created);
         // [... parent new_child]
         } else {
-            debug_assert!(shared_keys.contains(&new_child.key()));
+            debug_assert!(shared_keys.contains(&new_keys[new_index]));
             let temp = old_index_to_temp[old_index];
             debug_assert_ne!(temp, u32::MAX);
 
@@ -722,8 +1258,10 @@ fn diff_keyed_middle(
                 // [... parent successor new_child]
                 change_list.insert_before();
                 // [... parent new_child]
+                change_list.observe_child(shared_prefix_count + new_index, ChangeKind::Moved);
             }
 
+            change_list.enter_child(shared_prefix_count + new_index);
             diff(
                 cached_set,
                 change_list,
@@ -732,6 +1270,7 @@ fn diff_keyed_middle(
                 new_child,
                 cached_roots,
             );
+            change_list.exit_child();
         }
     }
 
@@ -747,9 +1286,9 @@ fn diff_keyed_middle(
 //     [... parent]
 //
 // When this function exits, the change list stack remains the same.
-fn diff_keyed_suffix(
+fn diff_keyed_suffix<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Node],
     new: &[Node],
@@ -765,6 +1304,7 @@ fn diff_keyed_suffix(
 
     for (i, (old_child, new_child)) in old.iter().zip(new.iter()).enumerate() {
         change_list.go_to_sibling(new_shared_suffix_start + i);
+        change_list.enter_child(new_shared_suffix_start + i);
         diff(
             cached_set,
             change_list,
@@ -773,6 +1313,7 @@ fn diff_keyed_suffix(
             new_child,
             cached_roots,
         );
+        change_list.exit_child();
     }
 
     // [... parent]
@@ -787,9 +1328,9 @@ fn diff_keyed_suffix(
 //     [... parent]
 //
 // the change list stack is in the same state when this function returns.
-fn diff_non_keyed_children(
+fn diff_non_keyed_children<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Node],
     new: &[Node],
@@ -803,18 +1344,49 @@ fn diff_non_keyed_children(
     change_list.go_down();
     //     [... parent child]
 
-    for (i, (new_child, old_child)) in new.iter().zip(old.iter()).enumerate() {
+    let shared_count = old.len().min(new.len());
+    let mut i = 0;
+    while i < shared_count {
+        // When several consecutive children all have to be replaced, such as
+        // when every item in a list changes from a `<div>` into a `<span>`,
+        // replace them all at once instead of one by one.
+        let replace_count = (i..shared_count)
+            .take_while(|&j| must_replace(&old[j], &new[j]))
+            .count();
+        if replace_count > 1 {
+            let end = i + replace_count;
+            // [... parent prev_child]
+            change_list.go_to_sibling(i);
+            // [... parent first_child_to_replace]
+            change_list.commit_traversal();
+            replace_siblings(
+                cached_set,
+                change_list,
+                registry,
+                &old[i..end],
+                &new[i..end],
+                i,
+                cached_roots,
+            );
+            // [... parent last_replacement_child]
+            i = end;
+            continue;
+        }
+
         // [... parent prev_child]
         change_list.go_to_sibling(i);
         // [... parent this_child]
+        change_list.enter_child(i);
         diff(
             cached_set,
             change_list,
             registry,
-            old_child,
-            new_child,
+            &old[i],
+            &new[i],
             cached_roots,
         );
+        change_list.exit_child();
+        i += 1;
     }
 
     match old.len().cmp(&new.len()) {
@@ -823,7 +1395,7 @@ fn diff_non_keyed_children(
             change_list.go_to_sibling(new.len());
             // [... parent first_child_to_remove]
             change_list.commit_traversal();
-            remove_self_and_next_siblings(change_list, registry, &old[new.len()..]);
+            remove_self_and_next_siblings(change_list, registry, &old[new.len()..], new.len());
             // [... parent]
         }
         Ordering::Less => {
@@ -836,6 +1408,7 @@ fn diff_non_keyed_children(
                 change_list,
                 registry,
                 &new[old.len()..],
+                old.len(),
                 cached_roots,
             );
         }
@@ -847,18 +1420,74 @@ fn diff_non_keyed_children(
     }
 }
 
-// Create the given children and append them to the parent node.
+// Would diffing `old` with `new` replace the physical node outright, rather
+// than update it in place? Cached nodes are not considered.
+fn must_replace(old: &Node, new: &Node) -> bool {
+    match (&new.kind, &old.kind) {
+        (NodeKind::Text(_), NodeKind::Element(_)) | (NodeKind::Element(_), NodeKind::Text(_)) => {
+            true
+        }
+        (NodeKind::Element(new), NodeKind::Element(old)) => {
+            new.tag_name != old.tag_name
+                || new.namespace != old.namespace
+                || customized_builtin_name(new.attributes, new.namespace.is_some())
+                    != customized_builtin_name(old.attributes, old.namespace.is_some())
+                // The old virtual children don't match the physical ones when
+                // they are managed elsewhere, so they can't be diffed.
+                || new.managed_children != old.managed_children
+        }
+        _ => false,
+    }
+}
+
+// Replace a run of sibling children that must all be replaced rather than
+// diffed, with a single DOM operation. `first_index` is the index of the first
+// replaced child amongst all of its siblings.
+//
+// The change list stack must have this shape upon entry to this function:
+//
+//     [... parent first_old_child]
+//
+// When this function returns, the last new child is on top of the stack:
+//
+//     [... parent last_new_child]
+fn replace_siblings<S: ChangeSink>(
+    cached_set: &CachedSet,
+    change_list: &mut ChangeListBuilder<S>,
+    registry: &mut EventsRegistry,
+    old: &[Node],
+    new: &[Node],
+    first_index: usize,
+    cached_roots: &mut FxHashSet<CacheId>,
+) {
+    debug_assert_eq!(old.len(), new.len());
+    debug_assert!(change_list.traversal_is_committed());
+    for child in new {
+        create(cached_set, change_list, registry, child, cached_roots);
+    }
+    for child in old {
+        registry.remove_subtree(child);
+    }
+    change_list.replace_siblings_with(new.len());
+    let end = first_index + new.len();
+    change_list.observe_children(first_index, end, ChangeKind::Removed);
+    change_list.observe_children(first_index, end, ChangeKind::Created);
+}
+
+// Create the given children and append them to the parent node. `first_index`
+// is the index of the first new child amongst all of the parent's children.
 //
 // The parent node must currently be on top of the change list stack:
 //
 //     [... parent]
 //
 // When this function returns, the change list stack is in the same state.
-fn create_and_append_children(
+fn create_and_append_children<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     new: &[Node],
+    first_index: usize,
     cached_roots: &mut FxHashSet<CacheId>,
 ) {
     debug_assert!(change_list.traversal_is_committed());
@@ -866,6 +1495,7 @@ fn create_and_append_children(
         create(cached_set, change_list, registry, child, cached_roots);
         change_list.append_child();
     }
+    change_list.observe_children(first_index, first_index + new.len(), ChangeKind::Created);
 }
 
 // Remove all of a node's children.
@@ -875,8 +1505,8 @@ fn create_and_append_children(
 //     [... parent]
 //
 // When this function returns, the change list stack is in the same state.
-fn remove_all_children(
-    change_list: &mut ChangeListBuilder,
+fn remove_all_children<S: ChangeSink>(
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Node],
 ) {
@@ -887,9 +1517,11 @@ fn remove_all_children(
     // Fast way to remove all children: set the node's textContent to an empty
     // string.
     change_list.set_text("");
+    change_list.observe_children(0, old.len(), ChangeKind::Removed);
 }
 
-// Remove the current child and all of its following siblings.
+// Remove the current child and all of its following siblings. `first_index` is
+// the index of the current child amongst all of its siblings.
 //
 // The change list stack must have this shape upon entry to this function:
 //
@@ -898,16 +1530,24 @@ fn remove_all_children(
 // After the function returns, the child is no longer on the change list stack:
 //
 //     [... parent]
-fn remove_self_and_next_siblings(
-    change_list: &mut ChangeListBuilder,
+fn remove_self_and_next_siblings<S: ChangeSink>(
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     old: &[Node],
+    first_index: usize,
 ) {
     debug_assert!(change_list.traversal_is_committed());
     for child in old {
         registry.remove_subtree(child);
     }
     change_list.remove_self_and_next_siblings();
+    change_list.observe_children(first_index, first_index + old.len(), ChangeKind::Removed);
+}
+
+// Report that the node currently being diffed was replaced with a new node.
+fn observe_replaced<S: ChangeSink>(change_list: &mut ChangeListBuilder<S>) {
+    change_list.observe(ChangeKind::Removed);
+    change_list.observe(ChangeKind::Created);
 }
 
 // Emit instructions to create the given virtual node.
@@ -919,12 +1559,38 @@ fn remove_self_and_next_siblings(
 // When this function returns, the new node is on top of the change list stack:
 //
 //     [... node]
-fn create(
+fn create<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     node: &Node,
     cached_roots: &mut FxHashSet<CacheId>,
+) {
+    let mut static_counts = if change_list.allows_static_html() {
+        Some(StaticCounts::new(node))
+    } else {
+        None
+    };
+    create_node(
+        cached_set,
+        change_list,
+        registry,
+        node,
+        cached_roots,
+        static_counts.as_mut(),
+    );
+}
+
+// Like `create`, but with the static counts for the subtree that `node` is
+// part of, or `None` when we already know that `node` is not worth creating
+// via HTML.
+fn create_node<S: ChangeSink>(
+    cached_set: &CachedSet,
+    change_list: &mut ChangeListBuilder<S>,
+    registry: &mut EventsRegistry,
+    node: &Node,
+    cached_roots: &mut FxHashSet<CacheId>,
+    mut static_counts: Option<&mut StaticCounts>,
 ) {
     debug_assert!(change_list.traversal_is_committed());
     match node.kind {
@@ -938,9 +1604,26 @@ fn create(
             attributes,
             children,
             namespace,
+            managed_children: _,
         }) => {
+            // Fast path: large, fully static subtrees are serialized to HTML
+            // and created all at once by the browser's HTML parser, rather
+            // than with several change list instructions per node.
+            if let Some(counts) = static_counts.as_mut() {
+                match counts.next(node) {
+                    StaticHtml::Html(html) => {
+                        change_list.create_element_from_html(html);
+                        return;
+                    }
+                    StaticHtml::TooSmall => static_counts = None,
+                    StaticHtml::Dynamic => {}
+                }
+            }
+
             if let Some(namespace) = namespace {
                 change_list.create_element_ns(tag_name, namespace);
+            } else if let Some(is) = customized_builtin_name(attributes, false) {
+                change_list.create_element_with_is(tag_name, is);
             } else {
                 change_list.create_element(tag_name);
             }
@@ -952,9 +1635,20 @@ fn create(
                 change_list.new_event_listener(l);
             }
 
+            // Attributes are only checked when creating an element, to avoid
+            // warning again on every render.
+            if cfg!(debug_assertions) {
+                check_attribute_names(tag_name, attributes);
+                check_keyword_attributes(tag_name, attributes);
+                check_inline_event_handlers(tag_name, attributes);
+            }
             for attr in attributes {
-                change_list.set_attribute(&attr.name, &attr.value, namespace.is_some());
+                if attr.value.is_absent() || attr.is_customized_builtin_name(namespace.is_some()) {
+                    continue;
+                }
+                set_attribute_value(change_list, attr.name, attr.value, namespace.is_some());
             }
+            change_list.flush_attributes();
 
             // Fast path: if there is a single text child, it is faster to
             // create-and-append the text node all at once via setting the
@@ -973,10 +1667,20 @@ fn create(
             }
 
             for child in children {
-                create(cached_set, change_list, registry, child, cached_roots);
+                create_node(
+                    cached_set,
+                    change_list,
+                    registry,
+                    child,
+                    cached_roots,
+                    static_counts.as_deref_mut(),
+                );
                 change_list.append_child();
             }
         }
+        NodeKind::Raw(RawNode { node }) => {
+            change_list.push_raw_node(node);
+        }
         NodeKind::Cached(ref c) => {
             cached_roots.insert(c.id);
             let (node, template) = cached_set.get(c.id);
@@ -1007,9 +1711,9 @@ fn create(
 //
 //     [... template]
 #[inline]
-fn get_or_create_template<'a>(
+fn get_or_create_template<'a, S: ChangeSink>(
     cached_set: &'a CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     cached_roots: &mut FxHashSet<CacheId>,
     template_id: CacheId,
@@ -1041,9 +1745,9 @@ fn get_or_create_template<'a>(
     }
 }
 
-fn create_and_replace(
+fn create_and_replace<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     new_template: Option<CacheId>,
     old: &Node,
@@ -1056,6 +1760,7 @@ fn create_and_replace(
         let (template, needs_listeners) =
             get_or_create_template(cached_set, change_list, registry, cached_roots, template_id);
         change_list.replace_with();
+        observe_replaced(change_list);
 
         let mut old_forcing = None;
         if needs_listeners {
@@ -1079,13 +1784,14 @@ fn create_and_replace(
     } else {
         create(cached_set, change_list, registry, new, cached_roots);
         change_list.replace_with();
+        observe_replaced(change_list);
     }
     registry.remove_subtree(old);
 }
 
-fn create_with_template(
+fn create_with_template<S: ChangeSink>(
     cached_set: &CachedSet,
-    change_list: &mut ChangeListBuilder,
+    change_list: &mut ChangeListBuilder<S>,
     registry: &mut EventsRegistry,
     template_id: CacheId,
     node: &Node,
@@ -1123,3 +1829,163 @@ fn create_with_template(
     // Make sure that we come back up to the level we were at originally.
     change_list.commit_traversal();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srcset_whitespace_is_ignored() {
+        let value = |s| AttributeValue::Str(s);
+        assert!(attribute_values_are_equal(
+            "srcset",
+            value("small.jpg 480w, large.jpg 1080w"),
+            value("  small.jpg   480w,large.jpg\n\t1080w "),
+        ));
+        assert!(attribute_values_are_equal(
+            "sizes",
+            value("(max-width: 600px) 480px, 800px"),
+            value("(max-width:  600px) 480px,800px"),
+        ));
+        assert!(!attribute_values_are_equal(
+            "srcset",
+            value("small.jpg 480w, large.jpg 1080w"),
+            value("small.jpg 480w, large.jpg 2x"),
+        ));
+        assert!(!attribute_values_are_equal(
+            "srcset",
+            value("small.jpg 480w, large.jpg 1080w"),
+            value("small.jpg 480w"),
+        ));
+        assert!(!attribute_values_are_equal(
+            "srcset",
+            value("small.jpg 480w"),
+            value("small.jpg480w"),
+        ));
+
+        // Other attributes are compared exactly.
+        assert!(!attribute_values_are_equal(
+            "alt",
+            value("a  picture"),
+            value("a picture"),
+        ));
+    }
+
+    #[test]
+    fn keyword_attribute_values() {
+        assert_eq!(invalid_keyword("fetchpriority", "high"), None);
+        assert_eq!(invalid_keyword("fetchpriority", "LOW"), None);
+        assert_eq!(invalid_keyword("loading", "lazy"), None);
+        assert_eq!(invalid_keyword("decoding", "async"), None);
+
+        assert_eq!(
+            invalid_keyword("fetchpriority", "hgh"),
+            Some(&["high", "low", "auto"][..])
+        );
+        assert_eq!(invalid_keyword("loading", ""), Some(&["eager", "lazy"][..]));
+        assert!(invalid_keyword("decoding", "asynchronous").is_some());
+
+        assert_eq!(invalid_keyword("spellcheck", "false"), None);
+        assert_eq!(invalid_keyword("spellcheck", ""), None);
+        assert_eq!(invalid_keyword("translate", "NO"), None);
+        assert_eq!(invalid_keyword("autocapitalize", "words"), None);
+        assert!(invalid_keyword("spellcheck", "no").is_some());
+        assert!(invalid_keyword("translate", "false").is_some());
+        assert!(invalid_keyword("autocapitalize", "").is_some());
+
+        // Other attributes take any value.
+        assert_eq!(invalid_keyword("title", "hgh"), None);
+    }
+
+    #[test]
+    fn inline_event_handler_names() {
+        assert!(is_inline_event_handler_name("onclick"));
+        assert!(is_inline_event_handler_name("onpointerdown"));
+
+        assert!(!is_inline_event_handler_name("on"));
+        assert!(!is_inline_event_handler_name("open"));
+        assert!(!is_inline_event_handler_name("onClick"));
+        assert!(!is_inline_event_handler_name("on-click"));
+    }
+
+    #[test]
+    fn aliased_elements_are_detected() {
+        use crate::builder::*;
+
+        fn element<'a>(node: &Node<'a>) -> &'a ElementNode<'a> {
+            match node.kind {
+                NodeKind::Element(el) => el,
+                _ => unreachable!(),
+            }
+        }
+
+        let old_bump = Bump::new();
+        let new_bump = Bump::new();
+        let item = li(&old_bump).children([text("one")]).finish();
+        let old = ul(&old_bump).children([item.clone()]).finish();
+
+        // Rendering an identical tree into the new arena is fine.
+        let new = ul(&new_bump)
+            .children([li(&new_bump).children([text("one")]).finish()])
+            .finish();
+        assert!(!is_aliased(element(&old), element(&new)));
+        assert!(!is_aliased(
+            element(&div(&old_bump).finish()),
+            element(&div(&new_bump).finish())
+        ));
+
+        // Reusing the old tree's nodes is not.
+        assert!(is_aliased(element(&old), element(&old.clone())));
+        assert!(is_aliased(element(&item), element(&item.clone())));
+        let old_children = element(&old).children;
+        let new = ElementBuilder::new(&new_bump, "ol")
+            .children(old_children)
+            .finish();
+        assert!(is_aliased(element(&old), element(&new)));
+    }
+
+    #[test]
+    fn find_duplicate_keys() {
+        let a = ChildKey::Keyed(NodeKey::new(1));
+        let b = ChildKey::Keyed(NodeKey::new(2));
+        let header = ChildKey::Unkeyed(0);
+        let footer = ChildKey::Unkeyed(1);
+
+        assert_eq!(find_duplicate_key(&[]), None);
+        assert_eq!(find_duplicate_key(&[a, b]), None);
+        assert_eq!(find_duplicate_key(&[header, a, b, footer]), None);
+        assert_eq!(find_duplicate_key(&[a, b, a]), Some(NodeKey::new(1)));
+        assert_eq!(
+            find_duplicate_key(&[header, b, a, b, footer]),
+            Some(NodeKey::new(2))
+        );
+    }
+
+    // Diffing natively needs the stand-in events registry, which is only built
+    // for internal use.
+    #[test]
+    #[cfg(all(
+        feature = "strict-keys",
+        feature = "xxx-unstable-internal-use-only",
+        not(target_arch = "wasm32"),
+        debug_assertions
+    ))]
+    #[should_panic(expected = "keyed siblings must each have a unique key")]
+    fn duplicate_keys_panic_with_strict_keys() {
+        use crate::builder::*;
+
+        fn list<'a>(b: &'a Bump, keys: &[u32]) -> Node<'a> {
+            let items = bumpalo::collections::Vec::from_iter_in(
+                keys.iter().map(|&key| li(b).key(key).finish()),
+                b,
+            );
+            ul(b).children(items).finish()
+        }
+
+        let old_bump = Bump::new();
+        let new_bump = Bump::new();
+        let old = list(&old_bump, &[1, 2, 3]);
+        let new = list(&new_bump, &[1, 2, 1]);
+        crate::replay::record_ops(&old, &new);
+    }
+}
@@ -0,0 +1,505 @@
+//! Render to draw commands instead of DOM nodes.
+//!
+//! `DrawSink` is a `ChangeSink` that keeps its own lightweight node tree and,
+//! whenever a batch of changes is finished, lays that tree out and paints it
+//! into a list of `DrawCommand`s. The commands can then be replayed against a
+//! 2D context, such as an `OffscreenCanvas` in a worker, or any other non-DOM
+//! target.
+//!
+//! Layout is deliberately simple:
+//!
+//! * every element is a block box that spans the full width of its parent,
+//! * children are stacked vertically, in order,
+//! * every text node is a single line, `line_height` pixels tall, and never
+//!   wraps,
+//! * an element's height is the sum of its children's heights, unless it has a
+//!   `height` attribute with a number of pixels,
+//! * an element with a `fill` attribute paints its box in that color, before
+//!   its children are painted.
+//!
+//! Everything else, including listeners, properties, and subtrees created from
+//! HTML, has no effect on the drawing.
+
+use crate::{cached_set::CacheId, ChangeSink, Listener};
+use fxhash::FxHashMap;
+use wasm_bindgen::JsValue;
+
+/// A single painting operation. Coordinates are in pixels, relative to the
+/// top-left corner of the drawing surface.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    /// Fill a rectangle with a color.
+    FillRect {
+        /// The left edge of the rectangle.
+        x: f64,
+        /// The top edge of the rectangle.
+        y: f64,
+        /// The width of the rectangle.
+        width: f64,
+        /// The height of the rectangle.
+        height: f64,
+        /// The CSS color to fill the rectangle with.
+        color: String,
+    },
+
+    /// Draw a line of text, with its top-left corner at the given position.
+    FillText {
+        /// The left edge of the text.
+        x: f64,
+        /// The top edge of the text's line box.
+        y: f64,
+        /// The text to draw.
+        text: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+enum DrawNodeKind {
+    Text(String),
+    // Every element is laid out the same way, regardless of its tag name.
+    Element { attributes: Vec<(String, String)> },
+}
+
+#[derive(Clone, Debug)]
+struct DrawNode {
+    kind: DrawNodeKind,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/// A `ChangeSink` that lays out and paints the rendered tree as a list of
+/// `DrawCommand`s. See the module-level documentation for details.
+#[derive(Debug)]
+pub struct DrawSink {
+    width: f64,
+    line_height: f64,
+    nodes: Vec<DrawNode>,
+    container: usize,
+    // Like the JavaScript interpreter, every batch of changes starts with the
+    // root on the stack. Rather than pushing it explicitly, an empty stack
+    // implicitly holds the root.
+    stack: Vec<usize>,
+    temporaries: Vec<usize>,
+    templates: FxHashMap<CacheId, usize>,
+    commands: Vec<DrawCommand>,
+}
+
+impl DrawSink {
+    /// Create a new `DrawSink` for a drawing surface that is `width` pixels
+    /// wide, where each line of text is `line_height` pixels tall.
+    ///
+    /// Like a freshly mounted `Vdom`, the sink starts out with a single empty
+    /// `<div>` as its root.
+    pub fn new(width: f64, line_height: f64) -> DrawSink {
+        let mut sink = DrawSink {
+            width,
+            line_height,
+            nodes: vec![],
+            container: 0,
+            stack: vec![],
+            temporaries: vec![],
+            templates: FxHashMap::default(),
+            commands: vec![],
+        };
+        sink.container = sink.new_element();
+        let root = sink.new_element();
+        sink.append(sink.container, root);
+        sink
+    }
+
+    /// The draw commands produced by the last call to `finish`.
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    fn new_node(&mut self, kind: DrawNodeKind) -> usize {
+        self.nodes.push(DrawNode {
+            kind,
+            parent: None,
+            children: vec![],
+        });
+        self.nodes.len() - 1
+    }
+
+    fn new_element(&mut self) -> usize {
+        self.new_node(DrawNodeKind::Element { attributes: vec![] })
+    }
+
+    fn root(&self) -> usize {
+        self.nodes[self.container].children[0]
+    }
+
+    fn top(&self) -> usize {
+        self.stack.last().cloned().unwrap_or_else(|| self.root())
+    }
+
+    fn pop_node(&mut self) -> usize {
+        self.stack.pop().unwrap_or_else(|| self.root())
+    }
+
+    fn child(&self, parent: usize, n: u32) -> usize {
+        self.nodes[parent].children[n as usize]
+    }
+
+    fn reverse_child(&self, parent: usize, n: u32) -> usize {
+        let children = &self.nodes[parent].children;
+        children[children.len() - 1 - n as usize]
+    }
+
+    fn parent(&self, node: usize) -> usize {
+        self.nodes[node]
+            .parent
+            .expect("the draw sink's node has no parent")
+    }
+
+    fn index_in_parent(&self, node: usize) -> usize {
+        let parent = self.parent(node);
+        self.nodes[parent]
+            .children
+            .iter()
+            .position(|&child| child == node)
+            .unwrap()
+    }
+
+    fn detach(&mut self, node: usize) {
+        if self.nodes[node].parent.is_some() {
+            let index = self.index_in_parent(node);
+            let parent = self.parent(node);
+            self.nodes[parent].children.remove(index);
+            self.nodes[node].parent = None;
+        }
+    }
+
+    fn append(&mut self, parent: usize, child: usize) {
+        self.detach(child);
+        self.nodes[parent].children.push(child);
+        self.nodes[child].parent = Some(parent);
+    }
+
+    // Insert `nodes` into `parent`'s children at `index`.
+    fn insert_at(&mut self, parent: usize, index: usize, nodes: &[usize]) {
+        for (i, &node) in nodes.iter().enumerate() {
+            self.detach(node);
+            self.nodes[parent].children.insert(index + i, node);
+            self.nodes[node].parent = Some(parent);
+        }
+    }
+
+    fn deep_clone(&mut self, node: usize) -> usize {
+        let kind = self.nodes[node].kind.clone();
+        let clone = self.new_node(kind);
+        for child in self.nodes[node].children.clone() {
+            let child = self.deep_clone(child);
+            self.append(clone, child);
+        }
+        clone
+    }
+
+    fn attributes_mut(&mut self) -> &mut Vec<(String, String)> {
+        let top = self.top();
+        match self.nodes[top].kind {
+            DrawNodeKind::Element { ref mut attributes } => attributes,
+            DrawNodeKind::Text(_) => panic!("expected an element on top of the draw sink's stack"),
+        }
+    }
+
+    fn set_attribute_value(&mut self, name: &str, value: &str) {
+        let attributes = self.attributes_mut();
+        match attributes.iter_mut().find(|(n, _)| n == name) {
+            Some(attr) => attr.1 = value.to_string(),
+            None => attributes.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // Lay out and paint `node` at the given position, returning its height.
+    fn paint(
+        &self,
+        node: usize,
+        x: f64,
+        y: f64,
+        width: f64,
+        commands: &mut Vec<DrawCommand>,
+    ) -> f64 {
+        match self.nodes[node].kind {
+            DrawNodeKind::Text(ref text) => {
+                commands.push(DrawCommand::FillText {
+                    x,
+                    y,
+                    text: text.clone(),
+                });
+                self.line_height
+            }
+            DrawNodeKind::Element { ref attributes } => {
+                let attribute = |name: &str| {
+                    attributes
+                        .iter()
+                        .find(|(n, _)| n == name)
+                        .map(|(_, value)| value.as_str())
+                };
+
+                // Reserve the box's spot in painting order, since its height
+                // is only known once its children are laid out.
+                let rect = commands.len();
+                let mut children_height = 0.0;
+                for &child in &self.nodes[node].children {
+                    children_height += self.paint(child, x, y + children_height, width, commands);
+                }
+
+                let height = attribute("height")
+                    .and_then(|height| height.trim().parse().ok())
+                    .unwrap_or(children_height);
+                if let Some(color) = attribute("fill") {
+                    commands.insert(
+                        rect,
+                        DrawCommand::FillRect {
+                            x,
+                            y,
+                            width,
+                            height,
+                            color: color.to_string(),
+                        },
+                    );
+                }
+                height
+            }
+        }
+    }
+}
+
+impl ChangeSink for DrawSink {
+    fn push_child(&mut self, n: u32) {
+        let child = self.child(self.top(), n);
+        self.stack.push(child);
+    }
+
+    fn push_reverse_child(&mut self, n: u32) {
+        let child = self.reverse_child(self.top(), n);
+        self.stack.push(child);
+    }
+
+    fn pop_push_child(&mut self, n: u32) {
+        let node = self.pop_node();
+        let sibling = self.child(self.parent(node), n);
+        self.stack.push(sibling);
+    }
+
+    fn pop_push_reverse_child(&mut self, n: u32) {
+        let node = self.pop_node();
+        let sibling = self.reverse_child(self.parent(node), n);
+        self.stack.push(sibling);
+    }
+
+    fn pop(&mut self) {
+        self.pop_node();
+    }
+
+    fn push_temporary(&mut self, temp: u32) {
+        let node = self.temporaries[temp as usize];
+        self.stack.push(node);
+    }
+
+    fn save_children_to_temporaries(&mut self, temp_base: u32, start: u32, end: u32) {
+        let parent = self.top();
+        for (i, n) in (start..end).enumerate() {
+            let temp = temp_base as usize + i;
+            if self.temporaries.len() <= temp {
+                self.temporaries.resize(temp + 1, 0);
+            }
+            self.temporaries[temp] = self.child(parent, n);
+        }
+    }
+
+    fn remove_child(&mut self, n: u32) {
+        let child = self.child(self.top(), n);
+        self.detach(child);
+    }
+
+    fn insert_before(&mut self) {
+        let new = self.pop_node();
+        let next = self.pop_node();
+        let parent = self.parent(next);
+        let index = self.index_in_parent(next);
+        self.insert_at(parent, index, &[new]);
+        self.stack.push(new);
+    }
+
+    fn append_child(&mut self) {
+        let child = self.pop_node();
+        let parent = self.top();
+        self.append(parent, child);
+    }
+
+    fn replace_with(&mut self) {
+        self.replace_siblings_with(1);
+    }
+
+    fn replace_siblings_with(&mut self, count: u32) {
+        let count = count as usize;
+        let new = self.stack.split_off(self.stack.len() - count);
+        let old = self.pop_node();
+        let parent = self.parent(old);
+        let index = self.index_in_parent(old);
+        for _ in 0..count {
+            let old = self.nodes[parent].children[index];
+            self.detach(old);
+        }
+        self.insert_at(parent, index, &new);
+        self.stack.push(*new.last().unwrap());
+    }
+
+    fn remove_self_and_next_siblings(&mut self) {
+        let node = self.pop_node();
+        let parent = self.parent(node);
+        let index = self.index_in_parent(node);
+        for old in self.nodes[parent].children.split_off(index) {
+            self.nodes[old].parent = None;
+        }
+    }
+
+    fn create_text_node(&mut self, text: &str) {
+        let node = self.new_node(DrawNodeKind::Text(text.to_string()));
+        self.stack.push(node);
+    }
+
+    fn create_element(&mut self, _tag_name: &str) {
+        let node = self.new_element();
+        self.stack.push(node);
+    }
+
+    fn create_element_ns(&mut self, tag_name: &str, _ns: &str) {
+        self.create_element(tag_name);
+    }
+
+    fn create_element_with_is(&mut self, tag_name: &str, _is: &str) {
+        self.create_element(tag_name);
+    }
+
+    fn create_element_from_html(&mut self, _html: String) {
+        // We do not parse HTML, so the subtree is drawn as an empty box.
+        self.create_element("div");
+    }
+
+    fn push_raw_node(&mut self, _node: &web_sys::Node) {
+        // We know nothing about the raw node, so it is drawn as an empty box.
+        self.create_element("div");
+    }
+
+    fn set_text(&mut self, text: &str) {
+        let top = self.top();
+        if let DrawNodeKind::Text(ref mut old) = self.nodes[top].kind {
+            *old = text.to_string();
+            return;
+        }
+
+        // Like `textContent`, setting the text of an element replaces all of
+        // its children with a single text node.
+        for child in std::mem::take(&mut self.nodes[top].children) {
+            self.nodes[child].parent = None;
+        }
+        let text = self.new_node(DrawNodeKind::Text(text.to_string()));
+        self.append(top, text);
+    }
+
+    fn set_attribute(&mut self, name: &str, value: &str) {
+        self.set_attribute_value(name, value);
+    }
+
+    fn remove_attribute(&mut self, name: &str) {
+        self.attributes_mut().retain(|(n, _)| n != name);
+    }
+
+    fn set_class(&mut self, class: &str) {
+        self.set_attribute_value("class", class);
+    }
+
+    fn set_style_property(&mut self, name: &str, value: &str) {
+        self.set_attribute_value(name, value);
+    }
+
+    fn remove_style_property(&mut self, name: &str) {
+        self.remove_attribute(name);
+    }
+
+    fn set_property(&mut self, _name: &str, _value: &JsValue) {}
+
+    fn remove_property(&mut self, _name: &str) {}
+
+    fn new_event_listener(&mut self, _listener: &Listener) {}
+
+    fn update_event_listener(&mut self, _listener: &Listener) {}
+
+    fn remove_event_listener(&mut self, _event: &str) {}
+
+    fn save_template(&mut self, id: CacheId) {
+        let template = self.deep_clone(self.top());
+        self.templates.insert(id, template);
+    }
+
+    fn push_template(&mut self, id: CacheId) {
+        let template = self.templates[&id];
+        let clone = self.deep_clone(template);
+        self.stack.push(clone);
+    }
+
+    fn finish(&mut self) {
+        self.stack.clear();
+        self.temporaries.clear();
+
+        let mut commands = vec![];
+        let mut y = 0.0;
+        for &child in &self.nodes[self.container].children {
+            y += self.paint(child, 0.0, y, self.width, &mut commands);
+        }
+        self.commands = commands;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_boxes_are_stacked_vertically() {
+        let mut sink = DrawSink::new(100.0, 16.0);
+
+        // Build `<div><div fill="red" height="10"/><div fill="blue">hi</div></div>`
+        // and replace the root with it.
+        sink.create_element("div");
+        sink.create_element("div");
+        sink.set_attribute("fill", "red");
+        sink.set_attribute("height", "10");
+        sink.append_child();
+        sink.create_element("div");
+        sink.set_attribute("fill", "blue");
+        sink.create_text_node("hi");
+        sink.append_child();
+        sink.append_child();
+        sink.replace_with();
+        sink.finish();
+
+        assert_eq!(
+            sink.commands(),
+            &[
+                DrawCommand::FillRect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 100.0,
+                    height: 10.0,
+                    color: "red".into(),
+                },
+                DrawCommand::FillRect {
+                    x: 0.0,
+                    y: 10.0,
+                    width: 100.0,
+                    height: 16.0,
+                    color: "blue".into(),
+                },
+                DrawCommand::FillText {
+                    x: 0.0,
+                    y: 10.0,
+                    text: "hi".into(),
+                },
+            ][..]
+        );
+    }
+}
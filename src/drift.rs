@@ -0,0 +1,107 @@
+//! Detecting when the physical DOM has drifted from the virtual DOM that was
+//! last rendered into it, for example because a browser extension modified it.
+//! See `DiffMode::Lenient`.
+
+use crate::cached_set::CachedSet;
+use crate::node::Node;
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "xxx-unstable-internal-use-only", not(target_arch = "wasm32")))] {
+        pub(crate) fn physical_dom_matches(
+            _cached_set: &CachedSet,
+            _node: &Node,
+            _physical: Option<&web_sys::Node>,
+        ) -> bool {
+            true
+        }
+    } else {
+        use crate::node::{ElementNode, NodeKind, TextNode};
+        use wasm_bindgen::JsCast;
+
+        /// Does the physical DOM still have the shape that diffing expects for
+        /// `node`?
+        ///
+        /// Only node types, tag names, and the number of children are checked,
+        /// since those are what diffing relies on to find its way around the
+        /// physical DOM. Changed text or attributes are left alone, as are the
+        /// children of raw nodes, of managed elements, and of document head
+        /// portals.
+        pub(crate) fn physical_dom_matches(
+            cached_set: &CachedSet,
+            node: &Node,
+            physical: Option<&web_sys::Node>,
+        ) -> bool {
+            let physical = match physical {
+                Some(physical) => physical,
+                None => return false,
+            };
+            match node.kind {
+                NodeKind::Text(_) => physical.node_type() == web_sys::Node::TEXT_NODE,
+                NodeKind::Raw(_) => true,
+                NodeKind::Cached(ref c) => {
+                    let (node, _template) = cached_set.get(c.id);
+                    physical_dom_matches(cached_set, node, Some(physical))
+                }
+                NodeKind::Element(&ElementNode {
+                    tag_name,
+                    children,
+                    managed_children,
+                    ..
+                }) => {
+                    let element = match physical.dyn_ref::<web_sys::Element>() {
+                        Some(element) => element,
+                        None => return false,
+                    };
+                    if !element.local_name().eq_ignore_ascii_case(tag_name) {
+                        return false;
+                    }
+                    if managed_children || element.has_attribute("data-dodrio-head") {
+                        return true;
+                    }
+                    children_match(cached_set, children, physical.first_child())
+                }
+            }
+        }
+
+        fn children_match(
+            cached_set: &CachedSet,
+            children: &[Node],
+            first: Option<web_sys::Node>,
+        ) -> bool {
+            let mut physical = skip_leaving(first);
+
+            // A lone text child is set with `textContent`, which creates no
+            // text node at all when the text is empty.
+            if let [Node {
+                kind: NodeKind::Text(TextNode { text: "" }),
+            }] = children
+            {
+                if physical.is_none() {
+                    return true;
+                }
+            }
+
+            for child in children {
+                if !physical_dom_matches(cached_set, child, physical.as_ref()) {
+                    return false;
+                }
+                physical = skip_leaving(physical.and_then(|p| p.next_sibling()));
+            }
+            physical.is_none()
+        }
+
+        // Skip over elements in the middle of their leave transition, which are
+        // no longer in the virtual DOM.
+        fn skip_leaving(mut physical: Option<web_sys::Node>) -> Option<web_sys::Node> {
+            while let Some(node) = physical {
+                match node.dyn_ref::<web_sys::Element>() {
+                    Some(element) if element.has_attribute("data-dodrio-leaving") => {
+                        physical = node.next_sibling();
+                    }
+                    _ => return Some(node),
+                }
+            }
+            None
+        }
+    }
+}
@@ -14,9 +14,12 @@ cfg_if::cfg_if! {
                 (Rc::new(RefCell::new(EventsRegistry {})), ())
             }
             pub(crate) fn remove(&mut self, _listener: &Listener) {}
+            pub(crate) fn remove_if_current(&mut self, _listener: &Listener) {}
             pub(crate) fn remove_subtree(&mut self, _node: &Node) {}
             pub(crate) unsafe fn add<'a>(&mut self, _listener: &'a Listener<'a>) {}
             pub(crate) fn clear_active_listeners(&mut self) {}
+            pub(crate) fn namespace(&self) -> u32 { 0 }
+            pub(crate) fn len(&self) -> usize { 0 }
         }
     } else {
         use crate::{
@@ -26,21 +29,35 @@ cfg_if::cfg_if! {
         use fxhash::FxHashMap;
         use std::fmt;
         use std::mem;
+        use std::sync::atomic::{AtomicU32, Ordering};
         use wasm_bindgen::closure::Closure;
         use wasm_bindgen::prelude::*;
 
+        /// The next id namespace to hand out to a new events registry.
+        static NEXT_NAMESPACE: AtomicU32 = AtomicU32::new(0);
+
         /// The events registry manages event listeners for a virtual DOM.
         ///
         /// The events registry is persistent across virtual DOM rendering and double
         /// buffering.
+        ///
+        /// Listener ids are only unique within a single registry, so each
+        /// registry is given its own id namespace. The physical DOM stores a
+        /// listener's id under a property name that includes the namespace, and
+        /// each `Vdom` only ever reads ids from its own namespace. Therefore
+        /// multiple `Vdom`s on the same page, even ones whose physical DOM
+        /// nodes are nested inside one another or that listen to the same
+        /// element, never dispatch each other's events.
         pub(crate) struct EventsRegistry {
             vdom: Weak<VdomInner>,
+            namespace: u32,
             active: FxHashMap<(u32, u32), ListenerCallback<'static>>,
         }
 
         impl fmt::Debug for EventsRegistry {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 f.debug_struct("EventsRegistry")
+                    .field("namespace", &self.namespace)
                     .field("active", &self.active.keys().collect::<Vec<_>>())
                     .finish()
             }
@@ -58,6 +75,7 @@ cfg_if::cfg_if! {
             ) {
                 let registry = Rc::new(RefCell::new(EventsRegistry {
                     vdom,
+                    namespace: NEXT_NAMESPACE.fetch_add(1, Ordering::Relaxed),
                     active: FxHashMap::default(),
                 }));
 
@@ -92,14 +110,26 @@ cfg_if::cfg_if! {
             }
 
             pub(crate) fn remove(&mut self, listener: &Listener) {
-                let id = listener.get_callback_parts();
+                let id = listener.registry_parts();
                 debug_assert!(id.0 != 0);
                 self.active.remove(&id);
             }
 
+            /// Remove the given listener from the registry, unless its entry
+            /// has since been pointed at some other callback.
+            pub(crate) fn remove_if_current(&mut self, listener: &Listener) {
+                let id = listener.registry_parts();
+                let is_current = self.active.get(&id).map_or(false, |callback| {
+                    *callback as *const _ as *const u8 == listener.callback as *const _ as *const u8
+                });
+                if is_current {
+                    self.active.remove(&id);
+                }
+            }
+
             pub(crate) fn remove_subtree(&mut self, node: &Node) {
                 match node.kind {
-                    NodeKind::Cached(_) | NodeKind::Text(_) => {},
+                    NodeKind::Cached(_) | NodeKind::Text(_) | NodeKind::Raw(_) => {},
                     NodeKind::Element(&ElementNode {listeners, children, ..}) => {
                         for l in listeners {
                             self.remove(l);
@@ -120,7 +150,7 @@ cfg_if::cfg_if! {
             /// in the registry after it is dropped. This is maintained during
             /// diffing.
             pub(crate) unsafe fn add<'a>(&mut self, listener: &'a Listener<'a>) {
-                let id = listener.get_callback_parts();
+                let id = listener.registry_parts();
                 debug_assert!(id.0 != 0);
 
                 let callback =
@@ -133,6 +163,16 @@ cfg_if::cfg_if! {
             pub(crate) fn clear_active_listeners(&mut self) {
                 self.active.clear();
             }
+
+            /// Get this registry's id namespace.
+            pub(crate) fn namespace(&self) -> u32 {
+                self.namespace
+            }
+
+            /// Get the number of listeners currently in the registry.
+            pub(crate) fn len(&self) -> usize {
+                self.active.len()
+            }
         }
     }
 }
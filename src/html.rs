@@ -0,0 +1,629 @@
+//! Serializing large, fully static subtrees to HTML.
+//!
+//! Creating a big subtree one node at a time costs several change list
+//! instructions per node. When a subtree has no listeners, no cached or raw
+//! nodes, and nothing else that requires creating its nodes one by one, it is
+//! cheaper to serialize it to an HTML string and have the browser's HTML parser
+//! build the whole thing in one go.
+//!
+//! The physical DOM that the parser builds must line up exactly with the
+//! virtual DOM, or else subsequent diffs would target the wrong physical
+//! nodes. Therefore we are conservative about which subtrees are eligible:
+//!
+//! * only allow-listed HTML tags whose parsing never implicitly opens or closes
+//!   other elements,
+//! * no namespaced elements,
+//! * no empty or adjacent text nodes, which the parser would drop or merge,
+//! * no `JsValue` attributes, since those are not attributes at all,
+//! * custom style properties only on elements without a `style` attribute, and
+//!   only with plain values, so that they can be merged into a `style`
+//!   attribute that parses the same as setting them one by one,
+//! * no `data-dodrio-*` directives, which are only noticed when set one by one,
+//! * no `indeterminate`, `currenttime`, or `volume` attributes, which only take
+//!   effect as properties.
+//!
+//! Whitespace-only text nodes, such as the indentation between list items, are
+//! kept as they are: the parser only drops or collapses whitespace inside
+//! elements like `<pre>`, `<textarea>`, and `<table>`, none of which are
+//! allowed. So a subtree ends up with the same text nodes whether it is created
+//! via HTML or node by node.
+//!
+//! `render_to_string` uses the same serializer in its page mode, which accepts
+//! any subtree and leaves out whatever only exists on the client. Without the
+//! `dom` feature, that is the only mode used.
+#![cfg_attr(not(feature = "dom"), allow(dead_code))]
+
+use crate::cached_set::CachedSet;
+use crate::node::{Attribute, AttributeValue, ElementNode, Node, NodeKind, TextNode};
+
+/// The minimum number of nodes in a static subtree before we create it via
+/// HTML instead of node by node.
+pub(crate) const STATIC_HTML_THRESHOLD: usize = 64;
+
+/// The result of trying to serialize a subtree to HTML.
+pub(crate) enum StaticHtml {
+    /// The subtree is static and large enough; here is its HTML.
+    Html(String),
+
+    /// The subtree is static, but too small to be worth it. None of its
+    /// descendants are worth it either.
+    TooSmall,
+
+    /// The subtree is not static. Some of its descendants might be.
+    Dynamic,
+}
+
+/// Which elements of a subtree that is about to be created are static, and
+/// how many nodes they have.
+///
+/// These are computed bottom-up, in a single pass over the subtree, and then
+/// handed out in the order that creating the subtree visits its elements. That
+/// way, trying each level of a dynamic subtree does not walk all of its
+/// descendants again, and each element is judged in the context of its actual
+/// ancestors.
+pub(crate) struct StaticCounts {
+    // One entry per element, in pre-order: its number of nodes if it is
+    // static, and its number of descendant elements.
+    elements: Vec<(Option<usize>, usize)>,
+    next: usize,
+}
+
+impl StaticCounts {
+    pub(crate) fn new(node: &Node) -> StaticCounts {
+        let mut elements = Vec::new();
+        count_static_nodes(node, Context::default(), &mut elements);
+        StaticCounts { elements, next: 0 }
+    }
+
+    /// Try to serialize the next element, which must be `node`, to an HTML
+    /// string. Unless it is dynamic, its descendants are skipped.
+    pub(crate) fn next(&mut self, node: &Node) -> StaticHtml {
+        let (count, descendants) = self.elements[self.next];
+        self.next += 1;
+        let count = match count {
+            Some(count) => count,
+            None => return StaticHtml::Dynamic,
+        };
+        self.next += descendants;
+        if count < STATIC_HTML_THRESHOLD {
+            return StaticHtml::TooSmall;
+        }
+
+        let mut html = String::new();
+        serialize(node, None, Mode::Static, &mut html);
+        StaticHtml::Html(html)
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Context<'a> {
+    parent: Option<&'a str>,
+    in_anchor: bool,
+    in_heading: bool,
+}
+
+// Count the nodes in the given subtree, or return `None` if it is not static.
+// Every element's result is recorded in `elements`, including those of the
+// descendants of elements that are not static.
+fn count_static_nodes(
+    node: &Node,
+    cx: Context,
+    elements: &mut Vec<(Option<usize>, usize)>,
+) -> Option<usize> {
+    match node.kind {
+        NodeKind::Text(TextNode { text }) => {
+            if text.is_empty() || text.contains(|c: char| c == '\r' || c == '\0') {
+                None
+            } else {
+                Some(1)
+            }
+        }
+        NodeKind::Element(&ElementNode {
+            tag_name,
+            listeners,
+            attributes,
+            children,
+            namespace,
+            ..
+        }) => {
+            let index = elements.len();
+            elements.push((None, 0));
+
+            let is_heading = is_heading(tag_name);
+            let mut is_static = namespace.is_none() && listeners.is_empty();
+            match tag_name {
+                "li" if cx.parent != Some("ul") && cx.parent != Some("ol") => is_static = false,
+                "a" if cx.in_anchor => is_static = false,
+                _ if is_heading && cx.in_heading => is_static = false,
+                _ if is_allowed_void(tag_name) && !children.is_empty() => is_static = false,
+                _ if !is_allowed_void(tag_name) && !is_allowed_container(tag_name) => {
+                    is_static = false
+                }
+                _ => {}
+            }
+
+            let has_style = attributes.iter().any(|attr| attr.name() == "style");
+            if !attributes
+                .iter()
+                .all(|attr| is_static_attribute(attr, has_style))
+            {
+                is_static = false;
+            }
+
+            // Keep counting even once this element turns out not to be static,
+            // since its descendants might still be.
+            let child_cx = Context {
+                parent: Some(tag_name),
+                in_anchor: cx.in_anchor || tag_name == "a",
+                in_heading: cx.in_heading || is_heading,
+            };
+            let mut count = 1;
+            let mut previous_was_text = false;
+            for child in children {
+                let is_text = match child.kind {
+                    NodeKind::Text(_) => true,
+                    _ => false,
+                };
+                if is_text && previous_was_text {
+                    is_static = false;
+                }
+                previous_was_text = is_text;
+                match count_static_nodes(child, child_cx, elements) {
+                    Some(child_count) => count += child_count,
+                    None => is_static = false,
+                }
+            }
+
+            let count = if is_static { Some(count) } else { None };
+            elements[index] = (count, elements.len() - index - 1);
+            count
+        }
+        NodeKind::Cached(_) => None,
+        #[cfg(feature = "dom")]
+        NodeKind::Raw(_) => None,
+    }
+}
+
+fn is_static_attribute(attr: &Attribute, has_style: bool) -> bool {
+    let name = attr.name();
+    #[cfg(feature = "dom")]
+    {
+        if let AttributeValue::JsValue(_) = attr.value() {
+            return false;
+        }
+    }
+    if is_custom_property(name) {
+        return !has_style && is_static_custom_property(attr);
+    }
+    if name.starts_with("data-dodrio-") {
+        return false;
+    }
+    match name {
+        "indeterminate" | "currenttime" | "volume" => return false,
+        _ => {}
+    }
+    is_valid_attribute_name(name)
+}
+
+pub(crate) fn is_custom_property(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+// Can this custom property be written into a `style` attribute, and be sure to
+// parse the same as setting it with `style.setProperty`? Only allow simple
+// names, and values without anything that could end the declaration early or
+// swallow the ones after it.
+fn is_static_custom_property(attr: &Attribute) -> bool {
+    let name = &attr.name()[2..];
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return false;
+    }
+
+    let value = match attr.value() {
+        AttributeValue::Str(value) => value,
+        AttributeValue::Number(_) => return true,
+        _ => return false,
+    };
+    let mut depth = 0;
+    for c in value.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' if depth == 0 => return false,
+            ')' | ']' => depth -= 1,
+            ';' | '{' | '}' | '!' | '"' | '\'' | '\\' => return false,
+            c if c.is_control() => return false,
+            _ => {}
+        }
+    }
+    depth == 0 && !value.trim().is_empty() && !value.contains("/*")
+}
+
+fn is_heading(tag_name: &str) -> bool {
+    match tag_name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => true,
+        _ => false,
+    }
+}
+
+// Void elements have no closing tag, and so no children.
+pub(crate) fn is_void(tag_name: &str) -> bool {
+    match tag_name {
+        "area" | "base" | "br" | "col" | "embed" | "hr" | "img" | "input" | "link" | "meta"
+        | "source" | "track" | "wbr" => true,
+        _ => false,
+    }
+}
+
+// The void elements that static subtrees may contain. The others either
+// belong in the `<head>`, or are only parsed as such within particular
+// parents.
+fn is_allowed_void(tag_name: &str) -> bool {
+    match tag_name {
+        "br" | "hr" | "img" | "input" | "wbr" => true,
+        _ => false,
+    }
+}
+
+fn is_allowed_container(tag_name: &str) -> bool {
+    match tag_name {
+        "a" | "abbr" | "article" | "aside" | "b" | "blockquote" | "cite" | "code" | "div"
+        | "em" | "figcaption" | "figure" | "footer" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+        | "header" | "i" | "kbd" | "label" | "li" | "main" | "mark" | "nav" | "ol" | "q" | "s"
+        | "samp" | "section" | "small" | "span" | "strong" | "sub" | "sup" | "time" | "u"
+        | "ul" | "var" => true,
+        _ => false,
+    }
+}
+
+// Is `name` a valid attribute name for `setAttribute`? This is a conservative
+// approximation of the XML `Name` production: it must not be empty, must not
+// start with a digit, `-`, or `.`, and may only contain alphanumerics, `-`,
+// `_`, `.`, `:`, and non-ASCII characters. Tag names follow the same
+// production, so this doubles as a check for those.
+pub(crate) fn is_valid_attribute_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return false,
+    };
+    let is_name_char = |c: char| {
+        c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == ':' || !c.is_ascii()
+    };
+    is_name_char(first)
+        && !first.is_ascii_digit()
+        && first != '-'
+        && first != '.'
+        && chars.all(is_name_char)
+}
+
+/// What the HTML written by `serialize` is for.
+#[derive(Clone, Copy)]
+pub(crate) enum Mode<'a> {
+    /// A static subtree, which must parse back into exactly the nodes and
+    /// attributes that creating it node by node would. It has already been
+    /// checked by `count_static_nodes`.
+    Static,
+
+    /// A whole page, which only has to look the same once parsed. Whatever
+    /// only exists on the client is left out, and cached nodes are looked up
+    /// in the given cached set.
+    #[cfg_attr(not(feature = "ssr"), allow(dead_code))]
+    Page(&'a CachedSet),
+}
+
+// An empty comment, rendered where the parser would otherwise merge or drop
+// text nodes.
+const PLACEHOLDER: &str = "<!---->";
+
+pub(crate) fn serialize(node: &Node, parent: Option<&str>, mode: Mode, html: &mut String) {
+    match node.kind {
+        NodeKind::Text(TextNode { text }) => match parent {
+            // The contents of these elements are not parsed as HTML, so they
+            // must not be escaped. They end at the first `</` that starts
+            // their closing tag, though, so write `</` as `<\/`, which means
+            // the same within scripts' strings and within style sheets.
+            Some("script") | Some("style") => html.push_str(&text.replace("</", "<\\/")),
+            _ if text.is_empty() && !has_text_contents(parent) => html.push_str(PLACEHOLDER),
+            _ => escape(text, false, html),
+        },
+        NodeKind::Element(&ElementNode {
+            tag_name,
+            attributes,
+            children,
+            ..
+        }) => {
+            // Writing an invalid tag name could produce any markup at all,
+            // and creating it on the client would throw anyway.
+            if !is_valid_attribute_name(tag_name) {
+                warn!("invalid tag name {:?}; leaving it out", tag_name);
+                if !has_text_contents(parent) {
+                    html.push_str(PLACEHOLDER);
+                }
+                return;
+            }
+
+            html.push('<');
+            html.push_str(tag_name);
+            serialize_attributes(tag_name, attributes, mode, html);
+            html.push('>');
+
+            if is_void(tag_name) {
+                return;
+            }
+
+            // The parser drops a newline right after these start tags, so
+            // that the contents can start on their own line. Add one for it to
+            // drop, rather than the contents' own leading newline.
+            let first = children.first().and_then(|c| as_text(c, mode));
+            if drops_leading_newline(tag_name) && first.map_or(false, |t| t.starts_with('\n')) {
+                html.push('\n');
+            }
+
+            let mut previous_was_text = false;
+            for child in children {
+                let is_text = as_text(child, mode).is_some();
+                if is_text && previous_was_text && !has_text_contents(Some(tag_name)) {
+                    html.push_str(PLACEHOLDER);
+                }
+                previous_was_text = is_text;
+                serialize(child, Some(tag_name), mode, html);
+            }
+
+            html.push_str("</");
+            html.push_str(tag_name);
+            html.push('>');
+        }
+        NodeKind::Cached(ref c) => match mode {
+            Mode::Page(cached_set) => serialize(cached_set.get(c.id).0, parent, mode, html),
+            Mode::Static => unreachable!("cached nodes are never static"),
+        },
+        #[cfg(feature = "dom")]
+        NodeKind::Raw(_) => match mode {
+            Mode::Page(_) if !has_text_contents(parent) => html.push_str(PLACEHOLDER),
+            Mode::Page(_) => {}
+            Mode::Static => unreachable!("raw nodes are never static"),
+        },
+    }
+}
+
+fn as_text<'a>(node: &Node<'a>, mode: Mode<'a>) -> Option<&'a str> {
+    match node.kind {
+        NodeKind::Text(TextNode { text }) => Some(text),
+        NodeKind::Cached(ref c) => match mode {
+            Mode::Page(cached_set) => as_text(cached_set.get(c.id).0, mode),
+            Mode::Static => None,
+        },
+        NodeKind::Element(_) => None,
+        #[cfg(feature = "dom")]
+        NodeKind::Raw(_) => None,
+    }
+}
+
+// Are the contents of the given element parsed as text only, so that comments
+// within them would be text too?
+fn has_text_contents(tag_name: Option<&str>) -> bool {
+    match tag_name {
+        Some("script") | Some("style") | Some("textarea") | Some("title") => true,
+        _ => false,
+    }
+}
+
+fn drops_leading_newline(tag_name: &str) -> bool {
+    match tag_name {
+        "pre" | "textarea" | "listing" => true,
+        _ => false,
+    }
+}
+
+// Serialize an element's attributes, merging its custom style properties into
+// a single `style` attribute. For a page, its `style` attribute is merged in
+// too, since there is no telling whether it has one up front.
+fn serialize_attributes(tag_name: &str, attributes: &[Attribute], mode: Mode, html: &mut String) {
+    let is_page = match mode {
+        Mode::Page(_) => true,
+        Mode::Static => false,
+    };
+    let mut style = String::new();
+    for attr in attributes {
+        let name = attr.name();
+        if is_page && !is_real_attribute(name) {
+            continue;
+        }
+        let value = match attr.value() {
+            AttributeValue::Str(value) => value.to_string(),
+            AttributeValue::Bool(true) => String::new(),
+            AttributeValue::Bool(false) => continue,
+            AttributeValue::Number(n) => n.to_string(),
+            #[cfg(feature = "dom")]
+            AttributeValue::JsValue(_) => continue,
+        };
+        if is_custom_property(name) || (is_page && name == "style") {
+            let value = value.trim().trim_end_matches(';');
+            if value.is_empty() {
+                continue;
+            }
+            if !style.is_empty() {
+                style.push_str("; ");
+            }
+            if name != "style" {
+                style.push_str(name);
+                style.push_str(": ");
+            }
+            style.push_str(value);
+            continue;
+        }
+        if !is_valid_attribute_name(name) {
+            warn!(
+                "invalid attribute name {:?} on a <{}> element; leaving it out",
+                name, tag_name
+            );
+            continue;
+        }
+        serialize_attribute(name, &value, html);
+    }
+    if !style.is_empty() {
+        serialize_attribute("style", &style, html);
+    }
+}
+
+// Directives are carried out by the client-side interpreter, and these
+// attributes only take effect through the properties that it sets along with
+// them, so none of them mean anything in a page's HTML.
+fn is_real_attribute(name: &str) -> bool {
+    match name {
+        "indeterminate" | "currenttime" | "volume" | "muted" => false,
+        _ => !name.starts_with("data-dodrio-"),
+    }
+}
+
+pub(crate) fn serialize_attribute(name: &str, value: &str, html: &mut String) {
+    html.push(' ');
+    html.push_str(name);
+    html.push_str("=\"");
+    escape(value, true, html);
+    html.push('"');
+}
+
+pub(crate) fn escape(s: &str, in_attribute: bool, html: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' if !in_attribute => html.push_str("&lt;"),
+            '>' if !in_attribute => html.push_str("&gt;"),
+            '"' if in_attribute => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::*;
+    use bumpalo::Bump;
+
+    #[test]
+    fn serialize_merges_custom_properties_into_style() {
+        let b = Bump::new();
+        let node = div(&b)
+            .attr("class", "card wide")
+            .attr("--accent", "rgb(10, 20, 30)")
+            .attr("title", "\"Cats\" & <dogs>")
+            .attr("--gap", " 4px ")
+            .attr("hidden", "")
+            .children([text("<hello>")])
+            .finish();
+
+        let mut html = String::new();
+        serialize(&node, None, Mode::Static, &mut html);
+        assert_eq!(
+            html,
+            "<div class=\"card wide\" title=\"&quot;Cats&quot; &amp; <dogs>\" hidden=\"\" \
+             style=\"--accent: rgb(10, 20, 30); --gap: 4px\">&lt;hello&gt;</div>"
+        );
+    }
+
+    #[test]
+    fn custom_properties_that_cannot_be_merged_are_not_static() {
+        let b = Bump::new();
+        let is_static = |attr: Attribute, has_style| is_static_attribute(&attr, has_style);
+
+        assert!(is_static(attr("--accent", "red"), false));
+        assert!(is_static(
+            attr("--shadow", "0 0 calc(2px + 1em) var(--c)"),
+            false
+        ));
+
+        assert!(!is_static(attr("--accent", "red"), true));
+        assert!(!is_static(attr("--accent", "red; color: blue"), false));
+        assert!(!is_static(attr("--accent", "red !important"), false));
+        assert!(!is_static(attr("--accent", "url(\"x\")"), false));
+        assert!(!is_static(attr("--accent", "var(--c"), false));
+        assert!(!is_static(attr("--accent", "c)"), false));
+        assert!(!is_static(attr("--accent", "red /* comment"), false));
+        assert!(!is_static(attr("--accent", " "), false));
+        assert!(!is_static(attr("--", "red"), false));
+        assert!(!is_static(attr("--a b", "red"), false));
+
+        let node = div(&b)
+            .attr("style", "color: red")
+            .attr("--accent", "blue")
+            .finish();
+        assert!(count_static_nodes(&node, Context::default(), &mut Vec::new()).is_none());
+    }
+
+    #[test]
+    fn static_counts_are_computed_once_in_context() {
+        let b = Bump::new();
+        let one = li(&b).children([text("one")]).finish();
+        let two = li(&b).children([text("two")]).finish();
+        let nested = a(&b).children([text("nested")]).finish();
+        let list = ul(&b).children([one, two]).finish();
+        // The outer link is not static, but the inner one is still nested in
+        // it, and the list items are still in a list.
+        let node = a(&b)
+            .attr("volume", "0.5")
+            .children([nested.clone(), list.clone()])
+            .finish();
+
+        let mut counts = StaticCounts::new(&node);
+        assert_eq!(
+            counts.elements,
+            [
+                (None, 4),
+                (None, 0),
+                (Some(5), 2),
+                (Some(2), 0),
+                (Some(2), 0)
+            ]
+        );
+
+        assert!(is_dynamic(counts.next(&node)));
+        assert!(is_dynamic(counts.next(&nested)));
+        match counts.next(&list) {
+            StaticHtml::TooSmall => {}
+            _ => panic!("the list should be static, but too small"),
+        }
+        assert_eq!(counts.next, counts.elements.len());
+    }
+
+    fn is_dynamic(html: StaticHtml) -> bool {
+        match html {
+            StaticHtml::Dynamic => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn valid_attribute_names() {
+        assert!(is_valid_attribute_name("id"));
+        assert!(is_valid_attribute_name("aria-label"));
+        assert!(is_valid_attribute_name("data-foo.bar_baz"));
+        assert!(is_valid_attribute_name("xlink:href"));
+        assert!(is_valid_attribute_name("defaultValue"));
+        assert!(is_valid_attribute_name("_private"));
+
+        assert!(!is_valid_attribute_name(""));
+        assert!(!is_valid_attribute_name("aria label"));
+        assert!(!is_valid_attribute_name("on click"));
+        assert!(!is_valid_attribute_name("title="));
+        assert!(!is_valid_attribute_name("\"quoted\""));
+        assert!(!is_valid_attribute_name("1st"));
+        assert!(!is_valid_attribute_name("-dash"));
+    }
+
+    #[test]
+    fn directives_and_property_only_attributes_are_not_static() {
+        let is_static = |attr: Attribute| is_static_attribute(&attr, false);
+
+        assert!(is_static(attr("data-id", "1")));
+        assert!(!is_static(attr("data-dodrio-leave", "fade-out")));
+        assert!(!is_static(attr("data-dodrio-head", "")));
+        assert!(!is_static(attr("indeterminate", "")));
+        assert!(!is_static(attr("volume", "0.5")));
+    }
+}
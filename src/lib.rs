@@ -81,31 +81,72 @@ cfg_if::cfg_if! {
     }
 }
 
-// Only `pub` so that the wasm-bindgen bindings work.
-#[doc(hidden)]
-pub mod change_list;
-
 mod cached;
 mod cached_set;
-mod diff;
-mod events;
+mod html;
+mod live_region;
 mod node;
 mod render;
 mod render_context;
-mod strace;
-mod vdom;
+mod snapshot;
+#[cfg(feature = "ssr")]
+mod ssr;
 
 pub mod builder;
 
 // Re-export items at the top level.
 pub use self::cached::Cached;
-pub use self::node::{Attribute, Listener, Node, NodeKey};
+pub use self::cached_set::{CacheId, CachedSet};
+pub use self::live_region::{LiveRegion, Politeness};
+pub use self::node::{Attribute, AttributeValue, Complexity, Listener, Node, NodeKey};
 pub use self::render::{Render, RootRender};
 pub use self::render_context::RenderContext;
-pub use self::vdom::{Vdom, VdomWeak};
+pub use self::snapshot::{diff_snapshots, SnapshotDifference, VdomSnapshot};
+#[cfg(feature = "ssr")]
+pub use self::ssr::render_to_string;
 
+// Everything that renders to, or otherwise talks to, the physical DOM.
 cfg_if::cfg_if! {
-    if #[cfg(all(target_arch = "wasm32", not(feature = "xxx-unstable-internal-use-only")))] {
+    if #[cfg(feature = "dom")] {
+        // Only `pub` so that the wasm-bindgen bindings work.
+        #[doc(hidden)]
+        pub mod change_list;
+
+        mod diff;
+        mod drift;
+        mod events;
+        mod observer;
+        mod strace;
+        mod vdom;
+        mod virtual_list;
+
+        pub mod draw;
+        pub mod replay;
+        pub mod userdata;
+
+        pub use self::change_list::{ChangeSink, DiffStats};
+        pub use self::observer::{AttributeChange, ChangeKind};
+        pub use self::vdom::{DiffMode, MountOptions, PatchError, Scheduling, Vdom, VdomWeak};
+        pub use self::virtual_list::VirtualList;
+
+        use wasm_bindgen::UnwrapThrowExt;
+    } else {
+        // Without the DOM, there is no JavaScript to throw an exception into,
+        // so just panic.
+        trait UnwrapThrowExt<T> {
+            fn expect_throw(self, message: &str) -> T;
+        }
+
+        impl<T> UnwrapThrowExt<T> for Option<T> {
+            fn expect_throw(self, message: &str) -> T {
+                self.expect(message)
+            }
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(all(target_arch = "wasm32", feature = "dom", not(feature = "xxx-unstable-internal-use-only")))] {
         use wasm_bindgen::__rt::WasmRefCell as RefCell;
     } else {
         use std::cell::RefCell;
@@ -119,7 +160,7 @@ cfg_if::cfg_if! {
         pub type Element = ();
 
         pub(crate) type EventsTrampoline = ();
-    } else {
+    } else if #[cfg(feature = "dom")] {
         /// An element node in the physical DOM.
         pub type Element = web_sys::Element;
 
@@ -129,7 +170,6 @@ cfg_if::cfg_if! {
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "xxx-unstable-internal-use-only")] {
-        pub use self::cached_set::{CachedSet};
-        pub use self::node::{ElementNode, NodeKind, TextNode};
+        pub use self::node::{ElementNode, NodeKind, RawNode, TextNode};
     }
 }
@@ -0,0 +1,128 @@
+use crate::{builder::*, Node, RenderContext};
+
+// Hide the live region visually, while keeping it in the accessibility tree.
+const VISUALLY_HIDDEN: &str = "position: absolute; width: 1px; height: 1px; margin: -1px; \
+                               padding: 0; border: 0; overflow: hidden; \
+                               clip: rect(0, 0, 0, 0); white-space: nowrap";
+
+/// How urgently a `LiveRegion` announcement should interrupt the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Politeness {
+    /// Announce the message once the user is idle, with `aria-live="polite"`.
+    Polite,
+    /// Announce the message immediately, with `aria-live="assertive"`.
+    Assertive,
+}
+
+/// A helper for announcing messages to screen reader users, through a
+/// visually hidden `aria-live` region.
+///
+/// A `LiveRegion` renders two hidden regions, one polite and one assertive,
+/// and `announce` puts its message in the text node of the one with the
+/// requested politeness. Screen readers only announce changes to a live
+/// region that is already in the document, so render the `LiveRegion` on
+/// every render, even when there is nothing to announce, and re-render after
+/// announcing.
+///
+/// Keep the `LiveRegion` in your component's state, and render it from your
+/// component's `render` method.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, LiveRegion, Node, Politeness, Render, RenderContext};
+///
+/// struct Editor {
+///     region: LiveRegion,
+/// }
+///
+/// impl Editor {
+///     fn save(&mut self) {
+///         // Save the document...
+///         self.region.announce("Saved", Politeness::Polite);
+///     }
+/// }
+///
+/// impl<'a> Render<'a> for Editor {
+///     fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+///         div(&cx)
+///             .children([
+///                 textarea(&cx).finish(),
+///                 self.region.render(cx),
+///             ])
+///             .finish()
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LiveRegion {
+    message: String,
+    politeness: Option<Politeness>,
+    // Toggled when the same message is announced twice in a row, so that the
+    // region's text still changes and is announced again.
+    repeated: bool,
+}
+
+impl LiveRegion {
+    /// Create a new `LiveRegion` that has not announced anything yet.
+    pub fn new() -> LiveRegion {
+        LiveRegion::default()
+    }
+
+    /// Announce `message` with the given politeness on the next render.
+    ///
+    /// This replaces the previous announcement, if any.
+    pub fn announce<S>(&mut self, message: S, politeness: Politeness)
+    where
+        S: Into<String>,
+    {
+        let message = message.into();
+        self.repeated = message == self.message && !self.repeated;
+        self.message = message;
+        self.politeness = Some(politeness);
+    }
+
+    /// Remove the current announcement, if any, from the live regions.
+    pub fn clear(&mut self) {
+        self.message.clear();
+        self.politeness = None;
+        self.repeated = false;
+    }
+
+    /// Get the most recently announced message, or an empty string if there
+    /// is none.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Render the hidden live regions.
+    pub fn render<'a>(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        div(&cx)
+            .attr("style", VISUALLY_HIDDEN)
+            .children([
+                self.region(cx, Politeness::Polite),
+                self.region(cx, Politeness::Assertive),
+            ])
+            .finish()
+    }
+
+    // A live region with the given politeness, whose only child is a text node
+    // that holds the message if it was announced with that politeness.
+    fn region<'a>(&self, cx: &mut RenderContext<'a>, politeness: Politeness) -> Node<'a> {
+        let message = if self.politeness == Some(politeness) {
+            let suffix = if self.repeated { "\u{a0}" } else { "" };
+            bumpalo::format!(in cx.bump, "{}{}", self.message, suffix).into_bump_str()
+        } else {
+            ""
+        };
+        let live = match politeness {
+            Politeness::Polite => "polite",
+            Politeness::Assertive => "assertive",
+        };
+        div(&cx)
+            .attr("aria-live", live)
+            .attr("aria-atomic", "true")
+            .children([text(message)])
+            .finish()
+    }
+}
@@ -1,9 +1,19 @@
-use crate::{cached_set::CacheId, RootRender, VdomWeak};
+use crate::cached_set::{CacheId, CachedSet};
+#[cfg(feature = "dom")]
+use crate::{RootRender, VdomWeak};
 use bumpalo::Bump;
+#[cfg(feature = "dom")]
+use std::cell::Cell;
 use std::fmt;
+use std::hash::Hash;
 use std::iter;
+#[cfg(feature = "dom")]
 use std::mem;
+#[cfg(feature = "dom")]
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::u32;
+#[cfg(feature = "dom")]
+use wasm_bindgen::JsValue;
 
 /// A virtual DOM node.
 #[derive(Debug, Clone)]
@@ -29,6 +39,10 @@ pub_unstable_internal! {
         /// A node in the vdom's `CachedSet`. This allows us to avoid
         /// re-rendering and re-diffing subtrees.
         Cached(CachedNode),
+
+        /// A physical DOM node that was created outside of the virtual DOM.
+        #[cfg(feature = "dom")]
+        Raw(RawNode<'a>),
     }
 }
 
@@ -52,6 +66,10 @@ pub_unstable_internal! {
         pub attributes: &'a [Attribute<'a>],
         pub children: &'a [Node<'a>],
         pub namespace: Option<&'a str>,
+        // Are this element's children managed outside of dodrio? See
+        // `ElementBuilder::managed_children`.
+        #[cfg_attr(not(feature = "dom"), allow(dead_code))]
+        pub managed_children: bool,
     }
 }
 
@@ -68,11 +86,22 @@ pub_unstable_internal! {
     }
 }
 
+#[cfg(feature = "dom")]
+pub_unstable_internal! {
+    /// A raw node embeds an existing physical DOM node into the virtual DOM
+    /// as is. It is never diffed, only compared by identity.
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct RawNode<'a> {
+        pub node: &'a web_sys::Node,
+    }
+}
+
 /// The key for keyed children.
 ///
 /// Keys must be unique among siblings.
 ///
-/// If any sibling is keyed, then they all must be keyed.
+/// Keyed and unkeyed siblings may be mixed: unkeyed siblings of keyed nodes are
+/// matched up with each other by their position amongst the unkeyed siblings.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct NodeKey(pub(crate) u32);
 
@@ -106,6 +135,36 @@ impl NodeKey {
         debug_assert_ne!(key, u32::MAX);
         NodeKey(key)
     }
+
+    /// Create a new `NodeKey` by hashing `key`.
+    ///
+    /// This lets nodes be keyed by any `Hash` type, such as `u64` ids, strings,
+    /// or enums, without having to map them to `u32`s first. Equal keys always
+    /// hash to the same `NodeKey`, and the keyed diff compares these hashes.
+    ///
+    /// Distinct keys can hash to the same `NodeKey`, although it is unlikely
+    /// among a list of siblings. Such a collision is reported in debug builds
+    /// like any other duplicate key.
+    #[inline]
+    pub fn from_hash<K: Hash + ?Sized>(key: &K) -> Self {
+        match fxhash::hash32(key) {
+            // `u32::MAX` is reserved for `NodeKey::NONE`.
+            u32::MAX => NodeKey(u32::MAX - 1),
+            hash => NodeKey(hash),
+        }
+    }
+}
+
+/// An estimate of how much work creating a subtree costs.
+///
+/// See `Node::complexity`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Complexity {
+    /// The number of element and text nodes in the subtree.
+    pub nodes: usize,
+
+    /// The number of event listeners attached to elements in the subtree.
+    pub listeners: usize,
 }
 
 /// An event listener callback function.
@@ -115,6 +174,7 @@ impl NodeKey {
 /// 1. The virtual DOM's root rendering component.
 /// 2. A capability to scheduler virtual DOM re-rendering.
 /// 3. The event that occurred.
+#[cfg(feature = "dom")]
 pub(crate) type ListenerCallback<'a> =
     &'a (dyn Fn(&mut dyn RootRender, VdomWeak, web_sys::Event) + 'static);
 
@@ -123,7 +183,19 @@ pub struct Listener<'a> {
     /// The type of event to listen for.
     pub(crate) event: &'a str,
     /// The callback to invoke when the event happens.
+    #[cfg(feature = "dom")]
     pub(crate) callback: ListenerCallback<'a>,
+    /// The identity token given to `ElementBuilder::on_with_id`, if any.
+    #[cfg(feature = "dom")]
+    pub(crate) id: Option<u32>,
+    /// For listeners with an `id`, the parts that identify the physical
+    /// listener in the events registry and the DOM. See `registry_parts`.
+    #[cfg(feature = "dom")]
+    pub(crate) parts: Cell<Option<(u32, u32)>>,
+    /// Was this listener added with `ElementBuilder::on_non_passive`, so that
+    /// it can cancel scrolling and touch events?
+    #[cfg(feature = "dom")]
+    pub(crate) non_passive: bool,
 }
 
 /// An attribute on a DOM node, such as `id="my-thing"` or
@@ -131,7 +203,105 @@ pub struct Listener<'a> {
 #[derive(Clone, Debug)]
 pub struct Attribute<'a> {
     pub(crate) name: &'a str,
-    pub(crate) value: &'a str,
+    pub(crate) value: AttributeValue<'a>,
+    // Was this attribute marked volatile with `ElementBuilder::attr_volatile`?
+    #[cfg_attr(not(feature = "dom"), allow(dead_code))]
+    pub(crate) volatile: bool,
+    // Is this attribute only applied when its element is created, like
+    // `ElementBuilder::default_value`?
+    #[cfg_attr(not(feature = "dom"), allow(dead_code))]
+    pub(crate) create_only: bool,
+}
+
+/// The value of an attribute.
+///
+/// The type of the value determines how the attribute is applied to the
+/// physical DOM node.
+///
+/// The `JsValue` variant only exists with the `dom` feature, so matching on an
+/// `AttributeValue` outside of this crate needs a wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum AttributeValue<'a> {
+    /// A string value, which is set with `setAttribute`.
+    Str(&'a str),
+
+    /// A boolean attribute, such as `hidden` or `disabled`. When `true`, the
+    /// attribute is set to the empty string. When `false`, the attribute is
+    /// removed.
+    Bool(bool),
+
+    /// A number, which is set with `setAttribute` as its string representation.
+    Number(f64),
+
+    /// A JavaScript value, which is assigned to the element's property of the
+    /// same name, rather than set as an attribute.
+    #[cfg(feature = "dom")]
+    JsValue(&'a JsValue),
+}
+
+impl<'a> AttributeValue<'a> {
+    /// Get this value if it is a string.
+    #[inline]
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            AttributeValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Does this value mean that the attribute is absent?
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub(crate) fn is_absent(&self) -> bool {
+        match *self {
+            AttributeValue::Bool(false) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for AttributeValue<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        AttributeValue::Str(s)
+    }
+}
+
+impl From<bool> for AttributeValue<'_> {
+    #[inline]
+    fn from(b: bool) -> Self {
+        AttributeValue::Bool(b)
+    }
+}
+
+impl From<f64> for AttributeValue<'_> {
+    #[inline]
+    fn from(n: f64) -> Self {
+        AttributeValue::Number(n)
+    }
+}
+
+impl From<i32> for AttributeValue<'_> {
+    #[inline]
+    fn from(n: i32) -> Self {
+        AttributeValue::Number(n.into())
+    }
+}
+
+impl From<u32> for AttributeValue<'_> {
+    #[inline]
+    fn from(n: u32) -> Self {
+        AttributeValue::Number(n.into())
+    }
+}
+
+#[cfg(feature = "dom")]
+impl<'a> From<&'a JsValue> for AttributeValue<'a> {
+    #[inline]
+    fn from(v: &'a JsValue) -> Self {
+        AttributeValue::JsValue(v)
+    }
 }
 
 impl<'a> From<CachedNode> for Node<'a> {
@@ -145,13 +315,84 @@ impl<'a> From<CachedNode> for Node<'a> {
 
 impl fmt::Debug for Listener<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let (a, b) = self.get_callback_parts();
-        let a = a as *mut u32;
-        let b = b as *mut u32;
-        f.debug_struct("Listener")
-            .field("event", &self.event)
-            .field("callback", &(a, b))
-            .finish()
+        let mut f = f.debug_struct("Listener");
+        f.field("event", &self.event);
+        #[cfg(feature = "dom")]
+        {
+            let (a, b) = self.get_callback_parts();
+            f.field("callback", &(a as *mut u32, b as *mut u32));
+            f.field("non_passive", &self.non_passive);
+        }
+        f.finish()
+    }
+}
+
+impl<'a> Listener<'a> {
+    /// Construct a new listener without an identity token.
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub(crate) fn new(event: &'a str, callback: ListenerCallback<'a>) -> Listener<'a> {
+        Listener {
+            event,
+            callback,
+            id: None,
+            parts: Cell::new(None),
+            non_passive: false,
+        }
+    }
+
+    /// Get the type of event this listener is for, such as `"click"`.
+    #[inline]
+    pub fn event(&self) -> &'a str {
+        self.event
+    }
+
+    /// Get the parts that identify this listener in the events registry and
+    /// the physical DOM.
+    ///
+    /// Usually these are the callback's parts. Listeners with an identity
+    /// token are instead given parts that outlive their callback, so that a
+    /// later listener with the same token can take the physical listener over
+    /// without touching the DOM.
+    #[cfg(feature = "dom")]
+    pub(crate) fn registry_parts(&self) -> (u32, u32) {
+        if self.id.is_none() {
+            return self.get_callback_parts();
+        }
+        if let Some(parts) = self.parts.get() {
+            return parts;
+        }
+
+        // The second part of callback parts is a vtable pointer, which is
+        // never zero, so these never collide with them.
+        static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+        let parts = (NEXT_ID.fetch_add(1, Ordering::Relaxed), 0);
+        self.parts.set(Some(parts));
+        parts
+    }
+
+    /// Does this listener have the same identity token as `old`, so that it
+    /// can take over `old`'s physical listener with `take_over`?
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub(crate) fn has_same_id(&self, old: &Listener) -> bool {
+        self.id.is_some() && self.id == old.id && self.event == old.event
+    }
+
+    /// Is this listener added as passive, or not, the same as `old`? If not,
+    /// `old`'s physical listener has to be replaced rather than updated.
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub(crate) fn has_same_passivity(&self, old: &Listener) -> bool {
+        self.non_passive == old.non_passive
+    }
+
+    /// Take over `old`'s physical listener.
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub(crate) fn take_over(&self, old: &Listener) {
+        debug_assert!(self.has_same_id(old));
+        self.parts.set(Some(old.registry_parts()));
     }
 }
 
@@ -164,7 +405,7 @@ impl<'a> Attribute<'a> {
 
     /// The attribute value, such as `"my-thing"` in `<div id="my-thing" />`.
     #[inline]
-    pub fn value(&self) -> &'a str {
+    pub fn value(&self) -> AttributeValue<'a> {
         self.value
     }
 
@@ -172,12 +413,35 @@ impl<'a> Attribute<'a> {
     /// input that we can't see when diffing against the old virtual DOM. For
     /// these attributes, we want to always re-set the attribute on the physical
     /// DOM node, even if the old and new virtual DOM nodes have the same value.
+    ///
+    /// Any attribute can also be marked volatile individually, with
+    /// `ElementBuilder::attr_volatile`.
+    #[cfg(feature = "dom")]
     #[inline]
     pub(crate) fn is_volatile(&self) -> bool {
-        match self.name {
-            "value" | "checked" | "selected" => true,
-            _ => false,
-        }
+        self.volatile
+            || match self.name {
+                "value" | "checked" | "selected" | "indeterminate" | "muted" => true,
+                _ => false,
+            }
+    }
+
+    /// Is this the `is` attribute of a customized built-in element? Its value
+    /// is given to `document.createElement` when the element is created, and
+    /// it is never set as a regular attribute.
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub(crate) fn is_customized_builtin_name(&self, is_namespaced: bool) -> bool {
+        self.name == "is" && !is_namespaced
+    }
+
+    /// Some attributes, like those set with `ElementBuilder::default_value`,
+    /// are only applied when the element is created, and are ignored when
+    /// diffing.
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub(crate) fn is_create_only(&self) -> bool {
+        self.create_only
     }
 }
 
@@ -198,20 +462,69 @@ impl<'a> Node<'a> {
         children: &'a [Node<'a>],
         namespace: Option<&'a str>,
     ) -> Node<'a> {
-        let element = bump.alloc_with(|| ElementNode {
-            key,
-            tag_name,
-            listeners,
-            attributes,
-            children,
-            namespace,
-        });
+        Node::from_element_node(
+            bump,
+            ElementNode {
+                key,
+                tag_name,
+                listeners,
+                attributes,
+                children,
+                namespace,
+                managed_children: false,
+            },
+        )
+    }
 
+    #[inline]
+    pub(crate) fn from_element_node(bump: &'a Bump, element: ElementNode<'a>) -> Node<'a> {
+        let element = bump.alloc_with(|| element);
         Node {
             kind: NodeKind::Element(element),
         }
     }
 
+    /// Embed an existing physical DOM node, such as an element created by a
+    /// third-party widget, into the virtual DOM.
+    ///
+    /// When the raw node is created, the given node itself is inserted into
+    /// the physical DOM, rather than a new node being created. Diffing leaves
+    /// it alone as long as the next render embeds the same node, compared by
+    /// identity, at the same position. Dodrio never modifies the node, its
+    /// attributes, or its children, and does not manage any of its listeners.
+    ///
+    /// A physical DOM node can only be in one place at a time, so embed each
+    /// node at most once per render. Raw nodes should not be rendered within
+    /// `Cached` components that use templates, since templates are copied
+    /// with `cloneNode`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, Node, Render, RenderContext};
+    ///
+    /// struct Map {
+    ///     // A `<div>` that a third-party mapping library draws into.
+    ///     canvas: &'static web_sys::Node,
+    /// }
+    ///
+    /// impl<'a> Render<'a> for Map {
+    ///     fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+    ///         div(&cx)
+    ///             .attr("class", "map")
+    ///             .children([Node::raw(self.canvas)])
+    ///             .finish()
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "dom")]
+    #[inline]
+    pub fn raw(node: &'a web_sys::Node) -> Node<'a> {
+        Node {
+            kind: NodeKind::Raw(RawNode { node }),
+        }
+    }
+
     /// Construct a new text node with the given text.
     #[inline]
     pub(crate) fn text(text: &'a str) -> Node<'a> {
@@ -220,10 +533,86 @@ impl<'a> Node<'a> {
         }
     }
 
+    /// Estimate how much work creating this subtree costs, for example to
+    /// decide whether to split up rendering across multiple frames.
+    ///
+    /// Cached nodes are resolved through `cached_set` and counted like any
+    /// other subtree, even though re-rendering them is usually free.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this subtree references a cached node that is not in
+    /// `cached_set`.
+    pub fn complexity(&self, cached_set: &CachedSet) -> Complexity {
+        let mut complexity = Complexity::default();
+        self.add_complexity(cached_set, &mut complexity);
+        complexity
+    }
+
+    fn add_complexity(&self, cached_set: &CachedSet, complexity: &mut Complexity) {
+        match &self.kind {
+            NodeKind::Text(_) => complexity.nodes += 1,
+            #[cfg(feature = "dom")]
+            NodeKind::Raw(_) => complexity.nodes += 1,
+            NodeKind::Element(el) => {
+                complexity.nodes += 1;
+                complexity.listeners += el.listeners.len();
+                for child in el.children {
+                    child.add_complexity(cached_set, complexity);
+                }
+            }
+            NodeKind::Cached(c) => {
+                let (node, _template) = cached_set.get(c.id);
+                node.add_complexity(cached_set, complexity);
+            }
+        }
+    }
+
+    /// Find the first node in this subtree, in depth-first order, that has
+    /// the given key. This node itself is included in the search.
+    ///
+    /// Cached nodes are searched through `cached_set`. Searching for
+    /// `NodeKey::NONE` always returns `None`.
+    ///
+    /// This is useful for asserting on specific items of a keyed list in
+    /// tests, or for finding the item that an animation should target.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this subtree references a cached node that is not in
+    /// `cached_set`.
+    pub fn find_by_key<'b>(
+        &'b self,
+        cached_set: &'b CachedSet,
+        key: NodeKey,
+    ) -> Option<&'b Node<'b>> {
+        if key.is_none() {
+            return None;
+        }
+        if self.key() == key {
+            return Some(self);
+        }
+        match &self.kind {
+            NodeKind::Text(_) => None,
+            #[cfg(feature = "dom")]
+            NodeKind::Raw(_) => None,
+            NodeKind::Element(el) => el
+                .children
+                .iter()
+                .find_map(|child| child.find_by_key(cached_set, key)),
+            NodeKind::Cached(c) => {
+                let (node, _template) = cached_set.get(c.id);
+                node.find_by_key(cached_set, key)
+            }
+        }
+    }
+
     #[inline]
     pub(crate) fn key(&self) -> NodeKey {
         match &self.kind {
             NodeKind::Text(_) => NodeKey::NONE,
+            #[cfg(feature = "dom")]
+            NodeKind::Raw(_) => NodeKey::NONE,
             NodeKind::Element(e) => e.key,
             NodeKind::Cached(c) => c.key,
         }
@@ -246,11 +635,13 @@ impl<'a> IntoIterator for Node<'a> {
     }
 }
 
+#[cfg(feature = "dom")]
 union CallbackFatPtr<'a> {
     callback: ListenerCallback<'a>,
     parts: (u32, u32),
 }
 
+#[cfg(feature = "dom")]
 impl Listener<'_> {
     #[inline]
     pub(crate) fn get_callback_parts(&self) -> (u32, u32) {
@@ -269,3 +660,95 @@ impl Listener<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::*;
+
+    #[cfg(feature = "dom")]
+    #[test]
+    fn complexity_of_small_tree() {
+        let mut cached_set = CachedSet::default();
+        let footer = cached_set.insert(|cx| {
+            footer(&cx)
+                .on("click", |_, _, _| {})
+                .children([text("Bye")])
+                .finish()
+        });
+
+        let b = Bump::new();
+        let node = div(&b)
+            .on("click", |_, _, _| {})
+            .on("keydown", |_, _, _| {})
+            .children([
+                h1(&b).children([text("Hello")]).finish(),
+                ul(&b)
+                    .children([
+                        li(&b).children([text("one")]).finish(),
+                        li(&b).on("click", |_, _, _| {}).finish(),
+                    ])
+                    .finish(),
+                CachedNode {
+                    id: footer,
+                    key: NodeKey::NONE,
+                }
+                .into(),
+            ])
+            .finish();
+
+        // div, h1, "Hello", ul, li, "one", li, footer, "Bye"
+        assert_eq!(
+            node.complexity(&cached_set),
+            Complexity {
+                nodes: 9,
+                listeners: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn find_by_key_in_nested_list() {
+        let cached_set = CachedSet::default();
+        let b = Bump::new();
+        let node = div(&b)
+            .children([ul(&b)
+                .key(1)
+                .children([
+                    li(&b).key(2).children([text("two")]).finish(),
+                    li(&b)
+                        .key(3)
+                        .children([ol(&b)
+                            .children([li(&b).key(4).children([text("four")]).finish()])
+                            .finish()])
+                        .finish(),
+                ])
+                .finish()])
+            .finish();
+
+        let found = node.find_by_key(&cached_set, NodeKey::new(4)).unwrap();
+        assert_eq!(found.key(), NodeKey::new(4));
+        match &found.kind {
+            NodeKind::Element(el) => assert_eq!(el.tag_name, "li"),
+            _ => panic!("should find an element"),
+        }
+        assert_eq!(
+            node.find_by_key(&cached_set, NodeKey::new(1))
+                .map(Node::key),
+            Some(NodeKey::new(1))
+        );
+
+        assert!(node.find_by_key(&cached_set, NodeKey::new(5)).is_none());
+        assert!(node.find_by_key(&cached_set, NodeKey::NONE).is_none());
+    }
+
+    #[test]
+    fn hashed_keys_are_deterministic_and_never_none() {
+        assert_eq!(NodeKey::from_hash(&42_u64), NodeKey::from_hash(&42_u64));
+        assert_ne!(NodeKey::from_hash(&42_u64), NodeKey::from_hash(&43_u64));
+        assert_eq!(NodeKey::from_hash("apple"), NodeKey::from_hash("apple"));
+        for i in 0..1000_u64 {
+            assert!(NodeKey::from_hash(&i).is_some());
+        }
+    }
+}
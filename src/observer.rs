@@ -0,0 +1,84 @@
+//! Observing which nodes change during a diff.
+
+use crate::node::AttributeValue;
+use std::fmt;
+
+/// The kind of change that diffing made to a node.
+///
+/// See `Vdom::set_diff_observer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// The node was created.
+    Created,
+
+    /// The node was removed.
+    Removed,
+
+    /// The node was moved amongst its siblings.
+    Moved,
+}
+
+/// A change that diffing made to one of a node's attributes.
+///
+/// See `Vdom::set_attribute_observer`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AttributeChange<'a> {
+    /// The attribute's name.
+    pub name: &'a str,
+
+    /// The attribute's old value, or `None` if the attribute was added.
+    pub old: Option<AttributeValue<'a>>,
+
+    /// The attribute's new value, or `None` if the attribute was removed.
+    pub new: Option<AttributeValue<'a>>,
+}
+
+// The callback that a `DiffObserver` calls with each changed node's path.
+type DiffCallback = dyn FnMut(&[usize], ChangeKind);
+
+pub(crate) struct DiffObserver(Box<DiffCallback>);
+
+impl fmt::Debug for DiffObserver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("DiffObserver").finish()
+    }
+}
+
+impl DiffObserver {
+    pub(crate) fn new<F>(f: F) -> DiffObserver
+    where
+        F: 'static + FnMut(&[usize], ChangeKind),
+    {
+        DiffObserver(Box::new(f))
+    }
+
+    #[inline]
+    pub(crate) fn observe(&mut self, path: &[usize], kind: ChangeKind) {
+        (self.0)(path, kind)
+    }
+}
+
+// The callback that an `AttributeObserver` calls with each changed attribute.
+type AttributeCallback = dyn FnMut(&[usize], AttributeChange);
+
+pub(crate) struct AttributeObserver(Box<AttributeCallback>);
+
+impl fmt::Debug for AttributeObserver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AttributeObserver").finish()
+    }
+}
+
+impl AttributeObserver {
+    pub(crate) fn new<F>(f: F) -> AttributeObserver
+    where
+        F: 'static + FnMut(&[usize], AttributeChange),
+    {
+        AttributeObserver(Box::new(f))
+    }
+
+    #[inline]
+    pub(crate) fn observe(&mut self, path: &[usize], change: AttributeChange) {
+        (self.0)(path, change)
+    }
+}
@@ -1,7 +1,6 @@
-use crate::{Node, RenderContext};
+use crate::{Node, RenderContext, UnwrapThrowExt};
 use std::any::Any;
 use std::rc::Rc;
-use wasm_bindgen::UnwrapThrowExt;
 
 /// A trait for any component that can be rendered to HTML.
 ///
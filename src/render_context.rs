@@ -1,11 +1,14 @@
 use crate::{
     cached::{Cached, TemplateId},
     cached_set::{CacheId, CachedSet},
+    node::{CachedNode, Complexity},
     Node, Render,
 };
 use bumpalo::Bump;
 use fxhash::FxHashMap;
+use std::any::Any;
 use std::fmt;
+use std::rc::Rc;
 
 /// Common context available to all `Render` implementations.
 ///
@@ -30,6 +33,16 @@ pub struct RenderContext<'a> {
 
     pub(crate) templates: &'a mut FxHashMap<TemplateId, Option<CacheId>>,
 
+    // When rendering a cached subtree, the id of its cache entry, which keeps
+    // its `unique_id`s apart from those of the rest of the render.
+    pub(crate) id_scope: Option<CacheId>,
+
+    // The values provided with `provide` by the render functions that we are
+    // nested within, innermost last.
+    pub(crate) contexts: Vec<Rc<dyn Any>>,
+
+    next_unique_id: u32,
+
     // Prevent exhaustive matching on the rendering context, so we can always
     // add more members in a semver-compatible way.
     _non_exhaustive: (),
@@ -54,16 +67,215 @@ impl<'a> RenderContext<'a> {
                 bump,
                 cached_set,
                 templates,
+                id_scope: None,
+                contexts: Vec::new(),
+                next_unique_id: 0,
                 _non_exhaustive: (),
             }
         }
     }
 
+    /// Get the bump arena that virtual DOMs are rendered into.
+    ///
+    /// This is the same as the `bump` field.
+    #[inline]
+    pub fn bump(&self) -> &'a Bump {
+        self.bump
+    }
+
+    /// Copy the given string into the bump arena that virtual DOMs are
+    /// rendered into, so that it can be referenced from rendered nodes.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use dodrio::{builder::*, Node, RenderContext};
+    ///
+    /// fn greeting<'a>(cx: &mut RenderContext<'a>, name: &str) -> Node<'a> {
+    ///     let title = cx.alloc_str(&format!("Hello, {}!", name));
+    ///     div(&cx).attr("title", title).finish()
+    /// }
+    /// ```
+    #[inline]
+    pub fn alloc_str(&self, s: &str) -> &'a str {
+        bumpalo::collections::String::from_str_in(s, self.bump).into_bump_str()
+    }
+
+    /// Create a new, empty string that grows within the bump arena that
+    /// virtual DOMs are rendered into.
+    ///
+    /// This is handy for computed values, such as formatted numbers, that
+    /// rendered nodes need to reference. The string lives as long as the
+    /// render, and `into_bump_str` turns it into a `&'a str` without copying.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use dodrio::{builder::*, Node, RenderContext};
+    /// use std::fmt::Write;
+    ///
+    /// fn total<'a>(cx: &mut RenderContext<'a>, cents: u32) -> Node<'a> {
+    ///     let mut s = cx.new_string();
+    ///     write!(s, "${}.{:02}", cents / 100, cents % 100).unwrap();
+    ///     span(&cx).children([text(s.into_bump_str())]).finish()
+    /// }
+    /// ```
+    #[inline]
+    pub fn new_string(&self) -> bumpalo::collections::String<'a> {
+        bumpalo::collections::String::new_in(self.bump)
+    }
+
+    /// Create a new, empty vector that grows within the bump arena that
+    /// virtual DOMs are rendered into.
+    ///
+    /// Like `new_string`, the vector lives as long as the render, and
+    /// `into_bump_slice` turns it into a `&'a [T]` without copying. Note that
+    /// values in the bump arena are never dropped.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use dodrio::{builder::*, Node, RenderContext};
+    ///
+    /// fn list<'a>(cx: &mut RenderContext<'a>, items: &[&'a str]) -> Node<'a> {
+    ///     let mut children = cx.new_vec();
+    ///     for item in items {
+    ///         children.push(li(&cx).children([text(item)]).finish());
+    ///     }
+    ///     ul(&cx).children(children).finish()
+    /// }
+    /// ```
+    #[inline]
+    pub fn new_vec<T>(&self) -> bumpalo::collections::Vec<'a, T> {
+        bumpalo::collections::Vec::new_in(self.bump)
+    }
+
+    /// Generate an id, starting with `prefix`, that is unique within this
+    /// render. This is useful for linking elements together, such as a
+    /// `<label for>` to its `<input id>`, or an element to its description
+    /// with `aria-describedby`.
+    ///
+    /// Ids are numbered in the order they are generated, so the same logical
+    /// element gets the same id on every render, as long as each render
+    /// generates its ids in the same order. Generate ids before rendering
+    /// anything conditionally, or inside the condition's own cached
+    /// subtree, to keep them stable when the condition changes. Ids
+    /// generated within cached subtrees are only stable for as long as the
+    /// subtree stays cached.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use dodrio::{builder::*, Node, RenderContext};
+    ///
+    /// fn email_field<'a>(cx: &mut RenderContext<'a>) -> Node<'a> {
+    ///     let id = cx.unique_id("email");
+    ///     div(&cx)
+    ///         .children([
+    ///             label(&cx).for_(id).children([text("Email")]).finish(),
+    ///             input(&cx).id(id).type_("email").finish(),
+    ///         ])
+    ///         .finish()
+    /// }
+    /// ```
+    pub fn unique_id(&mut self, prefix: &str) -> &'a str {
+        let n = self.next_unique_id;
+        self.next_unique_id += 1;
+        let id = match self.id_scope {
+            None => bumpalo::format!(in self.bump, "{}-{}", prefix, n),
+            Some(scope) => bumpalo::format!(in self.bump, "{}-c{}-{}", prefix, scope.0, n),
+        };
+        id.into_bump_str()
+    }
+
+    /// Provide `value` to everything that `f` renders, and return what `f`
+    /// returns.
+    ///
+    /// Render functions nested within `f`, however deeply, can get the value
+    /// with `use_context`, without it being passed down through each of the
+    /// render functions in between. Providing a value of a type that is
+    /// already provided shadows the outer value within `f`.
+    ///
+    /// Cached subtrees are only re-rendered when they are invalidated, not
+    /// when the values provided around them change.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use dodrio::{builder::*, Node, RenderContext};
+    ///
+    /// struct Theme {
+    ///     accent: &'static str,
+    /// }
+    ///
+    /// fn app<'a>(cx: &mut RenderContext<'a>) -> Node<'a> {
+    ///     cx.provide(Theme { accent: "purple" }, |cx| {
+    ///         div(&cx).children([toolbar(cx)]).finish()
+    ///     })
+    /// }
+    ///
+    /// fn toolbar<'a>(cx: &mut RenderContext<'a>) -> Node<'a> {
+    ///     let accent = cx.use_context::<Theme>().map_or("black", |theme| theme.accent);
+    ///     button(&cx).attr("--accent", accent).finish()
+    /// }
+    /// ```
+    pub fn provide<T, F, R>(&mut self, value: T, f: F) -> R
+    where
+        T: 'static,
+        F: FnOnce(&mut Self) -> R,
+    {
+        self.contexts.push(Rc::new(value));
+        let result = f(self);
+        self.contexts.pop();
+        result
+    }
+
+    /// Get the innermost value of type `T` provided with `provide` around the
+    /// current render function, if any.
+    ///
+    /// See `provide` for an example.
+    pub fn use_context<T>(&self) -> Option<Rc<T>>
+    where
+        T: 'static,
+    {
+        self.contexts
+            .iter()
+            .rev()
+            .find(|value| value.is::<T>())
+            .and_then(|value| value.clone().downcast().ok())
+    }
+
+    /// Estimate how much work creating the given subtree costs, resolving any
+    /// cached nodes through this context's cached set.
+    ///
+    /// See `Node::complexity` for details.
+    pub fn complexity(&self, node: &Node) -> Complexity {
+        node.complexity(&self.cached_set.borrow())
+    }
+
     pub(crate) fn cache<F>(&mut self, pinned: bool, template: Option<CacheId>, f: F) -> CacheId
     where
         F: for<'b> FnOnce(&mut RenderContext<'b>) -> Node<'b>,
     {
-        CachedSet::insert(self, pinned, template, f)
+        CachedSet::insert_with_context(self, pinned, template, f)
+    }
+
+    /// Reference a subtree that was pre-warmed into the `Vdom`'s cached set
+    /// with `CachedSet::insert`.
+    ///
+    /// Rendering the returned node neither re-renders nor re-diffs the cached
+    /// subtree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not in this context's cached set.
+    pub fn cached(&self, id: CacheId) -> Node<'a> {
+        let key = {
+            let cached_set = self.cached_set.borrow();
+            let (node, _template) = cached_set.get(id);
+            node.key()
+        };
+        CachedNode { id, key }.into()
     }
 
     /// Get or create the cached template for `Cached<R>`.
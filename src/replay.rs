@@ -0,0 +1,642 @@
+//! Recording the operations produced by diffing, and replaying them later.
+//!
+//! `record_ops` diffs two virtual DOM trees and captures the resulting
+//! operations as owned `ChangeOp`s, rather than applying them. The recorded
+//! operations can then be replayed against a fresh physical DOM with
+//! `replay_ops`, or against any other `ChangeSink` with `ChangeOp::apply_to`,
+//! for example to check that the operations alone are enough to reconstruct
+//! the new tree, or to persist them and apply them elsewhere.
+//!
+//! Together, `reconcile` and `commit` split diffing into two phases: the first
+//! computes a `ChangeList` without touching the DOM, and the second applies it
+//! to a `Vdom`'s physical DOM. In between, the change list can be inspected,
+//! logged, or transformed.
+//!
+//! `materialize` uses the same machinery to build the physical DOM for a
+//! virtual DOM node in a detached `DocumentFragment`, for code outside of
+//! Dodrio to insert wherever it likes.
+//!
+//! Listeners' callbacks are not recorded, only the types of the events they
+//! listen for and the parts that identify them, so replaying skips adding,
+//! updating, and removing listeners. A `ChangeList` borrows the callbacks
+//! instead, and `commit` registers them with the `Vdom`.
+
+use crate::{
+    cached_set::{CacheId, CachedSet},
+    change_list::interpreter::InterpreterSink,
+    change_list::ChangeListPersistentState,
+    events::EventsRegistry,
+    node::{ElementNode, NodeKind},
+    ChangeSink, Listener, Node, Vdom,
+};
+use std::cell::RefCell;
+use std::rc::Weak;
+use wasm_bindgen::JsValue;
+
+/// An owned operation that diffing sent to a `ChangeSink`.
+///
+/// There is one variant for each of `ChangeSink`'s methods, with the same
+/// arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChangeOp {
+    /// See `ChangeSink::push_child`.
+    PushChild(u32),
+    /// See `ChangeSink::push_reverse_child`.
+    PushReverseChild(u32),
+    /// See `ChangeSink::pop_push_child`.
+    PopPushChild(u32),
+    /// See `ChangeSink::pop_push_reverse_child`.
+    PopPushReverseChild(u32),
+    /// See `ChangeSink::pop`.
+    Pop,
+    /// See `ChangeSink::push_temporary`.
+    PushTemporary(u32),
+    /// See `ChangeSink::save_children_to_temporaries`.
+    SaveChildrenToTemporaries(u32, u32, u32),
+    /// See `ChangeSink::remove_child`.
+    RemoveChild(u32),
+    /// See `ChangeSink::insert_before`.
+    InsertBefore,
+    /// See `ChangeSink::append_child`.
+    AppendChild,
+    /// See `ChangeSink::replace_with`.
+    ReplaceWith,
+    /// See `ChangeSink::replace_siblings_with`.
+    ReplaceSiblingsWith(u32),
+    /// See `ChangeSink::remove_self_and_next_siblings`.
+    RemoveSelfAndNextSiblings,
+    /// See `ChangeSink::create_text_node`.
+    CreateTextNode(String),
+    /// See `ChangeSink::create_element`.
+    CreateElement(String),
+    /// See `ChangeSink::create_element_ns`.
+    CreateElementNs(String, String),
+    /// See `ChangeSink::create_element_with_is`.
+    CreateElementWithIs(String, String),
+    /// See `ChangeSink::create_element_from_html`.
+    CreateElementFromHtml(String),
+    /// See `ChangeSink::push_raw_node`.
+    PushRawNode(web_sys::Node),
+    /// See `ChangeSink::set_text`.
+    SetText(String),
+    /// See `ChangeSink::set_attribute`.
+    SetAttribute(String, String),
+    /// See `ChangeSink::remove_attribute`.
+    RemoveAttribute(String),
+    /// See `ChangeSink::flush_attributes`.
+    FlushAttributes,
+    /// See `ChangeSink::set_class`.
+    SetClass(String),
+    /// See `ChangeSink::set_style_property`.
+    SetStyleProperty(String, String),
+    /// See `ChangeSink::remove_style_property`.
+    RemoveStyleProperty(String),
+    /// See `ChangeSink::set_property`.
+    SetProperty(String, JsValue),
+    /// See `ChangeSink::remove_property`.
+    RemoveProperty(String),
+    /// See `ChangeSink::new_event_listener`.
+    NewEventListener {
+        /// The type of event to listen for.
+        event: String,
+        /// The parts that identify the listener's callback in the events
+        /// registry.
+        parts: (u32, u32),
+        /// Can the listener cancel scrolling and touch events?
+        non_passive: bool,
+    },
+    /// See `ChangeSink::update_event_listener`.
+    UpdateEventListener {
+        /// The type of event listened for.
+        event: String,
+        /// The parts that identify the listener's new callback in the events
+        /// registry.
+        parts: (u32, u32),
+    },
+    /// See `ChangeSink::remove_event_listener`.
+    RemoveEventListener(String),
+    /// See `ChangeSink::save_template`.
+    SaveTemplate(CacheId),
+    /// See `ChangeSink::push_template`.
+    PushTemplate(CacheId),
+    /// See `ChangeSink::finish`.
+    Finish,
+}
+
+impl ChangeOp {
+    /// Apply this operation to the given sink.
+    ///
+    /// Listener operations are skipped, since their callbacks were not
+    /// recorded.
+    pub fn apply_to<S: ChangeSink>(&self, sink: &mut S) {
+        match self {
+            ChangeOp::PushChild(n) => sink.push_child(*n),
+            ChangeOp::PushReverseChild(n) => sink.push_reverse_child(*n),
+            ChangeOp::PopPushChild(n) => sink.pop_push_child(*n),
+            ChangeOp::PopPushReverseChild(n) => sink.pop_push_reverse_child(*n),
+            ChangeOp::Pop => sink.pop(),
+            ChangeOp::PushTemporary(temp) => sink.push_temporary(*temp),
+            ChangeOp::SaveChildrenToTemporaries(temp_base, start, end) => {
+                sink.save_children_to_temporaries(*temp_base, *start, *end)
+            }
+            ChangeOp::RemoveChild(n) => sink.remove_child(*n),
+            ChangeOp::InsertBefore => sink.insert_before(),
+            ChangeOp::AppendChild => sink.append_child(),
+            ChangeOp::ReplaceWith => sink.replace_with(),
+            ChangeOp::ReplaceSiblingsWith(count) => sink.replace_siblings_with(*count),
+            ChangeOp::RemoveSelfAndNextSiblings => sink.remove_self_and_next_siblings(),
+            ChangeOp::CreateTextNode(text) => sink.create_text_node(text),
+            ChangeOp::CreateElement(tag_name) => sink.create_element(tag_name),
+            ChangeOp::CreateElementNs(tag_name, ns) => sink.create_element_ns(tag_name, ns),
+            ChangeOp::CreateElementWithIs(tag_name, is) => {
+                sink.create_element_with_is(tag_name, is)
+            }
+            ChangeOp::CreateElementFromHtml(html) => sink.create_element_from_html(html.clone()),
+            ChangeOp::PushRawNode(node) => sink.push_raw_node(node),
+            ChangeOp::SetText(text) => sink.set_text(text),
+            ChangeOp::SetAttribute(name, value) => sink.set_attribute(name, value),
+            ChangeOp::RemoveAttribute(name) => sink.remove_attribute(name),
+            ChangeOp::FlushAttributes => sink.flush_attributes(),
+            ChangeOp::SetClass(class) => sink.set_class(class),
+            ChangeOp::SetStyleProperty(name, value) => sink.set_style_property(name, value),
+            ChangeOp::RemoveStyleProperty(name) => sink.remove_style_property(name),
+            ChangeOp::SetProperty(name, value) => sink.set_property(name, value),
+            ChangeOp::RemoveProperty(name) => sink.remove_property(name),
+            ChangeOp::NewEventListener { .. }
+            | ChangeOp::UpdateEventListener { .. }
+            | ChangeOp::RemoveEventListener(_) => {}
+            ChangeOp::SaveTemplate(id) => sink.save_template(*id),
+            ChangeOp::PushTemplate(id) => sink.push_template(*id),
+            ChangeOp::Finish => sink.finish(),
+        }
+    }
+
+    // Apply this operation to a `Vdom`'s interpreter, including listener
+    // operations, but leave finishing to the caller.
+    pub(crate) fn apply_to_interpreter(&self, sink: &mut InterpreterSink) {
+        match self {
+            ChangeOp::NewEventListener {
+                event,
+                parts,
+                non_passive,
+            } => sink.new_event_listener_with_parts(event, *parts, *non_passive),
+            ChangeOp::UpdateEventListener { event, parts } => {
+                sink.update_event_listener_with_parts(event, *parts)
+            }
+            ChangeOp::RemoveEventListener(event) => sink.remove_event_listener(event),
+            ChangeOp::Finish => {}
+            op => op.apply_to(sink),
+        }
+    }
+}
+
+/// A `ChangeSink` that records the operations it receives as `ChangeOp`s.
+#[derive(Debug, Default)]
+pub struct OpRecorder {
+    ops: Vec<ChangeOp>,
+}
+
+impl OpRecorder {
+    /// Create a new, empty recorder.
+    pub fn new() -> OpRecorder {
+        OpRecorder::default()
+    }
+
+    /// Get the operations recorded so far.
+    pub fn ops(&self) -> &[ChangeOp] {
+        &self.ops
+    }
+
+    /// Take the operations recorded so far, leaving this recorder empty.
+    pub fn take_ops(&mut self) -> Vec<ChangeOp> {
+        std::mem::replace(&mut self.ops, vec![])
+    }
+}
+
+impl ChangeSink for OpRecorder {
+    fn push_child(&mut self, n: u32) {
+        self.ops.push(ChangeOp::PushChild(n));
+    }
+
+    fn push_reverse_child(&mut self, n: u32) {
+        self.ops.push(ChangeOp::PushReverseChild(n));
+    }
+
+    fn pop_push_child(&mut self, n: u32) {
+        self.ops.push(ChangeOp::PopPushChild(n));
+    }
+
+    fn pop_push_reverse_child(&mut self, n: u32) {
+        self.ops.push(ChangeOp::PopPushReverseChild(n));
+    }
+
+    fn pop(&mut self) {
+        self.ops.push(ChangeOp::Pop);
+    }
+
+    fn push_temporary(&mut self, temp: u32) {
+        self.ops.push(ChangeOp::PushTemporary(temp));
+    }
+
+    fn save_children_to_temporaries(&mut self, temp_base: u32, start: u32, end: u32) {
+        self.ops
+            .push(ChangeOp::SaveChildrenToTemporaries(temp_base, start, end));
+    }
+
+    fn remove_child(&mut self, n: u32) {
+        self.ops.push(ChangeOp::RemoveChild(n));
+    }
+
+    fn insert_before(&mut self) {
+        self.ops.push(ChangeOp::InsertBefore);
+    }
+
+    fn append_child(&mut self) {
+        self.ops.push(ChangeOp::AppendChild);
+    }
+
+    fn replace_with(&mut self) {
+        self.ops.push(ChangeOp::ReplaceWith);
+    }
+
+    fn replace_siblings_with(&mut self, count: u32) {
+        self.ops.push(ChangeOp::ReplaceSiblingsWith(count));
+    }
+
+    fn remove_self_and_next_siblings(&mut self) {
+        self.ops.push(ChangeOp::RemoveSelfAndNextSiblings);
+    }
+
+    fn create_text_node(&mut self, text: &str) {
+        self.ops.push(ChangeOp::CreateTextNode(text.to_string()));
+    }
+
+    fn create_element(&mut self, tag_name: &str) {
+        self.ops.push(ChangeOp::CreateElement(tag_name.to_string()));
+    }
+
+    fn create_element_ns(&mut self, tag_name: &str, ns: &str) {
+        self.ops.push(ChangeOp::CreateElementNs(
+            tag_name.to_string(),
+            ns.to_string(),
+        ));
+    }
+
+    fn create_element_with_is(&mut self, tag_name: &str, is: &str) {
+        self.ops.push(ChangeOp::CreateElementWithIs(
+            tag_name.to_string(),
+            is.to_string(),
+        ));
+    }
+
+    fn create_element_from_html(&mut self, html: String) {
+        self.ops.push(ChangeOp::CreateElementFromHtml(html));
+    }
+
+    fn push_raw_node(&mut self, node: &web_sys::Node) {
+        self.ops.push(ChangeOp::PushRawNode(node.clone()));
+    }
+
+    fn set_text(&mut self, text: &str) {
+        self.ops.push(ChangeOp::SetText(text.to_string()));
+    }
+
+    fn set_attribute(&mut self, name: &str, value: &str) {
+        self.ops
+            .push(ChangeOp::SetAttribute(name.to_string(), value.to_string()));
+    }
+
+    fn remove_attribute(&mut self, name: &str) {
+        self.ops.push(ChangeOp::RemoveAttribute(name.to_string()));
+    }
+
+    fn flush_attributes(&mut self) {
+        self.ops.push(ChangeOp::FlushAttributes);
+    }
+
+    fn set_class(&mut self, class: &str) {
+        self.ops.push(ChangeOp::SetClass(class.to_string()));
+    }
+
+    fn set_style_property(&mut self, name: &str, value: &str) {
+        self.ops.push(ChangeOp::SetStyleProperty(
+            name.to_string(),
+            value.to_string(),
+        ));
+    }
+
+    fn remove_style_property(&mut self, name: &str) {
+        self.ops
+            .push(ChangeOp::RemoveStyleProperty(name.to_string()));
+    }
+
+    fn set_property(&mut self, name: &str, value: &JsValue) {
+        self.ops
+            .push(ChangeOp::SetProperty(name.to_string(), value.clone()));
+    }
+
+    fn remove_property(&mut self, name: &str) {
+        self.ops.push(ChangeOp::RemoveProperty(name.to_string()));
+    }
+
+    fn new_event_listener(&mut self, listener: &Listener) {
+        self.ops.push(ChangeOp::NewEventListener {
+            event: listener.event.to_string(),
+            parts: listener.registry_parts(),
+            non_passive: listener.non_passive,
+        });
+    }
+
+    fn update_event_listener(&mut self, listener: &Listener) {
+        self.ops.push(ChangeOp::UpdateEventListener {
+            event: listener.event.to_string(),
+            parts: listener.registry_parts(),
+        });
+    }
+
+    fn remove_event_listener(&mut self, event: &str) {
+        self.ops
+            .push(ChangeOp::RemoveEventListener(event.to_string()));
+    }
+
+    fn save_template(&mut self, id: CacheId) {
+        self.ops.push(ChangeOp::SaveTemplate(id));
+    }
+
+    fn push_template(&mut self, id: CacheId) {
+        self.ops.push(ChangeOp::PushTemplate(id));
+    }
+
+    fn finish(&mut self) {
+        self.ops.push(ChangeOp::Finish);
+    }
+}
+
+/// Diff `old` against `new`, and record the resulting operations, ending with
+/// `ChangeOp::Finish`.
+///
+/// Replaying the operations against a physical DOM whose root matches `old`
+/// turns it into a physical DOM that matches `new`.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo::Bump, replay};
+///
+/// let b = Bump::new();
+/// let old = div(&b).finish();
+/// let new = div(&b).children([p(&b).children([text("hi")]).finish()]).finish();
+/// let ops = replay::record_ops(&old, &new);
+///
+/// // Later, and somewhere else...
+/// # let container: dodrio::Element = unimplemented!();
+/// // `container` contains a single, empty `<div>`, which matches `old`.
+/// replay::replay_ops(&container, &ops);
+/// ```
+///
+/// # Panics
+///
+/// Panics if either tree contains cached nodes, since there is no cached set
+/// to look them up in.
+pub fn record_ops(old: &Node, new: &Node) -> Vec<ChangeOp> {
+    record_ops_with_cached_set(&CachedSet::default(), old, new)
+}
+
+// Like `record_ops`, but look up cached nodes in the given cached set.
+fn record_ops_with_cached_set(cached_set: &CachedSet, old: &Node, new: &Node) -> Vec<ChangeOp> {
+    let (registry, _trampoline) = EventsRegistry::new(Weak::new());
+    let mut state = ChangeListPersistentState::with_sink(OpRecorder::new());
+    {
+        let mut cached_roots = cached_set.new_roots_set();
+        let mut change_list = state.builder();
+        crate::diff::diff(
+            cached_set,
+            &mut change_list,
+            &mut registry.borrow_mut(),
+            old,
+            new,
+            &mut cached_roots,
+        );
+        change_list.finish();
+    }
+    state.sink_mut().take_ops()
+}
+
+/// Replay recorded operations against the physical DOM in `container`.
+///
+/// The container's first child is the root that the operations start from,
+/// the same as for a container that a `Vdom` is mounted in.
+pub fn replay_ops(container: &crate::Element, ops: &[ChangeOp]) {
+    let (registry, trampoline) = EventsRegistry::new(Weak::new());
+    let mut state = ChangeListPersistentState::new(container);
+    state.init_events_trampoline(&trampoline, registry.borrow().namespace());
+    for op in ops {
+        op.apply_to(state.sink_mut());
+    }
+}
+
+/// The operations that turn one virtual DOM tree's physical DOM into another's,
+/// computed by `reconcile` and applied by `commit`.
+///
+/// Also borrows the new tree's listeners, for `commit` to register.
+#[derive(Clone, Debug, Default)]
+pub struct ChangeList<'a> {
+    ops: Vec<ChangeOp>,
+    listeners: Vec<&'a Listener<'a>>,
+}
+
+impl<'a> ChangeList<'a> {
+    /// Get the operations in this change list, in order.
+    #[inline]
+    pub fn ops(&self) -> &[ChangeOp] {
+        &self.ops
+    }
+
+    /// Get mutable access to the operations in this change list, for example
+    /// to transform them before committing.
+    #[inline]
+    pub fn ops_mut(&mut self) -> &mut Vec<ChangeOp> {
+        &mut self.ops
+    }
+
+    /// Take the operations out of this change list.
+    #[inline]
+    pub fn into_ops(self) -> Vec<ChangeOp> {
+        self.ops
+    }
+}
+
+impl PartialEq for ChangeList<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ops == other.ops
+    }
+}
+
+impl From<Vec<ChangeOp>> for ChangeList<'_> {
+    #[inline]
+    fn from(ops: Vec<ChangeOp>) -> Self {
+        ChangeList {
+            ops,
+            listeners: Vec::new(),
+        }
+    }
+}
+
+/// Diff `old` against `new` and return the resulting change list, without
+/// applying it to any physical DOM.
+///
+/// This is the reconcile phase of diffing; see `commit` for the other half.
+/// The operations are the same as `record_ops`', and the same caveats apply.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo::Bump, replay, Vdom};
+///
+/// let b = Bump::new();
+/// let old = div(&b).finish();
+/// let new = div(&b).attr("class", "ready").finish();
+///
+/// let change_list = replay::reconcile(&old, &new);
+/// for op in change_list.ops() {
+///     log::debug!("{:?}", op);
+/// }
+///
+/// # let container: dodrio::Element = unimplemented!();
+/// let vdom = Vdom::with_render_fn(&container, |cx| div(cx.bump).finish());
+/// let _listeners = replay::commit(&vdom, &change_list);
+/// ```
+///
+/// # Panics
+///
+/// Panics if either tree contains cached nodes.
+pub fn reconcile<'a>(old: &Node, new: &Node<'a>) -> ChangeList<'a> {
+    let mut listeners = Vec::new();
+    collect_listeners(new, &mut listeners);
+    ChangeList {
+        ops: record_ops(old, new),
+        listeners,
+    }
+}
+
+fn collect_listeners<'a>(node: &Node<'a>, listeners: &mut Vec<&'a Listener<'a>>) {
+    if let NodeKind::Element(&ElementNode {
+        listeners: ls,
+        children,
+        ..
+    }) = node.kind
+    {
+        listeners.extend(ls);
+        for child in children {
+            collect_listeners(child, listeners);
+        }
+    }
+}
+
+/// Apply a change list to `vdom`'s physical DOM.
+///
+/// This is the commit phase of diffing; see `reconcile`. The `Vdom`'s physical
+/// DOM must be that of the old tree that the change list was reconciled from.
+/// The `Vdom`'s own virtual DOM is not updated, so this is meant for `Vdom`s
+/// whose contents are driven by change lists rather than by re-rendering.
+///
+/// The change list is finished once all of its operations are applied, even
+/// if it was transformed to no longer end with `ChangeOp::Finish`.
+///
+/// The new tree's listeners are registered with the `Vdom`, and stay
+/// registered until the returned `CommittedListeners` is dropped.
+pub fn commit<'a>(vdom: &Vdom, change_list: &ChangeList<'a>) -> CommittedListeners<'a> {
+    let registry = vdom.commit_ops(&change_list.ops, &change_list.listeners);
+    CommittedListeners {
+        registry,
+        listeners: change_list.listeners.clone(),
+    }
+}
+
+/// Keeps the listeners of a committed change list registered with its `Vdom`.
+///
+/// Returned by `commit`. Dropping it unregisters the listeners, after which
+/// their events are ignored, so keep it until the physical DOM it was
+/// committed to is replaced.
+#[must_use = "the committed listeners are unregistered as soon as this is dropped"]
+#[derive(Debug)]
+pub struct CommittedListeners<'a> {
+    registry: Weak<RefCell<EventsRegistry>>,
+    listeners: Vec<&'a Listener<'a>>,
+}
+
+impl Drop for CommittedListeners<'_> {
+    fn drop(&mut self) {
+        if let Some(registry) = self.registry.upgrade() {
+            let mut registry = registry.borrow_mut();
+            for l in &self.listeners {
+                // A later commit may have registered another callback under
+                // the same parts, which must stay.
+                registry.remove_if_current(l);
+            }
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(not(all(feature = "xxx-unstable-internal-use-only", not(target_arch = "wasm32"))))] {
+        use wasm_bindgen::UnwrapThrowExt;
+
+        /// Build the physical DOM for `node` in a new, detached
+        /// `DocumentFragment`, and return the fragment.
+        ///
+        /// Cached nodes within `node` are looked up in `cached_set`. Like
+        /// `replay_ops`, this does not attach listeners, since there is no
+        /// `Vdom` to dispatch their events to. The fragment is not tracked in
+        /// any way afterwards, so it can be inserted anywhere, including
+        /// outside of Dodrio's control.
+        ///
+        /// # Example
+        ///
+        /// ```no_run
+        /// use dodrio::{builder::*, bumpalo::Bump, replay, CachedSet};
+        ///
+        /// let b = Bump::new();
+        /// let cached_set = CachedSet::default();
+        /// let tooltip = div(&b)
+        ///     .attr("role", "tooltip")
+        ///     .children([text("Copied!")])
+        ///     .finish();
+        ///
+        /// let fragment = replay::materialize(&cached_set, &tooltip);
+        /// # let body: web_sys::Element = unimplemented!();
+        /// body.append_child(&fragment).unwrap();
+        /// ```
+        pub fn materialize(cached_set: &CachedSet, node: &Node) -> web_sys::DocumentFragment {
+            let document = web_sys::window()
+                .expect_throw("should have access to the Window")
+                .document()
+                .expect_throw("should have access to the Document");
+
+            // Build the node in place of an empty root, inside a detached
+            // container, and then move it into the fragment.
+            let container = document
+                .create_element("div")
+                .expect_throw("should create element OK");
+            let root = document
+                .create_element("div")
+                .expect_throw("should create element OK");
+            container
+                .append_child(&root)
+                .expect_throw("should append child OK");
+
+            let bump = bumpalo::Bump::new();
+            let empty = crate::builder::div(&bump).finish();
+            let ops = record_ops_with_cached_set(cached_set, &empty, node);
+            replay_ops(&container, &ops);
+
+            let fragment = document.create_document_fragment();
+            while let Some(child) = container.first_child() {
+                fragment
+                    .append_child(&child)
+                    .expect_throw("should append child OK");
+            }
+            fragment
+        }
+    }
+}
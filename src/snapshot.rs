@@ -0,0 +1,326 @@
+//! Owned snapshots of a rendered virtual DOM, for debugging tools.
+//!
+//! A rendered virtual DOM is bump allocated and only lives until the next
+//! render, so debugging tools cannot hold onto it. A `VdomSnapshot` is an owned
+//! copy of its structure, attributes, and keys, that can be kept around to
+//! display and compare renders over time. Listener callbacks are not captured,
+//! only the types of the events they listen for.
+//!
+//! With the `serde` feature enabled, snapshots implement `serde::Serialize` and
+//! `serde::Deserialize`, so that they can be sent to a devtools extension, for
+//! example as JSON over `postMessage`.
+
+#[cfg(feature = "dom")]
+use crate::{
+    cached_set::CachedSet,
+    node::{ElementNode, NodeKind, TextNode},
+    AttributeValue, Node,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An owned snapshot of a virtual DOM node and its descendants.
+///
+/// See `Vdom::snapshot`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VdomSnapshot {
+    /// A text node.
+    Text(String),
+
+    /// An element.
+    Element {
+        /// The element's tag name.
+        tag_name: String,
+
+        /// The element's namespace, if any.
+        namespace: Option<String>,
+
+        /// The element's key, if it has one.
+        key: Option<u32>,
+
+        /// The element's attributes, in order. Values are as they would be
+        /// set on the physical DOM, and absent boolean attributes are omitted.
+        /// Attributes that are set as properties have a description of their
+        /// JavaScript value as their value.
+        attributes: Vec<(String, String)>,
+
+        /// The types of events that the element listens for, in order.
+        listeners: Vec<String>,
+
+        /// The element's children.
+        children: Vec<VdomSnapshot>,
+    },
+
+    /// A raw physical DOM node embedded with `Node::raw`. Its contents are not
+    /// part of the virtual DOM, so they are not captured.
+    Raw,
+}
+
+impl VdomSnapshot {
+    /// Snapshot the given node, looking up any cached nodes it references in
+    /// `cached_set`.
+    #[cfg(feature = "dom")]
+    pub(crate) fn new(node: &Node, cached_set: &CachedSet) -> VdomSnapshot {
+        match node.kind {
+            NodeKind::Text(TextNode { text }) => VdomSnapshot::Text(text.to_string()),
+            NodeKind::Element(&ElementNode {
+                key,
+                tag_name,
+                listeners,
+                attributes,
+                children,
+                namespace,
+                managed_children: _,
+            }) => VdomSnapshot::Element {
+                tag_name: tag_name.to_string(),
+                namespace: namespace.map(str::to_string),
+                key: if key.is_some() { Some(key.0) } else { None },
+                attributes: attributes
+                    .iter()
+                    .filter_map(|attr| {
+                        let value = match attr.value() {
+                            AttributeValue::Str(s) => s.to_string(),
+                            AttributeValue::Bool(true) => String::new(),
+                            AttributeValue::Bool(false) => return None,
+                            AttributeValue::Number(n) => n.to_string(),
+                            AttributeValue::JsValue(v) => format!("{:?}", v),
+                        };
+                        Some((attr.name().to_string(), value))
+                    })
+                    .collect(),
+                listeners: listeners
+                    .iter()
+                    .map(|listener| listener.event.to_string())
+                    .collect(),
+                children: children
+                    .iter()
+                    .map(|child| VdomSnapshot::new(child, cached_set))
+                    .collect(),
+            },
+            NodeKind::Cached(ref c) => {
+                let (node, _template) = cached_set.get(c.id);
+                VdomSnapshot::new(node, cached_set)
+            }
+            NodeKind::Raw(_) => VdomSnapshot::Raw,
+        }
+    }
+}
+
+/// A difference between two `VdomSnapshot`s.
+///
+/// Nodes are identified by their path of child indices from the root, the
+/// same as with `Vdom::set_diff_observer`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotDifference {
+    /// The node at `path` was replaced with a different kind of node, or an
+    /// element with a different tag name, namespace, or key.
+    Replaced {
+        /// The node's path.
+        path: Vec<usize>,
+    },
+
+    /// The text node at `path` had its text changed.
+    TextChanged {
+        /// The node's path.
+        path: Vec<usize>,
+        /// The old text.
+        old: String,
+        /// The new text.
+        new: String,
+    },
+
+    /// The element at `path` had an attribute added, removed, or changed.
+    AttributeChanged {
+        /// The element's path.
+        path: Vec<usize>,
+        /// The attribute's name.
+        name: String,
+        /// The attribute's old value, or `None` if it was added.
+        old: Option<String>,
+        /// The attribute's new value, or `None` if it was removed.
+        new: Option<String>,
+    },
+
+    /// The element at `path` started or stopped listening for some events.
+    ListenersChanged {
+        /// The element's path.
+        path: Vec<usize>,
+        /// The old event types.
+        old: Vec<String>,
+        /// The new event types.
+        new: Vec<String>,
+    },
+
+    /// A child was added at `path`.
+    Added {
+        /// The new child's path.
+        path: Vec<usize>,
+    },
+
+    /// The child at `path` was removed.
+    Removed {
+        /// The old child's path.
+        path: Vec<usize>,
+    },
+}
+
+/// List the differences between two snapshots.
+///
+/// Children are compared by their position, so inserting a child in the
+/// middle of a list shows up as changes to all of the children after it.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{diff_snapshots, Vdom};
+///
+/// # fn example(vdom: &Vdom) {
+/// let before = vdom.snapshot();
+/// // Re-render...
+/// let after = vdom.snapshot();
+/// for difference in diff_snapshots(&before, &after) {
+///     web_sys::console::log_1(&format!("{:?}", difference).into());
+/// }
+/// # }
+/// ```
+pub fn diff_snapshots(old: &VdomSnapshot, new: &VdomSnapshot) -> Vec<SnapshotDifference> {
+    let mut differences = vec![];
+    let mut path = vec![];
+    diff_nodes(old, new, &mut path, &mut differences);
+    differences
+}
+
+fn diff_nodes(
+    old: &VdomSnapshot,
+    new: &VdomSnapshot,
+    path: &mut Vec<usize>,
+    differences: &mut Vec<SnapshotDifference>,
+) {
+    match (old, new) {
+        (VdomSnapshot::Text(old), VdomSnapshot::Text(new)) => {
+            if old != new {
+                differences.push(SnapshotDifference::TextChanged {
+                    path: path.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+        }
+        (
+            VdomSnapshot::Element {
+                tag_name: old_tag_name,
+                namespace: old_namespace,
+                key: old_key,
+                attributes: old_attributes,
+                listeners: old_listeners,
+                children: old_children,
+            },
+            VdomSnapshot::Element {
+                tag_name: new_tag_name,
+                namespace: new_namespace,
+                key: new_key,
+                attributes: new_attributes,
+                listeners: new_listeners,
+                children: new_children,
+            },
+        ) if old_tag_name == new_tag_name
+            && old_namespace == new_namespace
+            && old_key == new_key =>
+        {
+            let value = |attributes: &[(String, String)], name: &str| {
+                attributes
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, value)| value.clone())
+            };
+            for (name, old_value) in old_attributes {
+                let new_value = value(new_attributes, name);
+                if new_value.as_ref() != Some(old_value) {
+                    differences.push(SnapshotDifference::AttributeChanged {
+                        path: path.clone(),
+                        name: name.clone(),
+                        old: Some(old_value.clone()),
+                        new: new_value,
+                    });
+                }
+            }
+            for (name, new_value) in new_attributes {
+                if value(old_attributes, name).is_none() {
+                    differences.push(SnapshotDifference::AttributeChanged {
+                        path: path.clone(),
+                        name: name.clone(),
+                        old: None,
+                        new: Some(new_value.clone()),
+                    });
+                }
+            }
+
+            if old_listeners != new_listeners {
+                differences.push(SnapshotDifference::ListenersChanged {
+                    path: path.clone(),
+                    old: old_listeners.clone(),
+                    new: new_listeners.clone(),
+                });
+            }
+
+            for (i, (old_child, new_child)) in old_children.iter().zip(new_children).enumerate() {
+                path.push(i);
+                diff_nodes(old_child, new_child, path, differences);
+                path.pop();
+            }
+            for i in new_children.len()..old_children.len() {
+                path.push(i);
+                differences.push(SnapshotDifference::Removed { path: path.clone() });
+                path.pop();
+            }
+            for i in old_children.len()..new_children.len() {
+                path.push(i);
+                differences.push(SnapshotDifference::Added { path: path.clone() });
+                path.pop();
+            }
+        }
+        (VdomSnapshot::Raw, VdomSnapshot::Raw) => {}
+        _ => differences.push(SnapshotDifference::Replaced { path: path.clone() }),
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_round_trip_through_json() {
+        let snapshot = VdomSnapshot::Element {
+            tag_name: "ul".to_string(),
+            namespace: None,
+            key: None,
+            attributes: vec![("class".to_string(), "todos".to_string())],
+            listeners: vec!["click".to_string(), "keydown".to_string()],
+            children: vec![
+                VdomSnapshot::Element {
+                    tag_name: "li".to_string(),
+                    namespace: None,
+                    key: Some(7),
+                    attributes: vec![("hidden".to_string(), String::new())],
+                    listeners: vec![],
+                    children: vec![VdomSnapshot::Text("Walk the \"dog\"".to_string())],
+                },
+                VdomSnapshot::Element {
+                    tag_name: "svg".to_string(),
+                    namespace: Some("http://www.w3.org/2000/svg".to_string()),
+                    key: None,
+                    attributes: vec![],
+                    listeners: vec![],
+                    children: vec![],
+                },
+                VdomSnapshot::Raw,
+            ],
+        };
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.starts_with("{\"Element\":{\"tag_name\":\"ul\""));
+        let round_tripped: VdomSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped, snapshot);
+    }
+}
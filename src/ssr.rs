@@ -0,0 +1,201 @@
+//! Rendering components to HTML strings, such as on a server.
+//!
+//! Unlike the static subtrees in `html.rs`, which must parse back into exactly
+//! the physical DOM that diffing expects, the string rendered here is only ever
+//! parsed by a browser loading the page. So any tree can be rendered, at the
+//! cost of dropping whatever only exists on the client: listeners, properties
+//! set from `JsValue` attributes, directives, and raw physical DOM nodes.
+//!
+//! The parser would still merge adjacent text nodes and drop empty ones, so an
+//! empty comment is rendered between adjacent text nodes, and in place of empty
+//! text nodes and raw nodes. That way, the page has one physical node for each
+//! virtual one, except within elements like `<script>` and `<textarea>` whose
+//! contents are all text.
+//!
+//! The serializer itself is shared with static subtrees: see `html::Mode`.
+
+use crate::cached_set::CachedSet;
+use crate::html::{self, Mode};
+use crate::{Render, RenderContext};
+use bumpalo::Bump;
+use fxhash::FxHashMap;
+
+/// Render `component` to an HTML string.
+///
+/// Listeners are left out, since there is nothing to dispatch their events to,
+/// as are `JsValue` attributes, directives such as `leave_transition`, and
+/// attributes that only take effect as properties, such as `indeterminate`.
+/// Raw nodes, which only exist on the client, are rendered as empty comments.
+/// Custom style properties are merged into the element's `style` attribute.
+/// Elements and attributes whose names are not valid are left out too, rather
+/// than written out as whatever markup they happen to contain.
+///
+/// # Example
+///
+/// ```
+/// use dodrio::{builder::*, render_to_string, Node, Render, RenderContext};
+///
+/// struct Hello;
+///
+/// impl<'a> Render<'a> for Hello {
+///     fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+///         p(&cx).children([text("Hello & welcome!")]).finish()
+///     }
+/// }
+///
+/// assert_eq!(render_to_string(&Hello), "<p>Hello &amp; welcome!</p>");
+/// ```
+pub fn render_to_string<R>(component: &R) -> String
+where
+    R: for<'a> Render<'a>,
+{
+    let bump = Bump::new();
+    let cached_set = crate::RefCell::new(CachedSet::default());
+    let mut templates = FxHashMap::default();
+    let mut cx = RenderContext::new(&bump, &cached_set, &mut templates);
+    let node = component.render(&mut cx);
+
+    let mut html = String::new();
+    html::serialize(&node, None, Mode::Page(&cached_set.borrow()), &mut html);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::*;
+    use crate::Node;
+
+    struct Page;
+
+    impl<'a> Render<'a> for Page {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .attr("class", "page")
+                .attr("style", "color: red;")
+                .attr("--accent", "blue")
+                .children([
+                    style(&cx)
+                        .children([text("p > a { color: blue }")])
+                        .finish(),
+                    button(&cx)
+                        .attr("disabled", "")
+                        .children([text("<Save>")])
+                        .finish(),
+                    input(&cx).attr("title", "\"quoted\"").finish(),
+                ])
+                .finish()
+        }
+    }
+
+    #[test]
+    fn render_to_string_serializes_the_whole_tree() {
+        assert_eq!(
+            render_to_string(&Page),
+            "<div class=\"page\" style=\"color: red; --accent: blue\">\
+             <style>p > a { color: blue }</style>\
+             <button disabled=\"\">&lt;Save&gt;</button>\
+             <input title=\"&quot;quoted&quot;\">\
+             </div>"
+        );
+    }
+
+    #[cfg(feature = "dom")]
+    struct Clickable;
+
+    #[cfg(feature = "dom")]
+    impl<'a> Render<'a> for Clickable {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            button(&cx)
+                .on("click", |_root, _vdom, _event| {})
+                .children([text("Save")])
+                .finish()
+        }
+    }
+
+    #[cfg(feature = "dom")]
+    #[test]
+    fn render_to_string_leaves_out_listeners() {
+        assert_eq!(render_to_string(&Clickable), "<button>Save</button>");
+    }
+
+    struct Tricky;
+
+    impl<'a> Render<'a> for Tricky {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .leave_transition("fade-out")
+                .children([
+                    script(&cx)
+                        .children([text("let s = \"</script><b>\";")])
+                        .finish(),
+                    input(&cx)
+                        .attr("type", "checkbox")
+                        .attr("indeterminate", "")
+                        .finish(),
+                    text("a"),
+                    text("b"),
+                    text(""),
+                    textarea(&cx).children([text("c"), text("d")]).finish(),
+                ])
+                .finish()
+        }
+    }
+
+    #[test]
+    fn render_to_string_keeps_the_shape_of_the_tree() {
+        assert_eq!(
+            render_to_string(&Tricky),
+            "<div>\
+             <script>let s = \"<\\/script><b>\";</script>\
+             <input type=\"checkbox\">\
+             a<!---->b<!----><!---->\
+             <textarea>cd</textarea>\
+             </div>"
+        );
+    }
+
+    struct Preformatted;
+
+    impl<'a> Render<'a> for Preformatted {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .children([
+                    pre(&cx).children([text("\nfn main() {}")]).finish(),
+                    textarea(&cx).children([text("notes")]).finish(),
+                ])
+                .finish()
+        }
+    }
+
+    #[test]
+    fn render_to_string_keeps_leading_newlines_in_pre() {
+        assert_eq!(
+            render_to_string(&Preformatted),
+            "<div><pre>\n\nfn main() {}</pre><textarea>notes</textarea></div>"
+        );
+    }
+
+    struct BadNames;
+
+    impl<'a> Render<'a> for BadNames {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .attr("title=\"x\" onload", "alert(1)")
+                .attr("id", "ok")
+                .children([
+                    ElementBuilder::new(cx.bump, "img src=x onerror=alert(1)").finish(),
+                    span(&cx).children([text("kept")]).finish(),
+                ])
+                .finish()
+        }
+    }
+
+    #[test]
+    fn render_to_string_leaves_out_invalid_names() {
+        assert_eq!(
+            render_to_string(&BadNames),
+            "<div id=\"ok\"><!----><span>kept</span></div>"
+        );
+    }
+}
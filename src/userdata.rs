@@ -0,0 +1,62 @@
+//! Attach arbitrary JavaScript values to physical DOM elements.
+//!
+//! This is useful for interop with JavaScript libraries that need to associate
+//! some state with an element that `dodrio` rendered, for example a chart
+//! instance that was constructed inside a `<canvas>`.
+//!
+//! Userdata is stored on the physical DOM element itself, so it lives exactly
+//! as long as the element: it persists across re-renders that reuse the
+//! element, and is cleaned up along with the element once the element is
+//! removed from the DOM. Note that elements cloned from `Cached` templates do
+//! not inherit any userdata.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use dodrio::{builder::*, bumpalo::Bump, userdata};
+//! use wasm_bindgen::{JsCast, JsValue};
+//!
+//! let b = Bump::new();
+//!
+//! let chart = canvas(&b)
+//!     .on("click", |_root, _vdom, event| {
+//!         let canvas = event
+//!             .target()
+//!             .unwrap()
+//!             .unchecked_into::<web_sys::Element>();
+//!         if userdata::get(&canvas, "chart").is_none() {
+//!             userdata::set(&canvas, "chart", &JsValue::from_str("my chart instance"));
+//!         }
+//!     })
+//!     .finish();
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+fn property(key: &str) -> JsValue {
+    JsValue::from_str(&format!("dodrio-userdata-{}", key))
+}
+
+/// Attach `value` to the given element under `key`, replacing any value that
+/// was previously attached under the same key.
+pub fn set(element: &web_sys::Element, key: &str, value: &JsValue) {
+    js_sys::Reflect::set(element.as_ref(), &property(key), value)
+        .expect_throw("should set userdata on element OK");
+}
+
+/// Get the value attached to the given element under `key`, if any.
+pub fn get(element: &web_sys::Element, key: &str) -> Option<JsValue> {
+    let value = js_sys::Reflect::get(element.as_ref(), &property(key))
+        .expect_throw("should get userdata from element OK");
+    if value.is_undefined() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Remove the value attached to the given element under `key`, if any.
+pub fn remove(element: &web_sys::Element, key: &str) {
+    js_sys::Reflect::delete_property(element.as_ref(), &property(key))
+        .expect_throw("should remove userdata from element OK");
+}
@@ -1,10 +1,14 @@
-use super::change_list::ChangeListPersistentState;
+use super::change_list::{AttributeComparator, ChangeListPersistentState, ChangeSink, DiffStats};
 use super::RootRender;
 use crate::cached::TemplateId;
 use crate::cached_set::{CacheId, CachedSet};
+use crate::drift::physical_dom_matches;
 use crate::events::EventsRegistry;
-use crate::node::{Node, NodeKey};
-use crate::RenderContext;
+use crate::node::{Listener, Node, NodeKey};
+use crate::observer::{AttributeChange, AttributeObserver, ChangeKind, DiffObserver};
+use crate::replay::ChangeOp;
+use crate::snapshot::VdomSnapshot;
+use crate::{Render, RenderContext};
 use bumpalo::Bump;
 use fxhash::FxHashMap;
 use std::cell::Cell;
@@ -18,6 +22,18 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 
+// The root rendering component of a `Vdom` created with `Vdom::with_render_fn`.
+struct RenderFn<F>(F);
+
+impl<'a, F> Render<'a> for RenderFn<F>
+where
+    F: for<'b> Fn(&mut RenderContext<'b>) -> Node<'b>,
+{
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        (self.0)(cx)
+    }
+}
+
 /// A strong handle to a mounted virtual DOM.
 ///
 /// When this handle is dropped, the virtual DOM is unmounted and its listeners
@@ -49,6 +65,188 @@ pub(crate) struct VdomInner {
 
 pub(crate) struct VdomInnerShared {
     pub(crate) render_scheduled: Cell<Option<js_sys::Promise>>,
+    pub(crate) scheduling: Scheduling,
+    // How many `VdomWeak::batch` calls we are nested within, and whether
+    // `schedule_render` was called during them.
+    pub(crate) batch_depth: Cell<u32>,
+    pub(crate) batch_render_pending: Cell<bool>,
+    // Whether rendering is paused with `VdomWeak::pause`, and the `resolve`
+    // and `reject` functions of the scheduled render that was held back
+    // because of it, if any.
+    pub(crate) paused: Cell<bool>,
+    pub(crate) paused_render: RefCell<Option<(js_sys::Function, js_sys::Function)>>,
+}
+
+/// When a `Vdom` performs the renders scheduled with
+/// `VdomWeak::schedule_render` and `VdomWeak::render`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheduling {
+    /// Render during the next animation frame. This is the default, and
+    /// batches all of the renders scheduled in between frames into one.
+    AnimationFrame,
+
+    /// Render in a microtask, as soon as the currently running JavaScript
+    /// finishes. This is useful when animation frames are unavailable or
+    /// throttled, such as in background tabs or headless tests.
+    Microtask,
+
+    /// Render when the browser is idle, with `requestIdleCallback`, falling
+    /// back to the next animation frame where it is unsupported. This is
+    /// useful for virtual DOMs that only show non-urgent content, such as
+    /// prefetched results, whose renders should not compete with handling
+    /// user input. All of the renders scheduled before the browser becomes
+    /// idle are batched into one.
+    Idle,
+}
+
+impl Default for Scheduling {
+    fn default() -> Scheduling {
+        Scheduling::AnimationFrame
+    }
+}
+
+/// How a `Vdom` copes with a physical DOM that was modified behind its back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffMode {
+    /// Assume that the physical DOM still matches the last render. This is the
+    /// default, and the fastest. If something else, such as a browser
+    /// extension, has added, removed, or replaced nodes, then diffing may
+    /// throw or modify the wrong nodes.
+    Strict,
+
+    /// Before each render, check that the physical DOM's node types, tag
+    /// names, and numbers of children still match the last render, and if
+    /// they do not, recreate the whole tree from scratch. Partial re-renders
+    /// with `Vdom::patch` are not checked.
+    Lenient,
+}
+
+impl Default for DiffMode {
+    fn default() -> DiffMode {
+        DiffMode::Strict
+    }
+}
+
+/// Options for mounting a `Vdom` with `Vdom::new_with_options`.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, MountOptions, Node, Render, RenderContext, Scheduling, Vdom};
+///
+/// struct Banner;
+///
+/// impl<'a> Render<'a> for Banner {
+///     fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+///         p(&cx).children([text("Rendered with dodrio")]).finish()
+///     }
+/// }
+///
+/// # let container: dodrio::Element = unimplemented!();
+/// # let footer: web_sys::Node = unimplemented!();
+/// // Render the banner just before the container's existing footer, and
+/// // re-render in microtasks rather than animation frames.
+/// let options = MountOptions::new()
+///     .before(footer)
+///     .scheduling(Scheduling::Microtask);
+/// let vdom = Vdom::new_with_options(&container, Banner, options);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MountOptions {
+    preserve_existing_content: bool,
+    before: Option<web_sys::Node>,
+    scheduling: Scheduling,
+    clone_elements: bool,
+    diff_mode: DiffMode,
+    strict_csp: bool,
+    style_nonce: Option<String>,
+}
+
+impl MountOptions {
+    /// Construct the default options, which empty the container, and schedule
+    /// renders in animation frames.
+    pub fn new() -> MountOptions {
+        Default::default()
+    }
+
+    /// Whether to keep the container's existing children, and mount alongside
+    /// them, rather than emptying the container first.
+    ///
+    /// See `Vdom::mount_append` for details.
+    pub fn preserve_existing_content(mut self, preserve: bool) -> Self {
+        self.preserve_existing_content = preserve;
+        self
+    }
+
+    /// Mount just before the given child of the container, instead of after
+    /// all of its children.
+    ///
+    /// This implies `preserve_existing_content(true)`.
+    pub fn before(mut self, node: web_sys::Node) -> Self {
+        self.before = Some(node);
+        self
+    }
+
+    /// When to perform scheduled renders.
+    pub fn scheduling(mut self, scheduling: Scheduling) -> Self {
+        self.scheduling = scheduling;
+        self
+    }
+
+    /// How to cope with a physical DOM that was modified behind the virtual
+    /// DOM's back. Defaults to `DiffMode::Strict`.
+    pub fn diff_mode(mut self, mode: DiffMode) -> Self {
+        self.diff_mode = mode;
+        self
+    }
+
+    /// Whether to create elements by cloning a cached, empty prototype element
+    /// with the same tag name, with `cloneNode(false)`, rather than with
+    /// `document.createElement`. Defaults to `false`.
+    ///
+    /// Attributes and listeners are always set after cloning, so this only
+    /// changes how the bare element is created. Namespaced elements and
+    /// customized built-in elements are always created directly. Whether
+    /// cloning is faster depends on the browser, so measure before enabling
+    /// it.
+    pub fn clone_elements(mut self, clone: bool) -> Self {
+        self.clone_elements = clone;
+        self
+    }
+
+    /// Whether to avoid the ways of styling elements that a strict [Content
+    /// Security Policy](https://developer.mozilla.org/en-US/docs/Web/HTTP/CSP)
+    /// blocks. Defaults to `false`.
+    ///
+    /// A policy without `'unsafe-inline'` in its `style-src` blocks `style`
+    /// attributes, whether they are set with `setAttribute` or parsed from
+    /// HTML. When this is enabled, `style` attributes are instead applied one
+    /// declaration at a time with the CSSOM's `style.setProperty`, which such
+    /// a policy allows, and large static subtrees are created node by node
+    /// rather than parsed from HTML. Use `style_nonce` to allow rendered
+    /// `<style>` elements as well.
+    pub fn strict_csp(mut self, strict: bool) -> Self {
+        self.strict_csp = strict;
+        self
+    }
+
+    /// Give every `<style>` element that this virtual DOM creates the given
+    /// `nonce`, so that a Content Security Policy that lists the nonce allows
+    /// its styles. By default, `<style>` elements have no nonce.
+    ///
+    /// This should be the same nonce that the server sent in the page's
+    /// policy. See also `strict_csp`.
+    pub fn style_nonce<S>(mut self, nonce: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.style_nonce = Some(nonce.into());
+        self
+    }
+
+    fn preserves_existing_content(&self) -> bool {
+        self.preserve_existing_content || self.before.is_some()
+    }
 }
 
 pub(crate) struct VdomInnerExclusive {
@@ -60,10 +258,16 @@ pub(crate) struct VdomInnerExclusive {
     dom_buffers: Option<[Bump; 2]>,
     change_list: ManuallyDrop<ChangeListPersistentState>,
     container: crate::Element,
+    // Was this virtual DOM mounted alongside the container's existing
+    // children, e.g. with `Vdom::mount_append`, rather than replacing them?
+    appended: bool,
+    diff_mode: DiffMode,
     events_registry: Option<Rc<RefCell<EventsRegistry>>>,
     events_trampoline: Option<crate::EventsTrampoline>,
     cached_set: crate::RefCell<CachedSet>,
     templates: FxHashMap<TemplateId, Option<CacheId>>,
+    // Has a callback given to `Vdom::on_mount` run yet?
+    mounted_callback_ran: bool,
 
     // Actually a reference into `self.dom_buffers[0]` or if `self.component` is
     // caching renders, into `self.component`'s bump.
@@ -82,12 +286,29 @@ impl fmt::Debug for VdomInnerShared {
         let r = f
             .debug_struct("VdomInnerShared")
             .field("render_scheduled", &render_scheduled)
+            .field("scheduling", &self.scheduling)
+            .field("batch_depth", &self.batch_depth)
+            .field("batch_render_pending", &self.batch_render_pending)
+            .field("paused", &self.paused)
+            .field("paused_render", &self.paused_render)
             .finish();
         self.render_scheduled.set(render_scheduled);
         r
     }
 }
 
+impl Drop for VdomInnerShared {
+    fn drop(&mut self) {
+        // A render that was held back by `VdomWeak::pause` will never happen
+        // now, so fail its `render` futures rather than leave them pending
+        // forever.
+        if let Some((_resolve, reject)) = self.paused_render.get_mut().take() {
+            let r = reject.call0(&JsValue::null());
+            debug_assert!(r.is_ok());
+        }
+    }
+}
+
 impl fmt::Debug for VdomInnerExclusive {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("VdomInnerExclusive")
@@ -95,8 +316,11 @@ impl fmt::Debug for VdomInnerExclusive {
             .field("dom_buffers", &self.dom_buffers)
             .field("change_list", &self.change_list)
             .field("container", &self.container)
+            .field("appended", &self.appended)
+            .field("diff_mode", &self.diff_mode)
             .field("events_registry", &self.events_registry)
             .field("events_trampoline", &"..")
+            .field("mounted_callback_ran", &self.mounted_callback_ran)
             .field("current_root", &self.current_root)
             .finish()
     }
@@ -106,6 +330,12 @@ impl Drop for VdomInnerExclusive {
     fn drop(&mut self) {
         debug!("Dropping VdomInnerExclusive");
 
+        // Leave the container's other children alone, and only remove our own
+        // root.
+        if self.appended {
+            self.change_list.remove_root();
+        }
+
         // Make sure that we clean up our JS listeners and all that before we
         // empty the container.
         unsafe {
@@ -116,7 +346,9 @@ impl Drop for VdomInnerExclusive {
         let mut registry = registry.borrow_mut();
         registry.clear_active_listeners();
 
-        empty_container(&self.container);
+        if !self.appended {
+            empty_container(&self.container);
+        }
     }
 }
 
@@ -124,6 +356,10 @@ cfg_if::cfg_if! {
     if #[cfg(all(feature = "xxx-unstable-internal-use-only", not(target_arch = "wasm32")))] {
         fn empty_container(_container: &crate::Element) {}
         fn initialize_container(_container: &crate::Element) {}
+        fn insert_root(
+            _container: &crate::Element,
+            _before: Option<&web_sys::Node>,
+        ) -> crate::Element {}
     } else {
         fn empty_container(container: &crate::Element) {
             container.set_inner_html("");
@@ -131,20 +367,26 @@ cfg_if::cfg_if! {
 
         fn initialize_container(container: &crate::Element) {
             empty_container(container);
+            insert_root(container, None);
+        }
 
-            // Create the dummy `<div/>` child in the container.
+        // Insert a dummy `<div/>` child into the container, before the given
+        // child or at the end, and return it.
+        fn insert_root(
+            container: &crate::Element,
+            before: Option<&web_sys::Node>,
+        ) -> crate::Element {
             let window = web_sys::window().expect_throw("should have access to the Window");
             let document = window
                 .document()
                 .expect("should have access to the Document");
+            let root = document
+                .create_element("div")
+                .expect("should create element OK");
             container
-                .append_child(
-                    document
-                        .create_element("div")
-                        .expect("should create element OK")
-                        .as_ref(),
-                )
-                .expect("should append child OK");
+                .insert_before(root.as_ref(), before)
+                .expect("should insert child OK");
+            root
         }
     }
 }
@@ -166,14 +408,173 @@ impl Vdom {
     pub fn with_boxed_root_render(
         container: &crate::Element,
         component: Box<dyn RootRender>,
+    ) -> Vdom {
+        Self::with_boxed_root_render_and_cached_set(container, component, Default::default())
+    }
+
+    /// Mount a new `Vdom` in the given container element with the given root
+    /// rendering component, using a `CachedSet` that was pre-warmed with
+    /// `CachedSet::insert`.
+    ///
+    /// The pre-warmed subtrees can be referenced during the very first render
+    /// with `RenderContext::cached`.
+    pub fn with_cached_set<R>(
+        container: &crate::Element,
+        component: R,
+        cached_set: CachedSet,
+    ) -> Vdom
+    where
+        R: RootRender,
+    {
+        Self::with_boxed_root_render_and_cached_set(
+            container,
+            Box::new(component) as Box<dyn RootRender>,
+            cached_set,
+        )
+    }
+
+    /// Mount a new `Vdom` in the given container element that renders with the
+    /// given function, rather than a root rendering component.
+    ///
+    /// The function is called with a fresh render context to render the
+    /// whole virtual DOM, both now and on every subsequent render, such as
+    /// those scheduled with `VdomWeak::schedule_render`. Keep the application
+    /// state that it renders somewhere it can get at, for example in an
+    /// `Rc<RefCell<_>>` that it shares with the event listeners that update
+    /// that state.
+    ///
+    /// Event listeners cannot downcast the root rendering component with
+    /// `RootRender::unwrap_mut`, since its type cannot be named.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo, Vdom};
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// # let container: dodrio::Element = unimplemented!();
+    /// let clicks = Rc::new(Cell::new(0));
+    ///
+    /// let vdom = Vdom::with_render_fn(&container, {
+    ///     let clicks = clicks.clone();
+    ///     move |cx| {
+    ///         let label = bumpalo::format!(in cx.bump, "Clicked {} times", clicks.get());
+    ///         let clicks = clicks.clone();
+    ///         button(&cx)
+    ///             .on("click", move |_root, vdom, _event| {
+    ///                 clicks.set(clicks.get() + 1);
+    ///                 vdom.schedule_render();
+    ///             })
+    ///             .children([text(label.into_bump_str())])
+    ///             .finish()
+    ///     }
+    /// });
+    /// ```
+    pub fn with_render_fn<F>(container: &crate::Element, render: F) -> Vdom
+    where
+        F: 'static + for<'a> Fn(&mut RenderContext<'a>) -> Node<'a>,
+    {
+        Self::new(container, RenderFn(render))
+    }
+
+    /// Mount a new `Vdom` with the given root rendering component after the
+    /// given container element's existing children, rather than replacing
+    /// them.
+    ///
+    /// This lets a `Vdom` share its container with other content. Diffing
+    /// only ever touches the `Vdom`'s own root node and its descendants, and
+    /// dropping the `Vdom` removes just its root from the container.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, Node, Render, RenderContext, Vdom};
+    ///
+    /// struct Banner;
+    ///
+    /// impl<'a> Render<'a> for Banner {
+    ///     fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+    ///         p(&cx).children([text("Rendered with dodrio")]).finish()
+    ///     }
+    /// }
+    ///
+    /// # let container: dodrio::Element = unimplemented!();
+    /// // `container` already has server-rendered content in it.
+    /// let vdom = Vdom::mount_append(&container, Banner);
+    /// ```
+    pub fn mount_append<R>(container: &crate::Element, component: R) -> Vdom
+    where
+        R: RootRender,
+    {
+        Self::new_with_options(
+            container,
+            component,
+            MountOptions::new().preserve_existing_content(true),
+        )
+    }
+
+    /// Mount a new `Vdom` in the given container element with the given root
+    /// rendering component, according to the given options.
+    ///
+    /// See `MountOptions` for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `MountOptions::before` node is not a child of
+    /// `container`.
+    pub fn new_with_options<R>(
+        container: &crate::Element,
+        component: R,
+        options: MountOptions,
+    ) -> Vdom
+    where
+        R: RootRender,
+    {
+        Self::mount(
+            container,
+            Box::new(component) as Box<dyn RootRender>,
+            Default::default(),
+            &options,
+        )
+    }
+
+    fn with_boxed_root_render_and_cached_set(
+        container: &crate::Element,
+        component: Box<dyn RootRender>,
+        cached_set: CachedSet,
+    ) -> Vdom {
+        Self::mount(container, component, cached_set, &MountOptions::new())
+    }
+
+    fn mount(
+        container: &crate::Element,
+        component: Box<dyn RootRender>,
+        cached_set: CachedSet,
+        options: &MountOptions,
     ) -> Vdom {
         crate::strace::init_strace();
 
         let dom_buffers = [Bump::new(), Bump::new()];
-        let change_list = ManuallyDrop::new(ChangeListPersistentState::new(container));
+        let mut change_list = ManuallyDrop::new(ChangeListPersistentState::new(container));
+        if options.clone_elements {
+            change_list.set_clone_elements(true);
+        }
+        if options.strict_csp {
+            change_list.set_strict_csp(true);
+        }
+        if let Some(nonce) = &options.style_nonce {
+            change_list.set_style_nonce(nonce);
+        }
 
         // Create a dummy `<div/>` in our container.
-        initialize_container(container);
+        let appended = options.preserves_existing_content();
+        if appended {
+            let root = insert_root(container, options.before.as_ref());
+            change_list.set_root(&root);
+        } else {
+            initialize_container(container);
+        }
         let current_root =
             Node::element(&dom_buffers[0], NodeKey::NONE, "div", &[], &[], &[], None);
         let current_root = Some(unsafe { extend_node_lifetime(current_root) });
@@ -182,17 +583,25 @@ impl Vdom {
         let inner = Rc::new(VdomInner {
             shared: VdomInnerShared {
                 render_scheduled: Cell::new(None),
+                scheduling: options.scheduling,
+                batch_depth: Cell::new(0),
+                batch_render_pending: Cell::new(false),
+                paused: Cell::new(false),
+                paused_render: RefCell::new(None),
             },
             exclusive: RefCell::new(VdomInnerExclusive {
                 component: Some(component),
                 dom_buffers: Some(dom_buffers),
                 change_list,
                 container,
+                appended,
+                diff_mode: options.diff_mode,
                 current_root,
                 events_registry: None,
                 events_trampoline: None,
-                cached_set: crate::RefCell::new(Default::default()),
+                cached_set: crate::RefCell::new(cached_set),
                 templates: Default::default(),
+                mounted_callback_ran: false,
             }),
         });
 
@@ -200,8 +609,11 @@ impl Vdom {
 
         {
             let mut inner = inner.exclusive.borrow_mut();
+            let namespace = events_registry.borrow().namespace();
             inner.events_registry = Some(events_registry);
-            inner.change_list.init_events_trampoline(&events_trampoline);
+            inner
+                .change_list
+                .init_events_trampoline(&events_trampoline, namespace);
             debug_assert!(inner.events_trampoline.is_none());
             inner.events_trampoline = Some(events_trampoline);
 
@@ -225,6 +637,432 @@ impl Vdom {
         exclusive.render();
     }
 
+    /// Immediately render the virtual DOM returned by `render`, and diff it
+    /// against the current contents.
+    ///
+    /// This runs a whole render cycle without going through the root
+    /// component: `render` is given a fresh render context for the inactive
+    /// bump arena, its result is diffed against the current virtual DOM, the
+    /// changes are applied to the physical DOM, and the arenas are swapped.
+    /// This is convenient for apps that regenerate their whole view from their
+    /// state every frame.
+    ///
+    /// The next render of the root component, for example one scheduled with
+    /// `VdomWeak::schedule_render`, replaces whatever `render` returned.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo, Vdom};
+    ///
+    /// # fn example(vdom: &Vdom) {
+    /// for frame in 0..3 {
+    ///     vdom.render_with(|cx| {
+    ///         let label = bumpalo::format!(in cx.bump, "frame {}", frame);
+    ///         div(&cx).children([text(label.into_bump_str())]).finish()
+    ///     });
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn render_with<F>(&self, render: F)
+    where
+        F: for<'a> FnOnce(&mut RenderContext<'a>) -> Node<'a>,
+    {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive.render_with(render);
+    }
+
+    /// Push a new tree: diff what `render` renders against the current
+    /// virtual DOM, and apply the changes to the physical DOM right away.
+    ///
+    /// This is the imperative core for driving a `Vdom` from a reactive
+    /// library: whenever a signal produces a new state, push a new tree that
+    /// renders it, and only what changed since the previous tree is updated.
+    ///
+    /// Unlike `render_with`, `render` replaces the root rendering component,
+    /// as with `Vdom::with_render_fn`. So the pushed tree is re-rendered by
+    /// later renders, for example those scheduled by event listeners, until
+    /// the next push.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, bumpalo, Vdom};
+    ///
+    /// # fn example(vdom: &Vdom, counts: &[u32]) {
+    /// for &count in counts {
+    ///     vdom.push(move |cx| {
+    ///         let label = bumpalo::format!(in cx.bump, "count: {}", count);
+    ///         p(&cx).children([text(label.into_bump_str())]).finish()
+    ///     });
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn push<F>(&self, render: F)
+    where
+        F: 'static + for<'a> Fn(&mut RenderContext<'a>) -> Node<'a>,
+    {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive.component = Some(Box::new(RenderFn(render)));
+        exclusive.render();
+    }
+
+    /// Re-render and diff only the subtree at the given `path`, rather than the
+    /// whole virtual DOM.
+    ///
+    /// The `path` is the sequence of child indices leading from the root node
+    /// to the subtree to replace, the same as the paths given to diff
+    /// observers. The `render` function renders the new subtree, which is then
+    /// diffed against the old subtree. The rest of the virtual DOM is neither
+    /// re-rendered nor diffed, and its physical DOM nodes are left untouched.
+    ///
+    /// The patch only lasts until the next full render, which re-renders the
+    /// root component from scratch. Therefore, by then, the root component
+    /// should render the same subtree that `render` did.
+    ///
+    /// The rest of the virtual DOM is shared with the last render, so the new
+    /// subtree is rendered into the same bump arena as it, and that memory is
+    /// only reclaimed by the next full render. Patching over and over without
+    /// ever rendering in full keeps growing the arena, so render in full every
+    /// now and then.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{builder::*, Vdom};
+    ///
+    /// # fn example(vdom: &Vdom) {
+    /// // Update the text in the root's second child, leaving its siblings be.
+    /// vdom.patch(&[1], |cx| {
+    ///     div(&cx).children([text("updated")]).finish()
+    /// })
+    /// .unwrap();
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PatchError`, without calling `render`, if `path` does not
+    /// lead to a node in the current virtual DOM through element nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn patch<F>(&self, path: &[usize], render: F) -> Result<(), PatchError>
+    where
+        F: for<'a> FnOnce(&mut RenderContext<'a>) -> Node<'a>,
+    {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive.patch(path, render)
+    }
+
+    /// Set an observer that is notified of which nodes are created, removed,
+    /// and moved by each subsequent diff. This is useful for triggering
+    /// enter, leave, and move animations.
+    ///
+    /// The observer is called with the node's path, which is the sequence of
+    /// child indices leading from the root node to the node in question, and
+    /// the kind of change made to it:
+    ///
+    /// * For created and moved nodes, the path is their path within the new
+    ///   virtual DOM.
+    ///
+    /// * For removed nodes, the path is their parent's path within the new
+    ///   virtual DOM, followed by their index amongst their old siblings.
+    ///
+    /// Only the root of a created or removed subtree is reported, not each of
+    /// its descendants. A node that is replaced with a different node (for
+    /// example, because its tag name changed) is reported as removed and then
+    /// created.
+    ///
+    /// The observer is called during diffing, before the changes are applied to
+    /// the physical DOM, and must not use this virtual DOM.
+    ///
+    /// Replaces any previously set observer.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{ChangeKind, Vdom};
+    ///
+    /// # fn example(vdom: &Vdom) {
+    /// vdom.set_diff_observer(|path: &[usize], kind: ChangeKind| {
+    ///     if kind == ChangeKind::Created {
+    ///         println!("created node at {:?}", path);
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn set_diff_observer<F>(&self, observer: F)
+    where
+        F: 'static + FnMut(&[usize], ChangeKind),
+    {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive
+            .change_list
+            .set_diff_observer(Some(DiffObserver::new(observer)));
+    }
+
+    /// Remove the observer set with `set_diff_observer`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn remove_diff_observer(&self) {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive.change_list.set_diff_observer(None);
+    }
+
+    /// Set an observer that is notified of every attribute that each
+    /// subsequent diff adds, changes, or removes, along with the attribute's
+    /// old and new values.
+    ///
+    /// This lets an animation layer interpolate between the old and new values
+    /// of, for example, an SVG `<path>`'s `d` attribute, rather than snapping
+    /// to the new value. It also lets a data binding layer built on top of
+    /// Dodrio keep its own model of the attributes in sync.
+    ///
+    /// The observer is called with the path of the node whose attribute
+    /// changed, as described in `set_diff_observer`, and the change itself.
+    /// Attributes of newly created nodes are not reported.
+    ///
+    /// The observer is called while diffing, before any of the changes are
+    /// applied to the physical DOM. To get at the physical node whose attribute
+    /// changed, follow the path from the root once the render has finished.
+    ///
+    /// Replaces any previously set attribute observer.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::{AttributeChange, Vdom};
+    ///
+    /// # fn example(vdom: &Vdom) {
+    /// vdom.set_attribute_observer(|path: &[usize], change: AttributeChange| {
+    ///     if change.name == "d" {
+    ///         println!("morph {:?} from {:?} to {:?}", path, change.old, change.new);
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn set_attribute_observer<F>(&self, observer: F)
+    where
+        F: 'static + FnMut(&[usize], AttributeChange),
+    {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive
+            .change_list
+            .set_attribute_observer(Some(AttributeObserver::new(observer)));
+    }
+
+    /// Remove the observer set with `set_attribute_observer`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn remove_attribute_observer(&self) {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive.change_list.set_attribute_observer(None);
+    }
+
+    /// Use `comparator` to decide whether the string values of attributes
+    /// named `name` have changed, instead of comparing them character by
+    /// character.
+    ///
+    /// When diffing finds that such an attribute's old and new values differ,
+    /// but `comparator` says they are equivalent, the attribute is left as it
+    /// is on the physical DOM node. This is useful for attributes whose values
+    /// can be written in different ways that mean the same thing, such as JSON
+    /// objects with their keys in a different order. Volatile attributes are
+    /// always re-set regardless.
+    ///
+    /// Replaces any comparator previously set for `name`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::Vdom;
+    ///
+    /// # fn example(vdom: &Vdom) {
+    /// // Class lists are the same regardless of their order.
+    /// vdom.set_attribute_comparator("class", |old: &str, new: &str| {
+    ///     let mut old: Vec<_> = old.split_whitespace().collect();
+    ///     let mut new: Vec<_> = new.split_whitespace().collect();
+    ///     old.sort();
+    ///     new.sort();
+    ///     old == new
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn set_attribute_comparator<F>(&self, name: &str, comparator: F)
+    where
+        F: 'static + Fn(&str, &str) -> bool,
+    {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive
+            .change_list
+            .set_attribute_comparator(name, Some(AttributeComparator::new(comparator)));
+    }
+
+    /// Remove the comparator set for `name` with `set_attribute_comparator`,
+    /// if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn remove_attribute_comparator(&self, name: &str) {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        exclusive.change_list.set_attribute_comparator(name, None);
+    }
+
+    /// Get the number of operations that the last render or patch applied to
+    /// the physical DOM, such as creating a node or setting an attribute.
+    ///
+    /// This is mostly useful for testing that re-rendering an unchanged
+    /// component is a no-op, for example.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn last_diff_op_count(&self) -> usize {
+        let exclusive = self.inner.exclusive.borrow();
+        exclusive.change_list.op_count()
+    }
+
+    /// Get statistics about the last render or patch, such as how deep it
+    /// descended into the tree and the widest list of children it diffed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn last_diff_stats(&self) -> DiffStats {
+        let exclusive = self.inner.exclusive.borrow();
+        exclusive.change_list.stats()
+    }
+
+    /// Get the number of event listeners that this virtual DOM currently has
+    /// registered.
+    ///
+    /// Listeners are registered when the nodes they are attached to are
+    /// created, and unregistered when those nodes are removed, so this is
+    /// useful for testing that listeners are not leaked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn listener_count(&self) -> usize {
+        let exclusive = self.inner.exclusive.borrow();
+        let registry = exclusive.events_registry.as_ref().unwrap_throw();
+        let registry = registry.borrow();
+        registry.len()
+    }
+
+    // Apply recorded operations to this virtual DOM's physical DOM, and
+    // register `listeners` with its events registry, for `replay::commit`.
+    // The caller is responsible for removing the listeners from the returned
+    // registry before they are dropped.
+    pub(crate) fn commit_ops(
+        &self,
+        ops: &[ChangeOp],
+        listeners: &[&Listener],
+    ) -> Weak<RefCell<EventsRegistry>> {
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        let exclusive = &mut *exclusive;
+
+        let registry = exclusive.events_registry.as_ref().unwrap_throw();
+        {
+            let mut registry = registry.borrow_mut();
+            for l in listeners {
+                registry.remove(l);
+                unsafe {
+                    registry.add(l);
+                }
+            }
+        }
+
+        let sink = exclusive.change_list.sink_mut();
+        for op in ops {
+            op.apply_to_interpreter(sink);
+        }
+        sink.flush_attributes();
+        sink.finish();
+
+        Rc::downgrade(registry)
+    }
+
+    /// Take an owned snapshot of the currently rendered virtual DOM, for
+    /// debugging tools to display, or to compare with `diff_snapshots`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn snapshot(&self) -> VdomSnapshot {
+        let exclusive = self.inner.exclusive.borrow();
+        let cached_set = exclusive.cached_set.borrow();
+        let root = exclusive.current_root.as_ref().unwrap_throw();
+        VdomSnapshot::new(root, &cached_set)
+    }
+
+    /// Run a bootstrapping callback, such as focusing a field or starting a
+    /// timer, once the initial render has been committed to the physical DOM.
+    ///
+    /// Mounting renders synchronously, so by the time a `Vdom` has been
+    /// constructed its DOM is already live, and `on_mount` invokes `callback`
+    /// right away. The callback is given the root rendering component and a
+    /// weak handle to this virtual DOM, just like listener callbacks are.
+    ///
+    /// Only the first callback is ever run: calling `on_mount` again, once the
+    /// virtual DOM has been mounted and bootstrapped, does nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
+    pub fn on_mount<F>(&self, callback: F)
+    where
+        F: FnOnce(&mut dyn RootRender, VdomWeak),
+    {
+        let weak = self.weak();
+        let mut exclusive = self.inner.exclusive.borrow_mut();
+        if mem::replace(&mut exclusive.mounted_callback_ran, true) {
+            return;
+        }
+        callback(exclusive.component_raw_mut(), weak);
+    }
+
     /// Run this virtual DOM and its listeners forever and never unmount it.
     #[inline]
     pub fn forget(self) {
@@ -237,13 +1075,53 @@ impl Vdom {
         VdomWeak::new(&self.inner)
     }
 
+    /// Run `f`, coalescing every render it schedules into a single render
+    /// that is scheduled once `f` returns.
+    ///
+    /// See `VdomWeak::batch`.
+    #[inline]
+    pub fn batch<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.weak().batch(f)
+    }
+
+    /// Pause rendering until `resume` is called.
+    ///
+    /// See `VdomWeak::pause`.
+    #[inline]
+    pub fn pause(&self) {
+        self.weak().pause();
+    }
+
+    /// Resume rendering after `pause`, performing any renders that were
+    /// scheduled in the meantime as a single render.
+    ///
+    /// See `VdomWeak::resume`.
+    #[inline]
+    pub fn resume(&self) {
+        self.weak().resume();
+    }
+
     /// Unmount this virtual DOM, unregister its event listeners, and return its
     /// root render component.
+    ///
+    /// Unmounting removes every physical DOM node that this virtual DOM created
+    /// from the container, clears all of its listeners out of the events
+    /// registry, and frees its bump arenas and cached set. Any outstanding
+    /// `VdomWeak` handles will fail with `VdomDroppedError` afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the virtual DOM is in the middle of rendering or
+    /// dispatching an event.
     #[inline]
     pub fn unmount(self) -> Box<dyn RootRender> {
-        Rc::try_unwrap(self.inner.clone())
+        let Vdom { inner } = self;
+        Rc::try_unwrap(inner)
             .map_err(|_| ())
-            .unwrap_throw()
+            .expect_throw("should not unmount a `Vdom` while it is rendering or dispatching events")
             .exclusive
             .into_inner()
             .component
@@ -262,23 +1140,58 @@ impl VdomInnerExclusive {
 
     /// Re-render this virtual dom's current component.
     pub(crate) fn render(&mut self) {
+        let component = self.component.take().unwrap_throw();
+        self.render_with(|cx| component.render(cx));
+        self.component = Some(component);
+    }
+
+    /// Render the virtual DOM returned by `render`, and diff it against the
+    /// current contents. See `Vdom::render_with`.
+    pub(crate) fn render_with<F>(&mut self, render: F)
+    where
+        F: for<'a> FnOnce(&mut RenderContext<'a>) -> Node<'a>,
+    {
         unsafe {
             let events_registry = self.events_registry.take().unwrap();
             {
                 let mut registry = events_registry.borrow_mut();
 
-                // Reset the inactive bump arena's pointer.
+                // Reset the inactive bump arena's pointer. Resetting keeps the
+                // arena's largest chunk around, so once an app reaches a steady
+                // state, rendering allocates nodes without hitting the global
+                // allocator at all. Cached subtrees live in their own arenas,
+                // which the cached set pools instead.
                 let mut dom_buffers = self.dom_buffers.take().unwrap_throw();
                 dom_buffers[1].reset();
 
                 // Render the new current contents into the inactive bump arena.
                 let mut cx =
                     RenderContext::new(&dom_buffers[1], &self.cached_set, &mut self.templates);
-                let new_contents = self.component.as_ref().unwrap_throw().render(&mut cx);
+                let new_contents = render(&mut cx);
                 let new_contents = extend_node_lifetime(new_contents);
 
                 // Diff the old contents with the new contents.
-                let old_contents = self.current_root.take().unwrap();
+                let mut old_contents = self.current_root.take().unwrap();
+                if self.diff_mode == DiffMode::Lenient
+                    && !physical_dom_matches(
+                        &self.cached_set.borrow(),
+                        &old_contents,
+                        self.change_list.root_node().as_ref(),
+                    )
+                {
+                    // Start over from an empty root, as when mounting.
+                    registry.remove_subtree(&old_contents);
+                    self.change_list.reset_root();
+                    old_contents = extend_node_lifetime(Node::element(
+                        &dom_buffers[0],
+                        NodeKey::NONE,
+                        "div",
+                        &[],
+                        &[],
+                        &[],
+                        None,
+                    ));
+                }
                 let mut cache_roots;
                 {
                     let cached_set = self.cached_set.borrow();
@@ -313,6 +1226,63 @@ impl VdomInnerExclusive {
         }
     }
 
+    /// Re-render and diff only the subtree at `path`. See `Vdom::patch`.
+    pub(crate) fn patch<F>(&mut self, path: &[usize], render: F) -> Result<(), PatchError>
+    where
+        F: for<'a> FnOnce(&mut RenderContext<'a>) -> Node<'a>,
+    {
+        if !crate::diff::patch_path_is_valid(self.current_root.as_ref().unwrap(), path) {
+            return Err(PatchError {
+                path: path.to_vec(),
+            });
+        }
+
+        unsafe {
+            let events_registry = self.events_registry.take().unwrap();
+            {
+                let mut registry = events_registry.borrow_mut();
+
+                // Render the new subtree into the active bump arena, alongside
+                // the current contents that it is patched into.
+                let dom_buffers = self.dom_buffers.take().unwrap_throw();
+                let mut cx =
+                    RenderContext::new(&dom_buffers[0], &self.cached_set, &mut self.templates);
+                let new_subtree = extend_node_lifetime(render(&mut cx));
+
+                // Diff the old subtree with the new subtree, and copy the
+                // path leading to it into a new root.
+                let old_contents = self.current_root.take().unwrap();
+                let new_contents;
+                {
+                    let cached_set = self.cached_set.borrow();
+                    let mut cache_roots = cached_set.new_roots_set();
+                    let mut change_list = self.change_list.builder();
+                    new_contents = crate::diff::patch(
+                        &dom_buffers[0],
+                        &cached_set,
+                        &mut change_list,
+                        &mut registry,
+                        &old_contents,
+                        path,
+                        new_subtree,
+                        &mut cache_roots,
+                    );
+                    change_list.finish();
+                }
+
+                // Unlike a full render, we didn't see the whole tree, so we
+                // can't tell which cached renders are unused. Leave that to
+                // the next full render.
+                let new_contents = extend_node_lifetime(new_contents);
+                self.dom_buffers = Some(dom_buffers);
+                self.set_current_root(new_contents);
+            }
+
+            self.events_registry = Some(events_registry);
+        }
+        Ok(())
+    }
+
     fn swap_buffers(&mut self, mut dom_buffers: [Bump; 2]) {
         debug_assert!(self.dom_buffers.is_none());
         let (first, second) = dom_buffers.as_mut().split_at_mut(1);
@@ -349,6 +1319,75 @@ where
     *h.borrow_mut() = Some(f);
 }
 
+fn with_idle_callback<F>(mut f: F)
+where
+    F: 'static + FnMut(),
+{
+    let window = web_sys::window().expect_throw("should have a window");
+    let request_idle_callback = match js_sys::Reflect::get(&window, &"requestIdleCallback".into())
+        .ok()
+        .and_then(|r| r.dyn_into::<js_sys::Function>().ok())
+    {
+        Some(r) => r,
+        None => return with_animation_frame(f),
+    };
+
+    let g = Rc::new(RefCell::new(None));
+    let h = g.clone();
+
+    let f = Closure::wrap(Box::new(move || {
+        *g.borrow_mut() = None;
+        f();
+    }) as Box<dyn FnMut()>);
+    request_idle_callback
+        .call1(&window, f.as_ref())
+        .expect_throw("should register `requestIdleCallback` OK");
+
+    *h.borrow_mut() = Some(f);
+}
+
+fn with_scheduling<F>(scheduling: Scheduling, mut f: F)
+where
+    F: 'static + FnMut(),
+{
+    match scheduling {
+        Scheduling::AnimationFrame => with_animation_frame(f),
+        Scheduling::Microtask => wasm_bindgen_futures::spawn_local(async move { f() }),
+        Scheduling::Idle => with_idle_callback(f),
+    }
+}
+
+// Perform a render scheduled by `VdomWeak::render`, and settle its promise with
+// `resolve` or `reject`. If rendering is paused, hold the render back until
+// `VdomWeak::resume` instead.
+fn run_scheduled_render(vdom: &VdomWeak, resolve: &js_sys::Function, reject: &js_sys::Function) {
+    match vdom.inner.upgrade() {
+        None => {
+            warn!("VdomWeak::render: vdom unmounted before we could render");
+            let r = reject.call0(&JsValue::null());
+            debug_assert!(r.is_ok());
+        }
+        Some(inner) => {
+            if inner.shared.paused.get() {
+                // Keep the promise around, so that renders scheduled while
+                // paused all wait on this one.
+                *inner.shared.paused_render.borrow_mut() = Some((resolve.clone(), reject.clone()));
+                return;
+            }
+
+            let mut exclusive = inner.exclusive.borrow_mut();
+            exclusive.render();
+
+            // We did the render, so take the promise away and let future
+            // `render` calls request new animation frames.
+            let _ = inner.shared.render_scheduled.take();
+
+            let r = resolve.call0(&JsValue::null());
+            debug_assert!(r.is_ok());
+        }
+    }
+}
+
 /// An operation failed because the virtual DOM was already dropped and
 /// unmounted.
 #[derive(Debug)]
@@ -362,6 +1401,25 @@ impl fmt::Display for VdomDroppedError {
 
 impl std::error::Error for VdomDroppedError {}
 
+/// A `Vdom::patch` failed because its path does not lead to a node in the
+/// current virtual DOM through element nodes.
+#[derive(Debug)]
+pub struct PatchError {
+    path: Vec<usize>,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The patch path {:?} does not lead to a node in the virtual DOM.",
+            self.path
+        )
+    }
+}
+
+impl std::error::Error for PatchError {}
+
 impl VdomWeak {
     /// Construct a new weak handle to the given virtual DOM.
     #[inline]
@@ -412,13 +1470,21 @@ impl VdomWeak {
         Ok(f(exclusive.component_raw_mut()))
     }
 
-    /// Schedule a render to occur during the next animation frame.
+    /// Schedule a render to occur during the next animation frame, or as
+    /// otherwise configured with `MountOptions::scheduling`.
     ///
     /// If you want a future that resolves after the render has finished, use
     /// `render` instead.
     pub fn schedule_render(&self) {
         debug!("VdomWeak::schedule_render");
 
+        if let Some(inner) = self.inner.upgrade() {
+            if inner.shared.batch_depth.get() > 0 {
+                inner.shared.batch_render_pending.set(true);
+                return;
+            }
+        }
+
         let future = self.render();
 
         wasm_bindgen_futures::spawn_local(async move {
@@ -426,7 +1492,123 @@ impl VdomWeak {
         });
     }
 
-    /// Schedule a render to occur during the next animation frame and return a
+    /// Run `f`, coalescing every render it schedules into a single render
+    /// that is scheduled once `f` returns.
+    ///
+    /// Calls to `schedule_render` within `f` only note that a render is
+    /// needed, rather than each scheduling one, and the outermost `batch`
+    /// schedules one render at its end if any were. This is useful when
+    /// mutating state many times in a loop, where each mutation schedules a
+    /// render of its own.
+    ///
+    /// Batches may be nested. If the virtual DOM has been dropped, `f` is
+    /// still run.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::VdomWeak;
+    ///
+    /// # fn example(vdom: &VdomWeak, mut add_row: impl FnMut(usize, &VdomWeak)) {
+    /// vdom.batch(|| {
+    ///     for i in 0..100 {
+    ///         // Each call to `add_row` updates some state and calls
+    ///         // `vdom.schedule_render()`.
+    ///         add_row(i, vdom);
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn batch<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        let inner = match self.inner.upgrade() {
+            Some(inner) => inner,
+            None => return f(),
+        };
+
+        struct EndBatch<'a>(&'a VdomWeak, &'a VdomInnerShared);
+
+        impl Drop for EndBatch<'_> {
+            fn drop(&mut self) {
+                let depth = self.1.batch_depth.get() - 1;
+                self.1.batch_depth.set(depth);
+                if depth == 0 && self.1.batch_render_pending.replace(false) {
+                    self.0.schedule_render();
+                }
+            }
+        }
+
+        inner
+            .shared
+            .batch_depth
+            .set(inner.shared.batch_depth.get() + 1);
+        let _end = EndBatch(self, &inner.shared);
+        f()
+    }
+
+    /// Pause rendering until `resume` is called.
+    ///
+    /// While paused, renders scheduled with `schedule_render` and `render` are
+    /// held back rather than performed, and the futures returned by `render`
+    /// do not complete until they are. This is useful during performance
+    /// sensitive moments, such as an animation, when state may change many
+    /// times but the DOM should be left alone.
+    ///
+    /// Renders requested directly on the `Vdom`, for example with
+    /// `Vdom::render_with`, are not affected. Pausing an already paused, or
+    /// dropped, virtual DOM does nothing.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use dodrio::VdomWeak;
+    ///
+    /// # fn example(vdom: &VdomWeak, mut step: impl FnMut(&VdomWeak) -> bool) {
+    /// vdom.pause();
+    /// // Each `step` of the animation updates some state and calls
+    /// // `vdom.schedule_render()`.
+    /// while step(vdom) {}
+    /// // Render all of the updates at once.
+    /// vdom.resume();
+    /// # }
+    /// ```
+    pub fn pause(&self) {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.shared.paused.set(true);
+        }
+    }
+
+    /// Resume rendering after `pause`.
+    ///
+    /// If any renders were scheduled while paused, a single render is
+    /// scheduled in their place, and completes all of their `render` futures.
+    /// Resuming a virtual DOM that is not paused, or was dropped, does nothing.
+    ///
+    /// If the virtual DOM is dropped or unmounted while paused instead, the
+    /// held back render never happens, and its `render` futures fail with
+    /// `VdomDroppedError`.
+    pub fn resume(&self) {
+        let inner = match self.inner.upgrade() {
+            Some(inner) => inner,
+            None => return,
+        };
+        if !inner.shared.paused.replace(false) {
+            return;
+        }
+        let (resolve, reject) = match inner.shared.paused_render.borrow_mut().take() {
+            Some(paused_render) => paused_render,
+            None => return,
+        };
+        let vdom = self.clone();
+        with_scheduling(inner.shared.scheduling, move || {
+            run_scheduled_render(&vdom, &resolve, &reject)
+        });
+    }
+
+    /// Schedule a render to occur during the next animation frame, or as
+    /// otherwise configured with `MountOptions::scheduling`, and return a
     /// future that will complete once the render has finished.
     ///
     /// If you don't want to do more things after the render completes, then use
@@ -443,30 +1625,20 @@ impl VdomWeak {
                         inner: Rc::downgrade(&inner),
                     };
 
-                    with_animation_frame(move || match vdom.inner.upgrade() {
-                        None => {
-                            warn!("VdomWeak::render: vdom unmounted before we could render");
-                            let r = reject.call0(&JsValue::null());
-                            debug_assert!(r.is_ok());
-                        }
-                        Some(inner) => {
-                            let mut exclusive = inner.exclusive.borrow_mut();
-                            exclusive.render();
-
-                            // We did the render, so take the promise away
-                            // and let future `render` calls request new
-                            // animation frames.
-                            let _ = inner.shared.render_scheduled.take();
-
-                            let r = resolve.call0(&JsValue::null());
-                            debug_assert!(r.is_ok());
-                        }
+                    let scheduling = inner.shared.scheduling;
+                    with_scheduling(scheduling, move || {
+                        run_scheduled_render(&vdom, &resolve, &reject)
                     });
                 })
             });
 
             inner.shared.render_scheduled.set(Some(promise.clone()));
 
+            // Don't keep the virtual DOM alive while waiting, so that it can
+            // still be dropped or unmounted, which rejects a render that is
+            // held back by `pause`.
+            drop(inner);
+
             JsFuture::from(promise)
                 .await
                 .map(drop)
@@ -0,0 +1,168 @@
+use crate::{builder::*, Node, RenderContext};
+use std::cell::Cell;
+use std::ops::Range;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+
+// The `on_with_id` identity of every virtual list's scroll listener. They all
+// behave the same way: see `VirtualList::render`.
+const SCROLL_LISTENER_ID: u32 = 0x7669_7274;
+
+/// A helper for rendering a long list of fixed-height rows, where only the rows
+/// that are scrolled into view are in the virtual DOM.
+///
+/// A `VirtualList` renders a scrollable viewport containing the visible rows,
+/// plus a few rows of "overscan" above and below them, between two spacer
+/// elements that take up the height of all the rows that are not rendered.
+/// When the viewport is scrolled far enough that a different set of rows
+/// becomes visible, it schedules a re-render.
+///
+/// Each row is wrapped in an element that is keyed by the row's index, so
+/// that rows that stay visible while scrolling keep their physical DOM nodes.
+///
+/// Keep the `VirtualList` in your component's state, since it remembers the
+/// viewport's scroll position, and render it from your component's `render`
+/// method.
+///
+/// # Example
+///
+/// ```no_run
+/// use dodrio::{builder::*, bumpalo, Node, Render, RenderContext, VirtualList};
+///
+/// struct Log {
+///     lines: Vec<String>,
+///     list: VirtualList,
+/// }
+///
+/// impl Log {
+///     fn new(lines: Vec<String>) -> Log {
+///         // Rows are 20 pixels tall, in a viewport 400 pixels tall.
+///         let list = VirtualList::new(lines.len(), 20.0, 400.0);
+///         Log { lines, list }
+///     }
+/// }
+///
+/// impl<'a> Render<'a> for Log {
+///     fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+///         self.list.render(cx, |cx, i| {
+///             let line = bumpalo::collections::String::from_str_in(&self.lines[i], cx.bump);
+///             text(line.into_bump_str())
+///         })
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VirtualList {
+    len: usize,
+    row_height: f64,
+    height: f64,
+    overscan: usize,
+    scroll_top: Rc<Cell<f64>>,
+}
+
+impl VirtualList {
+    /// Create a new `VirtualList` of `len` rows that are each `row_height`
+    /// pixels tall, in a viewport that is `height` pixels tall.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_height` is not positive.
+    pub fn new(len: usize, row_height: f64, height: f64) -> VirtualList {
+        assert!(
+            row_height > 0.0,
+            "VirtualList rows must have a positive height"
+        );
+        VirtualList {
+            len,
+            row_height,
+            height,
+            overscan: 3,
+            scroll_top: Rc::new(Cell::new(0.0)),
+        }
+    }
+
+    /// Set how many rows to render above and below the visible rows, so that
+    /// they are already there when scrolling a little. Defaults to 3.
+    pub fn overscan(mut self, rows: usize) -> Self {
+        self.overscan = rows;
+        self
+    }
+
+    /// Get the total number of rows.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Are there no rows at all?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Set the total number of rows, for example after appending rows to the
+    /// underlying data.
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    /// Get the range of row indices that the next render will include: the
+    /// visible rows and their overscan.
+    pub fn rendered_rows(&self) -> Range<usize> {
+        let scroll_top = self.scroll_top.get().max(0.0);
+        let first = (scroll_top / self.row_height).floor() as usize;
+        let last = ((scroll_top + self.height) / self.row_height).ceil() as usize;
+        let start = first.saturating_sub(self.overscan).min(self.len);
+        let end = last.saturating_add(self.overscan).min(self.len);
+        start..end
+    }
+
+    /// Render the viewport, calling `render_row` with the index of each row
+    /// in `rendered_rows` to render that row's contents.
+    pub fn render<'a, F>(&self, cx: &mut RenderContext<'a>, mut render_row: F) -> Node<'a>
+    where
+        F: FnMut(&mut RenderContext<'a>, usize) -> Node<'a>,
+    {
+        let rows = self.rendered_rows();
+
+        let mut children = bumpalo::collections::Vec::with_capacity_in(rows.len() + 2, cx.bump);
+        children.push(self.spacer(cx, rows.start));
+        for i in rows.clone() {
+            let row = render_row(cx, i);
+            let style = bumpalo::format!(in cx.bump, "height: {}px", self.row_height);
+            children.push(
+                div(&cx)
+                    .key(i as u32)
+                    .attr("style", style.into_bump_str())
+                    .children([row])
+                    .finish(),
+            );
+        }
+        children.push(self.spacer(cx, self.len - rows.end));
+
+        let style = bumpalo::format!(in cx.bump, "overflow-y: auto; height: {}px", self.height);
+        let list = self.clone();
+        div(&cx)
+            .attr("style", style.into_bump_str())
+            // The callback only captures a clone of this list, which shares
+            // its scroll position, so there is no need to update the physical
+            // listener on every render.
+            .on_with_id("scroll", SCROLL_LISTENER_ID, move |_root, vdom, event| {
+                let viewport = match event.current_target() {
+                    Some(target) => target.unchecked_into::<web_sys::Element>(),
+                    None => return,
+                };
+                let before = list.rendered_rows();
+                list.scroll_top.set(f64::from(viewport.scroll_top()));
+                if list.rendered_rows() != before {
+                    vdom.schedule_render();
+                }
+            })
+            .children(children)
+            .finish()
+    }
+
+    // An element that takes up the height of `rows` rows.
+    fn spacer<'a>(&self, cx: &mut RenderContext<'a>, rows: usize) -> Node<'a> {
+        let style = bumpalo::format!(in cx.bump, "height: {}px", rows as f64 * self.row_height);
+        div(&cx).attr("style", style.into_bump_str()).finish()
+    }
+}
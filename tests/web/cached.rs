@@ -1,5 +1,7 @@
 use super::{assert_rendered, before_after, create_element, RenderFn};
-use dodrio::{builder::*, bumpalo, Cached, Node, Render, RenderContext, RootRender, Vdom};
+use dodrio::{
+    builder::*, bumpalo, CacheId, Cached, CachedSet, Node, Render, RenderContext, RootRender, Vdom,
+};
 use std::cell::Cell;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
@@ -106,6 +108,38 @@ async fn uses_cached_render() {
         .unwrap();
 }
 
+#[wasm_bindgen_test]
+async fn uses_pre_warmed_cached_set() {
+    struct PreWarmed(CacheId);
+
+    impl<'a> Render<'a> for PreWarmed {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            cx.cached(self.0)
+        }
+    }
+
+    let counter = Rc::new(CountRenders::new());
+    let mut cached_set = CachedSet::default();
+    let id = {
+        let counter = counter.clone();
+        cached_set.insert(move |cx| counter.render(cx))
+    };
+    assert_eq!(counter.render_count.get(), 1);
+
+    let container = create_element("div");
+    let vdom = Vdom::with_cached_set(&container, PreWarmed(id), cached_set);
+
+    // The first render references the pre-warmed subtree instead of rendering
+    // it again.
+    assert_eq!(counter.render_count.get(), 1);
+    assert_rendered(&container, &RenderFn(|_| text("1")));
+
+    // And so do all subsequent renders.
+    vdom.weak().render().await.unwrap();
+    assert_eq!(counter.render_count.get(), 1);
+    assert_rendered(&container, &RenderFn(|_| text("1")));
+}
+
 #[wasm_bindgen(module = "/tests/web/cached.js")]
 extern "C" {
     #[wasm_bindgen(js_name = getCloneNodeCount)]
@@ -0,0 +1,101 @@
+use super::{before_after, create_element};
+use dodrio::{builder::*, Node, Render, RenderContext, Vdom};
+use std::sync::Once;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+// Define the `fancy-button` customized built-in element, whose instances have
+// an `isFancy` property.
+fn define_fancy_button() {
+    static DEFINE: Once = Once::new();
+    DEFINE.call_once(|| {
+        js_sys::eval(
+            r#"
+            customElements.define(
+                "fancy-button",
+                class FancyButton extends HTMLButtonElement {
+                    get isFancy() {
+                        return true;
+                    }
+                },
+                { extends: "button" }
+            );
+            "#,
+        )
+        .unwrap();
+    });
+}
+
+struct Button(Option<&'static str>);
+
+impl<'a> Render<'a> for Button {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let b = button(&cx).attr("class", "button");
+        let b = match self.0 {
+            Some(is) => b.is(is),
+            None => b,
+        };
+        b.children([text("click me")]).finish()
+    }
+}
+
+fn is_fancy(container: &web_sys::Element) -> bool {
+    let button = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    js_sys::Reflect::get(&button, &"isFancy".into())
+        .unwrap()
+        .as_bool()
+        .unwrap_or(false)
+}
+
+#[wasm_bindgen_test]
+async fn create_customized_builtin_element() {
+    define_fancy_button();
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Button(Some("fancy-button")));
+    assert!(is_fancy(&container));
+
+    // The `is` value is given at creation, rather than set as an attribute.
+    let button = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    assert_eq!(button.get_attribute("is"), None);
+
+    // Removing the `is` value re-creates the element as a plain button.
+    vdom.weak()
+        .set_component(Box::new(Button(None)))
+        .await
+        .unwrap();
+    assert!(!is_fancy(&container));
+
+    // And adding it back re-creates the customized built-in element.
+    vdom.weak()
+        .set_component(Box::new(Button(Some("fancy-button"))))
+        .await
+        .unwrap();
+    assert!(is_fancy(&container));
+}
+
+before_after! {
+    add_is {
+        before(cx) {
+            Button(None).render(cx)
+        }
+        after(cx) {
+            Button(Some("fancy-button")).render(cx)
+        }
+    }
+
+    change_is {
+        before(cx) {
+            Button(Some("fancy-button")).render(cx)
+        }
+        after(cx) {
+            Button(Some("plain-button")).render(cx)
+        }
+    }
+}
@@ -2,7 +2,7 @@ use super::create_element;
 use dodrio::{Node, Render, RenderContext, Vdom};
 use futures::future::{select, Either};
 use std::cell::{Cell, RefCell};
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_test::*;
@@ -248,3 +248,671 @@ async fn event_does_not_fire_after_unmounting() {
         Either::Right((Err(_), outer)) => assert_eq!(outer.await, Ok("outer")),
     }
 }
+
+struct DropTarget {
+    on_drop: Box<dyn FnMut()>,
+}
+
+impl<'a> Render<'a> for DropTarget {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+        div(&cx)
+            .attr("id", "target")
+            .drop_target(|root, _scheduler, _event| {
+                (root.unwrap_mut::<DropTarget>().on_drop)();
+            })
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn drop_target() {
+    let container = create_element("div");
+
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let mut sender = Some(sender);
+
+    let _vdom = Vdom::new(
+        &container,
+        DropTarget {
+            on_drop: Box::new(move || {
+                sender
+                    .take()
+                    .expect_throw("should only call drop listener once")
+                    .send(())
+                    .expect_throw("should not have dropped the receiver");
+            }),
+        },
+    );
+
+    let target = target(&container);
+    for event_type in &["dragenter", "dragover", "drop"] {
+        let event = web_sys::Event::new_with_event_init_dict(
+            event_type,
+            web_sys::EventInit::new().bubbles(true).cancelable(true),
+        )
+        .expect_throw("should create event OK");
+        let not_canceled = target
+            .dispatch_event(&event)
+            .expect_throw("should dispatch event OK");
+        assert!(
+            !not_canceled,
+            "{} should have its default prevented",
+            event_type
+        );
+    }
+
+    receiver.await.unwrap();
+}
+
+struct SubmitForm {
+    on_submit: Box<dyn FnMut(bool)>,
+}
+
+impl<'a> Render<'a> for SubmitForm {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+        form(&cx)
+            .on_prevent_default("submit", |root, _scheduler, event| {
+                (root.unwrap_mut::<SubmitForm>().on_submit)(event.default_prevented());
+            })
+            .children([button(&cx)
+                .attr("id", "target")
+                .attr("type", "submit")
+                .finish()])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn prevent_default_submit_does_not_navigate() {
+    let container = create_element("div");
+    // The form must be connected to the document to be submitted at all.
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .body()
+        .unwrap()
+        .append_child(&container)
+        .unwrap();
+
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let mut sender = Some(sender);
+
+    let _vdom = Vdom::new(
+        &container,
+        SubmitForm {
+            on_submit: Box::new(move |default_prevented| {
+                sender
+                    .take()
+                    .expect_throw("should only call submit listener once")
+                    .send(default_prevented)
+                    .expect_throw("should not have dropped the receiver");
+            }),
+        },
+    );
+
+    // Clicking the submit button would navigate away from the test page, were
+    // the submission not prevented.
+    target(&container).click();
+    assert_eq!(receiver.await, Ok(true));
+
+    container.remove();
+}
+
+struct Nested {
+    outer_clicks: Rc<Cell<usize>>,
+    inner_clicks: Rc<Cell<usize>>,
+}
+
+impl<'a> Render<'a> for Nested {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+        let outer_clicks = self.outer_clicks.clone();
+        let inner_clicks = self.inner_clicks.clone();
+        div(&cx)
+            .on("click", move |_root, _scheduler, _event| {
+                outer_clicks.set(outer_clicks.get() + 1);
+            })
+            .children([div(&cx)
+                .attr("id", "target")
+                .on_stop_propagation("click", move |_root, _scheduler, _event| {
+                    inner_clicks.set(inner_clicks.get() + 1);
+                })
+                .finish()])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+fn stop_propagation_listener() {
+    let container = create_element("div");
+    let outer_clicks = Rc::new(Cell::new(0));
+    let inner_clicks = Rc::new(Cell::new(0));
+
+    let _vdom = Vdom::new(
+        &container,
+        Nested {
+            outer_clicks: outer_clicks.clone(),
+            inner_clicks: inner_clicks.clone(),
+        },
+    );
+
+    target(&container).click();
+    assert_eq!((outer_clicks.get(), inner_clicks.get()), (0, 1));
+}
+
+struct WeakClicker(Weak<Cell<usize>>);
+
+impl<'a> Render<'a> for WeakClicker {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+        let target = div(&cx).attr("id", "target");
+        let target = match self.0.upgrade() {
+            Some(clicks) => target.on_weak("click", &clicks, |clicks, _root, _vdom, _event| {
+                clicks.set(clicks.get() + 1);
+            }),
+            None => target,
+        };
+        target.finish()
+    }
+}
+
+#[wasm_bindgen_test]
+fn weak_listener_does_not_keep_state_alive() {
+    let container = create_element("div");
+    let clicks = Rc::new(Cell::new(0));
+    let _vdom = Vdom::new(&container, WeakClicker(Rc::downgrade(&clicks)));
+
+    // The listener does not hold a strong reference to the state.
+    assert_eq!(Rc::strong_count(&clicks), 1);
+
+    target(&container).click();
+    assert_eq!(clicks.get(), 1);
+
+    // Once the state is dropped, the listener is a no-op.
+    let weak_clicks = Rc::downgrade(&clicks);
+    drop(clicks);
+    assert!(weak_clicks.upgrade().is_none());
+    target(&container).click();
+}
+
+#[wasm_bindgen_test]
+fn multiple_vdoms_do_not_cross_talk() {
+    let container_a = create_element("div");
+    let container_b = create_element("div");
+
+    let clicks_a = Rc::new(Cell::new(0));
+    let clicks_b = Rc::new(Cell::new(0));
+
+    let _vdom_a = Vdom::new(&container_a, {
+        let clicks_a = clicks_a.clone();
+        EventContainer::new("click", move || clicks_a.set(clicks_a.get() + 1))
+    });
+    let _vdom_b = Vdom::new(&container_b, {
+        let clicks_b = clicks_b.clone();
+        EventContainer::new("click", move || clicks_b.set(clicks_b.get() + 1))
+    });
+
+    target(&container_a).click();
+    assert_eq!((clicks_a.get(), clicks_b.get()), (1, 0));
+
+    target(&container_b).click();
+    target(&container_b).click();
+    assert_eq!((clicks_a.get(), clicks_b.get()), (1, 2));
+}
+
+/// An `<a>` with a click listener, in the SVG namespace if `self.0` is true.
+struct Link(bool, Rc<Cell<usize>>);
+
+impl<'a> Render<'a> for Link {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let clicks = self.1.clone();
+        let namespace = if self.0 {
+            Some("http://www.w3.org/2000/svg")
+        } else {
+            None
+        };
+        a(&cx)
+            .namespace(namespace)
+            .on("click", move |_root, _vdom, _event| {
+                clicks.set(clicks.get() + 1);
+            })
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn listener_survives_namespace_change() {
+    let container = create_element("div");
+    let clicks = Rc::new(Cell::new(0));
+    let vdom = Vdom::new(&container, Link(false, clicks.clone()));
+
+    let click = || {
+        let event = web_sys::Event::new("click").expect_throw("should create event OK");
+        container
+            .first_child()
+            .unwrap()
+            .dispatch_event(&event)
+            .expect_throw("should dispatch event OK");
+    };
+
+    click();
+    assert_eq!(clicks.get(), 1);
+
+    for (i, &is_svg) in [true, false].iter().enumerate() {
+        vdom.weak()
+            .set_component(Box::new(Link(is_svg, clicks.clone())))
+            .await
+            .unwrap();
+        let namespace = container
+            .first_child()
+            .unwrap()
+            .unchecked_into::<web_sys::Element>()
+            .namespace_uri();
+        assert_eq!(
+            namespace.as_deref() == Some("http://www.w3.org/2000/svg"),
+            is_svg
+        );
+
+        click();
+        assert_eq!(clicks.get(), i + 2);
+    }
+}
+
+/// A `<div>` of `self.0` buttons, each with a click and a keydown listener.
+struct Buttons(usize);
+
+impl<'a> Render<'a> for Buttons {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let mut buttons = dodrio::bumpalo::collections::Vec::new_in(cx.bump);
+        for _ in 0..self.0 {
+            buttons.push(
+                button(&cx)
+                    .on("click", |_root, _vdom, _event| {})
+                    .on("keydown", |_root, _vdom, _event| {})
+                    .finish(),
+            );
+        }
+        div(&cx).children(buttons).finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn listener_count_tracks_created_and_removed_listeners() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Buttons(0));
+    assert_eq!(vdom.listener_count(), 0);
+
+    vdom.weak()
+        .set_component(Box::new(Buttons(3)))
+        .await
+        .unwrap();
+    assert_eq!(vdom.listener_count(), 6);
+
+    vdom.weak()
+        .set_component(Box::new(Buttons(5)))
+        .await
+        .unwrap();
+    assert_eq!(vdom.listener_count(), 10);
+
+    vdom.weak()
+        .set_component(Box::new(Buttons(0)))
+        .await
+        .unwrap();
+    assert_eq!(vdom.listener_count(), 0);
+}
+
+/// A `<div>` that records the type of every event that its `pointermove`
+/// listener is given, and prevents the default action of `wheel` events.
+struct Sketch(Rc<RefCell<Vec<String>>>);
+
+impl<'a> Render<'a> for Sketch {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let types = self.0.clone();
+        div(&cx)
+            .on_coalesced("pointermove", move |_root, _vdom, events| {
+                types
+                    .borrow_mut()
+                    .extend(events.iter().map(|event| event.type_()));
+            })
+            .on("wheel", |_root, _vdom, event| event.prevent_default())
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+fn coalesced_events_reach_the_listener() {
+    let container = create_element("div");
+    let types = Rc::new(RefCell::new(vec![]));
+    let _vdom = Vdom::new(&container, Sketch(types.clone()));
+    let target = container.first_child().unwrap();
+
+    // Without `getCoalescedEvents`, the listener gets the dispatched event.
+    let event = web_sys::Event::new("pointermove").unwrap_throw();
+    target.dispatch_event(&event).unwrap_throw();
+    assert_eq!(*types.borrow(), ["pointermove"]);
+    types.borrow_mut().clear();
+
+    let event = web_sys::Event::new("pointermove").unwrap_throw();
+    let get_coalesced_events =
+        js_sys::Function::new_no_args("return [new Event('first'), new Event('second')];");
+    js_sys::Reflect::set(&event, &"getCoalescedEvents".into(), &get_coalesced_events)
+        .unwrap_throw();
+    target.dispatch_event(&event).unwrap_throw();
+    assert_eq!(*types.borrow(), ["first", "second"]);
+}
+
+#[wasm_bindgen_test]
+fn wheel_listeners_are_passive() {
+    let container = create_element("div");
+    let _vdom = Vdom::new(&container, Sketch(Rc::new(RefCell::new(vec![]))));
+
+    let mut init = web_sys::EventInit::new();
+    init.cancelable(true);
+    let event = web_sys::Event::new_with_event_init_dict("wheel", &init).unwrap_throw();
+    container
+        .first_child()
+        .unwrap()
+        .dispatch_event(&event)
+        .unwrap_throw();
+    assert!(!event.default_prevented());
+}
+
+/// A `<div>` whose wheel listener prevents scrolling, and is non-passive if
+/// `self.0` is true.
+struct Zoomable(bool);
+
+impl<'a> Render<'a> for Zoomable {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let zoom =
+            |_root: &mut dyn dodrio::RootRender, _vdom: dodrio::VdomWeak, event: web_sys::Event| {
+                event.prevent_default();
+            };
+        if self.0 {
+            div(&cx).on_non_passive("wheel", zoom).finish()
+        } else {
+            div(&cx).on("wheel", zoom).finish()
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+async fn non_passive_wheel_listeners_can_prevent_scrolling() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Zoomable(false));
+
+    let wheel = || {
+        let mut init = web_sys::EventInit::new();
+        init.cancelable(true);
+        let event = web_sys::Event::new_with_event_init_dict("wheel", &init).unwrap_throw();
+        container
+            .first_child()
+            .unwrap()
+            .dispatch_event(&event)
+            .unwrap_throw();
+        event.default_prevented()
+    };
+    assert!(!wheel());
+
+    // Re-rendering with a non-passive listener replaces the passive one.
+    vdom.weak()
+        .set_component(Box::new(Zoomable(true)))
+        .await
+        .unwrap_throw();
+    assert!(wheel());
+}
+
+/// A `<button>` whose click listener adds `self.0` to the count, and has an
+/// identity token if `self.1` is true.
+struct Increment(usize, bool, Rc<Cell<usize>>);
+
+impl<'a> Render<'a> for Increment {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let amount = self.0;
+        let count = self.2.clone();
+        let increment = move |_root: &mut dyn dodrio::RootRender,
+                              _vdom: dodrio::VdomWeak,
+                              _event: web_sys::Event| {
+            count.set(count.get() + amount);
+        };
+        let increment_button = if self.1 {
+            button(&cx).on_with_id("click", 1, increment)
+        } else {
+            button(&cx).on("click", increment)
+        };
+        div(&cx).children([increment_button.finish()]).finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn unchanged_listener_id_does_not_update_listener() {
+    let container = create_element("div");
+    let count = Rc::new(Cell::new(0));
+    let vdom = Vdom::new(&container, Increment(1, true, count.clone()));
+
+    let click = || {
+        let button = container.first_child().unwrap().first_child().unwrap();
+        button.unchecked_into::<web_sys::HtmlElement>().click();
+    };
+    click();
+    assert_eq!(count.get(), 1);
+
+    // Without an id, the physical listener is updated.
+    vdom.weak()
+        .set_component(Box::new(Increment(1, false, count.clone())))
+        .await
+        .unwrap();
+    assert_eq!(vdom.last_diff_op_count(), 2);
+
+    // With the same id as before, it is left alone, but the new callback is
+    // the one that is invoked.
+    vdom.weak()
+        .set_component(Box::new(Increment(10, true, count.clone())))
+        .await
+        .unwrap();
+    assert_eq!(vdom.last_diff_op_count(), 2);
+    vdom.weak()
+        .set_component(Box::new(Increment(100, true, count.clone())))
+        .await
+        .unwrap();
+    assert_eq!(vdom.last_diff_op_count(), 0);
+    click();
+    assert_eq!(count.get(), 101);
+    assert_eq!(vdom.listener_count(), 1);
+}
+
+/// A `<div>` that, when `self.0` is true, contains a child that records its
+/// size in `self.1` whenever it is resized.
+struct Resizable(bool, Rc<Cell<(f64, f64)>>);
+
+impl<'a> Render<'a> for Resizable {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let mut children = dodrio::bumpalo::collections::Vec::new_in(cx.bump);
+        if self.0 {
+            let size = self.1.clone();
+            children.push(
+                div(&cx)
+                    .on_resize(move |_root, _vdom, width, height| size.set((width, height)))
+                    .finish(),
+            );
+        }
+        div(&cx).children(children).finish()
+    }
+}
+
+// A mock `ResizeObserver` class that logs when it observes and disconnects,
+// and has a static `resize(width, height)` method to simulate resizing the
+// last observed element.
+fn mock_resize_observer() -> JsValue {
+    js_sys::Function::new_no_args(
+        "
+        const log = [];
+        class ResizeObserver {
+            constructor(callback) {
+                this.callback = callback;
+            }
+            observe(target) {
+                log.push('observe');
+                ResizeObserver.resize = (width, height) =>
+                    this.callback([{ target, contentRect: { width, height } }], this);
+            }
+            disconnect() {
+                log.push('disconnect');
+            }
+        }
+        ResizeObserver.log = log;
+        return ResizeObserver;
+        ",
+    )
+    .call0(&JsValue::NULL)
+    .unwrap_throw()
+}
+
+#[wasm_bindgen_test]
+async fn resize_observer_is_set_up_on_create_and_torn_down_on_remove() {
+    let window: JsValue = web_sys::window().unwrap_throw().into();
+    let original = js_sys::Reflect::get(&window, &"ResizeObserver".into()).unwrap_throw();
+    let mock = mock_resize_observer();
+    js_sys::Reflect::set(&window, &"ResizeObserver".into(), &mock).unwrap_throw();
+
+    let log = || -> Vec<String> {
+        let log = js_sys::Reflect::get(&mock, &"log".into()).unwrap_throw();
+        js_sys::Array::from(&log)
+            .iter()
+            .map(|entry| entry.as_string().unwrap_throw())
+            .collect()
+    };
+
+    let container = create_element("div");
+    let size = Rc::new(Cell::new((0.0, 0.0)));
+    let vdom = Vdom::new(&container, Resizable(true, size.clone()));
+    assert_eq!(log(), ["observe"]);
+
+    js_sys::Reflect::get(&mock, &"resize".into())
+        .unwrap_throw()
+        .unchecked_into::<js_sys::Function>()
+        .call2(&JsValue::NULL, &120.into(), &40.into())
+        .unwrap_throw();
+    assert_eq!(size.get(), (120.0, 40.0));
+
+    vdom.weak()
+        .set_component(Box::new(Resizable(false, size.clone())))
+        .await
+        .unwrap();
+    assert_eq!(log(), ["observe", "disconnect"]);
+
+    js_sys::Reflect::set(&window, &"ResizeObserver".into(), &original).unwrap_throw();
+}
+
+/// A `<button>` whose `onclick` attribute is `self.0`, if any.
+struct InlineHandler(Option<&'static str>);
+
+impl<'a> Render<'a> for InlineHandler {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let mut handler = button(&cx);
+        if let Some(onclick) = self.0 {
+            handler = handler.attr("onclick", onclick);
+        }
+        handler.finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn inline_event_handler_attributes_are_set_as_attributes() {
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        InlineHandler(Some(
+            "this.dataset.clicks = (this.dataset.clicks || '') + 'a'",
+        )),
+    );
+    let button = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::HtmlElement>();
+    let clicks = || button.get_attribute("data-clicks");
+
+    button.click();
+    assert_eq!(clicks().as_deref(), Some("a"));
+
+    // Changing the attribute changes the handler.
+    vdom.weak()
+        .set_component(Box::new(InlineHandler(Some(
+            "this.dataset.clicks = (this.dataset.clicks || '') + 'b'",
+        ))))
+        .await
+        .unwrap();
+    button.click();
+    assert_eq!(clicks().as_deref(), Some("ab"));
+
+    // Removing the attribute removes the handler.
+    vdom.weak()
+        .set_component(Box::new(InlineHandler(None)))
+        .await
+        .unwrap();
+    assert!(!button.has_attribute("onclick"));
+    button.click();
+    assert_eq!(clicks().as_deref(), Some("ab"));
+}
+
+/// A `<canvas>` that records the 2D context it is handed in `self.0`.
+struct Sketch(Rc<RefCell<Option<JsValue>>>);
+
+impl<'a> Render<'a> for Sketch {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        use dodrio::builder::*;
+
+        let context = self.0.clone();
+        canvas(&cx)
+            .canvas_2d(move |_root, _vdom, ctx| {
+                ctx.fill_rect(0.0, 0.0, 10.0, 10.0);
+                *context.borrow_mut() = Some(ctx.into());
+            })
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn canvas_2d_hands_the_created_canvas_context_to_the_callback() {
+    let container = create_element("div");
+    let context = Rc::new(RefCell::new(None));
+    let vdom = Vdom::new(&container, Sketch(context.clone()));
+    assert!(context.borrow().is_none());
+
+    wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL))
+        .await
+        .unwrap_throw();
+    let ctx = context
+        .borrow()
+        .clone()
+        .expect("should have received a context");
+    let canvas: JsValue = container.first_child().unwrap_throw().into();
+    assert_eq!(
+        js_sys::Reflect::get(&ctx, &"canvas".into()).unwrap_throw(),
+        canvas
+    );
+
+    // Re-rendering does not acquire the context again.
+    *context.borrow_mut() = None;
+    vdom.weak().render().await.unwrap();
+    wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL))
+        .await
+        .unwrap_throw();
+    assert!(context.borrow().is_none());
+}
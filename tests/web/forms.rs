@@ -0,0 +1,568 @@
+use super::{before_after, create_element};
+use dodrio::{builder::*, Node, Render, RenderContext, Vdom, VdomSnapshot};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::*;
+
+/// Two sibling forms, and an input outside of both of them that is associated
+/// with the form whose id is `self.0`.
+struct Forms(&'static str);
+
+impl<'a> Render<'a> for Forms {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        div(&cx)
+            .children([
+                form(&cx).attr("id", "f").finish(),
+                form(&cx).attr("id", "g").finish(),
+                input(&cx).attr("name", "query").form_owner(self.0).finish(),
+            ])
+            .finish()
+    }
+}
+
+// Get the `id` of the form that `input` is associated with, if any.
+fn form_owner_id(input: &JsValue) -> Option<String> {
+    let form = js_sys::Reflect::get(input, &"form".into()).unwrap();
+    if form.is_null() {
+        return None;
+    }
+    js_sys::Reflect::get(&form, &"id".into())
+        .unwrap()
+        .as_string()
+}
+
+/// A fieldset of form controls that is disabled if `self.0` is true.
+struct Settings(bool);
+
+impl<'a> Render<'a> for Settings {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        fieldset(&cx)
+            .disabled(self.0)
+            .children([
+                legend(&cx).children([text("Settings")]).finish(),
+                input(&cx).attr("type", "text").finish(),
+                input(&cx).attr("type", "checkbox").finish(),
+                button(&cx).children([text("Save")]).finish(),
+            ])
+            .finish()
+    }
+}
+
+before_after! {
+    change_form_attribute {
+        before(cx) {
+            Forms("f").render(cx)
+        }
+        after(cx) {
+            Forms("g").render(cx)
+        }
+    }
+
+    remove_radio_value {
+        before(cx) {
+            input(&cx).attr("type", "radio").attr("value", "small").finish()
+        }
+        after(cx) {
+            input(&cx).attr("type", "radio").finish()
+        }
+    }
+
+    toggle_fieldset_disabled {
+        before(cx) {
+            Settings(false).render(cx)
+        }
+        after(cx) {
+            Settings(true).render(cx)
+        }
+    }
+
+    add_form_attribute {
+        before(cx) {
+            input(&cx).attr("name", "query").finish()
+        }
+        after(cx) {
+            input(&cx).attr("name", "query").form_owner("f").finish()
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+async fn changing_form_attribute_reassociates_control() {
+    let container = create_element("div");
+    // Form owners are only resolved by id within a document.
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .body()
+        .unwrap()
+        .append_child(&container)
+        .unwrap();
+
+    let vdom = Vdom::new(&container, Forms("f"));
+    let input = container.query_selector("input").unwrap().unwrap();
+    assert_eq!(form_owner_id(&input), Some("f".to_string()));
+
+    vdom.weak()
+        .set_component(Box::new(Forms("g")))
+        .await
+        .unwrap();
+    assert_eq!(form_owner_id(&input), Some("g".to_string()));
+
+    vdom.weak()
+        .set_component(Box::new(Forms("no-such-form")))
+        .await
+        .unwrap();
+    assert_eq!(form_owner_id(&input), None);
+
+    container.remove();
+}
+
+/// A group of radio buttons where the `self.0`th one is checked.
+struct Sizes(usize);
+
+impl<'a> Render<'a> for Sizes {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut sizes = form(&cx);
+        for (i, &size) in ["small", "medium", "large"].iter().enumerate() {
+            sizes = sizes.child(
+                input(&cx)
+                    .attr("type", "radio")
+                    .attr("name", "size")
+                    .attr("value", size)
+                    .bool_attr("checked", i == self.0)
+                    .finish(),
+            );
+        }
+        sizes.finish()
+    }
+}
+
+// Get the index of each checked radio button in the given container.
+fn checked_radios(container: &web_sys::Element) -> Vec<u32> {
+    let radios = container.query_selector_all("input").unwrap();
+    (0..radios.length())
+        .filter(|&i| {
+            js_sys::Reflect::get(&radios.item(i).unwrap(), &"checked".into())
+                .unwrap()
+                .as_bool()
+                .unwrap()
+        })
+        .collect()
+}
+
+#[wasm_bindgen_test]
+async fn radio_group_has_single_checked_radio_across_renders() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Sizes(0));
+    assert_eq!(checked_radios(&container), vec![0]);
+
+    for &selected in &[2, 1, 0, 0, 2] {
+        vdom.weak()
+            .set_component(Box::new(Sizes(selected)))
+            .await
+            .unwrap();
+        assert_eq!(checked_radios(&container), vec![selected as u32]);
+    }
+
+    // A click that the component does not know about is undone by the next
+    // render.
+    container
+        .query_selector("input[value=small]")
+        .unwrap()
+        .unwrap()
+        .unchecked_into::<web_sys::HtmlElement>()
+        .click();
+    assert_eq!(checked_radios(&container), vec![0]);
+    vdom.weak().set_component(Box::new(Sizes(2))).await.unwrap();
+    assert_eq!(checked_radios(&container), vec![2]);
+
+    vdom.weak().set_component(Box::new(Sizes(1))).await.unwrap();
+    assert_eq!(checked_radios(&container), vec![1]);
+}
+
+/// A controlled `<details>` element, that sends its new open state through
+/// `self.toggled` when it is toggled.
+struct Disclosure {
+    open: bool,
+    toggled: Option<futures::channel::oneshot::Sender<bool>>,
+}
+
+impl<'a> Render<'a> for Disclosure {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        details(&cx)
+            .open(self.open)
+            .on_toggle(|root, vdom, open| {
+                let disclosure = root.unwrap_mut::<Disclosure>();
+                disclosure.open = open;
+                if let Some(toggled) = disclosure.toggled.take() {
+                    toggled.send(open).unwrap();
+                }
+                vdom.schedule_render();
+            })
+            .children([
+                summary(&cx).children([text("More")]).finish(),
+                p(&cx).children([text("Details")]).finish(),
+            ])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn details_open_state_follows_the_user_and_the_component() {
+    let container = create_element("div");
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .body()
+        .unwrap()
+        .append_child(&container)
+        .unwrap();
+
+    let (toggled, user_toggle) = futures::channel::oneshot::channel();
+    let vdom = Vdom::new(
+        &container,
+        Disclosure {
+            open: false,
+            toggled: Some(toggled),
+        },
+    );
+    let details = container.query_selector("details").unwrap().unwrap();
+    assert!(!details.has_attribute("open"));
+
+    // The user opens it, and the virtual DOM follows.
+    container
+        .query_selector("summary")
+        .unwrap()
+        .unwrap()
+        .unchecked_into::<web_sys::HtmlElement>()
+        .click();
+    assert!(user_toggle.await.unwrap());
+    vdom.weak().render().await.unwrap();
+    assert!(details.has_attribute("open"));
+    match vdom.snapshot() {
+        VdomSnapshot::Element { attributes, .. } => {
+            assert_eq!(attributes, [("open".to_string(), String::new())]);
+        }
+        VdomSnapshot::Text(_) => panic!("should render an element"),
+    }
+
+    // The component closes it, and the physical DOM follows.
+    let (toggled, component_toggle) = futures::channel::oneshot::channel();
+    vdom.weak()
+        .with_component(move |root| {
+            let disclosure = root.unwrap_mut::<Disclosure>();
+            disclosure.open = false;
+            disclosure.toggled = Some(toggled);
+        })
+        .await
+        .unwrap();
+    vdom.weak().render().await.unwrap();
+    assert!(!details.has_attribute("open"));
+    assert!(!component_toggle.await.unwrap());
+
+    container.remove();
+}
+
+#[wasm_bindgen_test]
+async fn form_control_attributes_set_their_properties() {
+    struct Input(bool);
+
+    impl<'a> Render<'a> for Input {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            input(&cx)
+                .type_("checkbox")
+                .required(self.0)
+                .readonly(self.0)
+                .indeterminate(self.0)
+                .autocomplete("off")
+                .finish()
+        }
+    }
+
+    let property =
+        |node: &web_sys::Node, name: &str| js_sys::Reflect::get(node, &name.into()).unwrap();
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Input(true));
+    let node = container.first_child().unwrap();
+    assert_eq!(property(&node, "required").as_bool(), Some(true));
+    assert_eq!(property(&node, "readOnly").as_bool(), Some(true));
+    assert_eq!(property(&node, "indeterminate").as_bool(), Some(true));
+    assert_eq!(
+        property(&node, "autocomplete").as_string().as_deref(),
+        Some("off")
+    );
+
+    vdom.weak()
+        .set_component(Box::new(Input(false)))
+        .await
+        .unwrap();
+    assert_eq!(property(&node, "required").as_bool(), Some(false));
+    assert_eq!(property(&node, "readOnly").as_bool(), Some(false));
+    assert_eq!(property(&node, "indeterminate").as_bool(), Some(false));
+}
+
+#[wasm_bindgen_test]
+async fn selection_range_is_restored_after_setting_the_value() {
+    struct Masked(&'static str, u32, u32);
+
+    impl<'a> Render<'a> for Masked {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            input(&cx)
+                .value(self.0)
+                .selection_range(self.1, self.2)
+                .finish()
+        }
+    }
+
+    let selection = |node: &web_sys::Node| {
+        let get = |name: &str| {
+            js_sys::Reflect::get(node, &name.into())
+                .unwrap()
+                .as_f64()
+                .unwrap()
+        };
+        (get("selectionStart"), get("selectionEnd"))
+    };
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Masked("555-01", 3, 3));
+    let node = container.first_child().unwrap();
+    assert_eq!(selection(&node), (3.0, 3.0));
+
+    vdom.weak()
+        .set_component(Box::new(Masked("555-0123", 2, 5)))
+        .await
+        .unwrap();
+    assert_eq!(
+        js_sys::Reflect::get(&node, &"value".into())
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some("555-0123")
+    );
+    assert_eq!(selection(&node), (2.0, 5.0));
+}
+
+#[wasm_bindgen_test]
+async fn toggling_a_fieldset_only_sets_its_own_disabled_attribute() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Settings(false));
+    let fieldset = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    let controls = fieldset.query_selector_all("input, button").unwrap();
+
+    for &disabled in &[true, false] {
+        vdom.weak()
+            .set_component(Box::new(Settings(disabled)))
+            .await
+            .unwrap();
+        // Just the fieldset's `disabled` attribute.
+        assert_eq!(vdom.last_diff_op_count(), 1);
+        assert_eq!(fieldset.has_attribute("disabled"), disabled);
+
+        for i in 0..controls.length() {
+            let control = controls
+                .item(i)
+                .unwrap()
+                .unchecked_into::<web_sys::Element>();
+            assert!(!control.has_attribute("disabled"));
+            assert_eq!(control.matches(":disabled").unwrap(), disabled);
+        }
+    }
+}
+
+/// Two popovers, and a button that toggles the one whose id is `self.0`.
+struct Popovers(&'static str);
+
+impl<'a> Render<'a> for Popovers {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        div(&cx)
+            .children([
+                div(&cx).attr("id", "p").attr("popover", "").finish(),
+                div(&cx).attr("id", "q").attr("popover", "").finish(),
+                button(&cx)
+                    .popover_target(self.0)
+                    .children([text("Toggle")])
+                    .finish(),
+            ])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn changing_popovertarget_reassociates_the_invoker() {
+    let container = create_element("div");
+    // Popover targets are only resolved by id within a document.
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .body()
+        .unwrap()
+        .append_child(&container)
+        .unwrap();
+
+    let vdom = Vdom::new(&container, Popovers("p"));
+    let button = container.query_selector("button").unwrap().unwrap();
+    // `popoverTargetElement` is only there in browsers that support popovers.
+    let target_id = || {
+        let target = js_sys::Reflect::get(&button, &"popoverTargetElement".into()).unwrap();
+        if target.is_undefined() {
+            return None;
+        }
+        Some(
+            target
+                .dyn_into::<web_sys::Element>()
+                .map(|target| target.id())
+                .unwrap_or_default(),
+        )
+    };
+    assert_eq!(button.get_attribute("popovertarget").as_deref(), Some("p"));
+    assert!(target_id().map_or(true, |id| id == "p"));
+
+    vdom.weak()
+        .set_component(Box::new(Popovers("q")))
+        .await
+        .unwrap();
+    assert_eq!(button.get_attribute("popovertarget").as_deref(), Some("q"));
+    assert!(target_id().map_or(true, |id| id == "q"));
+    // Move to the button and set its `popovertarget`.
+    assert_eq!(vdom.last_diff_op_count(), 2);
+
+    container.remove();
+}
+
+/// A `<progress>` bar that is `self.0` done, or indeterminate if `None`.
+struct Upload(Option<f64>);
+
+impl<'a> Render<'a> for Upload {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut bar = progress(&cx).typed_attr("max", 1);
+        if let Some(done) = self.0 {
+            bar = bar.value_as_number(done);
+        }
+        bar.finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn progress_value_is_updated_as_a_number_property() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Upload(Some(0.25)));
+    let bar = container.first_child().unwrap();
+    let property = |name: &str| js_sys::Reflect::get(&bar, &name.into()).unwrap().as_f64();
+    assert_eq!(property("value"), Some(0.25));
+
+    vdom.weak()
+        .set_component(Box::new(Upload(Some(0.75))))
+        .await
+        .unwrap();
+    assert_eq!(property("value"), Some(0.75));
+
+    // Removing the value makes the bar indeterminate, rather than setting it
+    // to zero.
+    vdom.weak()
+        .set_component(Box::new(Upload(None)))
+        .await
+        .unwrap();
+    let bar: &web_sys::Element = bar.unchecked_ref();
+    assert!(!bar.has_attribute("value"));
+    assert_eq!(property("position"), Some(-1.0));
+}
+
+/// A text field with spell checking and translation turned on or off.
+struct Username(bool);
+
+impl<'a> Render<'a> for Username {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        input(&cx)
+            .name("username")
+            .spellcheck(self.0)
+            .translate(self.0)
+            .autocapitalize("off")
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn spellcheck_and_translate_properties_follow_their_attributes() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Username(false));
+    let field = container.first_child().unwrap();
+    let property = |name: &str| js_sys::Reflect::get(&field, &name.into()).unwrap();
+    assert_eq!(property("spellcheck").as_bool(), Some(false));
+    assert_eq!(property("translate").as_bool(), Some(false));
+
+    vdom.weak()
+        .set_component(Box::new(Username(true)))
+        .await
+        .unwrap();
+    assert_eq!(property("spellcheck").as_bool(), Some(true));
+    assert_eq!(property("translate").as_bool(), Some(true));
+    let field: &web_sys::Element = field.unchecked_ref();
+    assert_eq!(field.get_attribute("spellcheck").as_deref(), Some("true"));
+    assert_eq!(
+        field.get_attribute("autocapitalize").as_deref(),
+        Some("off")
+    );
+}
+
+/// A volume slider from 0 to 200, which remembers the last value it was
+/// dragged to.
+struct Volume {
+    value: f64,
+    dragged_to: std::rc::Rc<std::cell::Cell<f64>>,
+}
+
+impl<'a> Render<'a> for Volume {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let dragged_to = self.dragged_to.clone();
+        input(&cx)
+            // The value comes before the bounds that allow it.
+            .value_as_number(self.value)
+            .attr("type", "range")
+            .attr("min", "0")
+            .attr("max", "200")
+            .on_input_value(move |_root, _vdom, value| dragged_to.set(value))
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn range_values_are_synced_and_reported_as_numbers() {
+    let dragged_to = std::rc::Rc::new(std::cell::Cell::new(0.0));
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        Volume {
+            value: 150.0,
+            dragged_to: dragged_to.clone(),
+        },
+    );
+    let slider = container.first_child().unwrap();
+    let value = || {
+        js_sys::Reflect::get(&slider, &"value".into())
+            .unwrap()
+            .as_string()
+    };
+    assert_eq!(value().as_deref(), Some("150"));
+
+    // Drag the slider.
+    js_sys::Reflect::set(&slider, &"value".into(), &"42".into()).unwrap();
+    let event = web_sys::Event::new("input").unwrap();
+    slider.dispatch_event(&event).unwrap();
+    assert_eq!(dragged_to.get(), 42.0);
+
+    vdom.weak()
+        .set_component(Box::new(Volume {
+            value: 180.0,
+            dragged_to: dragged_to.clone(),
+        }))
+        .await
+        .unwrap();
+    assert_eq!(value().as_deref(), Some("180"));
+}
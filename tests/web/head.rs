@@ -0,0 +1,105 @@
+use super::{create_element, document};
+use dodrio::{builder::*, bumpalo, Node, Render, RenderContext, Vdom};
+use wasm_bindgen_test::*;
+
+/// A page whose `<title>` is `self.0`, when `self.1` is true.
+struct Page(&'static str, bool);
+
+impl<'a> Render<'a> for Page {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut children = bumpalo::collections::Vec::new_in(cx.bump);
+        if self.1 {
+            children.push(
+                document_head(&cx)
+                    .children([title(&cx)
+                        .id("dodrio-test-title")
+                        .children([text(self.0)])
+                        .finish()])
+                    .finish(),
+            );
+        }
+        children.push(p(&cx).children([text(self.0)]).finish());
+        div(&cx).children(children).finish()
+    }
+}
+
+fn rendered_title() -> Option<String> {
+    document()
+        .query_selector("head > title#dodrio-test-title")
+        .unwrap()
+        .map(|title| title.text_content().unwrap())
+}
+
+#[wasm_bindgen_test]
+async fn document_head_children_update_the_title() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Page("One", true));
+    assert_eq!(rendered_title().as_deref(), Some("One"));
+    assert_eq!(
+        container.inner_html(),
+        "<div><dodrio-head data-dodrio-head=\"\"></dodrio-head><p>One</p></div>"
+    );
+
+    vdom.weak()
+        .set_component(Box::new(Page("Two", true)))
+        .await
+        .unwrap();
+    assert_eq!(rendered_title().as_deref(), Some("Two"));
+
+    vdom.weak()
+        .set_component(Box::new(Page("Two", false)))
+        .await
+        .unwrap();
+    assert_eq!(rendered_title(), None);
+    assert_eq!(container.inner_html(), "<div><p>Two</p></div>");
+}
+
+/// A `<dodrio-head>` element with a title, which is a document head
+/// placeholder only when `self.0` is true.
+struct Portal(bool);
+
+impl<'a> Render<'a> for Portal {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let head = if self.0 {
+            document_head(&cx)
+        } else {
+            ElementBuilder::new(&cx, "dodrio-head")
+        };
+        head.children([title(&cx)
+            .id("dodrio-test-title")
+            .children([text("Portal")])
+            .finish()])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn removing_the_document_head_directive_moves_the_children_back() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Portal(true));
+    assert_eq!(rendered_title().as_deref(), Some("Portal"));
+    assert_eq!(
+        container.inner_html(),
+        "<dodrio-head data-dodrio-head=\"\"></dodrio-head>"
+    );
+
+    vdom.weak()
+        .set_component(Box::new(Portal(false)))
+        .await
+        .unwrap();
+    assert_eq!(rendered_title(), None);
+    assert_eq!(
+        container.inner_html(),
+        "<dodrio-head><title id=\"dodrio-test-title\">Portal</title></dodrio-head>"
+    );
+
+    vdom.weak()
+        .set_component(Box::new(Portal(true)))
+        .await
+        .unwrap();
+    assert_eq!(rendered_title().as_deref(), Some("Portal"));
+    assert_eq!(
+        container.inner_html(),
+        "<dodrio-head data-dodrio-head=\"\"></dodrio-head>"
+    );
+}
@@ -0,0 +1,178 @@
+use super::{before_after, create_element};
+use dodrio::{builder::*, Node, Render, RenderContext, Vdom};
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+/// A 1x1 transparent GIF.
+const PIXEL: &str =
+    "data:image/gif;base64,R0lGODlhAQABAIAAAAAAAP///yH5BAEAAAAALAAAAAABAAEAAAIBRAA7";
+
+struct Image {
+    loads: Rc<Cell<usize>>,
+    errors: Rc<Cell<usize>>,
+}
+
+impl<'a> Render<'a> for Image {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let loads = self.loads.clone();
+        let errors = self.errors.clone();
+        img(&cx)
+            .lazy_loading()
+            .async_decoding()
+            .on_load(move |_root, _vdom, _event| loads.set(loads.get() + 1))
+            .on_error(move |_root, _vdom, _event| errors.set(errors.get() + 1))
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+fn load_and_error_listeners() {
+    let container = create_element("div");
+    let loads = Rc::new(Cell::new(0));
+    let errors = Rc::new(Cell::new(0));
+    let _vdom = Vdom::new(
+        &container,
+        Image {
+            loads: loads.clone(),
+            errors: errors.clone(),
+        },
+    );
+
+    let img = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    assert_eq!(img.get_attribute("loading").as_deref(), Some("lazy"));
+    assert_eq!(img.get_attribute("decoding").as_deref(), Some("async"));
+
+    let fire = |event_type: &str| {
+        let event = web_sys::Event::new(event_type).expect_throw("should create event OK");
+        img.dispatch_event(&event)
+            .expect_throw("should dispatch event OK");
+    };
+
+    fire("load");
+    assert_eq!((loads.get(), errors.get()), (1, 0));
+    fire("error");
+    assert_eq!((loads.get(), errors.get()), (1, 1));
+}
+
+struct DecodedImage {
+    on_decoded: Box<dyn FnMut(web_sys::Element)>,
+}
+
+impl<'a> Render<'a> for DecodedImage {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        img(&cx)
+            .attr("src", PIXEL)
+            .on_decoded(|root, _vdom, img| {
+                (root.unwrap_mut::<DecodedImage>().on_decoded)(img);
+            })
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn decoded_callback_is_called() {
+    let container = create_element("div");
+
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let mut sender = Some(sender);
+
+    let _vdom = Vdom::new(
+        &container,
+        DecodedImage {
+            on_decoded: Box::new(move |img| {
+                sender
+                    .take()
+                    .expect_throw("should only call decoded callback once")
+                    .send(img)
+                    .expect_throw("should not have dropped the receiver");
+            }),
+        },
+    );
+
+    let img = receiver.await.unwrap();
+    assert!(img.is_same_node(container.first_child().as_ref()));
+}
+
+/// A responsive image with a wide and a narrow source, where the wide source
+/// applies above `min_width` pixels.
+fn responsive<'a>(cx: &mut RenderContext<'a>, min_width: &'a str) -> Node<'a> {
+    picture(&cx)
+        .children([
+            source(&cx)
+                .media(min_width)
+                .srcset("wide.png 1x, wide@2x.png 2x")
+                .finish(),
+            source(&cx)
+                .media("(min-width: 0px)")
+                .srcset("narrow.png")
+                .finish(),
+            img(&cx).src(PIXEL).alt("A responsive image").finish(),
+        ])
+        .finish()
+}
+
+before_after! {
+    picture_source_media_changed {
+        before(cx) {
+            responsive(cx, "(min-width: 800px)")
+        }
+        after(cx) {
+            responsive(cx, "(min-width: 1200px)")
+        }
+    }
+
+    picture_source_added {
+        before(cx) {
+            picture(&cx)
+                .children([img(&cx).src(PIXEL).finish()])
+                .finish()
+        }
+        after(cx) {
+            responsive(cx, "(min-width: 800px)")
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+async fn picture_sources_are_created_before_the_img_and_diffed_minimally() {
+    struct Responsive(&'static str);
+
+    impl<'a> Render<'a> for Responsive {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            responsive(cx, self.0)
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Responsive("(min-width: 800px)"));
+
+    let picture = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    let children = picture.children();
+    let tag_names: Vec<_> = (0..children.length())
+        .map(|i| children.item(i).unwrap().tag_name())
+        .collect();
+    assert_eq!(tag_names, ["SOURCE", "SOURCE", "IMG"]);
+    let wide = children.item(0).unwrap();
+
+    vdom.weak()
+        .set_component(Box::new(Responsive("(min-width: 1200px)")))
+        .await
+        .unwrap();
+    assert!(wide.is_same_node(picture.first_child().as_ref()));
+    assert_eq!(
+        wide.get_attribute("media").as_deref(),
+        Some("(min-width: 1200px)")
+    );
+    // Move to the first `<source>` and set its media query. The other source
+    // and the `<img>` are left alone.
+    assert_eq!(vdom.last_diff_op_count(), 2);
+}
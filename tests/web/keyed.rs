@@ -1,5 +1,5 @@
 use crate::{assert_rendered, create_element};
-use dodrio::{builder::*, bumpalo, Node, Render, RenderContext, Vdom};
+use dodrio::{builder::*, bumpalo, replay, Node, Render, RenderContext, Vdom};
 use log::*;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
@@ -31,6 +31,44 @@ where
     parent.finish()
 }
 
+fn keyed_with_header_and_footer<'a, Keys>(cx: &mut RenderContext<'a>, keys: Keys) -> Node<'a>
+where
+    Keys: AsRef<[u16]>,
+{
+    let mut parent = div(&cx)
+        .attr("class", "parent")
+        .child(header(&cx).children([text("header")]).finish());
+
+    for &k in keys.as_ref() {
+        parent = parent.child(Keyed(k).render(cx));
+    }
+
+    parent
+        .child(footer(&cx).children([text("footer")]).finish())
+        .finish()
+}
+
+/// A list keyed by `u64` ids too large to fit in a `u32` key.
+fn keyed_by_id<'a, Ids>(cx: &mut RenderContext<'a>, ids: Ids) -> Node<'a>
+where
+    Ids: AsRef<[u64]>,
+{
+    let mut parent = div(&cx).attr("class", "parent");
+
+    for &id in ids.as_ref() {
+        let id_attr = bumpalo::format!(in cx.bump, "{}", id).into_bump_str();
+        parent = parent.child(
+            div(&cx)
+                .attr("class", "keyed")
+                .attr("id", id_attr)
+                .key_hashed(&id)
+                .finish(),
+        );
+    }
+
+    parent.finish()
+}
+
 async fn assert_keyed<Before, After>(before: Before, after: After) -> Result<(), JsValue>
 where
     Before: 'static + for<'a> Render<'a>,
@@ -266,4 +304,93 @@ keyed_tests! {
                 .finish()
         }
     }
+
+    hashed_u64_keys_reorder {
+        before(cx) {
+            keyed_by_id(cx, [1 << 40, (1 << 40) + 1, u64::MAX, 7])
+        }
+        after(cx) {
+            keyed_by_id(cx, [7, u64::MAX, 1 << 40, (1 << 40) + 1])
+        }
+    }
+
+    mixed_keyed_and_unkeyed_reorder_middle {
+        before(cx) {
+            keyed_with_header_and_footer(cx, [1, 2])
+        }
+        after(cx) {
+            keyed_with_header_and_footer(cx, [2, 1])
+        }
+    }
+
+    mixed_keyed_and_unkeyed_new_keys {
+        before(cx) {
+            keyed_with_header_and_footer(cx, [1, 2, 3])
+        }
+        after(cx) {
+            keyed_with_header_and_footer(cx, [4, 3, 1])
+        }
+    }
+
+    mixed_keyed_and_unkeyed_remove_all_keyed {
+        before(cx) {
+            keyed_with_header_and_footer(cx, [1, 2, 3])
+        }
+        after(cx) {
+            keyed_with_header_and_footer(cx, Vec::<u16>::new())
+        }
+    }
+}
+
+/// A list of keyed items where the item being edited is an `<input>`, and the
+/// rest are `<span>`s.
+fn editable<'a>(b: &'a bumpalo::Bump, editing: u32) -> Node<'a> {
+    let mut list = div(b).attr("class", "parent");
+    for key in 1..=3 {
+        let item = if key == editing {
+            input(b).attr("value", "editing").key(key).finish()
+        } else {
+            span(b).children([text("item")]).key(key).finish()
+        };
+        list = list.child(item);
+    }
+    list.finish()
+}
+
+#[wasm_bindgen_test]
+async fn changing_a_keyed_items_tag_replaces_it_in_place() {
+    let b = bumpalo::Bump::new();
+    let ops = replay::record_ops(&editable(&b, 0), &editable(&b, 2));
+    assert!(ops.contains(&replay::ChangeOp::CreateElement("input".into())));
+    assert!(ops.contains(&replay::ChangeOp::ReplaceWith));
+    for op in &ops {
+        match op {
+            replay::ChangeOp::RemoveChild(_)
+            | replay::ChangeOp::AppendChild
+            | replay::ChangeOp::InsertBefore
+            | replay::ChangeOp::RemoveSelfAndNextSiblings => {
+                panic!("should replace in place, found {:?}", op)
+            }
+            _ => {}
+        }
+    }
+
+    let editing = Rc::new(std::cell::Cell::new(0));
+    let container = create_element("div");
+    let vdom = Vdom::with_render_fn(&container, {
+        let editing = editing.clone();
+        move |cx| editable(cx.bump, editing.get())
+    });
+    let list = container.first_element_child().unwrap();
+    let first = list.first_element_child().unwrap();
+    let last = list.last_element_child().unwrap();
+
+    editing.set(2);
+    vdom.weak().render().await.unwrap();
+
+    let children = list.children();
+    assert_eq!(children.length(), 3);
+    assert!(first.is_same_node(children.item(0).as_deref()));
+    assert_eq!(children.item(1).unwrap().tag_name(), "INPUT");
+    assert!(last.is_same_node(children.item(2).as_deref()));
 }
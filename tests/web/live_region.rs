@@ -0,0 +1,64 @@
+use super::create_element;
+use dodrio::{builder::*, LiveRegion, Node, Politeness, Render, RenderContext, Vdom};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+/// A button next to a live region.
+struct Saver(LiveRegion);
+
+impl<'a> Render<'a> for Saver {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        div(&cx)
+            .children([
+                button(&cx).children([text("Save")]).finish(),
+                self.0.render(cx),
+            ])
+            .finish()
+    }
+}
+
+// Get the text node inside the region with the given `aria-live` value.
+fn region_text(container: &web_sys::Element, live: &str) -> web_sys::Node {
+    container
+        .query_selector(&format!("[aria-live={}]", live))
+        .unwrap()
+        .unwrap()
+        .first_child()
+        .unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn announce_updates_the_live_region_text_node() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Saver(LiveRegion::new()));
+    let polite = region_text(&container, "polite");
+    let assertive = region_text(&container, "assertive");
+    assert_eq!(polite.text_content().unwrap(), "");
+
+    let weak = vdom.weak();
+    let announce = |message: &'static str, politeness| {
+        weak.with_component(move |root| {
+            root.unwrap_mut::<Saver>().0.announce(message, politeness);
+        })
+    };
+
+    announce("Saved", Politeness::Polite).await.unwrap();
+    weak.render().await.unwrap();
+    assert_eq!(polite.text_content().unwrap(), "Saved");
+    assert_eq!(assertive.text_content().unwrap(), "");
+    // The text node is updated in place, not replaced.
+    assert!(polite.is_same_node(Some(&region_text(&container, "polite"))));
+
+    // Announcing the same message again still changes the text.
+    announce("Saved", Politeness::Polite).await.unwrap();
+    weak.render().await.unwrap();
+    assert_eq!(polite.text_content().unwrap(), "Saved\u{a0}");
+
+    announce("Failed", Politeness::Assertive).await.unwrap();
+    weak.render().await.unwrap();
+    assert_eq!(polite.text_content().unwrap(), "");
+    assert_eq!(assertive.text_content().unwrap(), "Failed");
+    let region = assertive.parent_node().unwrap();
+    let region: &web_sys::Element = region.unchecked_ref();
+    assert_eq!(region.get_attribute("aria-atomic").as_deref(), Some("true"));
+}
@@ -4,7 +4,8 @@
 
 use bumpalo::Bump;
 use dodrio::{
-    Attribute, CachedSet, ElementNode, Node, NodeKind, Render, RenderContext, TextNode, Vdom,
+    Attribute, AttributeValue, CachedSet, ElementNode, Node, NodeKind, RawNode, Render,
+    RenderContext, TextNode, Vdom,
 };
 use fxhash::FxHashMap;
 use log::*;
@@ -17,10 +18,26 @@ use wasm_bindgen_test::*;
 wasm_bindgen_test_configure!(run_in_browser);
 
 pub mod cached;
+pub mod custom_elements;
 pub mod events;
+pub mod forms;
+pub mod head;
+pub mod images;
 pub mod js_api;
 pub mod keyed;
+pub mod live_region;
+pub mod media;
+pub mod mount;
+pub mod observer;
+pub mod patch;
 pub mod render;
+pub mod replay;
+pub mod roving;
+pub mod scroll;
+pub mod style;
+pub mod tables;
+pub mod transitions;
+pub mod userdata;
 
 pub fn window() -> web_sys::Window {
     web_sys::window().expect("no global `window` exists")
@@ -115,24 +132,48 @@ pub fn assert_rendered<R: for<'a> Render<'a>>(container: &web_sys::Element, r: &
                 let (expected, _template) = cached_set.get(c.id);
                 check_node(cached_set, actual, &expected);
             }
+            NodeKind::Raw(RawNode { node }) => {
+                assert!(
+                    actual.is_same_node(Some(node)),
+                    "actual.is_same_node(expected.node)"
+                );
+            }
         }
     }
 
     fn check_attributes(actual: web_sys::NamedNodeMap, expected: &[Attribute]) {
+        // `false` booleans, `JsValue`s, and customized built-in elements' `is`
+        // values are not reflected as DOM attributes.
+        let expected: Vec<(&str, String)> = expected
+            .iter()
+            .filter(|attr| attr.name() != "is")
+            .filter_map(|attr| {
+                let name = attr.name();
+                let value = match attr.value() {
+                    AttributeValue::Str(value) => value.to_string(),
+                    AttributeValue::Bool(true) => String::new(),
+                    AttributeValue::Number(n) => n.to_string(),
+                    // `false` and `JsValue` attributes are not set as
+                    // attributes at all.
+                    _ => return None,
+                };
+                Some((name, value))
+            })
+            .collect();
         assert_eq!(
             actual.length(),
             expected.len() as u32,
             "actual's number of attributes == expected's number of attributes"
         );
-        for attr in expected {
+        for (name, value) in expected {
             let actual_attr = actual
-                .get_named_item(attr.name())
-                .expect(&format!("should have attribute \"{}\"", attr.name()));
+                .get_named_item(name)
+                .expect(&format!("should have attribute \"{}\"", name));
             assert_eq!(
                 actual_attr.value(),
-                attr.value(),
+                value,
                 "actual attr value == expected attr value for attr \"{}\"",
-                attr.name()
+                name
             );
         }
     }
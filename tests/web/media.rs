@@ -0,0 +1,83 @@
+use super::create_element;
+use dodrio::{builder::*, Node, Render, RenderContext, Vdom};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+
+/// A `data:` URL for a WAV file holding one second of silence.
+fn silence() -> String {
+    const SAMPLE_RATE: u32 = 8000;
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + SAMPLE_RATE).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    // Uncompressed, mono, 8-bit samples.
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&8_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.resize(wav.len() + SAMPLE_RATE as usize, 128);
+
+    let binary: String = wav.iter().map(|&byte| char::from(byte)).collect();
+    let base64 = web_sys::window().unwrap().btoa(&binary).unwrap();
+    format!("data:audio/wav;base64,{}", base64)
+}
+
+struct Player {
+    src: String,
+    volume: f64,
+}
+
+impl<'a> Render<'a> for Player {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        // A new copy of the same `src` on every render.
+        let src = cx.alloc_str(&self.src);
+        audio(&cx)
+            .attr("src", src)
+            .attr("preload", "auto")
+            .volume(self.volume)
+            .finish()
+    }
+}
+
+fn property(node: &JsValue, name: &str) -> JsValue {
+    js_sys::Reflect::get(node, &name.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+async fn rerendering_with_the_same_src_keeps_the_playback_position() {
+    let src = silence();
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        Player {
+            src: src.clone(),
+            volume: 0.5,
+        },
+    );
+    let player = container.first_child().unwrap();
+    assert_eq!(property(&player, "volume").as_f64(), Some(0.5));
+
+    // Wait for the media to load, and then seek into it.
+    let loaded = js_sys::Promise::new(&mut |resolve, _reject| {
+        if property(&player, "readyState").as_f64() >= Some(1.0) {
+            resolve.call0(&JsValue::null()).unwrap();
+        } else {
+            js_sys::Reflect::set(&player, &"onloadedmetadata".into(), &resolve).unwrap();
+        }
+    });
+    wasm_bindgen_futures::JsFuture::from(loaded).await.unwrap();
+    js_sys::Reflect::set(&player, &"currentTime".into(), &0.5.into()).unwrap();
+
+    vdom.weak()
+        .set_component(Box::new(Player { src, volume: 0.25 }))
+        .await
+        .unwrap();
+    assert!(player.is_same_node(container.first_child().as_ref()));
+    assert_eq!(property(&player, "currentTime").as_f64(), Some(0.5));
+    assert_eq!(property(&player, "volume").as_f64(), Some(0.25));
+}
@@ -0,0 +1,255 @@
+use super::create_element;
+use dodrio::{builder::*, DiffMode, MountOptions, Node, Render, RenderContext, Scheduling, Vdom};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+struct Tag(&'static str);
+
+impl<'a> Render<'a> for Tag {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        ElementBuilder::new(cx.bump, self.0)
+            .children([text(self.0)])
+            .finish()
+    }
+}
+
+const EXISTING: &str = "<header>header</header><footer>footer</footer>";
+
+fn container_with_existing_content() -> web_sys::Element {
+    let container = create_element("div");
+    container.set_inner_html(EXISTING);
+    container
+}
+
+#[wasm_bindgen_test]
+fn default_options_clear_the_container() {
+    let container = container_with_existing_content();
+    let vdom = Vdom::new_with_options(&container, Tag("span"), MountOptions::new());
+    assert_eq!(container.inner_html(), "<span>span</span>");
+
+    drop(vdom);
+    assert_eq!(container.inner_html(), "");
+}
+
+#[wasm_bindgen_test]
+fn preserve_existing_content_appends() {
+    let container = container_with_existing_content();
+    let options = MountOptions::new().preserve_existing_content(true);
+    let vdom = Vdom::new_with_options(&container, Tag("span"), options);
+    assert_eq!(
+        container.inner_html(),
+        "<header>header</header><footer>footer</footer><span>span</span>"
+    );
+
+    drop(vdom);
+    assert_eq!(container.inner_html(), EXISTING);
+}
+
+#[wasm_bindgen_test]
+async fn before_inserts_before_the_given_child() {
+    let container = container_with_existing_content();
+    let footer = container.last_child().unwrap();
+    let options = MountOptions::new().before(footer);
+    let vdom = Vdom::new_with_options(&container, Tag("span"), options);
+    assert_eq!(
+        container.inner_html(),
+        "<header>header</header><span>span</span><footer>footer</footer>"
+    );
+
+    vdom.weak()
+        .set_component(Box::new(Tag("em")))
+        .await
+        .unwrap();
+    assert_eq!(
+        container.inner_html(),
+        "<header>header</header><em>em</em><footer>footer</footer>"
+    );
+
+    drop(vdom);
+    assert_eq!(container.inner_html(), EXISTING);
+}
+
+#[wasm_bindgen_test]
+fn before_preserves_existing_content_even_if_told_not_to() {
+    let container = container_with_existing_content();
+    let footer = container.last_child().unwrap();
+    let options = MountOptions::new()
+        .preserve_existing_content(false)
+        .before(footer);
+    let _vdom = Vdom::new_with_options(&container, Tag("span"), options);
+    assert_eq!(
+        container.inner_html(),
+        "<header>header</header><span>span</span><footer>footer</footer>"
+    );
+}
+
+#[wasm_bindgen_test]
+async fn microtask_scheduling_renders() {
+    for &preserve in &[false, true] {
+        let container = container_with_existing_content();
+        let options = MountOptions::new()
+            .preserve_existing_content(preserve)
+            .scheduling(Scheduling::Microtask);
+        let vdom = Vdom::new_with_options(&container, Tag("span"), options);
+
+        vdom.weak()
+            .set_component(Box::new(Tag("em")))
+            .await
+            .unwrap();
+        let expected = if preserve {
+            "<header>header</header><footer>footer</footer><em>em</em>"
+        } else {
+            "<em>em</em>"
+        };
+        assert_eq!(container.inner_html(), expected);
+    }
+}
+
+// A mock `requestIdleCallback` that queues its callbacks until its static
+// `idle()` method is called.
+fn mock_request_idle_callback() -> JsValue {
+    js_sys::Function::new_no_args(
+        "
+        const queue = [];
+        function requestIdleCallback(callback) {
+            queue.push(callback);
+            return queue.length;
+        }
+        requestIdleCallback.pending = () => queue.length;
+        requestIdleCallback.idle = () => {
+            for (const callback of queue.splice(0)) {
+                callback({ didTimeout: false, timeRemaining: () => 50 });
+            }
+        };
+        return requestIdleCallback;
+        ",
+    )
+    .call0(&JsValue::NULL)
+    .unwrap_throw()
+}
+
+#[wasm_bindgen_test]
+async fn idle_scheduling_renders_when_idle() {
+    let window: JsValue = web_sys::window().unwrap_throw().into();
+    let original = js_sys::Reflect::get(&window, &"requestIdleCallback".into()).unwrap_throw();
+    let mock = mock_request_idle_callback();
+    js_sys::Reflect::set(&window, &"requestIdleCallback".into(), &mock).unwrap_throw();
+    let call = |method: &str| {
+        js_sys::Reflect::get(&mock, &method.into())
+            .unwrap_throw()
+            .unchecked_into::<js_sys::Function>()
+            .call0(&JsValue::NULL)
+            .unwrap_throw()
+    };
+
+    let container = create_element("div");
+    let options = MountOptions::new().scheduling(Scheduling::Idle);
+    let vdom = Vdom::new_with_options(&container, Tag("span"), options);
+
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let weak = vdom.weak();
+    wasm_bindgen_futures::spawn_local(async move {
+        weak.set_component(Box::new(Tag("em"))).await.unwrap();
+        sender.send(()).unwrap();
+    });
+
+    // Let the new component be set and its render be scheduled.
+    for _ in 0..5 {
+        let _ = JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL)).await;
+    }
+    assert_eq!(call("pending").as_f64(), Some(1.0));
+    assert_eq!(container.inner_html(), "<span>span</span>");
+
+    call("idle");
+    receiver.await.unwrap();
+    assert_eq!(container.inner_html(), "<em>em</em>");
+
+    js_sys::Reflect::set(&window, &"requestIdleCallback".into(), &original).unwrap_throw();
+}
+
+/// Two `<li>`s with different attributes, inside a `<ul>`.
+struct Items;
+
+impl<'a> Render<'a> for Items {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        ul(&cx)
+            .children([
+                li(&cx).attr("class", "a").children([text("a")]).finish(),
+                li(&cx).attr("id", "b").children([text("b")]).finish(),
+            ])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+fn cloned_elements_are_created_the_same_as_new_elements() {
+    let created = create_element("div");
+    let _created_vdom = Vdom::new(&created, Items);
+
+    let cloned = create_element("div");
+    let options = MountOptions::new().clone_elements(true);
+    let _cloned_vdom = Vdom::new_with_options(&cloned, Items, options);
+
+    assert_eq!(cloned.inner_html(), created.inner_html());
+    let list = cloned.first_element_child().unwrap();
+    assert!(!list
+        .first_child()
+        .unwrap()
+        .is_same_node(list.last_child().as_ref()));
+}
+
+/// A list of `self.0` items, each with a click listener.
+struct Clickable(&'static [&'static str]);
+
+impl<'a> Render<'a> for Clickable {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut list = ul(&cx);
+        for item in self.0 {
+            list = list.child(
+                li(&cx)
+                    .on("click", |_root, _vdom, _event| {})
+                    .children([text(item)])
+                    .finish(),
+            );
+        }
+        list.finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn lenient_mode_recovers_from_a_modified_dom() {
+    let container = create_element("div");
+    let options = MountOptions::new().diff_mode(DiffMode::Lenient);
+    let vdom = Vdom::new_with_options(&container, Clickable(&["a", "b", "c"]), options);
+    assert_eq!(vdom.listener_count(), 3);
+
+    // Something else replaces the second item and removes the third.
+    let list = container.first_element_child().unwrap();
+    let item = list.children().item(1).unwrap();
+    item.replace_with_with_str_1("not an item").unwrap();
+    list.last_element_child().unwrap().remove();
+
+    vdom.weak()
+        .set_component(Box::new(Clickable(&["a", "B", "c"])))
+        .await
+        .unwrap();
+    assert_eq!(
+        container.inner_html(),
+        "<ul><li>a</li><li>B</li><li>c</li></ul>"
+    );
+    assert_eq!(vdom.listener_count(), 3);
+
+    // Once recovered, renders diff as usual again.
+    vdom.weak()
+        .set_component(Box::new(Clickable(&["a", "B", "C"])))
+        .await
+        .unwrap();
+    assert_eq!(
+        container.inner_html(),
+        "<ul><li>a</li><li>B</li><li>C</li></ul>"
+    );
+    // Move to the last item's text and set it.
+    assert_eq!(vdom.last_diff_op_count(), 3);
+}
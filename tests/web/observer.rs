@@ -0,0 +1,343 @@
+use super::{create_element, RenderFn};
+use dodrio::{
+    builder::*, diff_snapshots, AttributeChange, AttributeValue, ChangeKind, Node, RenderContext,
+    SnapshotDifference, Vdom, VdomSnapshot,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen_test::*;
+
+fn list<'a>(cx: &mut RenderContext<'a>, keys: &[u32]) -> Node<'a> {
+    let mut items = dodrio::bumpalo::collections::Vec::new_in(cx.bump);
+    for &key in keys {
+        items.push(li(&cx).key(key).finish());
+    }
+    ul(&cx).children(items).finish()
+}
+
+#[wasm_bindgen_test]
+async fn observer_is_notified_of_created_and_removed_nodes() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| list(cx, &[1, 2, 3])));
+
+    let changes = Rc::new(RefCell::new(vec![]));
+    vdom.set_diff_observer({
+        let changes = changes.clone();
+        move |path: &[usize], kind| changes.borrow_mut().push((path.to_vec(), kind))
+    });
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| list(cx, &[1, 3, 4]))))
+        .await
+        .unwrap();
+
+    let changes = changes.borrow();
+    assert!(changes.contains(&(vec![1], ChangeKind::Removed)));
+    assert!(changes.contains(&(vec![2], ChangeKind::Created)));
+    assert!(!changes.iter().any(|(path, _)| path.is_empty()));
+}
+
+fn morph<'a>(cx: &mut RenderContext<'a>, d: &'a str) -> Node<'a> {
+    ElementBuilder::new(cx.bump, "svg")
+        .namespace(Some("http://www.w3.org/2000/svg"))
+        .children([ElementBuilder::new(cx.bump, "path")
+            .namespace(Some("http://www.w3.org/2000/svg"))
+            .attr("d", d)
+            .finish()])
+        .finish()
+}
+
+#[wasm_bindgen_test]
+async fn attribute_observer_is_notified_of_old_and_new_values() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| morph(cx, "M 0 0 L 10 10")));
+
+    let changes = Rc::new(RefCell::new(vec![]));
+    vdom.set_attribute_observer({
+        let changes = changes.clone();
+        move |path: &[usize], change: AttributeChange| {
+            let value = |v: Option<AttributeValue>| v.and_then(|v| v.as_str()).map(String::from);
+            changes.borrow_mut().push((
+                path.to_vec(),
+                change.name.to_string(),
+                value(change.old),
+                value(change.new),
+            ))
+        }
+    });
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| morph(cx, "M 0 0 L 20 5"))))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *changes.borrow(),
+        [(
+            vec![0],
+            "d".to_string(),
+            Some("M 0 0 L 10 10".to_string()),
+            Some("M 0 0 L 20 5".to_string()),
+        )]
+    );
+}
+
+fn field<'a>(cx: &mut RenderContext<'a>, attrs: &[(&'a str, &'a str)]) -> Node<'a> {
+    let mut field = input(&cx);
+    for &(name, value) in attrs {
+        field = field.attr(name, value);
+    }
+    form(&cx).children([field.finish()]).finish()
+}
+
+#[wasm_bindgen_test]
+async fn attribute_observer_is_notified_of_added_and_removed_attributes() {
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        RenderFn(|cx| field(cx, &[("name", "email"), ("placeholder", "Email")])),
+    );
+
+    let changes = Rc::new(RefCell::new(vec![]));
+    vdom.set_attribute_observer({
+        let changes = changes.clone();
+        move |path: &[usize], change: AttributeChange| {
+            let value = |v: Option<AttributeValue>| v.and_then(|v| v.as_str()).map(String::from);
+            changes.borrow_mut().push((
+                path.to_vec(),
+                change.name.to_string(),
+                value(change.old),
+                value(change.new),
+            ))
+        }
+    });
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| {
+            field(cx, &[("name", "login"), ("required", "")])
+        })))
+        .await
+        .unwrap();
+
+    let mut changes = changes.borrow().clone();
+    changes.sort();
+    assert_eq!(
+        changes,
+        [
+            (
+                vec![0],
+                "name".to_string(),
+                Some("email".to_string()),
+                Some("login".to_string()),
+            ),
+            (
+                vec![0],
+                "placeholder".to_string(),
+                Some("Email".to_string()),
+                None,
+            ),
+            (vec![0], "required".to_string(), None, Some(String::new())),
+        ]
+    );
+}
+
+#[wasm_bindgen_test]
+async fn keyed_reorders_only_move_nodes_outside_the_longest_increasing_subsequence() {
+    let container = create_element("div");
+    let forward: Vec<u32> = (0..100).collect();
+    let vdom = Vdom::new(&container, RenderFn(move |cx| list(cx, &forward)));
+
+    let moves = Rc::new(RefCell::new(0));
+    vdom.set_diff_observer({
+        let moves = moves.clone();
+        move |_path: &[usize], kind| {
+            if kind == ChangeKind::Moved {
+                *moves.borrow_mut() += 1;
+            }
+        }
+    });
+
+    // Naively moving every child into place would take 100 moves. A reversal
+    // has no increasing subsequence longer than one child, so that one child
+    // stays put and everything else moves around it.
+    let reversed: Vec<u32> = (0..100).rev().collect();
+    vdom.weak()
+        .set_component(Box::new(RenderFn(move |cx| list(cx, &reversed))))
+        .await
+        .unwrap();
+    assert_eq!(*moves.borrow(), 99);
+
+    // Rotating the last child to the front leaves the other 99 children in
+    // order, so only one of them needs to move.
+    *moves.borrow_mut() = 0;
+    let rotated: Vec<u32> = Some(0).into_iter().chain((1..100).rev()).collect();
+    vdom.weak()
+        .set_component(Box::new(RenderFn(move |cx| list(cx, &rotated))))
+        .await
+        .unwrap();
+    assert_eq!(*moves.borrow(), 1);
+}
+
+fn status<'a>(cx: &mut RenderContext<'a>, class: &'a str, label: &'a str) -> Node<'a> {
+    div(&cx)
+        .key(7)
+        .attr("class", class)
+        .children([span(&cx).children([text(label)]).finish()])
+        .finish()
+}
+
+#[wasm_bindgen_test]
+async fn snapshots_describe_what_changed_between_renders() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| status(cx, "idle", "Idle")));
+
+    let before = vdom.snapshot();
+    assert_eq!(
+        before,
+        VdomSnapshot::Element {
+            tag_name: "div".into(),
+            namespace: None,
+            key: Some(7),
+            attributes: vec![("class".into(), "idle".into())],
+            listeners: vec![],
+            children: vec![VdomSnapshot::Element {
+                tag_name: "span".into(),
+                namespace: None,
+                key: None,
+                attributes: vec![],
+                listeners: vec![],
+                children: vec![VdomSnapshot::Text("Idle".into())],
+            }],
+        }
+    );
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| status(cx, "busy", "Busy"))))
+        .await
+        .unwrap();
+    let after = vdom.snapshot();
+
+    assert_eq!(
+        diff_snapshots(&before, &after),
+        [
+            SnapshotDifference::AttributeChanged {
+                path: vec![],
+                name: "class".into(),
+                old: Some("idle".into()),
+                new: Some("busy".into()),
+            },
+            SnapshotDifference::TextChanged {
+                path: vec![0],
+                old: "Idle".into(),
+                new: "Busy".into(),
+            },
+        ]
+    );
+    assert!(diff_snapshots(&after, &after).is_empty());
+}
+
+fn configured<'a>(cx: &mut RenderContext<'a>, config: &'a str) -> Node<'a> {
+    div(&cx).attr("data-config", config).finish()
+}
+
+// Are the given flat JSON objects the same, ignoring the order of their keys?
+fn same_json_object(old: &str, new: &str) -> bool {
+    let members = |s: &str| {
+        let mut members: Vec<String> = s
+            .trim()
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(',')
+            .map(|member| member.trim().to_string())
+            .collect();
+        members.sort();
+        members
+    };
+    members(old) == members(new)
+}
+
+#[wasm_bindgen_test]
+async fn attribute_comparator_skips_equivalent_values() {
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        RenderFn(|cx| configured(cx, r#"{"a": 1, "b": 2}"#)),
+    );
+    vdom.set_attribute_comparator("data-config", same_json_object);
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| {
+            configured(cx, r#"{"b": 2, "a": 1}"#)
+        })))
+        .await
+        .unwrap();
+    assert_eq!(vdom.last_diff_op_count(), 0);
+    assert_eq!(
+        container.inner_html(),
+        r#"<div data-config="{&quot;a&quot;: 1, &quot;b&quot;: 2}"></div>"#
+    );
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| {
+            configured(cx, r#"{"b": 3, "a": 1}"#)
+        })))
+        .await
+        .unwrap();
+    assert_eq!(
+        container.inner_html(),
+        r#"<div data-config="{&quot;b&quot;: 3, &quot;a&quot;: 1}"></div>"#
+    );
+}
+
+#[cfg(feature = "op-paths")]
+fn greeting<'a>(cx: &mut RenderContext<'a>, name: &'a str) -> Node<'a> {
+    div(&cx)
+        .children([
+            h1(&cx).children([text("Hello")]).finish(),
+            p(&cx).children([text(name)]).finish(),
+        ])
+        .finish()
+}
+
+#[cfg(feature = "op-paths")]
+#[wasm_bindgen_test]
+async fn ops_are_logged_with_the_path_to_the_node_that_caused_them() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| greeting(cx, "Alice")));
+
+    // Capture everything logged to the console while re-rendering.
+    let capture = js_sys::Function::new_no_args(
+        r#"
+        const logged = [];
+        const originals = {};
+        for (const level of ["log", "debug", "info", "trace"]) {
+            originals[level] = console[level];
+            console[level] = (...args) => logged.push(args.join(" "));
+        }
+        return () => {
+            Object.assign(console, originals);
+            return logged;
+        };
+        "#,
+    )
+    .call0(&wasm_bindgen::JsValue::NULL)
+    .unwrap();
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| greeting(cx, "Bob"))))
+        .await
+        .unwrap();
+    let logged = js_sys::Function::from(capture)
+        .call0(&wasm_bindgen::JsValue::NULL)
+        .unwrap();
+    let logged: Vec<String> = js_sys::Array::from(&logged)
+        .iter()
+        .map(|message| message.as_string().unwrap())
+        .collect();
+
+    // The `set_text` is caused by the `<p>`'s text, the second child's first.
+    let set_text = logged
+        .iter()
+        .position(|message| message.contains("emit: set_text(\"Bob\")"))
+        .expect("should log the set_text op");
+    assert!(logged[set_text + 1].contains("caused by the node at [1, 0]"));
+}
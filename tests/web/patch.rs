@@ -0,0 +1,114 @@
+use super::{assert_rendered, create_element};
+use dodrio::{builder::*, bumpalo, ChangeKind, Node, Render, RenderContext, Vdom};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+/// A dashboard of three panels, each showing one of the given values.
+struct Dashboard([u32; 3]);
+
+fn panel<'a>(cx: &mut RenderContext<'a>, value: u32) -> Node<'a> {
+    let value = bumpalo::format!(in cx.bump, "{}", value).into_bump_str();
+    section(&cx)
+        .attr("class", "panel")
+        .children([
+            h2(&cx).children([text("value")]).finish(),
+            p(&cx).children([text(value)]).finish(),
+        ])
+        .finish()
+}
+
+impl<'a> Render<'a> for Dashboard {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let panels = [
+            panel(cx, self.0[0]),
+            panel(cx, self.0[1]),
+            panel(cx, self.0[2]),
+        ];
+        div(&cx)
+            .attr("class", "dashboard")
+            .children([div(&cx).children(panels).finish()])
+            .finish()
+    }
+}
+
+fn panel_element(container: &web_sys::Element, index: usize) -> web_sys::Element {
+    container
+        .query_selector_all(".panel")
+        .unwrap()
+        .item(index as u32)
+        .unwrap()
+        .unchecked_into()
+}
+
+#[wasm_bindgen_test]
+fn patch_nested_subtree() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Dashboard([1, 2, 3]));
+
+    let changes = Rc::new(RefCell::new(vec![]));
+    vdom.set_diff_observer({
+        let changes = changes.clone();
+        move |path: &[usize], kind| changes.borrow_mut().push((path.to_vec(), kind))
+    });
+
+    let first = panel_element(&container, 0);
+    let second = panel_element(&container, 1);
+    let third = panel_element(&container, 2);
+
+    vdom.patch(&[0, 1, 1], |cx| p(&cx).children([text("42")]).finish())
+        .unwrap();
+    assert_rendered(&container, &Dashboard([1, 42, 3]));
+
+    // The patched panel's paragraph is updated in place, and its siblings are
+    // the very same physical DOM nodes, with the same contents.
+    assert!(panel_element(&container, 0).is_same_node(Some(&first)));
+    assert!(panel_element(&container, 1).is_same_node(Some(&second)));
+    assert!(panel_element(&container, 2).is_same_node(Some(&third)));
+    assert_eq!(first.text_content().unwrap(), "value1");
+    assert_eq!(third.text_content().unwrap(), "value3");
+    assert!(changes.borrow().is_empty());
+
+    // Replacing the whole subtree reports its path to the diff observer.
+    vdom.patch(&[0, 1, 1], |cx| div(&cx).children([text("43")]).finish())
+        .unwrap();
+    assert_eq!(
+        *changes.borrow(),
+        [
+            (vec![0, 1, 1], ChangeKind::Removed),
+            (vec![0, 1, 1], ChangeKind::Created),
+        ]
+    );
+    assert!(panel_element(&container, 0).is_same_node(Some(&first)));
+    assert!(panel_element(&container, 2).is_same_node(Some(&third)));
+}
+
+#[wasm_bindgen_test]
+async fn full_render_after_patch() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Dashboard([1, 2, 3]));
+
+    vdom.patch(&[0, 2], |cx| panel(cx, 30)).unwrap();
+    assert_rendered(&container, &Dashboard([1, 2, 30]));
+
+    // The next full render diffs against the patched virtual DOM.
+    vdom.weak()
+        .set_component(Box::new(Dashboard([10, 2, 30])))
+        .await
+        .unwrap();
+    assert_rendered(&container, &Dashboard([10, 2, 30]));
+}
+
+#[wasm_bindgen_test]
+fn patch_with_invalid_path_is_an_error() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Dashboard([1, 2, 3]));
+
+    // Past the last panel, and through the text node in a panel's heading.
+    for path in &[&[0, 3][..], &[0, 0, 0, 0, 0]] {
+        let result = vdom.patch(path, |_cx| unreachable!("should not render"));
+        assert!(result.is_err());
+    }
+    assert_rendered(&container, &Dashboard([1, 2, 3]));
+}
@@ -1,5 +1,6 @@
 use super::{assert_rendered, before_after, create_element, RenderFn};
 use dodrio::{builder::*, bumpalo::collections::String, Node, Render, RenderContext, Vdom};
+use std::fmt::Write;
 use std::rc::Rc;
 use wasm_bindgen::{ JsCast};
 use wasm_bindgen_test::*;
@@ -35,6 +36,36 @@ fn container_is_emptied_upon_drop() {
     assert!(container.first_child().is_none());
 }
 
+#[wasm_bindgen_test]
+async fn unmount_tears_everything_down() {
+    struct Clickable;
+
+    impl<'a> Render<'a> for Clickable {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            button(&cx)
+                .on("click", |_root, _vdom, _event| {
+                    panic!("should not be invoked after unmounting")
+                })
+                .children([text("click me")])
+                .finish()
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Clickable);
+    let weak = vdom.weak();
+    assert!(container.first_child().is_some());
+
+    let component = vdom.unmount();
+    let _: &Clickable = component.unwrap_ref::<Clickable>();
+
+    // All physical DOM nodes are removed...
+    assert!(container.first_child().is_none());
+
+    // ...and the vdom, along with its events registry, is gone.
+    assert!(weak.render().await.is_err());
+}
+
 /// Renders a child with a lifetime scoped to the RenderContext bump arena.
 #[wasm_bindgen_test]
 fn render_bump_scoped_node() {        
@@ -122,7 +153,706 @@ async fn test_svg_set_class() {
     );
 }
 
+/// An SVG `<rect>` filled with the color `self.0`.
+struct Swatch(&'static str);
+
+impl<'a> Render<'a> for Swatch {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        svg(&cx)
+            .children([rect(&cx)
+                .attr("width", "10")
+                .attr("height", "10")
+                .attr("fill", self.0)
+                .finish()])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn svg_presentation_attributes_are_diffed_as_attributes() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Swatch("red"));
+    let rect = container
+        .first_child()
+        .unwrap()
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    assert_eq!(
+        rect.namespace_uri().as_deref(),
+        Some("http://www.w3.org/2000/svg")
+    );
+    assert_eq!(rect.get_attribute("fill").as_deref(), Some("red"));
+
+    // Override the fill with CSS, as a stylesheet or animation would.
+    rect.set_attribute("style", "fill: blue").unwrap();
+
+    vdom.weak()
+        .set_component(Box::new(Swatch("green")))
+        .await
+        .unwrap();
+    assert!(rect.is_same_node(container.first_child().unwrap().first_child().as_ref()));
+    assert_eq!(rect.get_attribute("fill").as_deref(), Some("green"));
+    assert_eq!(rect.get_attribute("style").as_deref(), Some("fill: blue"));
+    // Move to the `<rect>` and set its `fill`.
+    assert_eq!(vdom.last_diff_op_count(), 2);
+
+    vdom.weak()
+        .set_component(Box::new(Swatch("green")))
+        .await
+        .unwrap();
+    assert_eq!(vdom.last_diff_op_count(), 0);
+}
+
+#[wasm_bindgen_test]
+async fn render_fn_is_called_again_on_each_render() {
+    use std::cell::Cell;
+
+    let count = Rc::new(Cell::new(0));
+    let container = create_element("div");
+    let vdom = Vdom::with_render_fn(&container, {
+        let count = count.clone();
+        move |cx| {
+            let label = dodrio::bumpalo::format!(in cx.bump, "count: {}", count.get());
+            p(&cx).children([text(label.into_bump_str())]).finish()
+        }
+    });
+    assert_eq!(container.text_content().as_deref(), Some("count: 0"));
+
+    count.set(1);
+    vdom.weak().render().await.unwrap();
+    assert_eq!(container.text_content().as_deref(), Some("count: 1"));
+
+    count.set(2);
+    vdom.weak().schedule_render();
+    vdom.weak().render().await.unwrap();
+    assert_eq!(container.text_content().as_deref(), Some("count: 2"));
+}
+
+#[wasm_bindgen_test]
+async fn pushed_trees_are_diffed_incrementally() {
+    fn view<'a>(cx: &mut RenderContext<'a>, items: &[&'static str]) -> Node<'a> {
+        let mut list = ul(&cx);
+        for &item in items {
+            list = list.child(li(&cx).children([text(item)]).finish());
+        }
+        list.finish()
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| view(cx, &[])));
+    let list = container.first_child().unwrap();
+
+    vdom.push(|cx| view(cx, &["a", "b"]));
+    assert_rendered(&container, &RenderFn(|cx| view(cx, &["a", "b"])));
+    let first = list.first_child().unwrap();
+
+    // Only the second item's text changes.
+    vdom.push(|cx| view(cx, &["a", "c"]));
+    assert_rendered(&container, &RenderFn(|cx| view(cx, &["a", "c"])));
+    assert!(first.is_same_node(list.first_child().as_ref()));
+    // Move to the second `<li>`, then to its text, and set it.
+    assert_eq!(vdom.last_diff_op_count(), 3);
+
+    vdom.push(|cx| view(cx, &["a", "c", "d"]));
+    assert_rendered(&container, &RenderFn(|cx| view(cx, &["a", "c", "d"])));
+    assert!(first.is_same_node(list.first_child().as_ref()));
+
+    // Later renders re-render the last pushed tree.
+    vdom.weak().render().await.unwrap();
+    assert_rendered(&container, &RenderFn(|cx| view(cx, &["a", "c", "d"])));
+    assert_eq!(vdom.last_diff_op_count(), 0);
+}
+
+/// A container whose children are managed outside of dodrio, titled `self.0`.
+struct Chart(&'static str);
+
+impl<'a> Render<'a> for Chart {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        div(&cx)
+            .attr("title", self.0)
+            .managed_children()
+            .children([span(&cx).children([text(self.0)]).finish()])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn managed_children_are_not_diffed() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Chart("a"));
+    let chart = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    assert_eq!(chart.inner_html(), "<span>a</span>");
+    assert!(!chart.has_attribute("data-dodrio-managed"));
+
+    // Some third-party code takes over the children.
+    chart
+        .append_child(&create_element("canvas"))
+        .expect("should append child OK");
+
+    vdom.weak()
+        .set_component(Box::new(Chart("b")))
+        .await
+        .unwrap();
+    assert_eq!(chart.get_attribute("title").as_deref(), Some("b"));
+    assert_eq!(chart.inner_html(), "<span>a</span><canvas></canvas>");
+    // Just the `title` attribute.
+    assert_eq!(vdom.last_diff_op_count(), 1);
+}
+
+#[wasm_bindgen_test]
+fn successive_render_with() {
+    fn view<'a>(cx: &mut RenderContext<'a>, items: &[&'static str]) -> Node<'a> {
+        let mut list = ul(&cx);
+        for &item in items {
+            list = list.child(li(&cx).children([text(item)]).finish());
+        }
+        list.finish()
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| view(cx, &[])));
+
+    vdom.render_with(|cx| view(cx, &["a", "b"]));
+    assert_rendered(&container, &RenderFn(|cx| view(cx, &["a", "b"])));
+
+    vdom.render_with(|cx| view(cx, &["b", "c", "d"]));
+    assert_rendered(&container, &RenderFn(|cx| view(cx, &["b", "c", "d"])));
+}
+
+/// An uncontrolled input keeps the user's edits across re-renders.
+#[wasm_bindgen_test]
+async fn default_value_is_not_reset_by_re_render() {
+    struct Form(&'static str);
+
+    impl<'a> Render<'a> for Form {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .children([
+                    label(&cx).children([text(self.0)]).finish(),
+                    input(&cx).default_value("initial").finish(),
+                ])
+                .finish()
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Form("before"));
+
+    let input = container.query_selector("input").unwrap().unwrap();
+    assert_eq!(
+        js_sys::Reflect::get(&input, &"value".into())
+            .unwrap()
+            .as_string()
+            .unwrap(),
+        "initial"
+    );
+
+    // Simulate the user editing the input.
+    js_sys::Reflect::set(&input, &"value".into(), &"edited".into()).unwrap();
+
+    vdom.weak()
+        .set_component(Box::new(Form("after")))
+        .await
+        .unwrap();
+
+    assert_eq!(container.text_content().unwrap(), "after");
+    assert_eq!(
+        js_sys::Reflect::get(&input, &"value".into())
+            .unwrap()
+            .as_string()
+            .unwrap(),
+        "edited"
+    );
+}
+
+/// Attributes marked volatile are re-set on every render, even when unchanged.
+#[wasm_bindgen_test]
+async fn volatile_attribute_is_re_set_by_re_render() {
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        RenderFn(|cx| {
+            div(&cx)
+                .children([
+                    span(&cx).attr_volatile("title", "hello").finish(),
+                    span(&cx).attr("title", "hello").finish(),
+                ])
+                .finish()
+        }),
+    );
+
+    // Simulate something else modifying the attributes.
+    let spans = container.query_selector_all("span").unwrap();
+    let span = |i| spans.get(i).unwrap().unchecked_into::<web_sys::Element>();
+    span(0).set_attribute("title", "modified").unwrap();
+    span(1).set_attribute("title", "modified").unwrap();
+
+    vdom.weak().render().await.unwrap();
+
+    assert_eq!(span(0).get_attribute("title").as_deref(), Some("hello"));
+    assert_eq!(span(1).get_attribute("title").as_deref(), Some("modified"));
+}
+
+/// Re-rendering an unchanged component does not touch the physical DOM.
+#[wasm_bindgen_test]
+async fn unchanged_re_render_has_no_ops() {
+    struct Greeting(&'static str);
+
+    impl<'a> Render<'a> for Greeting {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .attr("class", "greeting")
+                .children([
+                    h1(&cx).children([text("Hello")]).finish(),
+                    p(&cx)
+                        .attr("title", self.0)
+                        .children([text(self.0)])
+                        .finish(),
+                ])
+                .finish()
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Greeting("world"));
+    assert!(vdom.last_diff_op_count() > 0);
+
+    vdom.weak().render().await.unwrap();
+    assert_eq!(vdom.last_diff_op_count(), 0);
+
+    vdom.weak()
+        .set_component(Box::new(Greeting("there")))
+        .await
+        .unwrap();
+    // Move to the `<p>`, set its title, move to its text node, and set its
+    // text.
+    assert_eq!(vdom.last_diff_op_count(), 4);
+}
+
+#[wasm_bindgen_test]
+async fn run_of_replaced_siblings_is_replaced_at_once() {
+    struct Items(&'static str);
+
+    impl<'a> Render<'a> for Items {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            let mut items = dodrio::bumpalo::collections::Vec::new_in(cx.bump);
+            for _ in 0..10 {
+                items.push(ElementBuilder::new(cx.bump, self.0).finish());
+            }
+            div(&cx).children(items).finish()
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Items("div"));
+
+    vdom.weak()
+        .set_component(Box::new(Items("span")))
+        .await
+        .unwrap();
+    assert_eq!(
+        container.inner_html(),
+        format!("<div>{}</div>", "<span></span>".repeat(10))
+    );
+    // Replacing each child one by one would take a move, a create, and a
+    // replace per child. Instead, move to the first child, create the ten new
+    // children, and replace all the old ones in one go.
+    assert_eq!(vdom.last_diff_op_count(), 12);
+}
+
+#[wasm_bindgen_test]
+async fn single_text_child_is_updated_in_place() {
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        RenderFn(|cx| p(&cx).children([text("before")]).finish()),
+    );
+    let text_node = container.first_child().unwrap().first_child().unwrap();
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| {
+            p(&cx).children([text("after")]).finish()
+        })))
+        .await
+        .unwrap();
+    assert_eq!(container.inner_html(), "<p>after</p>");
+    let new_text_node = container.first_child().unwrap().first_child();
+    assert!(text_node.is_same_node(new_text_node.as_ref()));
+}
+
+/// A `Vdom` mounted with `mount_append` leaves the container's existing
+/// children alone.
+#[wasm_bindgen_test]
+async fn mount_append_keeps_existing_children() {
+    struct Tag(&'static str);
+
+    impl<'a> Render<'a> for Tag {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            ElementBuilder::new(cx.bump, self.0)
+                .children([text(self.0)])
+                .finish()
+        }
+    }
+
+    let container = create_element("div");
+    container.set_inner_html("<p id=\"existing\">existing</p>");
+    let existing = container.first_child().unwrap();
+
+    let vdom = Vdom::mount_append(&container, Tag("span"));
+    assert!(existing.is_same_node(container.first_child().as_ref()));
+    assert_eq!(
+        container.inner_html(),
+        "<p id=\"existing\">existing</p><span>span</span>"
+    );
+
+    // Replacing the root replaces only our own node.
+    vdom.weak()
+        .set_component(Box::new(Tag("em")))
+        .await
+        .unwrap();
+    assert!(existing.is_same_node(container.first_child().as_ref()));
+    assert_eq!(
+        container.inner_html(),
+        "<p id=\"existing\">existing</p><em>em</em>"
+    );
+
+    vdom.weak()
+        .set_component(Box::new(Tag("strong")))
+        .await
+        .unwrap();
+    assert_eq!(
+        container.inner_html(),
+        "<p id=\"existing\">existing</p><strong>strong</strong>"
+    );
+
+    drop(vdom);
+    assert!(existing.is_same_node(container.first_child().as_ref()));
+    assert_eq!(container.inner_html(), "<p id=\"existing\">existing</p>");
+}
+
+#[wasm_bindgen_test]
+fn on_mount_runs_once_after_initial_render() {
+    let hello = Rc::new(RenderFn(|cx| p(&cx).children([text("hello")]).finish()));
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, hello);
+
+    let calls = Rc::new(std::cell::Cell::new(0));
+    vdom.on_mount({
+        let calls = calls.clone();
+        let container = container.clone();
+        move |_root, _vdom| {
+            calls.set(calls.get() + 1);
+            assert_eq!(container.inner_html(), "<p>hello</p>");
+        }
+    });
+    assert_eq!(calls.get(), 1);
+
+    vdom.weak().schedule_render();
+    assert_eq!(calls.get(), 1);
+
+    // The virtual DOM has already been bootstrapped.
+    vdom.on_mount({
+        let calls = calls.clone();
+        move |_root, _vdom| calls.set(calls.get() + 1)
+    });
+    assert_eq!(calls.get(), 1);
+}
+
+/// A paragraph whose text is computed into strings and vectors that live in
+/// the render's bump arena.
+fn item_count<'a>(cx: &mut RenderContext<'a>, count: usize) -> Node<'a> {
+    let mut label = cx.new_string();
+    write!(label, "{} items", count).unwrap();
+    let mut children = cx.new_vec();
+    children.push(text(label.into_bump_str()));
+    p(&cx).children(children).finish()
+}
+
+/// A static list large enough to be created via HTML rather than node by node.
+fn static_list<'a>(cx: &mut RenderContext<'a>, label: &'a str) -> Node<'a> {
+    let mut items = dodrio::bumpalo::collections::Vec::new_in(cx.bump);
+    for _ in 0..50 {
+        items.push(
+            li(&cx)
+                .attr("title", "\"quoted\" & escaped")
+                .children([text(label), strong(&cx).children([text("<b>")]).finish()])
+                .finish(),
+        );
+    }
+    ul(&cx).attr("class", "static").children(items).finish()
+}
+
+/// A static list with indentation between its items, as it would be written
+/// out by hand. When `on_click` is true, the list has a listener, so that it is
+/// created node by node instead of via HTML.
+fn indented_list<'a>(cx: &mut RenderContext<'a>, on_click: bool) -> Node<'a> {
+    let mut items = dodrio::bumpalo::collections::Vec::new_in(cx.bump);
+    for _ in 0..40 {
+        items.push(text("\n  "));
+        items.push(li(&cx).children([text("item")]).finish());
+    }
+    items.push(text("\n"));
+    let list = ul(&cx).children(items);
+    if on_click {
+        list.on("click", |_, _, _| {}).finish()
+    } else {
+        list.finish()
+    }
+}
+
+/// A layout with a named `header` slot and a default slot, with `header`
+/// content projected into it, if any.
+fn card<'a>(cx: &mut RenderContext<'a>, header_content: Option<&'a str>) -> Node<'a> {
+    let layout = div(&cx)
+        .attr("class", "card")
+        .children([
+            header(&cx)
+                .children([slot(&cx)
+                    .attr("name", "header")
+                    .children([text("fallback")])
+                    .finish()])
+                .finish(),
+            slot(&cx).finish(),
+        ])
+        .finish();
+    let body = p(&cx).children([text("body")]).finish();
+    match header_content {
+        Some(content) => project(&cx, layout, &[("header", text(content)), ("", body)]),
+        None => project(&cx, layout, &[("", body)]),
+    }
+}
+
 before_after! {
+    project_into_named_slot {
+        before(cx) {
+            card(cx, Some("before"))
+        }
+        after(cx) {
+            card(cx, Some("after"))
+        }
+    }
+
+    replace_slot_fallback_with_projected_content {
+        before(cx) {
+            card(cx, None)
+        }
+        after(cx) {
+            card(cx, Some("after"))
+        }
+    }
+
+    create_and_diff_static_html {
+        before(cx) {
+            static_list(cx, "before & after")
+        }
+        after(cx) {
+            static_list(cx, "after")
+        }
+    }
+
+    create_and_diff_indented_static_html {
+        before(cx) {
+            indented_list(cx, false)
+        }
+        after(cx) {
+            static_list(cx, "after")
+        }
+    }
+
+    // Only the children in the middle are replaced in one go. The ones around
+    // them are diffed as usual.
+    replace_run_of_siblings {
+        before(cx) {
+            div(&cx)
+                .children([
+                    p(&cx).children([text("first")]).finish(),
+                    div(&cx).children([text("a")]).finish(),
+                    div(&cx).children([text("b")]).finish(),
+                    text("c"),
+                    em(&cx).children([text("before")]).finish(),
+                ])
+                .finish()
+        }
+        after(cx) {
+            div(&cx)
+                .children([
+                    p(&cx).children([text("first")]).finish(),
+                    span(&cx).children([text("a")]).finish(),
+                    text("b"),
+                    span(&cx).children([text("c")]).finish(),
+                    em(&cx).children([text("after")]).finish(),
+                ])
+                .finish()
+        }
+    }
+
+    // Emoji outside the BMP are surrogate pairs in UTF-16, and the family
+    // emoji and flag are sequences of several code points.
+    set_text_with_emoji_and_combining_characters {
+        before(cx) {
+            p(&cx).children([text("plain")]).finish()
+        }
+        after(cx) {
+            p(&cx)
+                .attr("title", "\u{1F44D}\u{1F3FD}")
+                .children([text(
+                    "\u{1F469}\u{200D}\u{1F469}\u{200D}\u{1F467} ne\u{301}e \u{1F1EF}\u{1F1F5}",
+                )])
+                .finish()
+        }
+    }
+
+    create_text_with_emoji_and_combining_characters {
+        before(cx) {
+            div(&cx).finish()
+        }
+        after(cx) {
+            div(&cx)
+                .children([
+                    text("\u{1F600}"),
+                    span(&cx).children([text("a\u{308}\u{20DD}")]).finish(),
+                    text("\u{1F3F3}\u{FE0F}\u{200D}\u{1F308}"),
+                ])
+                .finish()
+        }
+    }
+
+    create_static_html_with_emoji_and_combining_characters {
+        before(cx) {
+            div(&cx).finish()
+        }
+        after(cx) {
+            static_list(cx, "\u{1F44D}\u{1F3FD} e\u{301}")
+        }
+    }
+
+    attribute_allocated_in_render_bump {
+        before(cx) {
+            div(&cx)
+                .attr("title", cx.alloc_str(&format!("{} items", 1)))
+                .finish()
+        }
+        after(cx) {
+            div(&cx)
+                .attr("title", cx.alloc_str(&format!("{} items", 2)))
+                .finish()
+        }
+    }
+
+    element_with_text_child_to_bare_text {
+        before(cx) {
+            div(&cx)
+                .children([span(&cx).children([text("text")]).finish()])
+                .finish()
+        }
+        after(cx) {
+            div(&cx).children([text("text")]).finish()
+        }
+    }
+
+    bare_text_to_element_with_text_child {
+        before(cx) {
+            div(&cx).children([text("text")]).finish()
+        }
+        after(cx) {
+            div(&cx)
+                .children([span(&cx).children([text("text")]).finish()])
+                .finish()
+        }
+    }
+
+    text_computed_in_render_arena {
+        before(cx) {
+            item_count(cx, 1)
+        }
+        after(cx) {
+            item_count(cx, 2)
+        }
+    }
+
+    false_bool_attribute_removes_attribute {
+        before(cx) {
+            div(&cx)
+                .attr("hidden", "")
+                .finish()
+        }
+        after(cx) {
+            div(&cx)
+                .typed_attr("hidden", false)
+                .finish()
+        }
+    }
+
+    true_bool_attribute_sets_empty_attribute {
+        before(cx) {
+            div(&cx)
+                .typed_attr("hidden", false)
+                .finish()
+        }
+        after(cx) {
+            div(&cx)
+                .typed_attr("hidden", true)
+                .finish()
+        }
+    }
+
+    number_attribute_is_stringified {
+        before(cx) {
+            input(&cx)
+                .attr("max", "10")
+                .finish()
+        }
+        after(cx) {
+            input(&cx)
+                .typed_attr("max", 5)
+                .finish()
+        }
+    }
+
+    change_five_attributes {
+        before(cx) {
+            div(&cx)
+                .attr("id", "before")
+                .attr("class", "before")
+                .attr("style", "color: red")
+                .attr("title", "before")
+                .attr("aria-label", "before")
+                .finish()
+        }
+        after(cx) {
+            div(&cx)
+                .attr("id", "after")
+                .attr("class", "after")
+                .attr("style", "color: blue")
+                .attr("title", "after")
+                .attr("aria-label", "after")
+                .finish()
+        }
+    }
+
+    add_update_and_remove_attributes {
+        before(cx) {
+            div(&cx)
+                .attr("id", "before")
+                .attr("title", "removed")
+                .attr("data-a", "removed")
+                .attr("data-b", "removed")
+                .finish()
+        }
+        after(cx) {
+            div(&cx)
+                .attr("id", "after")
+                .attr("data-c", "added")
+                .attr("data-d", "added")
+                .finish()
+        }
+    }
+
     same_text {
         before(_cx) {
             text("hello")
@@ -341,3 +1071,329 @@ before_after! {
         }
     }
 }
+
+#[wasm_bindgen_test]
+fn whitespace_text_is_the_same_via_html_and_node_by_node() {
+    let via_html = create_element("div");
+    let _vdom1 = Vdom::new(&via_html, RenderFn(|cx| indented_list(cx, false)));
+    assert_rendered(&via_html, &RenderFn(|cx| indented_list(cx, false)));
+
+    let node_by_node = create_element("div");
+    let _vdom2 = Vdom::new(&node_by_node, RenderFn(|cx| indented_list(cx, true)));
+
+    assert_eq!(via_html.inner_html(), node_by_node.inner_html());
+    let child_count =
+        |container: &web_sys::Element| container.first_child().unwrap().child_nodes().length();
+    assert_eq!(child_count(&via_html), 81);
+    assert_eq!(child_count(&node_by_node), 81);
+}
+
+/// An indented list as rendered on a server, followed by preformatted text,
+/// whose leading newline is easily lost.
+#[cfg(feature = "ssr")]
+fn server_page<'a>(cx: &mut RenderContext<'a>) -> Node<'a> {
+    div(&cx)
+        .children([
+            indented_list(cx, false),
+            pre(&cx).children([text("\n  indented\n")]).finish(),
+            textarea(&cx).children([text("\nnotes")]).finish(),
+        ])
+        .finish()
+}
+
+#[cfg(feature = "ssr")]
+#[wasm_bindgen_test]
+fn whitespace_text_is_the_same_on_the_server_and_the_client() {
+    let page = RenderFn(server_page);
+
+    let server = create_element("div");
+    server.set_inner_html(&dodrio::render_to_string(&page));
+    assert_rendered(&server, &page);
+
+    let client = create_element("div");
+    let _vdom = Vdom::new(&client, RenderFn(server_page));
+    assert_rendered(&client, &page);
+    assert_eq!(server.inner_html(), client.inner_html());
+}
+
+#[wasm_bindgen_test]
+async fn renders_scheduled_in_a_batch_are_coalesced() {
+    struct Counter {
+        count: Rc<std::cell::Cell<u32>>,
+        renders: Rc<std::cell::Cell<u32>>,
+    }
+
+    impl<'a> Render<'a> for Counter {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            self.renders.set(self.renders.get() + 1);
+            let count = dodrio::bumpalo::format!(in cx.bump, "{}", self.count.get());
+            p(&cx).children([text(count.into_bump_str())]).finish()
+        }
+    }
+
+    let count = Rc::new(std::cell::Cell::new(0));
+    let renders = Rc::new(std::cell::Cell::new(0));
+    let container = create_element("div");
+    let vdom = Vdom::new(
+        &container,
+        Counter {
+            count: count.clone(),
+            renders: renders.clone(),
+        },
+    );
+    assert_eq!(renders.get(), 1);
+
+    let weak = vdom.weak();
+    vdom.batch(|| {
+        for _ in 0..100 {
+            count.set(count.get() + 1);
+            weak.schedule_render();
+        }
+    });
+
+    weak.render().await.unwrap();
+    assert_eq!(renders.get(), 2);
+    assert_eq!(container.inner_html(), "<p>100</p>");
+}
+
+#[wasm_bindgen_test]
+async fn renders_scheduled_while_paused_are_deferred_until_resumed() {
+    struct Counter {
+        count: Rc<std::cell::Cell<u32>>,
+        renders: Rc<std::cell::Cell<u32>>,
+    }
+
+    impl<'a> Render<'a> for Counter {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            self.renders.set(self.renders.get() + 1);
+            let count = dodrio::bumpalo::format!(in cx.bump, "{}", self.count.get());
+            p(&cx).children([text(count.into_bump_str())]).finish()
+        }
+    }
+
+    let count = Rc::new(std::cell::Cell::new(0));
+    let renders = Rc::new(std::cell::Cell::new(0));
+    let container = create_element("div");
+    let vdom = Vdom::new_with_options(
+        &container,
+        Counter {
+            count: count.clone(),
+            renders: renders.clone(),
+        },
+        dodrio::MountOptions::new().scheduling(dodrio::Scheduling::Microtask),
+    );
+    assert_eq!(renders.get(), 1);
+
+    let weak = vdom.weak();
+    vdom.pause();
+    for _ in 0..10 {
+        count.set(count.get() + 1);
+        weak.schedule_render();
+    }
+    let rendered = weak.render();
+
+    // Wait for an animation frame, by which point the microtasks that would
+    // have rendered have run.
+    let other = Vdom::with_render_fn(&create_element("div"), |cx| div(&cx).finish());
+    other.weak().render().await.unwrap();
+    assert_eq!(renders.get(), 1);
+    assert_eq!(container.inner_html(), "<p>0</p>");
+
+    vdom.resume();
+    rendered.await.unwrap();
+    assert_eq!(renders.get(), 2);
+    assert_eq!(container.inner_html(), "<p>10</p>");
+}
+
+#[wasm_bindgen_test]
+async fn renders_deferred_by_pause_fail_when_the_vdom_goes_away() {
+    struct Empty;
+
+    impl<'a> Render<'a> for Empty {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx).finish()
+        }
+    }
+
+    let mount = || {
+        Vdom::new_with_options(
+            &create_element("div"),
+            Empty,
+            dodrio::MountOptions::new().scheduling(dodrio::Scheduling::Microtask),
+        )
+    };
+    let dropped = mount();
+    let unmounted = mount();
+
+    dropped.pause();
+    let dropped_render = dropped.weak().render();
+    unmounted.pause();
+    let unmounted_render = unmounted.weak().render();
+
+    // Wait for an animation frame, by which point both renders have been
+    // deferred.
+    let other = Vdom::with_render_fn(&create_element("div"), |cx| div(&cx).finish());
+    other.weak().render().await.unwrap();
+
+    drop(dropped);
+    unmounted.unmount();
+    assert!(dropped_render.await.is_err());
+    assert!(unmounted_render.await.is_err());
+}
+
+#[wasm_bindgen_test]
+async fn unique_ids_are_the_same_across_renders() {
+    struct Field;
+
+    impl<'a> Render<'a> for Field {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            let name = cx.unique_id("field");
+            let hint = cx.unique_id("field");
+            div(&cx)
+                .children([
+                    label(&cx).for_(name).children([text("Name")]).finish(),
+                    input(&cx).id(name).attr("aria-describedby", hint).finish(),
+                    p(&cx).id(hint).children([text("Your full name")]).finish(),
+                ])
+                .finish()
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Field);
+    let before = container.inner_html();
+    assert_eq!(
+        before,
+        "<div><label for=\"field-0\">Name</label>\
+         <input id=\"field-0\" aria-describedby=\"field-1\">\
+         <p id=\"field-1\">Your full name</p></div>"
+    );
+
+    vdom.weak().render().await.unwrap();
+    assert_eq!(container.inner_html(), before);
+    assert_eq!(vdom.last_diff_op_count(), 0);
+}
+
+#[wasm_bindgen_test]
+async fn provided_values_are_read_by_nested_components() {
+    struct Theme(&'static str);
+
+    struct Swatch;
+
+    impl<'a> Render<'a> for Swatch {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            let color = cx.use_context::<Theme>().map_or("none", |theme| theme.0);
+            span(&cx).attr("class", color).finish()
+        }
+    }
+
+    struct Panel;
+
+    impl<'a> Render<'a> for Panel {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            section(&cx)
+                .children([div(&cx).children([Swatch.render(cx)]).finish()])
+                .finish()
+        }
+    }
+
+    struct App(&'static str);
+
+    impl<'a> Render<'a> for App {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            let themed = cx.provide(Theme(self.0), |cx| {
+                let dark = cx.provide(Theme("dark"), |cx| Panel.render(cx));
+                div(&cx).children([Panel.render(cx), dark]).finish()
+            });
+            div(&cx).children([themed, Swatch.render(cx)]).finish()
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, App("light"));
+    let classes = || {
+        let spans = container.get_elements_by_tag_name("span");
+        (0..spans.length())
+            .map(|i| spans.item(i).unwrap().class_name())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(classes(), ["light", "dark", "none"]);
+
+    vdom.weak()
+        .set_component(Box::new(App("sepia")))
+        .await
+        .unwrap();
+    assert_eq!(classes(), ["sepia", "dark", "none"]);
+}
+
+#[wasm_bindgen_test]
+async fn raw_node_survives_rerenders() {
+    struct Widget(&'static web_sys::Node, &'static str);
+
+    impl<'a> Render<'a> for Widget {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .children([p(&cx).children([text(self.1)]).finish(), Node::raw(self.0)])
+                .finish()
+        }
+    }
+
+    let widget = create_element("canvas");
+    widget.set_attribute("data-widget", "map").unwrap();
+    widget.set_inner_html("drawn by a third party");
+    let widget: &'static web_sys::Node = Box::leak(Box::new(widget.into()));
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Widget(widget, "before"));
+    let parent = container.first_child().unwrap();
+    assert!(widget.is_same_node(parent.last_child().as_ref()));
+    assert_eq!(vdom.listener_count(), 0);
+
+    vdom.weak()
+        .set_component(Box::new(Widget(widget, "after")))
+        .await
+        .unwrap();
+    assert!(widget.is_same_node(parent.last_child().as_ref()));
+    assert_eq!(
+        container.inner_html(),
+        "<div><p>after</p>\
+         <canvas data-widget=\"map\">drawn by a third party</canvas></div>"
+    );
+    // Only the paragraph's text changed.
+    assert_eq!(vdom.last_diff_op_count(), 3);
+}
+
+#[wasm_bindgen_test]
+async fn diff_stats_report_the_deepest_level_and_widest_children() {
+    /// `<div><section><ul><li>self.0</li><li/><li/></ul></section></div>`
+    struct Nested(&'static str);
+
+    impl<'a> Render<'a> for Nested {
+        fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+            div(&cx)
+                .children([section(&cx)
+                    .children([ul(&cx)
+                        .children([
+                            li(&cx).children([text(self.0)]).finish(),
+                            li(&cx).finish(),
+                            li(&cx).finish(),
+                        ])
+                        .finish()])
+                    .finish()])
+                .finish()
+        }
+    }
+
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Nested("before"));
+
+    vdom.weak()
+        .set_component(Box::new(Nested("after")))
+        .await
+        .unwrap();
+    let stats = vdom.last_diff_stats();
+    // The root `<div>` is depth 0, and the text in the first `<li>` is depth 4.
+    assert_eq!(stats.max_depth, 4);
+    assert_eq!(stats.widest_children, 3);
+    assert_eq!(stats.op_count, vdom.last_diff_op_count());
+}
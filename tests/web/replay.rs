@@ -0,0 +1,209 @@
+use super::create_element;
+use dodrio::{builder::*, bumpalo::Bump, replay, CachedSet, Node, Vdom};
+use wasm_bindgen_test::*;
+
+fn app<'a>(b: &'a Bump) -> Node<'a> {
+    div(b)
+        .attr("class", "app")
+        .attr("--accent", "red")
+        .children([
+            h1(b).children([text("Replayed")]).finish(),
+            ul(b)
+                .children([
+                    li(b).attr("id", "a").children([text("one")]).finish(),
+                    li(b).attr("id", "b").children([text("two")]).finish(),
+                ])
+                .finish(),
+            button(b)
+                .on("click", |_root, _vdom, _event| {})
+                .children([text("Click")])
+                .finish(),
+        ])
+        .finish()
+}
+
+#[wasm_bindgen_test]
+fn replaying_recorded_ops_on_an_empty_tree_builds_the_same_dom() {
+    // Build the tree directly...
+    let direct = create_element("div");
+    let _vdom = Vdom::with_render_fn(&direct, |cx| app(cx.bump));
+
+    // ...and by recording the diff from an empty root to it.
+    let b = Bump::new();
+    let empty = div(&b).finish();
+    let new = app(&b);
+    let ops = replay::record_ops(&empty, &new);
+    assert_eq!(ops.last(), Some(&replay::ChangeOp::Finish));
+    assert!(ops.iter().any(|op| match op {
+        replay::ChangeOp::NewEventListener { event, .. } => event == "click",
+        _ => false,
+    }));
+
+    let replayed = create_element("div");
+    replayed
+        .append_child(&create_element("div"))
+        .expect("should append root OK");
+    replay::replay_ops(&replayed, &ops);
+
+    assert_eq!(replayed.inner_html(), direct.inner_html());
+}
+
+#[wasm_bindgen_test]
+fn materializing_builds_the_node_in_a_detached_fragment() {
+    let b = Bump::new();
+    let cached_set = CachedSet::default();
+    let fragment = replay::materialize(&cached_set, &app(&b));
+
+    assert_eq!(fragment.child_element_count(), 1);
+    let root = fragment
+        .first_element_child()
+        .expect("should have a root element");
+    assert_eq!(root.tag_name(), "DIV");
+    assert_eq!(root.get_attribute("class").as_deref(), Some("app"));
+    assert_eq!(root.child_element_count(), 3);
+    assert_eq!(
+        root.inner_html(),
+        "<h1>Replayed</h1>\
+         <ul><li id=\"a\">one</li><li id=\"b\">two</li></ul>\
+         <button>Click</button>"
+    );
+
+    // The fragment can be inserted anywhere.
+    let container = create_element("section");
+    container
+        .append_child(&fragment)
+        .expect("should append fragment OK");
+    assert_eq!(fragment.child_element_count(), 0);
+    assert_eq!(container.first_element_child(), Some(root));
+}
+
+#[wasm_bindgen_test]
+fn reconciling_does_not_touch_the_dom_until_committed() {
+    let b = Bump::new();
+    let old = ul(&b)
+        .children([li(&b).children([text("one")]).finish()])
+        .finish();
+    let new = ul(&b)
+        .attr("class", "done")
+        .children([
+            li(&b).children([text("uno")]).finish(),
+            li(&b).children([text("dos")]).finish(),
+        ])
+        .finish();
+
+    let container = create_element("div");
+    let vdom = Vdom::with_render_fn(&container, |cx| {
+        ul(cx.bump)
+            .children([li(cx.bump).children([text("one")]).finish()])
+            .finish()
+    });
+    let before = container.inner_html();
+
+    let mut change_list = replay::reconcile(&old, &new);
+    assert!(change_list
+        .ops()
+        .contains(&replay::ChangeOp::SetText("uno".into())));
+    assert_eq!(container.inner_html(), before);
+
+    // Transform the change list before committing it.
+    for op in change_list.ops_mut() {
+        if let replay::ChangeOp::SetText(text) = op {
+            *text = text.to_uppercase();
+        }
+    }
+    let _listeners = replay::commit(&vdom, &change_list);
+    assert_eq!(
+        container.inner_html(),
+        "<ul class=\"done\"><li>UNO</li><li>dos</li></ul>"
+    );
+}
+
+#[wasm_bindgen_test]
+fn committing_finishes_a_change_list_without_a_finish_op() {
+    let b = Bump::new();
+    let old = p(&b).children([text("before")]).finish();
+    let new = p(&b).children([text("after")]).finish();
+
+    let container = create_element("div");
+    let vdom = Vdom::with_render_fn(&container, |cx| {
+        p(cx.bump).children([text("before")]).finish()
+    });
+
+    let mut change_list = replay::reconcile(&old, &new);
+    assert_eq!(change_list.ops_mut().pop(), Some(replay::ChangeOp::Finish));
+    let _listeners = replay::commit(&vdom, &change_list);
+    assert_eq!(container.inner_html(), "<p>after</p>");
+}
+
+#[wasm_bindgen_test]
+fn committed_listeners_are_registered_with_the_vdom() {
+    let b = Bump::new();
+    let old = div(&b).finish();
+    let new = div(&b)
+        .on("click", |_root, _vdom, _event| {})
+        .on("keydown", |_root, _vdom, _event| {})
+        .finish();
+
+    let container = create_element("div");
+    let vdom = Vdom::with_render_fn(&container, |cx| div(cx.bump).finish());
+    assert_eq!(vdom.listener_count(), 0);
+
+    let change_list = replay::reconcile(&old, &new);
+    let listeners = replay::commit(&vdom, &change_list);
+    assert_eq!(vdom.listener_count(), 2);
+
+    drop(listeners);
+    assert_eq!(vdom.listener_count(), 0);
+}
+
+fn listeners<'a>(b: &'a Bump, events: &[&'a str]) -> Node<'a> {
+    let mut element = div(b);
+    for event in events {
+        element = element.on(event, |_root, _vdom, _event| {});
+    }
+    element.finish()
+}
+
+// Get the listener operations in `ops`, in order, as `(op, event)` pairs.
+fn listener_ops(ops: &[replay::ChangeOp]) -> Vec<(&'static str, String)> {
+    ops.iter()
+        .filter_map(|op| match op {
+            replay::ChangeOp::NewEventListener { event, .. } => Some(("new", event.clone())),
+            replay::ChangeOp::UpdateEventListener { event, .. } => Some(("update", event.clone())),
+            replay::ChangeOp::RemoveEventListener(event) => Some(("remove", event.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[wasm_bindgen_test]
+fn listeners_are_registered_in_builder_order_across_renders() {
+    let b = Bump::new();
+    let empty = div(&b).finish();
+    let first = listeners(&b, &["keydown", "click", "focus"]);
+    let ops = replay::record_ops(&empty, &first);
+    assert_eq!(
+        listener_ops(&ops),
+        [
+            ("new", "keydown".to_string()),
+            ("new", "click".to_string()),
+            ("new", "focus".to_string()),
+        ]
+    );
+
+    // Reordering the listeners only updates the existing registrations, in
+    // place, and new ones are registered after them.
+    let second = listeners(&b, &["focus", "input", "keydown", "blur"]);
+    let ops = replay::record_ops(&first, &second);
+    assert_eq!(
+        listener_ops(&ops),
+        [
+            ("update", "focus".to_string()),
+            ("new", "input".to_string()),
+            ("update", "keydown".to_string()),
+            ("new", "blur".to_string()),
+            ("remove", "click".to_string()),
+        ]
+    );
+    assert_eq!(replay::record_ops(&first, &second), ops);
+}
@@ -0,0 +1,223 @@
+use super::create_element;
+use dodrio::{builder::*, Node, Render, RenderContext, Vdom};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+/// A toolbar of three buttons, where the `self.0`th button is active.
+struct Toolbar(usize);
+
+impl<'a> Render<'a> for Toolbar {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        roving_tabindex(
+            div(&cx).attr("role", "toolbar"),
+            vec![
+                button(&cx).children([text("Bold")]),
+                button(&cx).children([text("Italic")]),
+                button(&cx).children([text("Underline")]),
+            ],
+            self.0,
+            |root, vdom, index| {
+                root.unwrap_mut::<Toolbar>().0 = index;
+                vdom.schedule_render();
+            },
+        )
+    }
+}
+
+// Get the index of each button whose `tabindex` is "0".
+fn focusable(toolbar: &web_sys::Element) -> Vec<u32> {
+    let buttons = toolbar.children();
+    (0..buttons.length())
+        .filter(|&i| {
+            buttons
+                .item(i)
+                .unwrap()
+                .get_attribute("tabindex")
+                .as_deref()
+                == Some("0")
+        })
+        .collect()
+}
+
+fn press(toolbar: &web_sys::Element, key: &str) {
+    let event = web_sys::Event::new("keydown").expect_throw("should create event OK");
+    js_sys::Reflect::set(&event, &"key".into(), &key.into()).expect_throw("should set key OK");
+    toolbar
+        .dispatch_event(&event)
+        .expect_throw("should dispatch event OK");
+}
+
+#[wasm_bindgen_test]
+async fn arrow_keys_move_the_single_focusable_item() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Toolbar(0));
+    let toolbar = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+
+    let buttons = toolbar.children();
+    for i in 0..buttons.length() {
+        let tabindex = buttons.item(i).unwrap().get_attribute("tabindex");
+        assert_eq!(tabindex.as_deref(), Some(if i == 0 { "0" } else { "-1" }));
+    }
+
+    for &(key, expected) in &[
+        ("ArrowRight", 1),
+        ("ArrowDown", 2),
+        ("ArrowRight", 0),
+        ("ArrowLeft", 2),
+        ("Home", 0),
+        ("End", 2),
+        ("ArrowUp", 1),
+        ("a", 1),
+    ] {
+        press(&toolbar, key);
+        vdom.weak().render().await.unwrap();
+        assert_eq!(
+            focusable(&toolbar),
+            vec![expected],
+            "after pressing {}",
+            key
+        );
+    }
+}
+
+/// A labeled toolbar, whose label comes before its items, and whose last item
+/// contains a text field.
+struct LabeledToolbar(usize);
+
+impl<'a> Render<'a> for LabeledToolbar {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        roving_tabindex(
+            div(&cx)
+                .attr("role", "toolbar")
+                .children([span(&cx).children([text("Format:")]).finish()]),
+            vec![
+                button(&cx).children([text("Bold")]),
+                button(&cx).children([text("Italic")]),
+                span(&cx).children([input(&cx).attr("type", "text").finish()]),
+            ],
+            self.0,
+            |root, vdom, index| {
+                root.unwrap_mut::<LabeledToolbar>().0 = index;
+                vdom.schedule_render();
+            },
+        )
+    }
+}
+
+// Press `key` in the given element, and get whether the press was cancelled.
+fn press_in(element: &web_sys::Node, key: &str) -> bool {
+    let mut init = web_sys::EventInit::new();
+    init.bubbles(true).cancelable(true);
+    let event = web_sys::Event::new_with_event_init_dict("keydown", &init)
+        .expect_throw("should create event OK");
+    js_sys::Reflect::set(&event, &"key".into(), &key.into()).expect_throw("should set key OK");
+    element
+        .dispatch_event(&event)
+        .expect_throw("should dispatch event OK");
+    event.default_prevented()
+}
+
+#[wasm_bindgen_test]
+async fn arrow_keys_move_from_the_item_they_are_pressed_in() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, LabeledToolbar(0));
+    let toolbar = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    let children = toolbar.children();
+    let italic = children.item(2).unwrap();
+    let field = children.item(3).unwrap().first_element_child().unwrap();
+
+    // The label is not an item.
+    assert_eq!(children.item(0).unwrap().get_attribute("tabindex"), None);
+    assert_eq!(focusable(&toolbar), vec![1]);
+
+    // Moving from the second item, even though the first one is active.
+    assert!(press_in(&italic, "ArrowRight"));
+    vdom.weak().render().await.unwrap();
+    assert_eq!(focusable(&toolbar), vec![3]);
+
+    // The text field keeps its arrow keys.
+    assert!(!press_in(&field, "ArrowLeft"));
+    vdom.weak().render().await.unwrap();
+    assert_eq!(focusable(&toolbar), vec![3]);
+}
+
+/// A dialog that traps focus. The buttons at the end are skipped: one opts out
+/// of tabbing, and the other is inert.
+struct Dialog;
+
+impl<'a> Render<'a> for Dialog {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        focus_trap(
+            div(&cx).attr("role", "dialog").children([
+                button(&cx).attr("id", "first").finish(),
+                input(&cx).attr("type", "text").finish(),
+                button(&cx).attr("id", "last").finish(),
+                button(&cx).attr("tabindex", "-1").finish(),
+                div(&cx)
+                    .inert(true)
+                    .children([button(&cx).finish()])
+                    .finish(),
+            ]),
+        )
+    }
+}
+
+fn tab(dialog: &web_sys::Element, shift: bool) {
+    let event = web_sys::Event::new("keydown").expect_throw("should create event OK");
+    js_sys::Reflect::set(&event, &"key".into(), &"Tab".into()).expect_throw("should set key OK");
+    js_sys::Reflect::set(&event, &"shiftKey".into(), &shift.into())
+        .expect_throw("should set shiftKey OK");
+    dialog
+        .dispatch_event(&event)
+        .expect_throw("should dispatch event OK");
+}
+
+fn active_id() -> Option<String> {
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .active_element()
+        .map(|active| active.id())
+}
+
+#[wasm_bindgen_test]
+fn tabbing_past_the_last_element_in_a_focus_trap_wraps_to_the_first() {
+    let container = create_element("div");
+    // Only elements within a document can be focused.
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .body()
+        .unwrap()
+        .append_child(&container)
+        .unwrap();
+
+    let _vdom = Vdom::new(&container, Dialog);
+    let dialog = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    let last = dialog
+        .query_selector("#last")
+        .unwrap()
+        .unwrap()
+        .unchecked_into::<web_sys::HtmlElement>();
+
+    last.focus().unwrap();
+    tab(&dialog, false);
+    assert_eq!(active_id().as_deref(), Some("first"));
+
+    tab(&dialog, true);
+    assert_eq!(active_id().as_deref(), Some("last"));
+
+    container.remove();
+}
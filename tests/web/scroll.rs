@@ -0,0 +1,150 @@
+use super::create_element;
+use dodrio::{builder::*, bumpalo, Node, Render, RenderContext, Vdom, VirtualList};
+use wasm_bindgen_test::*;
+
+/// A chat log whose `self.1`th message, if any, is scrolled into view.
+struct Chat(&'static [&'static str], Option<usize>);
+
+impl<'a> Render<'a> for Chat {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let mut log = ul(&cx);
+        for (i, &message) in self.0.iter().enumerate() {
+            let mut item = li(&cx).attr("id", message);
+            if self.1 == Some(i) {
+                item = item.scroll_into_view(ScrollAlignment::End, true);
+            }
+            log = log.child(item.children([text(message)]).finish());
+        }
+        log.finish()
+    }
+}
+
+// Replace `Element.prototype.scrollIntoView` with a function that records the
+// `id` and options of each element it is called on.
+fn record_scrolls() {
+    js_sys::eval(
+        r#"
+        window.dodrioScrolls = [];
+        window.dodrioOriginalScrollIntoView = Element.prototype.scrollIntoView;
+        Element.prototype.scrollIntoView = function (options) {
+            window.dodrioScrolls.push(`${this.id} ${options.block} ${options.behavior}`);
+        };
+        "#,
+    )
+    .unwrap();
+}
+
+fn recorded_scrolls() -> String {
+    js_sys::eval("window.dodrioScrolls.join(', ')")
+        .unwrap()
+        .as_string()
+        .unwrap()
+}
+
+fn restore_scrolls() {
+    js_sys::eval("Element.prototype.scrollIntoView = window.dodrioOriginalScrollIntoView").unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn scroll_into_view_only_when_newly_applied() {
+    record_scrolls();
+
+    let container = create_element("div");
+    // Only connected elements are scrolled into view.
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .body()
+        .unwrap()
+        .append_child(&container)
+        .unwrap();
+
+    let vdom = Vdom::new(&container, Chat(&["a", "b"], Some(1)));
+    assert_eq!(recorded_scrolls(), "b end smooth");
+
+    // Re-rendering with the same directive does not scroll again.
+    vdom.weak()
+        .set_component(Box::new(Chat(&["a", "b"], Some(1))))
+        .await
+        .unwrap();
+    assert_eq!(recorded_scrolls(), "b end smooth");
+
+    // A new message with the directive is scrolled into view.
+    vdom.weak()
+        .set_component(Box::new(Chat(&["a", "b", "c"], Some(2))))
+        .await
+        .unwrap();
+    assert_eq!(recorded_scrolls(), "b end smooth, c end smooth");
+
+    container.remove();
+    restore_scrolls();
+}
+
+/// A virtualized list of `self.0.len()` numbered rows.
+struct Rows(VirtualList);
+
+impl<'a> Render<'a> for Rows {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        self.0.render(cx, |cx, i| {
+            text(bumpalo::format!(in cx.bump, "Row {}", i).into_bump_str())
+        })
+    }
+}
+
+fn rendered_rows(viewport: &web_sys::Element) -> Vec<String> {
+    let children = viewport.children();
+    (1..children.length() - 1)
+        .map(|i| children.item(i).unwrap().text_content().unwrap())
+        .collect()
+}
+
+fn spacer_heights(viewport: &web_sys::Element) -> (String, String) {
+    let height = |spacer: Option<web_sys::Element>| spacer.unwrap().get_attribute("style").unwrap();
+    (
+        height(viewport.first_element_child()),
+        height(viewport.last_element_child()),
+    )
+}
+
+#[wasm_bindgen_test]
+async fn virtual_list_only_renders_the_rows_in_view() {
+    let container = create_element("div");
+    // Only connected elements can be scrolled.
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .body()
+        .unwrap()
+        .append_child(&container)
+        .unwrap();
+
+    let list = VirtualList::new(100_000, 20.0, 200.0).overscan(2);
+    let vdom = Vdom::new(&container, Rows(list));
+    let viewport = container.first_element_child().unwrap();
+
+    let expected: Vec<String> = (0..12).map(|i| format!("Row {}", i)).collect();
+    assert_eq!(rendered_rows(&viewport), expected);
+    assert_eq!(
+        spacer_heights(&viewport),
+        ("height: 0px".to_string(), "height: 1999760px".to_string())
+    );
+
+    // Scrolling to row 50 renders rows 50 through 59, plus two rows of
+    // overscan on either side.
+    viewport.set_scroll_top(1000);
+    viewport
+        .dispatch_event(&web_sys::Event::new("scroll").unwrap())
+        .unwrap();
+    vdom.weak().render().await.unwrap();
+
+    let expected: Vec<String> = (48..62).map(|i| format!("Row {}", i)).collect();
+    assert_eq!(rendered_rows(&viewport), expected);
+    assert_eq!(
+        spacer_heights(&viewport),
+        ("height: 960px".to_string(), "height: 1998760px".to_string())
+    );
+
+    container.remove();
+}
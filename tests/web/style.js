@@ -0,0 +1,13 @@
+let setPropertyCalls = [];
+
+const origSetProperty = CSSStyleDeclaration.prototype.setProperty;
+CSSStyleDeclaration.prototype.setProperty = function (name, value, ...args) {
+  setPropertyCalls.push(`${name}: ${value}`);
+  return origSetProperty.call(this, name, value, ...args);
+};
+
+export function takeSetPropertyCalls() {
+  const calls = setPropertyCalls;
+  setPropertyCalls = [];
+  return calls.join("\n");
+}
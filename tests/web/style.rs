@@ -0,0 +1,121 @@
+use super::{create_element, RenderFn};
+use dodrio::{builder::*, MountOptions, RootRender, Vdom};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen(module = "/tests/web/style.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = takeSetPropertyCalls)]
+    fn take_set_property_calls() -> String;
+}
+
+#[wasm_bindgen_test]
+async fn diff_custom_property() {
+    let container = create_element("div");
+
+    let vdom0 = Rc::new(Vdom::new(
+        &container,
+        RenderFn(|cx| div(&cx).attr("--accent", "red").finish()),
+    ));
+    let _vdom1 = vdom0.clone();
+    assert_eq!(take_set_property_calls(), "--accent: red");
+
+    vdom0
+        .weak()
+        .set_component(
+            Box::new(RenderFn(|cx| div(&cx).attr("--accent", "blue").finish()))
+                as Box<dyn RootRender>,
+        )
+        .await
+        .unwrap();
+    assert_eq!(take_set_property_calls(), "--accent: blue");
+
+    let element = container
+        .first_child()
+        .expect("should have rendered a child")
+        .dyn_into::<web_sys::HtmlElement>()
+        .expect("should be an `HtmlElement`");
+    assert!(!element.has_attribute("--accent"));
+    assert_eq!(
+        element
+            .style()
+            .get_property_value("--accent")
+            .expect("should get property OK"),
+        "blue"
+    );
+
+    vdom0
+        .weak()
+        .set_component(Box::new(RenderFn(|cx| div(&cx).finish())) as Box<dyn RootRender>)
+        .await
+        .unwrap();
+    assert_eq!(
+        element
+            .style()
+            .get_property_value("--accent")
+            .expect("should get property OK"),
+        ""
+    );
+}
+
+#[wasm_bindgen_test]
+async fn strict_csp_applies_styles_with_set_property() {
+    let container = create_element("div");
+    let options = MountOptions::new().strict_csp(true).style_nonce("r4nd0m");
+    let vdom = Vdom::new_with_options(
+        &container,
+        RenderFn(|cx| {
+            div(&cx)
+                .attr("style", "color: red; opacity: 0.5")
+                .attr("--accent", "red")
+                .children([style(&cx).children([text("p { margin: 0 }")]).finish()])
+                .finish()
+        }),
+        options,
+    );
+    // Custom properties are set right away, and other styles once the
+    // element's attributes are flushed.
+    assert_eq!(
+        take_set_property_calls(),
+        "--accent: red\ncolor: red\nopacity: 0.5"
+    );
+
+    let element = container
+        .first_child()
+        .expect("should have rendered a child")
+        .dyn_into::<web_sys::HtmlElement>()
+        .expect("should be an `HtmlElement`");
+    let property = |name: &str| {
+        element
+            .style()
+            .get_property_value(name)
+            .expect("should get property OK")
+    };
+    assert_eq!(property("color"), "red");
+
+    let style_element = element.first_child().unwrap();
+    assert_eq!(
+        js_sys::Reflect::get(&style_element, &"nonce".into())
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some("r4nd0m")
+    );
+
+    vdom.weak()
+        .set_component(Box::new(RenderFn(|cx| {
+            div(&cx)
+                .attr("style", "color: blue")
+                .attr("--accent", "red")
+                .children([style(&cx).children([text("p { margin: 0 }")]).finish()])
+                .finish()
+        })) as Box<dyn RootRender>)
+        .await
+        .unwrap();
+    assert_eq!(take_set_property_calls(), "color: blue");
+    assert_eq!(property("color"), "blue");
+    assert_eq!(property("opacity"), "");
+    assert_eq!(property("--accent"), "red");
+}
@@ -0,0 +1,122 @@
+use super::{before_after, create_element, RenderFn};
+use dodrio::{builder::*, bumpalo, Node, RenderContext, Vdom};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+/// A table with a header row and one body row per key. When `keyed` is true,
+/// the body rows are keyed.
+fn table_rows<'a, Keys>(cx: &mut RenderContext<'a>, keys: Keys, keyed: bool) -> Node<'a>
+where
+    Keys: AsRef<[u32]>,
+{
+    let mut body = tbody(&cx);
+    for &k in keys.as_ref() {
+        let label = bumpalo::format!(in cx.bump, "row {}", k).into_bump_str();
+        let row = tr(&cx).children([
+            th(&cx)
+                .attr("scope", "row")
+                .children([text(label)])
+                .finish(),
+            td(&cx)
+                .attr("colspan", "2")
+                .children([text("cell")])
+                .finish(),
+        ]);
+        let row = if keyed { row.key(k) } else { row };
+        body = body.child(row.finish());
+    }
+
+    table(&cx)
+        .children([
+            thead(&cx)
+                .children([tr(&cx)
+                    .children([
+                        th(&cx).children([text("name")]).finish(),
+                        th(&cx).children([text("value")]).finish(),
+                    ])
+                    .finish()])
+                .finish(),
+            body.finish(),
+        ])
+        .finish()
+}
+
+before_after! {
+    table_append_rows {
+        before(cx) {
+            table_rows(cx, [0], false)
+        }
+        after(cx) {
+            table_rows(cx, [0, 1, 2], false)
+        }
+    }
+
+    table_remove_rows {
+        before(cx) {
+            table_rows(cx, [0, 1, 2], false)
+        }
+        after(cx) {
+            table_rows(cx, [0], false)
+        }
+    }
+
+    table_insert_keyed_rows {
+        before(cx) {
+            table_rows(cx, [0, 3], true)
+        }
+        after(cx) {
+            table_rows(cx, [0, 1, 2, 3], true)
+        }
+    }
+
+    table_remove_and_reorder_keyed_rows {
+        before(cx) {
+            table_rows(cx, [0, 1, 2, 3], true)
+        }
+        after(cx) {
+            table_rows(cx, [3, 1], true)
+        }
+    }
+
+    table_from_empty {
+        before(cx) {
+            table(&cx).finish()
+        }
+        after(cx) {
+            table_rows(cx, [0, 1], true)
+        }
+    }
+}
+
+/// Rows created without an explicit `<tbody>` are not moved into one, like
+/// the HTML parser would do, so the physical DOM keeps matching the virtual
+/// DOM.
+#[wasm_bindgen_test]
+fn rows_are_not_reparented_into_implicit_tbody() {
+    let container = create_element("div");
+    let _vdom = Vdom::new(
+        &container,
+        RenderFn(|cx| {
+            table(&cx)
+                .children([
+                    tr(&cx).children([td(&cx).finish()]).finish(),
+                    tr(&cx).children([td(&cx).finish()]).finish(),
+                ])
+                .finish()
+        }),
+    );
+
+    let table = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    assert_eq!(table.child_element_count(), 2);
+    assert_eq!(
+        table
+            .first_element_child()
+            .unwrap()
+            .tag_name()
+            .to_lowercase(),
+        "tr"
+    );
+}
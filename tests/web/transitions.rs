@@ -0,0 +1,110 @@
+use super::{create_element, RenderFn};
+use dodrio::{builder::*, bumpalo, Cached, Node, Render, RenderContext, Vdom};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+fn items<'a>(cx: &mut RenderContext<'a>, keys: &[u32]) -> Node<'a> {
+    let mut children = bumpalo::collections::Vec::new_in(cx.bump);
+    for &key in keys {
+        let id = bumpalo::format!(in cx.bump, "item-{}", key).into_bump_str();
+        children.push(
+            div(&cx)
+                .key(key)
+                .attr("id", id)
+                .attr("style", "transition: opacity 10s")
+                .leave_transition("x-leave")
+                .finish(),
+        );
+    }
+    div(&cx).children(children).finish()
+}
+
+fn child_ids(parent: &web_sys::Element) -> Vec<String> {
+    let children = parent.children();
+    (0..children.length())
+        .map(|i| children.item(i).unwrap().id())
+        .collect()
+}
+
+#[wasm_bindgen_test]
+async fn removed_element_lingers_until_transition_ends() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| items(cx, &[1, 2])));
+    let weak = vdom.weak();
+    let parent = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+
+    weak.set_component(Box::new(RenderFn(|cx| items(cx, &[2]))))
+        .await
+        .unwrap();
+
+    // The removed element is still there, with the leave class.
+    assert_eq!(child_ids(&parent), ["item-1", "item-2"]);
+    let leaving = parent.first_element_child().unwrap();
+    assert!(leaving.class_list().contains("x-leave"));
+
+    // Subsequent diffs ignore the leaving element.
+    weak.set_component(Box::new(RenderFn(|cx| items(cx, &[2, 3]))))
+        .await
+        .unwrap();
+    assert_eq!(child_ids(&parent), ["item-1", "item-2", "item-3"]);
+
+    // Once the transition ends, the element is finally removed.
+    let event = web_sys::Event::new("transitionend").unwrap();
+    leaving.dispatch_event(&event).unwrap();
+    assert_eq!(child_ids(&parent), ["item-2", "item-3"]);
+}
+
+#[derive(Default)]
+struct Fading;
+
+impl<'a> Render<'a> for Fading {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        div(&cx)
+            .attr("style", "transition: opacity 10s")
+            .leave_transition("x-leave")
+            .finish()
+    }
+}
+
+fn fading<'a>(cx: &mut RenderContext<'a>, show: bool) -> Node<'a> {
+    let mut parent = div(&cx);
+    if show {
+        parent = parent.child(Cached::new(Fading).render(cx));
+    }
+    parent.finish()
+}
+
+#[wasm_bindgen_test]
+async fn elements_cloned_from_templates_keep_their_leave_transition() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, RenderFn(|cx| fading(cx, true)));
+    let weak = vdom.weak();
+    let parent = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+
+    // Let the first element leave completely.
+    weak.set_component(Box::new(RenderFn(|cx| fading(cx, false))))
+        .await
+        .unwrap();
+    let leaving = parent.first_element_child().unwrap();
+    let event = web_sys::Event::new("transitionend").unwrap();
+    leaving.dispatch_event(&event).unwrap();
+    assert_eq!(parent.child_element_count(), 0);
+
+    // This one is created from the cached template, rather than by setting
+    // its attributes one by one, and still lingers when it is removed.
+    weak.set_component(Box::new(RenderFn(|cx| fading(cx, true))))
+        .await
+        .unwrap();
+    weak.set_component(Box::new(RenderFn(|cx| fading(cx, false))))
+        .await
+        .unwrap();
+    assert_eq!(parent.child_element_count(), 1);
+    let leaving = parent.first_element_child().unwrap();
+    assert!(leaving.class_list().contains("x-leave"));
+}
@@ -0,0 +1,47 @@
+use super::create_element;
+use dodrio::{builder::*, bumpalo, userdata, Node, Render, RenderContext, Vdom};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::*;
+
+struct Counter(u32);
+
+impl<'a> Render<'a> for Counter {
+    fn render(&self, cx: &mut RenderContext<'a>) -> Node<'a> {
+        let count = bumpalo::format!(in cx.bump, "{}", self.0).into_bump_str();
+        div(&cx)
+            .attr("class", "counter")
+            .children([text(count)])
+            .finish()
+    }
+}
+
+#[wasm_bindgen_test]
+async fn userdata_survives_re_render() {
+    let container = create_element("div");
+    let vdom = Vdom::new(&container, Counter(0));
+
+    let element = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    assert!(userdata::get(&element, "chart").is_none());
+    userdata::set(&element, "chart", &JsValue::from(42));
+
+    vdom.weak()
+        .set_component(Box::new(Counter(1)))
+        .await
+        .unwrap();
+
+    let element = container
+        .first_child()
+        .unwrap()
+        .unchecked_into::<web_sys::Element>();
+    assert_eq!(element.text_content().unwrap(), "1");
+    assert_eq!(
+        userdata::get(&element, "chart").and_then(|v| v.as_f64()),
+        Some(42.0)
+    );
+
+    userdata::remove(&element, "chart");
+    assert!(userdata::get(&element, "chart").is_none());
+}